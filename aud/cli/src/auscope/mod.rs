@@ -1,13 +1,16 @@
+mod monitoring;
 mod ui;
 
+use crate::session::{self, Session};
 use aud::{
     audio::*,
     comms::Sockets,
     controllers::{
         audio::AudioProvider,
-        audio_midi::{AppEvent, AudioMidiController},
+        audio_midi::{AlertSeverity, AppEvent, AudioMidiController},
         audio_remote::RemoteAudioProvider,
     },
+    devices::{self, DeviceWatcher},
     lua::imported,
 };
 use ratatui::prelude::*;
@@ -17,42 +20,254 @@ struct TerminalApp {
     app: AudioMidiController,
     ui: ui::Ui,
     fps: f32,
+    monitor_delay: std::time::Duration,
+    monitor_gain: f32,
+    /// Remember the selected device for the next run.
+    saves_session: bool,
+    /// File the incoming audio is recorded to, and in which format.
+    record: Option<(std::path::PathBuf, WavSampleFormat)>,
+    recorder: Option<WavRecorder>,
+    /// Snapshots of the audio taken every interval.
+    monitoring: Option<monitoring::MonitoringSession>,
 }
 
 impl TerminalApp {
-    fn new(audio_provider: Box<dyn AudioProvider>, fps: f32) -> Self {
-        let app = AudioMidiController::with_audio(audio_provider, imported::auscope::API);
+    /// The audio comes from whichever source the controller was given,
+    /// a host, a file, a remote or a mock.
+    fn new(app: AudioMidiController, fps: f32) -> Self {
         let mut ui = ui::Ui::default();
         ui.update_device_names(app.audio().devices());
-        Self { app, ui, fps }
+        Self {
+            app,
+            ui,
+            fps,
+            monitor_delay: std::time::Duration::ZERO,
+            monitor_gain: 1.,
+            saves_session: true,
+            record: None,
+            recorder: None,
+            monitoring: None,
+        }
     }
 
     fn try_connect_to_audio_input(&mut self, index: usize) -> anyhow::Result<()> {
-        let Some(device) = self.app.audio().devices().get(index) else {
-            let num_devices = self.app.audio().devices().len();
-            log::warn!("Invalid device index selection {index}, with {num_devices} devices",);
+        let channels = AudioChannelSelection::Mono(0);
+        self.app
+            .audio_mut()
+            .connect_to_input_by_index(index, channels)?;
+        self.ui.clear_traces();
+        if self.saves_session {
+            let name = self.app.audio().selected_device_name();
+            Session::update(|session| session.audio_device = name);
+        }
+        Ok(())
+    }
+
+    /// Connect to a device by its name, or its index in the list.
+    fn connect_to_audio_input_by_name_or_index(&mut self, device: &str) -> anyhow::Result<()> {
+        let names = self.app.audio().device_names();
+        let index = match session::find_by_name(&names, Some(device)) {
+            Some(index) => index,
+            None => match device.parse::<usize>() {
+                Ok(index) if index < names.len() => index,
+                _ => anyhow::bail!(
+                    "audio device not found : {device}, available devices are : {}",
+                    names.join(", ")
+                ),
+            },
+        };
+
+        self.try_connect_to_audio_input(index)?;
+        self.ui.mark_device_selected(index);
+        Ok(())
+    }
+
+    /// Close the stream of the selected device, which
+    /// is then not reconnected to on the next run.
+    fn disconnect(&mut self) {
+        self.app.audio_mut().disconnect();
+        self.ui.clear_device_selection();
+        self.ui.clear_traces();
+        if self.saves_session {
+            Session::update(|session| session.audio_device = None);
+        }
+    }
+
+    fn toggle_monitor(&mut self) -> anyhow::Result<()> {
+        let monitor = if self.app.audio().is_monitoring() {
+            None
+        } else {
+            let mut monitor = AudioMonitor::open_default(self.monitor_delay)?;
+            monitor.set_gain(self.monitor_gain);
+            Some(monitor)
+        };
+
+        self.app.audio_mut().set_monitor(monitor)
+    }
+
+    /// Append interleaved samples to the recording, starting it with their
+    /// channels. The recording stops if the number of channels changes.
+    fn record_audio(&mut self, samples: &[f32], num_channels: usize) {
+        let Some((ref path, format)) = self.record else {
+            return;
+        };
+
+        if samples.is_empty() {
+            return;
+        }
+
+        if self.recorder.is_none() {
+            let sample_rate = self.app.audio().sample_rate().unwrap_or(48_000);
+            match WavRecorder::create(path, num_channels as u16, sample_rate, format) {
+                Ok(recorder) => self.recorder = Some(recorder),
+                Err(e) => {
+                    self.ui
+                        .show_alert_message(&format!("failed to record audio : {e}"));
+                    self.record = None;
+                    return;
+                }
+            }
+        }
+
+        let Some(ref mut recorder) = self.recorder else {
+            return;
+        };
+
+        if let Err(e) = recorder.write_interleaved(samples) {
+            self.ui
+                .show_alert_message(&format!("stopped recording audio : {e}"));
+            self.record = None;
+            self.stop_recording();
+        }
+    }
+
+    fn stop_recording(&mut self) {
+        let Some(recorder) = self.recorder.take() else {
+            return;
+        };
+
+        let num_frames = recorder.num_frames();
+        match recorder.finalize() {
+            Ok(()) => log::info!("recorded {num_frames} frames of audio"),
+            Err(e) => log::error!("failed to finalize the recording : {e}"),
+        }
+    }
+
+    /// Add the audio to the monitoring session.
+    fn record_monitoring(&mut self, samples: &[f32], num_channels: usize) {
+        let Some(ref mut monitoring) = self.monitoring else {
+            return;
+        };
+
+        let sample_rate = self.app.audio().sample_rate().unwrap_or(48_000);
+        if let Err(e) = monitoring.record(samples, num_channels, sample_rate) {
+            self.ui
+                .show_alert_message(&format!("failed to record the session : {e}"));
+        }
+    }
+
+    /// Write the snapshot of the monitoring session once its interval
+    /// is over, whether audio was received during this update or not.
+    fn update_monitoring(&mut self, now: std::time::Instant) {
+        if !self
+            .monitoring
+            .as_ref()
+            .is_some_and(|monitoring| monitoring.is_due(now))
+        {
+            return;
+        }
+
+        if let Err(e) = self.snapshot_monitoring(now) {
+            self.ui
+                .show_alert_message(&format!("failed to snapshot the session : {e}"));
+        }
+    }
+
+    /// Write the snapshot of the audio received since the last one.
+    fn snapshot_monitoring(&mut self, now: std::time::Instant) -> anyhow::Result<()> {
+        let Some(ref mut monitoring) = self.monitoring else {
             return Ok(());
         };
 
-        let device = device.clone();
-        let channels = AudioChannelSelection::Mono(0);
-        self.app.audio_mut().connect_to_input(&device, channels)
+        let audio = self.app.audio();
+        let sample_rate = audio.sample_rate().unwrap_or(48_000) as usize;
+        let time = std::time::SystemTime::now();
+        let title = format!(
+            "auscope─spectrum─{}",
+            humantime::format_rfc3339_seconds(time)
+        );
+        let spectrum = self.ui.spectrum_plot(title, audio.buffer(), sample_rate);
+        monitoring.snapshot(now, time, &spectrum)
+    }
+
+    /// Show the connected device as selected, wherever it is in the list.
+    fn mark_connected_device(&mut self) {
+        let audio = self.app.audio();
+        let selected = audio.selected_device_name();
+        if let Some(index) = session::find_by_name(&audio.device_names(), selected.as_deref()) {
+            self.ui.mark_device_selected(index);
+        }
+    }
+
+    /// Reselect the device of the last run, if it is still available.
+    fn restore_session(&mut self, session: &Session) {
+        let names = self.app.audio().device_names();
+        let Some(index) = session::find_by_name(&names, session.audio_device.as_deref()) else {
+            return;
+        };
+
+        match self.try_connect_to_audio_input(index) {
+            Ok(()) => self.ui.mark_device_selected(index),
+            Err(e) => log::warn!("failed to reconnect to the last audio device : {e}"),
+        }
     }
 }
 
 impl crate::app::Base for TerminalApp {
     fn update(&mut self) -> anyhow::Result<crate::app::Flow> {
-        self.app.audio_mut().update()?;
+        self.app.tick();
+        let buffer = self.app.audio().buffer();
+        let num_frames = buffer.data.len() / buffer.num_channels.max(1) as usize;
+        if let Err(e) = self.app.audio_mut().update() {
+            self.ui.show_alert_message(&e.to_string());
+        }
+
+        // frames already there keep their place when the number of channels changes
+        let buffer = self.app.audio().buffer();
+        let num_samples = num_frames * buffer.num_channels as usize;
+        let new_samples = buffer.data.get(num_samples..).unwrap_or(&buffer.data);
+        self.ui
+            .detect_clips(new_samples, buffer.num_channels as usize);
+        self.ui
+            .capture_one_shot(new_samples, buffer.num_channels as usize);
+        self.ui
+            .record_audio(new_samples.len() / buffer.num_channels.max(1) as usize);
+        if self.record.is_some() || self.monitoring.is_some() {
+            let (new_samples, num_channels) = (new_samples.to_vec(), buffer.num_channels);
+            self.record_audio(&new_samples, num_channels as usize);
+            self.record_monitoring(&new_samples, num_channels as usize);
+        }
+        self.update_monitoring(std::time::Instant::now());
+        self.app.audio_mut().skip_backlog();
         self.app.process_engine_events()?;
 
         if self.app.process_script_events()? == AppEvent::Stopping {
             return Ok(crate::app::Flow::Exit);
         }
 
+        for request in self.app.take_ui_requests() {
+            self.ui.apply_script_request(request);
+        }
+
         if self.app.process_file_events()? == AppEvent::ScriptLoaded {
             self.ui.clear_script_cache();
         }
 
+        if self.app.process_device_events()? == AppEvent::DevicesChanged {
+            self.ui.update_device_names(self.app.audio().devices());
+            self.mark_connected_device();
+        }
+
         Ok(crate::app::Flow::Continue)
     }
 
@@ -62,14 +277,31 @@ impl crate::app::Base for TerminalApp {
             ui::UiEvent::Exit => Ok(crate::app::Flow::Exit),
             ui::UiEvent::Select { id, index } => match id {
                 ui::Selector::Device => {
-                    self.try_connect_to_audio_input(index)?;
+                    if let Err(e) = self.try_connect_to_audio_input(index) {
+                        self.ui.show_alert_message(&e.to_string());
+                    }
                     Ok(crate::app::Flow::Continue)
                 }
                 ui::Selector::Script => Ok(crate::app::Flow::Continue),
             },
+            ui::UiEvent::ToggleRunningState => {
+                let run = !self.app.audio().is_running();
+                self.app.audio_mut().set_running(run);
+                self.ui.set_paused(!run);
+                Ok(crate::app::Flow::Continue)
+            }
+            ui::UiEvent::ToggleMonitor => {
+                if let Err(e) = self.toggle_monitor() {
+                    self.ui.show_alert_message(&e.to_string());
+                }
+                Ok(crate::app::Flow::Continue)
+            }
+            ui::UiEvent::Disconnect => {
+                self.disconnect();
+                Ok(crate::app::Flow::Continue)
+            }
             ui::UiEvent::LoadScript(index) => {
-                if let Some(script_name) = &self.ui.scripts().get(index) {
-                    let script = self.ui.script_dir().unwrap().join(script_name);
+                if let Some(script) = self.ui.scripts().path(index) {
                     self.app.load_script(script)?;
                 };
                 Ok(crate::app::Flow::Continue)
@@ -78,13 +310,29 @@ impl crate::app::Base for TerminalApp {
     }
 
     fn render(&mut self, f: &mut Frame) {
+        let severity = self.app.alert_severity();
         if let Some(alert) = self.app.take_alert() {
-            self.ui.show_alert_message(&alert);
+            let alert = crate::ui::timestamped_alert(&alert);
+            match severity {
+                Some(AlertSeverity::Error) => self.ui.show_alert_message(&alert),
+                _ => self.ui.show_expiring_alert_message(&alert),
+            }
         }
 
         self.ui.render(f, &self.app);
-        self.ui
-            .remove_offscreen_samples(&mut self.app, f.size().width as usize, self.fps);
+        self.ui.remove_offscreen_samples(&mut self.app, self.fps);
+    }
+
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        self.stop_recording();
+        if let Err(e) = self.snapshot_monitoring(std::time::Instant::now()) {
+            log::error!("failed to snapshot the session : {e}");
+        }
+        Ok(())
+    }
+
+    fn last_input(&self) -> Option<std::time::Instant> {
+        self.app.last_input()
     }
 }
 
@@ -103,6 +351,118 @@ pub struct Options {
     #[arg(long)]
     script: Option<std::path::PathBuf>,
 
+    /// Milliseconds a script callback may run before it is interrupted
+    #[arg(long, default_value_t = aud::lua::DEFAULT_CALLBACK_TIMEOUT.as_millis() as u64)]
+    script_timeout: u64,
+
+    /// Milliseconds an alert stays up, errors stay until dismissed
+    #[arg(long, default_value_t = aud::controllers::audio_midi::DEFAULT_ALERT_TIMEOUT.as_millis() as u64)]
+    alert_timeout: u64,
+
+    /// Distinct logs or alerts a script may emit per second, repeats are coalesced
+    #[arg(long, default_value_t = aud::controllers::audio_midi::DEFAULT_SCRIPT_MESSAGE_RATE)]
+    script_message_rate: usize,
+
+    /// Number of previous traces drawn when persistence is on
+    #[arg(long, default_value_t = crate::ui::widgets::scope::DEFAULT_PERSISTENCE_DEPTH)]
+    persistence_depth: usize,
+
+    /// Frames per transform of the spectrum, a power of two. Larger
+    /// sizes resolve finer frequencies but follow the audio slower
+    #[arg(long, default_value_t = crate::ui::widgets::scope::DEFAULT_FFT_SIZE,
+        value_parser = crate::ui::widgets::scope::parse_fft_size)]
+    fft_size: usize,
+
+    /// Analyse the spectrum window starting at the trigger crossing, when a
+    /// script set a trigger, instead of the latest audio. Toggled with `W`
+    #[arg(long, default_value_t = false)]
+    anchor_fft: bool,
+
+    /// Weight of each new spectrum in the smoothed spectrum, above 0 and
+    /// up to 1. Lower weights steady the spectrum but follow the audio slower
+    #[arg(long, default_value_t = crate::ui::widgets::scope::DEFAULT_SPECTRUM_SMOOTHING,
+        value_parser = crate::ui::widgets::scope::parse_spectrum_smoothing)]
+    spectrum_smoothing: f32,
+
+    /// Level above which a sample is reported as clipping
+    #[arg(long, default_value_t = ui::DEFAULT_CLIP_THRESHOLD)]
+    clip_threshold: f32,
+
+    /// Resample the incoming audio to this rate, in Hz, so that
+    /// it is displayed the same way whatever the device rate
+    #[arg(long)]
+    analysis_rate: Option<u32>,
+
+    /// Start with capture paused, until resumed with <SPACE>
+    #[arg(long, default_value_t = false)]
+    paused: bool,
+
+    /// Show the performance overlay on startup
+    #[arg(long, default_value_t = false)]
+    debug: bool,
+
+    /// Take a snapshot of the audio into this directory every
+    /// `--session-interval`, for unattended monitoring: the audio as a
+    /// WAV file, its spectrum as an SVG image and its levels in levels.csv
+    #[arg(long, value_name = "DIR")]
+    session: Option<std::path::PathBuf>,
+
+    /// Time between the snapshots of `--session`, e.g. "5m", at least a second
+    #[arg(long, value_parser = humantime::parse_duration, default_value = "1m")]
+    session_interval: std::time::Duration,
+
+    /// Snapshots kept by `--session`, the oldest ones are deleted
+    #[arg(long, default_value_t = monitoring::MonitoringSession::DEFAULT_NUM_KEPT)]
+    session_keep: usize,
+
+    /// Connect to this audio input on startup, by name or index,
+    /// instead of the device of the last run
+    #[arg(long)]
+    device: Option<String>,
+
+    /// Analyse this WAV file instead of an audio device
+    #[arg(long)]
+    file: Option<std::path::PathBuf>,
+
+    /// Analyse a generated signal instead of an audio device, given as
+    /// <waveform>[:<frequency>[:<amplitude>]] with the waveform one of
+    /// sine, square, noise or sweep, e.g. "sine:440" or "sweep:20000"
+    #[arg(long, conflicts_with = "file")]
+    generate: Option<Signal>,
+
+    /// Seed of the generated noise, the same seed generating the same noise
+    #[arg(long, default_value_t = SignalAudioInput::DEFAULT_SEED)]
+    seed: u64,
+
+    /// Record the incoming audio to this WAV file, with
+    /// the channels it has when the recording starts
+    #[arg(long)]
+    record: Option<std::path::PathBuf>,
+
+    /// Samples of the recording, int16 or float32
+    #[arg(long, default_value = "int16")]
+    record_format: WavSampleFormat,
+
+    /// Speed at which the file is played, 1 being real time
+    #[arg(long, default_value_t = 1.)]
+    speed: f32,
+
+    /// Play the file again once it has ended
+    #[arg(long = "loop", default_value_t = false)]
+    looping: bool,
+
+    /// Play the incoming audio on the default output device
+    #[arg(long, default_value_t = false)]
+    monitor: bool,
+
+    /// Milliseconds by which the monitored audio is delayed
+    #[arg(long, default_value_t = 0)]
+    monitor_delay: u64,
+
+    /// Gain applied to the monitored audio
+    #[arg(long, default_value_t = 1.)]
+    monitor_gain: f32,
+
     /// Flag to activate remote audio reception.
     /// By default the app uses the system audio device
     #[arg(long, default_value_t = false)]
@@ -115,9 +475,42 @@ pub struct Options {
     /// Fetch audio using these ports
     #[arg(long, default_value = "8080,8081")]
     ports: String,
+
+    /// Times the remote is asked for audio again once it went
+    /// silent, with a growing delay, before giving up with an alert
+    #[arg(long, default_value_t = RetryPolicy::default().max_retries)]
+    remote_retries: usize,
+
+    /// Buffers queued between the audio device and the scope, the
+    /// buffers captured once it is full are dropped and counted
+    #[arg(long, default_value_t = HostAudioInput::DEFAULT_CAPACITY)]
+    audio_queue: usize,
+
+    /// Colors of the channels, comma separated names, indices or hex colors,
+    /// e.g. "cyan,208,#ff8800", cycled through when there are more channels
+    #[arg(long)]
+    palette: Option<crate::ui::theme::Palette>,
+
+    /// File the view is exported to as an SVG image when pressing `E`,
+    /// defaults to a timestamped file in the working directory
+    #[arg(long, value_parser = crate::ui::widgets::plot::parse_image_path)]
+    export_image: Option<std::path::PathBuf>,
+
+    /// Print the audio input and output devices with their
+    /// index and default configuration and exit
+    #[arg(long, default_value_t = false)]
+    list_devices: bool,
+
+    /// Print the listed devices as JSON
+    #[arg(long, requires = "list_devices", default_value_t = false)]
+    json: bool,
 }
 
-fn create_remote_audio_provider(address: String, ports: String) -> Box<dyn AudioProvider> {
+fn create_remote_audio_provider(
+    address: String,
+    ports: String,
+    max_retries: usize,
+) -> Box<dyn AudioProvider> {
     let (in_port, out_port) = ports.split_at(
         ports
             .find(|c| c == ',')
@@ -129,7 +522,25 @@ fn create_remote_audio_provider(address: String, ports: String) -> Box<dyn Audio
         target: format!("{address}:{in_port}").parse().unwrap(),
     };
 
-    Box::new(RemoteAudioProvider::new(sockets).unwrap())
+    let mut provider = RemoteAudioProvider::new(sockets).unwrap();
+    provider.set_retry_policy(RetryPolicy {
+        max_retries,
+        ..Default::default()
+    });
+    Box::new(provider)
+}
+
+/// Print the devices instead of running the scope, returns whether it did.
+pub fn list_devices(opts: &Options) -> anyhow::Result<bool> {
+    if !opts.list_devices {
+        return Ok(false);
+    }
+
+    let (inputs, outputs) = describe_host_audio_devices()?;
+    let mut devices = crate::listing::audio_devices("input", &inputs);
+    devices.extend(crate::listing::audio_devices("output", &outputs));
+    crate::listing::print(&devices, opts.json);
+    Ok(true)
 }
 
 pub fn run(
@@ -138,16 +549,81 @@ pub fn run(
     common_opts: crate::CommonOptions,
 ) -> anyhow::Result<()> {
     if let Some(log_file) = opts.log.or_else(|| crate::locations::log_file("auscope")) {
-        crate::logger::start("auscope", log_file, common_opts.verbose)?;
+        crate::logger::start(
+            "auscope",
+            log_file,
+            common_opts.verbose,
+            common_opts.log_format,
+        )?;
     }
 
-    let audio_provider = if opts.remote {
-        create_remote_audio_provider(opts.address, opts.ports)
+    let audio_provider: Box<dyn AudioProvider> = if let Some(ref file) = opts.file {
+        let mut input = FileAudioInput::open(file)?;
+        input.set_speed(opts.speed);
+        input.set_looping(opts.looping);
+        Box::new(input)
+    } else if let Some(signal) = opts.generate {
+        let mut input = SignalAudioInput::new(signal);
+        input.set_seed(opts.seed);
+        Box::new(input)
+    } else if opts.remote {
+        create_remote_audio_provider(opts.address, opts.ports, opts.remote_retries)
     } else {
-        Box::<HostAudioInput>::default()
+        Box::new(HostAudioInput::with_capacity(opts.audio_queue))
     };
 
-    let mut app = TerminalApp::new(audio_provider, opts.fps);
+    if let Some(palette) = opts.palette {
+        crate::ui::theme::set_palette(palette);
+    }
+
+    let controller = AudioMidiController::with_audio(audio_provider, imported::auscope::API);
+    let mut app = TerminalApp::new(controller, opts.fps);
+    // the file or signal is not remembered as the device of the session
+    let is_virtual = opts.file.is_some() || opts.generate.is_some();
+    app.saves_session = !is_virtual;
+    app.ui.set_persistence_depth(opts.persistence_depth);
+    app.ui.set_clip_threshold(opts.clip_threshold);
+    app.ui.set_labels(crate::labels::Labels::load());
+    app.ui.set_fft_size(opts.fft_size);
+    app.ui.set_fft_anchored(opts.anchor_fft);
+    app.ui.set_spectrum_smoothing(opts.spectrum_smoothing);
+    if let Some(path) = opts.export_image {
+        app.ui.set_export_path(path);
+    }
+    if opts.debug {
+        app.ui.toggle_perf();
+    }
+    app.app.audio_mut().set_running(!opts.paused);
+    app.ui.set_paused(opts.paused);
+    app.app.audio_mut().set_analysis_rate(opts.analysis_rate);
+    if let Some(rate) = opts.analysis_rate {
+        app.ui.set_sample_rate(rate as usize);
+    }
+    app.app
+        .set_script_timeout(std::time::Duration::from_millis(opts.script_timeout));
+    app.app.set_script_message_rate(opts.script_message_rate);
+    let alert_timeout = std::time::Duration::from_millis(opts.alert_timeout);
+    app.app.set_alert_timeout(alert_timeout);
+    app.ui.set_alert_timeout(alert_timeout);
+    app.app
+        .set_module_paths(crate::locations::lua::lib().into_iter().collect());
+    app.monitor_delay = std::time::Duration::from_millis(opts.monitor_delay);
+    app.monitor_gain = opts.monitor_gain;
+    app.record = opts.record.map(|path| (path, opts.record_format));
+    if let Some(dir) = opts.session {
+        let monitoring = monitoring::MonitoringSession::create(
+            dir,
+            opts.session_interval,
+            opts.session_keep,
+            opts.record_format,
+            std::time::Instant::now(),
+        )?;
+        log::info!(
+            "taking snapshots of the audio in {}",
+            monitoring.dir().display()
+        );
+        app.monitoring = Some(monitoring);
+    }
 
     let scripts = opts
         .script
@@ -155,8 +631,249 @@ pub fn run(
 
     if let Some(script) = scripts {
         log::info!("{:#?}", script.canonicalize()?);
-        app.ui.update_script_dir(script)?;
+        app.ui.update_script_dir(script);
+    }
+
+    if is_virtual {
+        app.app
+            .audio_mut()
+            .connect_to_input_by_index(0, AudioChannelSelection::Mono(0))?;
+        app.ui.mark_device_selected(0);
+    } else {
+        match opts.device {
+            Some(ref device) => app.connect_to_audio_input_by_name_or_index(device)?,
+            None => app.restore_session(&Session::load()),
+        }
+    }
+
+    if !opts.remote && !is_virtual {
+        app.app.watch_devices(DeviceWatcher::spawn(
+            devices::DEFAULT_POLL_INTERVAL,
+            list_host_audio_devices,
+        ));
+    }
+    if opts.monitor {
+        app.toggle_monitor()?;
+    }
+
+    crate::app::run(terminal, &mut app, opts.fps.max(1.), &common_opts.limit())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::{
+        harness::{self, MockAudioSource, MockMidiInput, NUM_FRAMES},
+        Base,
+    };
+    use crossterm::event::KeyCode;
+
+    fn mock_app(source: MockAudioSource) -> TerminalApp {
+        let controller = AudioMidiController::new(
+            Box::new(source),
+            Box::<MockMidiInput>::default(),
+            imported::auscope::API,
+        );
+        let mut app = TerminalApp::new(controller, 30.);
+        app.saves_session = false;
+        app
+    }
+
+    fn press(app: &mut TerminalApp, code: KeyCode) {
+        app.on_keypress(code.into()).unwrap();
+    }
+
+    #[test]
+    fn selecting_a_device_connects_to_it_and_captures_its_audio() {
+        let mut app = mock_app(MockAudioSource::new(&["mic", "line"]));
+        assert!(app.app.audio().selected_device().is_none());
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.app.audio().selected_device().unwrap().name, "line");
+
+        app.update().unwrap();
+        app.update().unwrap();
+        assert_eq!(app.app.audio().buffer().data.len(), 2 * NUM_FRAMES as usize);
+
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.app.audio().selected_device().unwrap().name, "mic");
+        assert!(app.app.audio().buffer().data.is_empty());
+    }
+
+    #[test]
+    fn incoming_audio_is_recorded_until_shutdown() {
+        let path = std::env::temp_dir().join(format!("aud-auscope-{}.wav", std::process::id()));
+        let mut app = mock_app(MockAudioSource::new(&["mic"]));
+        app.record = Some((path.clone(), WavSampleFormat::Float32));
+        press(&mut app, KeyCode::Enter);
+
+        app.update().unwrap();
+        app.update().unwrap();
+        app.on_shutdown().unwrap();
+
+        let wav = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let data_len = 2 * NUM_FRAMES * std::mem::size_of::<f32>() as u32;
+        assert_eq!(&wav[40..44], &data_len.to_le_bytes());
+        assert_eq!(wav.len(), 44 + data_len as usize);
+    }
+
+    #[test]
+    fn session_snapshots_are_taken_once_the_interval_is_over() {
+        let dir = std::env::temp_dir().join(format!("aud-auscope-session-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let interval = std::time::Duration::from_secs(60);
+        let start = std::time::Instant::now();
+        let mut app = mock_app(MockAudioSource::new(&["mic"]));
+        app.monitoring = Some(
+            monitoring::MonitoringSession::create(
+                &dir,
+                interval,
+                10,
+                WavSampleFormat::Float32,
+                start.checked_sub(interval).unwrap(),
+            )
+            .unwrap(),
+        );
+        press(&mut app, KeyCode::Enter);
+
+        app.update().unwrap();
+        let names = std::fs::read_dir(&dir).unwrap().count();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // levels.csv and the audio and spectrum of the snapshot
+        assert_eq!(names, 3);
+        assert!(!app.monitoring.unwrap().is_due(start));
     }
 
-    crate::app::run(terminal, &mut app, opts.fps.max(1.))
+    #[test]
+    fn the_drawn_view_is_exported_as_svg() {
+        let path = std::env::temp_dir().join(format!("aud-auscope-{}.svg", std::process::id()));
+        let mut app = mock_app(MockAudioSource::new(&["mic"]));
+        app.ui.set_export_path(path.clone());
+        let keys = [
+            KeyCode::Enter,
+            harness::IDLE,
+            harness::IDLE,
+            KeyCode::Char('E'),
+        ];
+        harness::run(&mut app, 80, 24, keys).unwrap();
+
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let mut reader = quick_xml::Reader::from_str(&svg);
+        let mut polylines = vec![];
+        loop {
+            match reader.read_event().unwrap() {
+                quick_xml::events::Event::Empty(element)
+                    if element.name().as_ref() == "polyline" =>
+                {
+                    let points = element.try_get_attribute("points").unwrap().unwrap();
+                    polylines.push(points.value.to_string());
+                }
+                quick_xml::events::Event::Eof => break,
+                _ => (),
+            }
+        }
+
+        // the trace of the silent mock buffers, along the middle of the lane
+        assert_eq!(polylines.len(), 1, "{svg}");
+        for points in polylines {
+            assert!(!points.is_empty());
+            assert!(
+                points.split(' ').all(|point| point.ends_with(",144.00")),
+                "{points}"
+            );
+        }
+    }
+
+    fn render_text(app: &mut TerminalApp) -> String {
+        let mut terminal = Terminal::new(backend::TestBackend::new(160, 24)).unwrap();
+        let frame = terminal.draw(|f| app.render(f)).unwrap();
+        crate::app::buffer_text(frame.buffer)
+    }
+
+    /// Update and render the app until a frame shows `text`.
+    fn render_until_shown(app: &mut TerminalApp, text: &str) {
+        let start = std::time::Instant::now();
+        while !render_text(app).contains(text) {
+            assert!(
+                start.elapsed() < std::time::Duration::from_secs(2),
+                "{text} never shown"
+            );
+            app.update().unwrap();
+        }
+    }
+
+    #[test]
+    fn alerts_are_dismissed_after_the_alert_timeout_unless_they_are_errors() {
+        let timeout = std::time::Duration::from_millis(100);
+        let mut app = mock_app(MockAudioSource::new(&["mic"]));
+        app.app.set_alert_timeout(timeout);
+        app.ui.set_alert_timeout(timeout);
+
+        let dir = std::env::temp_dir().join(format!("aud-auscope-alerts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (info, error) = (dir.join("info.lua"), dir.join("error.lua"));
+        std::fs::write(&info, "alert(\"all good\")").unwrap();
+        std::fs::write(&error, "error(\"all bad\")").unwrap();
+
+        app.app.load_script(&info).unwrap();
+        render_until_shown(&mut app, "all good");
+        std::thread::sleep(timeout);
+        assert!(!render_text(&mut app).contains("all good"));
+
+        app.app.load_script(&error).unwrap();
+        render_until_shown(&mut app, "all bad");
+        std::thread::sleep(timeout);
+        app.update().unwrap();
+        assert!(render_text(&mut app).contains("all bad"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn disconnecting_stops_capturing_the_device_audio() {
+        let mut app = mock_app(MockAudioSource::new(&["mic"]));
+        press(&mut app, KeyCode::Enter);
+        app.update().unwrap();
+        assert!(!app.app.audio().buffer().data.is_empty());
+
+        press(&mut app, KeyCode::Char('X'));
+        assert!(app.app.audio().selected_device().is_none());
+        assert!(app.app.audio().selected_channels().is_none());
+
+        app.update().unwrap();
+        assert!(app.app.audio().buffer().data.is_empty());
+    }
+
+    #[test]
+    fn a_failed_connection_keeps_the_app_running() {
+        let mut source = MockAudioSource::new(&["mic"]);
+        source.devices[0].num_channels = 0;
+        let mut app = mock_app(source);
+
+        press(&mut app, KeyCode::Enter);
+        assert!(app.app.audio().selected_channels().is_none());
+
+        app.update().unwrap();
+        assert!(app.app.audio().buffer().data.is_empty());
+    }
+
+    #[test]
+    fn devices_are_navigated_and_selected_until_quitting() {
+        let mut app = mock_app(MockAudioSource::new(&["mic", "line"]));
+        let keys = [KeyCode::Char('j'), KeyCode::Enter, KeyCode::Char('q')];
+        let frames = harness::run(&mut app, 80, 24, keys).unwrap();
+
+        // no frame is rendered after quitting
+        assert_eq!(frames.len(), 3);
+        assert!(frames.text(0).contains("> mic"), "{}", frames.text(0));
+        assert!(frames.text(1).contains("> line"), "{}", frames.text(1));
+        assert!(frames.text(2).contains("> line"), "{}", frames.text(2));
+        assert_eq!(app.app.audio().selected_device().unwrap().name, "line");
+    }
 }
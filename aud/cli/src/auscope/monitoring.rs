@@ -0,0 +1,328 @@
+//! Unattended monitoring with `--session <dir>`. Every interval, the audio
+//! received during the interval is written to the directory as a snapshot:
+//! the audio as `snapshot-<time>.wav`, its spectrum as `snapshot-<time>.svg`,
+//! and its peak and RMS levels appended to `levels.csv`, one row per channel.
+//!
+//! Only the latest snapshots are kept, the older ones are deleted,
+//! including those of previous runs, along with their levels.
+
+use crate::ui::widgets::plot::{self, Plot};
+use aud::audio::{WavRecorder, WavSampleFormat};
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, Instant, SystemTime},
+};
+
+/// Audio of the ongoing interval, renamed after the time of the snapshot.
+const RECORDING: &str = "recording.wav.part";
+const SNAPSHOT_PREFIX: &str = "snapshot-";
+const LEVELS: &str = "levels.csv";
+const LEVELS_HEADER: &str = "time,channel,peak_dbfs,rms_dbfs";
+/// Level written for silence, instead of minus infinity.
+const FLOOR_DBFS: f32 = -120.;
+/// Snapshots are named after their time, to the second.
+const MIN_INTERVAL: Duration = Duration::from_secs(1);
+
+pub struct MonitoringSession {
+    dir: PathBuf,
+    interval: Duration,
+    num_kept: usize,
+    format: WavSampleFormat,
+    interval_start: Instant,
+    recorder: Option<WavRecorder>,
+    /// Peak and sum of the squares of each channel over the interval.
+    levels: Vec<(f32, f64)>,
+    num_frames: usize,
+}
+
+impl MonitoringSession {
+    pub const DEFAULT_NUM_KEPT: usize = 60;
+
+    /// Start a session in the directory, creating it if needed,
+    /// with its first interval starting `now`.
+    pub fn create(
+        dir: impl AsRef<Path>,
+        interval: Duration,
+        num_kept: usize,
+        format: WavSampleFormat,
+        now: Instant,
+    ) -> anyhow::Result<Self> {
+        if interval < MIN_INTERVAL {
+            anyhow::bail!("The interval of a session must be at least a second");
+        }
+
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir)?;
+
+        let levels = dir.join(LEVELS);
+        if !levels.exists() {
+            std::fs::write(levels, format!("{LEVELS_HEADER}\n"))?;
+        }
+
+        Ok(Self {
+            dir,
+            interval,
+            num_kept: num_kept.max(1),
+            format,
+            interval_start: now,
+            recorder: None,
+            levels: vec![],
+            num_frames: 0,
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Append interleaved samples to the audio of the interval. The
+    /// audio of the interval is dropped if the number of channels changes.
+    pub fn record(
+        &mut self,
+        samples: &[f32],
+        num_channels: usize,
+        sample_rate: u32,
+    ) -> anyhow::Result<()> {
+        let num_channels = num_channels.max(1);
+        if samples.is_empty() {
+            return Ok(());
+        }
+
+        if self.levels.len() != num_channels {
+            self.discard();
+            self.levels = vec![(0., 0.); num_channels];
+        }
+
+        let recorder = match self.recorder {
+            Some(ref mut recorder) => recorder,
+            None => self.recorder.insert(WavRecorder::create(
+                self.dir.join(RECORDING),
+                num_channels as u16,
+                sample_rate,
+                self.format,
+            )?),
+        };
+
+        recorder.write_interleaved(samples)?;
+        for frame in samples.chunks_exact(num_channels) {
+            for (&sample, (peak, sum_of_squares)) in frame.iter().zip(self.levels.iter_mut()) {
+                *peak = peak.max(sample.abs());
+                *sum_of_squares += (sample as f64).powi(2);
+            }
+        }
+        self.num_frames += samples.len() / num_channels;
+        Ok(())
+    }
+
+    pub fn is_due(&self, now: Instant) -> bool {
+        now.duration_since(self.interval_start) >= self.interval
+    }
+
+    /// Write the snapshot of the interval, named after `time`, and
+    /// start the next interval. Nothing is written if no audio was
+    /// recorded during the interval.
+    pub fn snapshot(
+        &mut self,
+        now: Instant,
+        time: SystemTime,
+        spectrum: &Plot,
+    ) -> anyhow::Result<()> {
+        self.interval_start = now;
+        let Some(recorder) = self.recorder.take() else {
+            return Ok(());
+        };
+
+        let levels = std::mem::take(&mut self.levels);
+        let num_frames = std::mem::take(&mut self.num_frames).max(1);
+        recorder.finalize()?;
+
+        let stamp = plot::file_timestamp(time);
+        std::fs::rename(
+            self.dir.join(RECORDING),
+            self.dir.join(format!("{SNAPSHOT_PREFIX}{stamp}.wav")),
+        )?;
+        spectrum.save(&self.dir.join(format!("{SNAPSHOT_PREFIX}{stamp}.svg")))?;
+
+        let mut rows = String::new();
+        for (channel, (peak, sum_of_squares)) in levels.into_iter().enumerate() {
+            let rms = (sum_of_squares / num_frames as f64).sqrt() as f32;
+            rows.push_str(&format!(
+                "{},{channel},{:.1},{:.1}\n",
+                humantime::format_rfc3339_seconds(time),
+                to_dbfs(peak),
+                to_dbfs(rms)
+            ));
+        }
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(self.dir.join(LEVELS))?
+            .write_all(rows.as_bytes())?;
+
+        self.remove_old_snapshots()?;
+        self.remove_old_levels()
+    }
+
+    /// Drop the audio of the interval without writing a snapshot.
+    pub fn discard(&mut self) {
+        if let Some(recorder) = self.recorder.take() {
+            let _ = recorder.finalize();
+            let _ = std::fs::remove_file(self.dir.join(RECORDING));
+        }
+        self.levels.clear();
+        self.num_frames = 0;
+    }
+
+    /// Keep the latest snapshots, their names sorting in chronological order.
+    fn remove_old_snapshots(&self) -> anyhow::Result<()> {
+        let mut snapshots: Vec<PathBuf> = std::fs::read_dir(&self.dir)?
+            .filter_map(|entry| Some(entry.ok()?.path()))
+            .filter(|path| {
+                let name = path.file_name().and_then(|name| name.to_str());
+                let extension = path.extension().and_then(|extension| extension.to_str());
+                name.is_some_and(|name| name.starts_with(SNAPSHOT_PREFIX))
+                    && matches!(extension, Some("wav" | "svg"))
+            })
+            .collect();
+        snapshots.sort_by_key(|path| path.file_stem().map(|stem| stem.to_owned()));
+        snapshots.dedup_by_key(|path| path.file_stem().map(|stem| stem.to_owned()));
+
+        let num_removed = snapshots.len().saturating_sub(self.num_kept);
+        for snapshot in &snapshots[..num_removed] {
+            for extension in ["wav", "svg"] {
+                let file = snapshot.with_extension(extension);
+                if file.exists() {
+                    std::fs::remove_file(file)?;
+                }
+            }
+        }
+
+        if num_removed > 0 {
+            log::info!(
+                "removed {num_removed} old snapshots from {}",
+                self.dir.display()
+            );
+        }
+        Ok(())
+    }
+
+    /// Keep the levels of as many snapshots as are kept, rows
+    /// being appended in chronological order.
+    fn remove_old_levels(&self) -> anyhow::Result<()> {
+        let path = self.dir.join(LEVELS);
+        let levels = std::fs::read_to_string(&path)?;
+        let rows: Vec<&str> = levels.lines().skip(1).collect();
+        let time = |row: &str| row.split(',').next().unwrap_or_default().to_owned();
+
+        let mut times: Vec<String> = rows.iter().map(|row| time(row)).collect();
+        times.dedup();
+        let num_removed = times.len().saturating_sub(self.num_kept);
+        if num_removed == 0 {
+            return Ok(());
+        }
+
+        let oldest_kept = &times[num_removed];
+        let first_kept = rows
+            .iter()
+            .position(|row| time(row) == *oldest_kept)
+            .unwrap_or(rows.len());
+
+        let mut kept = format!("{LEVELS_HEADER}\n");
+        for row in &rows[first_kept..] {
+            kept.push_str(row);
+            kept.push('\n');
+        }
+        std::fs::write(path, kept)?;
+        Ok(())
+    }
+}
+
+fn to_dbfs(level: f32) -> f32 {
+    (20. * level.log10()).max(FLOOR_DBFS)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn spectrum() -> Plot {
+        Plot {
+            title: "spectrum".into(),
+            lanes: vec![],
+        }
+    }
+
+    fn file_names(dir: &Path) -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn snapshots_are_written_every_interval_and_only_the_latest_kept() {
+        let dir = std::env::temp_dir().join(format!("aud-monitoring-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let interval = Duration::from_secs(10);
+        let start = Instant::now();
+        let time = SystemTime::UNIX_EPOCH;
+        let mut session =
+            MonitoringSession::create(&dir, interval, 2, WavSampleFormat::Int16, start).unwrap();
+
+        session
+            .record(&[0.5, -0.25, -0.5, 0.25], 2, 48_000)
+            .unwrap();
+        assert!(!session.is_due(start + interval / 2));
+        assert_eq!(file_names(&dir), ["levels.csv", RECORDING]);
+
+        for i in 1..=3 {
+            let now = start + interval * i;
+            assert!(session.is_due(now));
+            session
+                .snapshot(now, time + interval * i, &spectrum())
+                .unwrap();
+            assert!(!session.is_due(now));
+            session.record(&[1., 0.], 2, 48_000).unwrap();
+        }
+
+        // an interval without audio leaves no snapshot
+        session.discard();
+        session
+            .snapshot(start + interval * 4, time + interval * 4, &spectrum())
+            .unwrap();
+
+        assert_eq!(
+            file_names(&dir),
+            [
+                "levels.csv",
+                "snapshot-1970-01-01T00-00-20Z.svg",
+                "snapshot-1970-01-01T00-00-20Z.wav",
+                "snapshot-1970-01-01T00-00-30Z.svg",
+                "snapshot-1970-01-01T00-00-30Z.wav",
+            ]
+        );
+
+        // only the levels of the kept snapshots remain
+        let levels = std::fs::read_to_string(dir.join(LEVELS)).unwrap();
+        let rows: Vec<&str> = levels.lines().collect();
+        assert_eq!(rows.len(), 5);
+        assert_eq!(rows[0], LEVELS_HEADER);
+        assert_eq!(rows[1], "1970-01-01T00:00:20Z,0,0.0,0.0");
+        assert_eq!(rows[2], "1970-01-01T00:00:20Z,1,-120.0,-120.0");
+        assert_eq!(rows[4], "1970-01-01T00:00:30Z,1,-120.0,-120.0");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn sessions_cannot_snapshot_more_than_once_a_second() {
+        let dir = std::env::temp_dir().join(format!("aud-monitoring-min-{}", std::process::id()));
+        let interval = Duration::from_millis(500);
+        let session =
+            MonitoringSession::create(&dir, interval, 2, WavSampleFormat::Int16, Instant::now());
+        assert!(session.is_err());
+        assert!(!dir.exists());
+    }
+}
@@ -1,19 +1,65 @@
-use crate::ui::{components, widgets};
-use aud::{audio::AudioDevice, controllers::audio_midi::AudioMidiController, files};
+use crate::{
+    labels::Labels,
+    ui::{components, theme, widgets},
+};
+use aud::{
+    audio::{AudioBuffer, AudioDevice},
+    controllers::audio_midi::AudioMidiController,
+    dsp,
+    lua::traits::api::{ScopeMode, ScopeTrigger, UiApiEvent},
+};
 use crossterm::event::{KeyCode, KeyEvent};
-use ratatui::prelude::*;
+use ratatui::{prelude::*, widgets::Paragraph};
+
+/// Default level above which a sample counts as clipping.
+pub const DEFAULT_CLIP_THRESHOLD: f32 = 1.;
 
 const USAGE: &str = r#"
          ? : display help
          a : display API
          s : display script
          d : display docs
-         K : increase gain
-         J : decrease gain
+         f : freeze display
+         A : arm / disarm one-shot capture
+   <SPACE> : pause / resume capture
+         M : monitor audio on the output device
+         X : disconnect from device
+    ], K : increase gain
+    [, J : decrease gain
+         n : auto-normalize gain
+         m : cycle mono sum / average
+         b : toggle DC blocker
+         p : toggle persistence
+         v : cycle line style
+         g : cycle amplitude grid
+         x : toggle min/max or stride decimation
+         o : overlay / stack channels
+         u : cycle the vectorscope X channel
+         U : cycle the vectorscope Y channel
+         z : halve the spectrum FFT size
+         Z : double the spectrum FFT size
+         w : cycle the spectrum window overlap
+         e : cycle instant / smoothed / peak hold spectrum
+         W : anchor the spectrum window to the trigger
+    <, > : scroll stacked channels
+    <LEFT> : seek back in history while paused
+   <RIGHT> : seek forward in history while paused
+         c : toggle measure cursors
+         D : toggle performance overlay
+         E : export the view as an SVG image
+         i : toggle key hints
+         T : show / hide the aud log, paged through with <PGUP> and <PGDOWN>
+ <LEFT>, h : move cursor when measuring
+<RIGHT>, l : move cursor when measuring
+     <TAB> : add or switch cursor
          H : zoom out
          L : zoom in
    <UP>, k : scroll up
  <DOWN>, j : scroll down
+  <PGDOWN> : scroll a page down
+    <PGUP> : scroll a page up
+    <HOME> : jump to the first item
+     <END> : jump to the last item
  <LEFT>, h : cycle panes left
 <RIGHT>, l : cycle panes right
      Enter : confirm selection
@@ -21,6 +67,66 @@ const USAGE: &str = r#"
      <C-c> : force quit
 "#;
 
+/// What the keys act on, to hint at the ones relevant to the current view.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum KeyContext {
+    Waveform,
+    Spectrum,
+    Vector,
+    Sparkline,
+    Measure,
+    Seek,
+}
+
+impl KeyContext {
+    /// Keys of the usage hinted at, as they are listed in it.
+    fn keys(self) -> &'static [&'static str] {
+        match self {
+            Self::Waveform => &[
+                "?", "<SPACE>", "f", "], K", "[, J", "H", "L", "o", "v", "g", "c",
+            ],
+            Self::Spectrum => &[
+                "?", "<SPACE>", "f", "], K", "[, J", "v", "z", "Z", "w", "e", "W",
+            ],
+            Self::Vector => &["?", "<SPACE>", "f", "], K", "[, J", "H", "L", "u", "U"],
+            Self::Sparkline => &["?", "<SPACE>", "f", "], K", "[, J", "H", "L"],
+            Self::Measure => &["c", "<LEFT>, h", "<RIGHT>, l", "<TAB>"],
+            Self::Seek => &["<SPACE>", "<LEFT>", "<RIGHT>"],
+        }
+    }
+}
+
+/// Keys and what they do in a context, taken from the usage.
+fn key_hints(context: KeyContext) -> Vec<(&'static str, &'static str)> {
+    let usage: Vec<_> = USAGE
+        .lines()
+        .filter_map(|line| line.split_once(" : "))
+        .map(|(keys, action)| (keys.trim(), action.trim()))
+        .collect();
+
+    context
+        .keys()
+        .iter()
+        .filter_map(|key| usage.iter().find(|(keys, _)| keys == key).copied())
+        .collect()
+}
+
+/// Line of the key hints of a context, keys highlighted.
+fn hint_line(context: KeyContext) -> Line<'static> {
+    let spans = key_hints(context)
+        .into_iter()
+        .enumerate()
+        .flat_map(|(i, (keys, action))| {
+            let separator = if i == 0 { "" } else { "  " };
+            [
+                Span::raw(separator),
+                Span::styled(keys, theme::style(Style::default().fg(Color::Gray).bold())),
+                Span::raw(format!(" {action}")),
+            ]
+        });
+    Line::from(spans.collect::<Vec<_>>())
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum Popup {
     Usage,
@@ -28,6 +134,37 @@ enum Popup {
     Docs,
     Script,
     Alert,
+    Log,
+}
+
+/// Combine all channels into a single waveform.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash)]
+enum MonoSum {
+    #[default]
+    Off,
+    Sum,
+    Average,
+}
+
+impl MonoSum {
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Sum,
+            Self::Sum => Self::Average,
+            Self::Average => Self::Off,
+        }
+    }
+
+    fn apply<'a>(self, buffer: &'a AudioBuffer) -> std::borrow::Cow<'a, AudioBuffer> {
+        let average = match self {
+            Self::Off => return std::borrow::Cow::Borrowed(buffer),
+            Self::Sum => false,
+            Self::Average => true,
+        };
+
+        let sum = dsp::sum_channels(&buffer.deinterleave(), average);
+        std::borrow::Cow::Owned(AudioBuffer::from_deinterleaved(&[sum]))
+    }
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -40,18 +177,68 @@ pub enum UiEvent<Id> {
     Continue,
     Select { id: Id, index: usize },
     LoadScript(usize),
+    ToggleRunningState,
+    ToggleMonitor,
+    Disconnect,
     Exit,
 }
 
 pub struct Ui {
     popups: components::Popups<Popup>,
     selectors: components::Selectors<Selector>,
-    script_dir: Option<std::path::PathBuf>,
-    script_names: Vec<String>,
+    scripts: components::ScriptBrowser,
     alert_message: Option<String>,
+    /// How long alerts that are not errors stay up.
+    alert_timeout: std::time::Duration,
     cached_script: Option<String>,
     downsample: usize,
     gain: f32,
+    normalizer: Option<PeakFollower>,
+    mono_sum: MonoSum,
+    /// Remove the DC offset of the displayed audio, the captured audio is left as is.
+    dc_block: bool,
+    persistence_depth: usize,
+    persistence: Option<widgets::scope::Persistence>,
+    frozen: bool,
+    snapshot: Option<AudioBuffer>,
+    /// Changes whenever a frozen or captured buffer is replaced.
+    held_version: u64,
+    trace_cache: widgets::scope::TraceCache,
+    zoom_window: Option<std::time::Duration>,
+    decimation: widgets::scope::Decimation,
+    grid: widgets::scope::Grid,
+    /// Scope width the zoom window was last spread over.
+    zoomed_width: usize,
+    /// Columns the waveform is drawn over, as of the last render.
+    scope_width: usize,
+    clips: ClipDetector,
+    scope_mode: ScopeMode,
+    measure: Option<Measure>,
+    waveform_style: widgets::scope::LineStyle,
+    spectrum_style: widgets::scope::LineStyle,
+    spectrum: widgets::scope::SpectrumSettings,
+    spectrum_smoothing: widgets::scope::SpectrumSmoothing,
+    stacked: bool,
+    first_lane: usize,
+    labels: Labels,
+    vector_pair: widgets::scope::ChannelPair,
+    /// Whether the channels of the vectorscope were last out of phase.
+    out_of_phase: bool,
+    /// Channels of the audio drawn, as of the last render.
+    num_channels: usize,
+    one_shot: Option<OneShot>,
+    history: History,
+    /// Frames between the drawn window and the newest frame, only seeking while paused.
+    seek: Option<usize>,
+    sample_rate: usize,
+    perf: Option<widgets::perf::PerfStats>,
+    show_hints: bool,
+    /// Lines of the aud log, read while they are shown.
+    log_tail: components::LogTail,
+    /// File the view is exported to, a timestamped one if none.
+    export_path: Option<std::path::PathBuf>,
+    /// Export the view once it is next drawn.
+    export_requested: bool,
 }
 
 impl Default for Ui {
@@ -63,41 +250,156 @@ impl Default for Ui {
                 (Popup::Docs, components::PopupKind::Code),
                 (Popup::Script, components::PopupKind::Code),
                 (Popup::Alert, components::PopupKind::Text),
+                (Popup::Log, components::PopupKind::Log),
             ]),
             selectors: components::Selectors::new(&[Selector::Device, Selector::Script]),
-            script_dir: None,
-            script_names: vec![],
+            scripts: components::ScriptBrowser::default(),
             alert_message: None,
+            alert_timeout: aud::controllers::audio_midi::DEFAULT_ALERT_TIMEOUT,
             cached_script: None,
             downsample: 16,
             gain: 1.,
+            normalizer: None,
+            mono_sum: MonoSum::Off,
+            dc_block: false,
+            persistence_depth: widgets::scope::DEFAULT_PERSISTENCE_DEPTH,
+            persistence: None,
+            frozen: false,
+            snapshot: None,
+            held_version: 0,
+            trace_cache: widgets::scope::TraceCache::default(),
+            zoom_window: None,
+            decimation: widgets::scope::Decimation::default(),
+            grid: widgets::scope::Grid::default(),
+            zoomed_width: 0,
+            scope_width: 0,
+            clips: ClipDetector::default(),
+            scope_mode: ScopeMode::default(),
+            measure: None,
+            waveform_style: widgets::scope::LineStyle::default(),
+            spectrum_style: widgets::scope::LineStyle::default(),
+            spectrum: widgets::scope::SpectrumSettings::default(),
+            spectrum_smoothing: widgets::scope::SpectrumSmoothing::default(),
+            stacked: false,
+            first_lane: 0,
+            labels: Labels::default(),
+            vector_pair: widgets::scope::ChannelPair::default(),
+            out_of_phase: false,
+            num_channels: 0,
+            one_shot: None,
+            history: History::default(),
+            seek: None,
+            sample_rate: Self::DEFAULT_SAMPLE_RATE,
+            perf: None,
+            show_hints: true,
+            log_tail: components::LogTail::active(),
+            export_path: None,
+            export_requested: false,
         }
     }
 }
 
 impl Ui {
-    const SAMPLE_RATE: usize = 48000;
+    /// Sample rate assumed when the audio is not resampled.
+    const DEFAULT_SAMPLE_RATE: usize = 48000;
+
+    pub fn scripts(&self) -> &components::ScriptBrowser {
+        &self.scripts
+    }
+
+    /// Drop the held traces of the previous device. An armed
+    /// one-shot capture is rearmed on the new device.
+    pub fn clear_traces(&mut self) {
+        self.snapshot = None;
+        self.held_version += 1;
+        if self.persistence.is_some() {
+            self.persistence = Some(widgets::scope::Persistence::new(self.persistence_depth));
+        }
+        if self.one_shot.is_some() {
+            self.one_shot = Some(OneShot::default());
+        }
+        self.history = History::default();
+        self.seek = self.seek.map(|_| 0);
+    }
 
-    pub fn scripts(&self) -> &[String] {
-        self.script_names.as_slice()
+    /// Seeking through the history is only possible while paused,
+    /// resuming snaps back to the live audio.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.seek = paused.then_some(0);
     }
 
-    pub fn script_dir(&self) -> Option<&std::path::PathBuf> {
-        self.script_dir.as_ref()
+    /// Keys that pan through the history while paused.
+    /// Returns false if the key was not handled.
+    fn on_seek_keypress(&mut self, key: KeyEvent) -> bool {
+        let Some(ref mut offset) = self.seek else {
+            return false;
+        };
+
+        // a quarter of the drawn window per key press
+        let step = (self.scope_width * self.downsample / 4).max(1);
+        match key.code {
+            KeyCode::Left => *offset += step,
+            KeyCode::Right => *offset = offset.saturating_sub(step),
+            _ => return false,
+        }
+        true
     }
 
     pub fn clear_script_cache(&mut self) {
         self.cached_script = None;
     }
 
-    pub fn update_script_dir(&mut self, dir: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
-        let dir = dir.as_ref();
-        self.script_names = files::list_with_extension(dir, "lua")?;
-        self.script_dir = Some(dir.into());
+    pub fn update_script_dir(&mut self, dir: impl AsRef<std::path::Path>) {
+        self.scripts.update_dir(dir);
         if let Some(sel) = self.selectors.get_mut(Selector::Script) {
-            *sel = components::Selector::with_len(self.script_names.len());
+            *sel = components::Selector::with_len(self.scripts.names().len());
         }
-        Ok(())
+    }
+
+    /// Sample rate of the buffered audio, which the time axis is based on.
+    pub fn set_sample_rate(&mut self, sample_rate: usize) {
+        self.sample_rate = sample_rate;
+    }
+
+    /// Frames per transform of the spectrum, a power of two.
+    pub fn set_fft_size(&mut self, fft_size: usize) {
+        self.spectrum.fft_size = fft_size;
+    }
+
+    /// Analyse the window starting at the trigger crossing, if there is one.
+    pub fn set_fft_anchored(&mut self, anchored: bool) {
+        self.spectrum.anchored = anchored;
+    }
+
+    /// Weight of each new spectrum when it is smoothed.
+    pub fn set_spectrum_smoothing(&mut self, coefficient: f32) {
+        self.spectrum_smoothing = widgets::scope::SpectrumSmoothing::new(coefficient);
+    }
+
+    /// File the view is exported to when pressing `E`.
+    pub fn set_export_path(&mut self, path: std::path::PathBuf) {
+        self.export_path = Some(path);
+    }
+
+    /// Names shown instead of the channel numbers on the stacked lanes.
+    pub fn set_labels(&mut self, labels: Labels) {
+        self.labels = labels;
+    }
+
+    /// Labels of the drawn channels, none once they are summed to mono.
+    fn lane_labels(&self, app: &AudioMidiController) -> Vec<String> {
+        let audio = app.audio();
+        let (MonoSum::Off, Some(selection)) = (self.mono_sum, audio.selected_channels()) else {
+            return vec![];
+        };
+
+        let device = audio.selected_device_name();
+        let mut channels = selection.as_vec();
+        channels.sort_unstable();
+        channels
+            .into_iter()
+            .map(|channel| self.labels.channel(device.as_deref(), channel))
+            .collect()
     }
 
     pub fn update_device_names(&mut self, names: &[AudioDevice]) {
@@ -106,32 +408,300 @@ impl Ui {
         };
     }
 
+    /// Show a device as selected without going through the selector.
+    pub fn mark_device_selected(&mut self, index: usize) {
+        if let Some(devices) = self.selectors.get_mut(Selector::Device) {
+            devices.select(index);
+            devices.confirm_selection();
+        }
+    }
+
+    /// Show that no device is connected anymore.
+    pub fn clear_device_selection(&mut self) {
+        if let Some(devices) = self.selectors.get_mut(Selector::Device) {
+            devices.clear_selection();
+        }
+    }
+
     fn adjust_gain(&mut self, amount: f32) {
         self.gain = (self.gain + amount).clamp(0., 16.);
     }
 
+    /// Number of previous traces drawn in persistence mode.
+    pub fn set_persistence_depth(&mut self, depth: usize) {
+        self.persistence_depth = depth;
+    }
+
+    fn toggle_persistence(&mut self) {
+        self.persistence = match self.persistence {
+            Some(_) => None,
+            None => Some(widgets::scope::Persistence::new(self.persistence_depth)),
+        };
+    }
+
+    /// Level above which a sample counts as clipping.
+    pub fn set_clip_threshold(&mut self, threshold: f32) {
+        self.clips.threshold = threshold;
+    }
+
+    /// Check newly received interleaved samples for clipping,
+    /// logging one clip event per clipping channel.
+    pub fn detect_clips(&mut self, samples: &[f32], num_channels: usize) {
+        for channel in self.clips.detect(samples, num_channels) {
+            log::warn!(
+                "clip on channel {channel} at {}",
+                humantime::format_rfc3339_millis(std::time::SystemTime::now())
+            );
+        }
+    }
+
+    /// The instant spectrum of the audio with the current
+    /// settings, whatever the view, to export it.
+    pub fn spectrum_plot(
+        &self,
+        title: String,
+        audio: &AudioBuffer,
+        sample_rate: usize,
+    ) -> widgets::plot::Plot {
+        widgets::plot::Plot {
+            title,
+            lanes: vec![widgets::scope::spectrum_lane(
+                audio,
+                widgets::scope::SpectrumView {
+                    style: widgets::scope::LineStyle::default(),
+                    settings: self.spectrum,
+                    smoothing: &mut widgets::scope::SpectrumSmoothing::new(1.),
+                    sample_rate,
+                },
+            )],
+        }
+    }
+
+    /// Warn once the channels of the vectorscope fall out of phase,
+    /// and again only after they were back in phase.
+    fn check_phase(&mut self, correlation: Option<(widgets::scope::ChannelPair, f32)>) {
+        let Some((pair, correlation)) = correlation else {
+            return;
+        };
+
+        let out_of_phase = correlation < widgets::scope::PHASE_WARNING_CORRELATION;
+        if out_of_phase && !self.out_of_phase {
+            log::warn!(
+                "ch {} and ch {} are out of phase with a correlation of {correlation:+.2}, \
+                 they cancel out when summed to mono",
+                pair.x + 1,
+                pair.y + 1
+            );
+        }
+        self.out_of_phase = out_of_phase;
+    }
+
+    /// Write the drawn view to the export file, alerting if it cannot be written.
+    fn export(&mut self, plot: &widgets::plot::Plot) {
+        let path = self
+            .export_path
+            .clone()
+            .unwrap_or_else(|| widgets::plot::default_path(std::time::SystemTime::now()));
+        match plot.save(&path) {
+            Ok(()) => log::info!("exported the scope to {}", path.display()),
+            Err(e) => self.show_alert_message(&format!("failed to export the scope : {e}")),
+        }
+    }
+
+    /// Each view mode keeps its own line style.
+    fn cycle_line_style(&mut self) {
+        match self.scope_mode {
+            ScopeMode::Waveform => self.waveform_style = self.waveform_style.next(),
+            ScopeMode::Spectrum => self.spectrum_style = self.spectrum_style.next(),
+            // points are not joined, they have no line to style
+            ScopeMode::Vector | ScopeMode::Sparkline => {}
+        }
+    }
+
+    fn toggle_normalize(&mut self) {
+        self.normalizer = match self.normalizer {
+            Some(_) => None,
+            None => Some(PeakFollower::default()),
+        };
+    }
+
     fn adjust_downsample(&mut self, amount: isize) {
         self.downsample = (self.downsample as isize + amount).clamp(8, 4096) as usize;
     }
 
+    /// Zoom to fit a script requested window on screen. Applied
+    /// once per request so that zooming with keys still works.
+    fn apply_zoom_window(&mut self, window: Option<std::time::Duration>) {
+        // the window is spread over the scope again when it is resized
+        if window == self.zoom_window && self.scope_width == self.zoomed_width {
+            return;
+        }
+
+        self.zoom_window = window;
+        self.zoomed_width = self.scope_width;
+        if let Some(window) = window {
+            let num_frames = window.as_secs_f32() * self.sample_rate as f32;
+            let width = self.scope_width.max(1) as f32;
+            self.downsample = (num_frames / width).clamp(8., 4096.) as usize;
+        }
+    }
+
+    /// Arm a one-shot capture, re-arming it if it already captured.
+    /// Pressed while still waiting for the trigger, it disarms it.
+    fn toggle_one_shot(&mut self) {
+        self.one_shot = match self.one_shot {
+            Some(ref one_shot) if one_shot.captured.is_none() => None,
+            _ => Some(OneShot::default()),
+        };
+        self.held_version += 1;
+    }
+
+    /// Look for the trigger in newly received interleaved samples
+    /// while a one-shot capture is armed.
+    pub fn capture_one_shot(&mut self, samples: &[f32], num_channels: usize) {
+        if let Some(ref mut one_shot) = self.one_shot {
+            one_shot.feed(samples, num_channels);
+        }
+    }
+
+    fn toggle_freeze(&mut self) {
+        self.frozen = !self.frozen;
+        self.held_version += 1;
+    }
+
+    /// Buffer to draw in the scope. While frozen this is a held copy
+    /// taken on the first call, capture keeps running in the background.
+    fn scope_buffer<'a>(&'a mut self, live: &'a AudioBuffer) -> &'a AudioBuffer {
+        if let Some(captured) = self.one_shot.as_ref().and_then(|s| s.captured.as_ref()) {
+            return captured;
+        }
+
+        if !self.frozen {
+            self.snapshot = None;
+            return live;
+        }
+
+        self.snapshot.get_or_insert_with(|| live.clone())
+    }
+
+    /// Whether the scope draws a held buffer rather than the live one.
+    fn is_holding(&self) -> bool {
+        self.frozen || self.one_shot.as_ref().is_some_and(|s| s.captured.is_some())
+    }
+
+    /// Show the rendering and audio timing over the scope.
+    pub fn toggle_perf(&mut self) {
+        self.perf = match self.perf {
+            Some(_) => None,
+            None => Some(widgets::perf::PerfStats::default()),
+        };
+    }
+
+    /// Count the audio frames received for the performance overlay.
+    pub fn record_audio(&mut self, num_frames: usize) {
+        if let Some(ref mut perf) = self.perf {
+            perf.record_audio(num_frames, std::time::Instant::now());
+        }
+    }
+
+    fn toggle_measure(&mut self) {
+        self.measure = match self.measure {
+            Some(_) => None,
+            None => Some(Measure::default()),
+        };
+    }
+
+    /// Measuring and seeking take over the keys of the view they happen in.
+    fn key_context(&self) -> KeyContext {
+        if self.measure.is_some() {
+            return KeyContext::Measure;
+        }
+
+        if self.seek.is_some() {
+            return KeyContext::Seek;
+        }
+
+        match self.scope_mode {
+            ScopeMode::Waveform => KeyContext::Waveform,
+            ScopeMode::Spectrum => KeyContext::Spectrum,
+            ScopeMode::Vector => KeyContext::Vector,
+            ScopeMode::Sparkline => KeyContext::Sparkline,
+        }
+    }
+
+    /// Keys that drive the cursors while measuring.
+    /// Returns false if the key was not handled.
+    fn on_measure_keypress(&mut self, key: KeyEvent) -> bool {
+        let Some(ref mut measure) = self.measure else {
+            return false;
+        };
+
+        match key.code {
+            KeyCode::Left | KeyCode::Char('h') => measure.move_active(-1),
+            KeyCode::Right | KeyCode::Char('l') => measure.move_active(1),
+            KeyCode::Tab => measure.next_cursor(),
+            _ => return false,
+        }
+        true
+    }
+
     pub fn on_keypress(&mut self, key: KeyEvent) -> UiEvent<Selector> {
+        if self.on_measure_keypress(key) || self.on_seek_keypress(key) {
+            return UiEvent::Continue;
+        }
+
+        if self.popups.is_visible(Popup::Log) && self.log_tail.on_keypress(key.code) {
+            return UiEvent::Continue;
+        }
+
         match key.code {
             KeyCode::Char('?') => self.popups.toggle_visible(Popup::Usage),
             KeyCode::Char('a') => self.popups.toggle_visible(Popup::Api),
             KeyCode::Char('s') => self.popups.toggle_visible(Popup::Script),
             KeyCode::Char('d') => self.popups.toggle_visible(Popup::Docs),
+            KeyCode::Char('f') => self.toggle_freeze(),
+            KeyCode::Char('A') => self.toggle_one_shot(),
+            KeyCode::Char(' ') => return UiEvent::ToggleRunningState,
+            KeyCode::Char('M') => return UiEvent::ToggleMonitor,
+            KeyCode::Char('X') => return UiEvent::Disconnect,
             KeyCode::Char('q') | KeyCode::Esc => {
                 if !self.popups.any_visible() {
                     return UiEvent::Exit;
                 }
                 self.popups.hide()
             }
-            KeyCode::Char('K') => self.adjust_gain(0.1),
-            KeyCode::Char('J') => self.adjust_gain(-0.1),
+            KeyCode::Char('K') | KeyCode::Char(']') => self.adjust_gain(0.1),
+            KeyCode::Char('J') | KeyCode::Char('[') => self.adjust_gain(-0.1),
+            KeyCode::Char('n') => self.toggle_normalize(),
+            KeyCode::Char('m') => self.mono_sum = self.mono_sum.next(),
+            KeyCode::Char('b') => self.dc_block = !self.dc_block,
+            KeyCode::Char('p') => self.toggle_persistence(),
+            KeyCode::Char('v') => self.cycle_line_style(),
+            KeyCode::Char('g') => self.grid = self.grid.next(),
+            KeyCode::Char('x') => self.decimation = self.decimation.next(),
+            KeyCode::Char('c') => self.toggle_measure(),
+            KeyCode::Char('D') => self.toggle_perf(),
+            KeyCode::Char('E') => self.export_requested = true,
+            KeyCode::Char('i') => self.show_hints = !self.show_hints,
+            KeyCode::Char('T') => self.popups.toggle_visible(Popup::Log),
+            KeyCode::Char('o') => self.stacked = !self.stacked,
+            KeyCode::Char('u') => self.vector_pair = self.vector_pair.next_x(self.num_channels),
+            KeyCode::Char('U') => self.vector_pair = self.vector_pair.next_y(self.num_channels),
+            KeyCode::Char('z') => self.spectrum.adjust_fft_size(false),
+            KeyCode::Char('Z') => self.spectrum.adjust_fft_size(true),
+            KeyCode::Char('w') => self.spectrum.overlap = self.spectrum.overlap.next(),
+            KeyCode::Char('e') => self.spectrum_smoothing.cycle_response(),
+            KeyCode::Char('W') => self.spectrum.anchored = !self.spectrum.anchored,
+            KeyCode::Char('<') => self.first_lane = self.first_lane.saturating_sub(1),
+            KeyCode::Char('>') => self.first_lane += 1,
             KeyCode::Char('H') => self.adjust_downsample(-8),
             KeyCode::Char('L') => self.adjust_downsample(8),
             KeyCode::Up | KeyCode::Char('k') => self.selectors.previous_item(),
             KeyCode::Down | KeyCode::Char('j') => self.selectors.next_item(),
+            KeyCode::PageUp => self.selectors.previous_page(),
+            KeyCode::PageDown => self.selectors.next_page(),
+            KeyCode::Home => self.selectors.first_item(),
+            KeyCode::End => self.selectors.last_item(),
             KeyCode::Left | KeyCode::Char('h') => self.selectors.previous_selector(),
             KeyCode::Right | KeyCode::Char('l') => self.selectors.next_selector(),
             KeyCode::Enter => {
@@ -154,17 +724,48 @@ impl Ui {
         UiEvent::Continue
     }
 
+    /// Apply what a script requested through its `ui` table.
+    pub fn apply_script_request(&mut self, request: UiApiEvent) {
+        match request {
+            UiApiEvent::Clear => self.clear_traces(),
+            UiApiEvent::Reset => {
+                if self.popups.is_visible(Popup::Alert) {
+                    self.popups.hide();
+                }
+                self.alert_message = None;
+            }
+        }
+    }
+
     pub fn show_alert_message(&mut self, alert_message: &str) {
         self.popups.show(Popup::Alert);
         self.alert_message = Some(alert_message.into());
     }
 
+    /// Show an alert that is dismissed once the alert timeout passes.
+    pub fn show_expiring_alert_message(&mut self, alert_message: &str) {
+        let deadline = std::time::Instant::now() + self.alert_timeout;
+        self.popups.show_until(Popup::Alert, deadline);
+        self.alert_message = Some(alert_message.into());
+    }
+
+    pub fn set_alert_timeout(&mut self, timeout: std::time::Duration) {
+        self.alert_timeout = timeout;
+    }
+
     pub fn render(&mut self, f: &mut Frame, app: &AudioMidiController) {
-        let sections = Layout::default()
+        let frame_start = std::time::Instant::now();
+        self.popups.hide_expired(frame_start);
+        if let Some(ref mut perf) = self.perf {
+            perf.begin_frame(frame_start);
+        }
+
+        let mut sections = Layout::default()
             .direction(Direction::Horizontal)
             .margin(1)
             .constraints([Constraint::Min(32), Constraint::Percentage(90)].as_ref())
-            .split(f.size());
+            .split(f.size())
+            .to_vec();
 
         let left_sections = Layout::default()
             .direction(Direction::Vertical)
@@ -172,23 +773,20 @@ impl Ui {
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
             .split(sections[0]);
 
-        let has_script_dir = self.script_dir.as_ref().is_some_and(|dir| dir.is_dir());
+        let has_script_dir = self.scripts.is_available();
         let device_selector_section = if has_script_dir {
             left_sections[0]
         } else {
             sections[0]
         };
 
+        let device_names = app.audio().device_names();
         self.selectors.render(
             f,
             device_selector_section,
             Selector::Device,
             crate::title!("devices"),
-            &app.audio()
-                .devices()
-                .iter()
-                .map(|d| d.name.clone())
-                .collect::<Vec<_>>(),
+            &device_names,
         );
 
         if has_script_dir {
@@ -196,36 +794,410 @@ impl Ui {
                 f,
                 left_sections[1],
                 Selector::Script,
-                &crate::title!("{}", self.script_dir.as_ref().unwrap().to_string_lossy()),
-                self.script_names.as_slice(),
+                &crate::title!("{}", self.scripts.dir().unwrap().to_string_lossy()),
+                self.scripts.names(),
             );
         }
 
         let selected_device_name = self
             .selectors
             .get(Selector::Device)
-            .and_then(|s| {
-                s.selected()
-                    .and_then(|index| app.audio().devices().get(index))
-            })
-            .map(|device| device.name.clone())
+            .and_then(|s| s.selected().and_then(|index| device_names.get(index)))
+            .cloned()
             .unwrap_or_default();
 
-        let scope_tile = format!(
+        let scope = app.scope().clone();
+        self.scope_mode = scope.mode;
+
+        if self.show_hints {
+            let scope_sections = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(1)])
+                .split(sections[1]);
+            sections[1] = scope_sections[0];
+            let hints = Paragraph::new(hint_line(self.key_context()))
+                .style(theme::style(Style::default().fg(Color::DarkGray)));
+            f.render_widget(hints, scope_sections[1]);
+        }
+        self.scope_width = widgets::scope::drawable_width(sections[1]);
+        self.apply_zoom_window(scope.window);
+
+        let gain = match self.normalizer {
+            Some(ref mut normalizer) => {
+                normalizer.update(&app.audio().buffer().data);
+                normalizer.gain() * self.gain
+            }
+            None => self.gain,
+        };
+
+        let gain_title = match self.normalizer {
+            Some(_) => crate::title!("gain : auto {:.2}", gain),
+            None => crate::title!("gain : {:.2}", gain),
+        };
+
+        let mut scope_tile = format!(
             "{}───{}─{}",
             crate::title!("{}", selected_device_name),
             crate::title!("zoom : {}", self.downsample),
-            crate::title!("gain : {:.2}", self.gain),
+            gain_title,
         );
 
-        widgets::scope::render(
-            f,
-            sections[1],
-            &scope_tile,
-            app.audio().buffer(),
-            self.downsample,
-            self.gain,
-        );
+        if let Some(trigger) = scope.trigger {
+            scope_tile = format!(
+                "{scope_tile}─{}",
+                crate::title!("trigger : {:.2}", trigger.level)
+            );
+        }
+
+        match self.mono_sum {
+            MonoSum::Off => {}
+            MonoSum::Sum => scope_tile = format!("{scope_tile}─{}", crate::title!("mono sum")),
+            MonoSum::Average => {
+                scope_tile = format!("{scope_tile}─{}", crate::title!("mono average"))
+            }
+        }
+
+        if self.dc_block {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("dc blocked"));
+        }
+
+        if self.frozen {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("frozen"));
+        }
+
+        if !app.audio().is_running() {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("paused"));
+        }
+
+        if app.audio().is_reconnecting() {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("connecting…"));
+        }
+
+        if let Some(mismatch) = app.audio().channel_mismatch() {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("{}", mismatch));
+        }
+
+        let num_dropped = app.audio().num_dropped_buffers();
+        if num_dropped > 0 {
+            scope_tile = format!(
+                "{scope_tile}─{}",
+                crate::title!("dropped : {}", num_dropped)
+            );
+        }
+
+        // a captured window already starts at the trigger crossing
+        let mut trigger = scope.trigger;
+        if let Some(ref mut one_shot) = self.one_shot {
+            one_shot.trigger = scope.trigger.unwrap_or(OneShot::DEFAULT_TRIGGER);
+            one_shot.num_frames = self.scope_width * self.downsample;
+            let status = match one_shot.captured {
+                Some(_) => {
+                    trigger = None;
+                    "captured"
+                }
+                None => "armed",
+            };
+            scope_tile = format!("{scope_tile}─{}", crate::title!("{}", status));
+        }
+
+        if self.clips.count > 0 {
+            scope_tile = format!(
+                "{scope_tile}─{}",
+                crate::title!("clips : {}", self.clips.count)
+            );
+        }
+
+        if self.persistence.is_some() {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("persistence"));
+        }
+
+        if self.stacked {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("stacked"));
+        }
+
+        let line_style = match scope.mode {
+            ScopeMode::Waveform => self.waveform_style,
+            ScopeMode::Spectrum => self.spectrum_style,
+            ScopeMode::Vector | ScopeMode::Sparkline => widgets::scope::LineStyle::default(),
+        };
+
+        if self.decimation != widgets::scope::Decimation::default() {
+            scope_tile = format!(
+                "{scope_tile}─{}",
+                crate::title!("{}", self.decimation.name())
+            );
+        }
+
+        if line_style != widgets::scope::LineStyle::default() {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("{}", line_style.name()));
+        }
+
+        if self.grid != widgets::scope::Grid::default() && scope.mode == ScopeMode::Waveform {
+            scope_tile = format!("{scope_tile}─{}", crate::title!("{}", self.grid.name()));
+        }
+
+        let mut view = widgets::scope::ScopeView {
+            downsample: self.downsample,
+            gain,
+            style: line_style,
+            decimation: self.decimation,
+            grid: self.grid,
+            cursors: vec![],
+            source: None,
+        };
+        let mono_sum = self.mono_sum;
+        let dc_block = self.dc_block;
+        let clipping = self.clips.is_flashing();
+        let mut persistence = self.persistence.take();
+        let mut measure = self.measure.take();
+        let (stacked, mut first_lane) = (self.stacked, self.first_lane);
+        let lane_labels = self.lane_labels(app);
+        let width = self.scope_width;
+        let sample_rate = self.sample_rate;
+        let spectrum = self.spectrum;
+        let mut spectrum_smoothing = std::mem::take(&mut self.spectrum_smoothing);
+        let vector_pair = self.vector_pair;
+        let num_visible_frames = width * view.downsample;
+        let mut seek = self.seek.take();
+        let history = std::mem::take(&mut self.history);
+        let is_holding = self.is_holding();
+        let buffer_version = match is_holding {
+            true => self.held_version,
+            false => app.audio().buffer_version(),
+        };
+        let mut trace_cache = std::mem::take(&mut self.trace_cache);
+        let export_requested = std::mem::take(&mut self.export_requested);
+        let buffer = self.scope_buffer(app.audio().buffer());
+        let buffer = match seek {
+            Some(ref mut offset) => {
+                let window = history.window(buffer, offset, num_visible_frames);
+                let num_frames = history.num_frames_with(buffer);
+                scope_tile = format!(
+                    "{scope_tile}─{}",
+                    crate::title!(
+                        "history : -{:.2}s / {:.2}s",
+                        *offset as f32 / sample_rate as f32,
+                        num_frames as f32 / sample_rate as f32
+                    )
+                );
+                std::borrow::Cow::Owned(window)
+            }
+            None => std::borrow::Cow::Borrowed(buffer),
+        };
+        let buffer = mono_sum.apply(&buffer);
+        let buffer = match dc_block {
+            true => std::borrow::Cow::Owned(AudioBuffer {
+                data: dsp::block_dc(&buffer.data, buffer.num_channels as usize),
+                num_channels: buffer.num_channels,
+            }),
+            false => buffer,
+        };
+        let num_channels = buffer.num_channels as usize;
+        let mut exported = None;
+        let mut correlation = None;
+        match scope.mode {
+            ScopeMode::Waveform => {
+                view.source = Some(trace_source(&(
+                    is_holding,
+                    buffer_version,
+                    seek,
+                    mono_sum,
+                    dc_block,
+                    trigger.map(|trigger| (trigger.level.to_bits(), trigger.edge)),
+                )));
+                let drawn = triggered(&buffer, trigger);
+                if let Some(ref mut measure) = measure {
+                    let num_columns =
+                        widgets::scope::num_columns(drawn.num_frames(), view.downsample, width);
+                    measure.clamp(num_columns);
+                    view.cursors = measure.cursors.clone();
+                    let channel = match stacked {
+                        true => first_lane.min((drawn.num_channels as usize).saturating_sub(1)),
+                        false => 0,
+                    };
+                    scope_tile = format!(
+                        "{scope_tile}─{}",
+                        measure.describe(
+                            &drawn,
+                            channel,
+                            view.downsample,
+                            num_columns,
+                            sample_rate
+                        )
+                    );
+                }
+
+                if stacked {
+                    let num_channels = drawn.num_channels as usize;
+                    first_lane = first_lane.min(num_channels.saturating_sub(1));
+                    if export_requested {
+                        exported = Some(widgets::plot::Plot {
+                            title: scope_tile.clone(),
+                            lanes: widgets::scope::stacked_lanes(
+                                &drawn,
+                                &view,
+                                width,
+                                first_lane,
+                                &lane_labels,
+                            ),
+                        });
+                    }
+                    widgets::scope::render_stacked(
+                        f,
+                        sections[1],
+                        widgets::scope::block(&scope_tile, clipping),
+                        &drawn,
+                        &view,
+                        first_lane,
+                        &lane_labels,
+                    )
+                } else {
+                    if export_requested {
+                        exported = Some(widgets::plot::Plot {
+                            title: scope_tile.clone(),
+                            lanes: vec![widgets::scope::waveform_lane(&drawn, &view, width)],
+                        });
+                    }
+                    widgets::scope::render(
+                        f,
+                        sections[1],
+                        widgets::scope::block(&scope_tile, clipping),
+                        &drawn,
+                        &view,
+                        persistence.as_mut(),
+                        Some(&mut trace_cache),
+                    )
+                }
+            }
+            ScopeMode::Spectrum => {
+                let mut title = format!(
+                    "{scope_tile}─{}─{}",
+                    crate::title!("spectrum"),
+                    crate::title!(
+                        "fft {} / {} overlap",
+                        spectrum.fft_size,
+                        spectrum.overlap.name()
+                    )
+                );
+                let response = spectrum_smoothing.response;
+                if response != widgets::scope::SpectrumResponse::default() {
+                    title = format!("{title}─{}", crate::title!("{}", response.name()));
+                }
+                let anchored_start = match spectrum.anchored {
+                    true => anchored_spectrum_start(&buffer, trigger, spectrum.fft_size),
+                    false => None,
+                };
+                let buffer = match anchored_start {
+                    Some(start) => {
+                        title = format!("{title}─{}", crate::title!("anchored"));
+                        let num_channels = buffer.num_channels.max(1) as usize;
+                        let samples =
+                            start * num_channels..(start + spectrum.fft_size) * num_channels;
+                        std::borrow::Cow::Owned(AudioBuffer {
+                            data: buffer.data[samples].to_vec(),
+                            num_channels: buffer.num_channels,
+                        })
+                    }
+                    None => buffer,
+                };
+                if export_requested {
+                    exported = Some(widgets::plot::Plot {
+                        title: title.clone(),
+                        lanes: vec![widgets::scope::spectrum_lane(
+                            &buffer,
+                            widgets::scope::SpectrumView {
+                                style: line_style,
+                                settings: spectrum,
+                                smoothing: &mut spectrum_smoothing.clone(),
+                                sample_rate,
+                            },
+                        )],
+                    });
+                }
+                widgets::scope::render_spectrum(
+                    f,
+                    sections[1],
+                    &title,
+                    &buffer,
+                    widgets::scope::SpectrumView {
+                        style: line_style,
+                        settings: spectrum,
+                        smoothing: &mut spectrum_smoothing,
+                        sample_rate,
+                    },
+                )
+            }
+            ScopeMode::Vector => {
+                let num_channels = buffer.num_channels.max(1) as usize;
+                let window = last_frames(&buffer, num_visible_frames);
+
+                let title = match vector_pair.clamp(num_channels) {
+                    Some(pair) => format!(
+                        "{scope_tile}─{}─{}",
+                        crate::title!("vector"),
+                        crate::title!("{}", pair.name())
+                    ),
+                    None => format!("{scope_tile}─{}", crate::title!("vector")),
+                };
+                if export_requested {
+                    exported = Some(widgets::plot::Plot {
+                        title: title.clone(),
+                        lanes: vec![widgets::scope::vector_lane(&window, vector_pair, gain)],
+                    });
+                }
+                correlation = vector_pair
+                    .clamp(num_channels)
+                    .zip(widgets::scope::pair_correlation(&window, vector_pair));
+                widgets::scope::render_vector(
+                    f,
+                    sections[1],
+                    widgets::scope::block(&title, clipping),
+                    &window,
+                    vector_pair,
+                    gain,
+                )
+            }
+            ScopeMode::Sparkline => {
+                let title = format!("{scope_tile}─{}", crate::title!("sparkline"));
+                let window = last_frames(&buffer, num_visible_frames);
+                if export_requested {
+                    exported = Some(widgets::plot::Plot {
+                        title: title.clone(),
+                        lanes: widgets::scope::sparkline_lanes(&window, gain, &lane_labels, width),
+                    });
+                }
+                widgets::scope::render_sparklines(
+                    f,
+                    sections[1],
+                    widgets::scope::block(&title, clipping),
+                    &window,
+                    gain,
+                    &lane_labels,
+                )
+            }
+        }
+        self.num_channels = num_channels;
+        self.persistence = persistence;
+        self.measure = measure;
+        self.first_lane = first_lane;
+        self.history = history;
+        self.seek = seek;
+        self.trace_cache = trace_cache;
+        self.spectrum_smoothing = spectrum_smoothing;
+        self.check_phase(correlation);
+        if let Some(plot) = exported {
+            self.export(&plot);
+        }
+
+        if let Some(ref mut perf) = self.perf {
+            let audio = app.audio().buffer();
+            let num_frames = audio.data.len() / audio.num_channels.max(1) as usize;
+            let num_renderable_frames = self.scope_width * self.downsample;
+            perf.set_buffer_fill(num_frames as f32 / num_renderable_frames.max(1) as f32);
+            perf.set_num_sanitized_samples(app.audio().num_sanitized_samples());
+            perf.set_num_dropped_buffers(app.audio().num_dropped_buffers());
+            widgets::perf::render(f, sections[1], perf);
+        }
 
         self.popups.render(
             f,
@@ -251,6 +1223,13 @@ impl Ui {
             self.alert_message.as_ref().unwrap_or(&"".to_owned()),
         );
 
+        if self.popups.is_visible(Popup::Log) {
+            self.log_tail.poll();
+            let log_text = self.log_tail.text();
+            self.popups
+                .render(f, Popup::Log, crate::title!("log"), &log_text);
+        }
+
         if !self.popups.is_visible(Popup::Script) {
             self.popups
                 .render(f, Popup::Script, crate::title!(""), "No script loaded");
@@ -275,24 +1254,714 @@ impl Ui {
             &selected_script_name,
             self.cached_script.as_ref().unwrap(),
         );
+
+        if let Some(ref mut perf) = self.perf {
+            perf.end_frame(frame_start.elapsed());
+        }
     }
 
-    pub fn remove_offscreen_samples(
-        &mut self,
-        app: &mut AudioMidiController,
-        screen_width: usize,
-        fps: f32,
-    ) {
-        let audio = app.audio_mut().buffer_mut();
-        let num_renderable_samples = screen_width * self.downsample;
+    /// Drop the samples that no longer fit in the scope, as
+    /// sized by the last render, so it follows terminal resizes.
+    pub fn remove_offscreen_samples(&mut self, app: &mut AudioMidiController, fps: f32) {
+        let audio = app.audio().buffer();
+        let num_renderable_samples =
+            self.scope_width * self.downsample * audio.num_channels.max(1) as usize;
         let num_samples_to_purge =
-            ((Self::SAMPLE_RATE as f32 / fps) * audio.num_channels as f32) as usize;
+            ((self.sample_rate as f32 / fps) * audio.num_channels as f32) as usize;
 
+        // only borrowed mutably when purging, which changes the buffer version
         if audio.data.len() > num_renderable_samples {
+            let audio = app.audio_mut().buffer_mut();
             let num_samples_to_purge =
                 num_samples_to_purge.max(audio.data.len() - num_renderable_samples);
 
-            let _ = audio.data.drain(0..num_samples_to_purge);
+            let num_channels = audio.num_channels;
+            let purged = audio
+                .data
+                .drain(0..num_samples_to_purge.min(audio.data.len()));
+            let max_frames = (History::DURATION.as_secs_f32() * self.sample_rate as f32) as usize;
+            self.history.push(purged, num_channels, max_frames);
+        }
+    }
+}
+
+/// Audio that scrolled off the scope, kept to seek back through while paused.
+#[derive(Default)]
+struct History {
+    samples: std::collections::VecDeque<f32>,
+    num_channels: u32,
+}
+
+impl History {
+    /// How far back the history goes.
+    const DURATION: std::time::Duration = std::time::Duration::from_secs(10);
+
+    fn push(&mut self, samples: impl Iterator<Item = f32>, num_channels: u32, max_frames: usize) {
+        if num_channels != self.num_channels {
+            self.samples.clear();
+            self.num_channels = num_channels;
+        }
+
+        self.samples.extend(samples);
+        let max_samples = max_frames * num_channels.max(1) as usize;
+        if self.samples.len() > max_samples {
+            let _ = self.samples.drain(0..self.samples.len() - max_samples);
+        }
+    }
+
+    /// Frames of the history followed by the live buffer.
+    fn num_frames_with(&self, live: &AudioBuffer) -> usize {
+        let num_channels = live.num_channels.max(1) as usize;
+        let num_history_frames = match self.num_channels == live.num_channels {
+            true => self.samples.len() / num_channels,
+            false => 0,
+        };
+        num_history_frames + live.data.len() / num_channels
+    }
+
+    /// The `num_frames` long window ending `offset` frames before the newest
+    /// live frame. The offset is clamped to the oldest full window available.
+    fn window(&self, live: &AudioBuffer, offset: &mut usize, num_frames: usize) -> AudioBuffer {
+        let num_channels = live.num_channels.max(1) as usize;
+        let history = match self.num_channels == live.num_channels {
+            true => self.samples.iter(),
+            false => std::collections::vec_deque::Iter::default(),
+        };
+
+        let num_available = self.num_frames_with(live);
+        *offset = (*offset).min(num_available.saturating_sub(num_frames));
+        let end = num_available - *offset;
+        let start = end.saturating_sub(num_frames);
+
+        AudioBuffer {
+            data: history
+                .chain(live.data.iter())
+                .skip(start * num_channels)
+                .take((end - start) * num_channels)
+                .copied()
+                .collect(),
+            num_channels: live.num_channels,
+        }
+    }
+}
+
+/// Follows the peak level of the audio with a slow release,
+/// so that normalizing by it does not make the waveform jump.
+struct PeakFollower {
+    peak: f32,
+}
+
+impl Default for PeakFollower {
+    fn default() -> Self {
+        Self {
+            peak: Self::MIN_PEAK,
+        }
+    }
+}
+
+impl PeakFollower {
+    /// Portion of the peak kept on each update when the level drops.
+    const RELEASE: f32 = 0.97;
+    /// Lowest tracked peak, to avoid amplifying silence into noise.
+    const MIN_PEAK: f32 = 0.01;
+
+    fn update(&mut self, samples: &[f32]) {
+        let peak = samples
+            .iter()
+            .fold(0f32, |peak, sample| peak.max(sample.abs()));
+        self.peak = peak.max(self.peak * Self::RELEASE).max(Self::MIN_PEAK);
+    }
+
+    /// Gain that scales the tracked peak to full scale.
+    fn gain(&self) -> f32 {
+        1. / self.peak
+    }
+}
+
+/// Vertical cursors placed on the waveform, by column, to read out
+/// its samples. A second cursor measures the distance to the first.
+struct Measure {
+    cursors: Vec<usize>,
+    active: usize,
+    num_columns: usize,
+}
+
+impl Default for Measure {
+    fn default() -> Self {
+        Self {
+            cursors: vec![0],
+            active: 0,
+            num_columns: 1,
+        }
+    }
+}
+
+impl Measure {
+    fn move_active(&mut self, amount: isize) {
+        let column = &mut self.cursors[self.active];
+        *column = column
+            .saturating_add_signed(amount)
+            .min(self.num_columns.saturating_sub(1));
+    }
+
+    /// Add a second cursor where the first one is, or switch between both.
+    fn next_cursor(&mut self) {
+        if self.cursors.len() < 2 {
+            self.cursors.push(self.cursors[self.active]);
+        }
+        self.active = (self.active + 1) % self.cursors.len();
+    }
+
+    /// Keep the cursors on the drawn waveform.
+    fn clamp(&mut self, num_columns: usize) {
+        self.num_columns = num_columns;
+        let last = num_columns.saturating_sub(1);
+        self.cursors
+            .iter_mut()
+            .for_each(|column| *column = (*column).min(last));
+    }
+
+    /// Read out a channel of the drawn audio, the top lane when stacked.
+    fn describe(
+        &self,
+        drawn: &AudioBuffer,
+        channel: usize,
+        downsample: usize,
+        num_columns: usize,
+        sample_rate: usize,
+    ) -> String {
+        let samples: Vec<f32> = drawn
+            .data
+            .iter()
+            .skip(channel)
+            .step_by(drawn.num_channels.max(1) as usize)
+            .copied()
+            .collect();
+        let readings: Vec<_> = self
+            .cursors
+            .iter()
+            .filter_map(|&column| {
+                Reading::at(&samples, column, downsample, num_columns, sample_rate)
+            })
+            .collect();
+
+        match readings.as_slice() {
+            [] => crate::title!("cursor : -").to_owned(),
+            [reading] => crate::title!(
+                "cursor : {:.2} ms {:.3} ({:.1} dB)",
+                reading.time_ms,
+                reading.amplitude,
+                reading.db()
+            ),
+            [first, second, ..] => {
+                let delta = first.delta(second);
+                crate::title!(
+                    "cursors : Δ {:.2} ms Δ {:.3}",
+                    delta.time_ms,
+                    delta.amplitude
+                )
+            }
+        }
+    }
+}
+
+/// Amplitude of the waveform under a cursor and how long ago it was captured.
+#[derive(Debug, PartialEq, Clone, Copy)]
+struct Reading {
+    time_ms: f32,
+    amplitude: f32,
+}
+
+impl Reading {
+    fn at(
+        samples: &[f32],
+        column: usize,
+        downsample: usize,
+        num_columns: usize,
+        sample_rate: usize,
+    ) -> Option<Self> {
+        let index = widgets::scope::sample_at_column(column, downsample, num_columns)?;
+        Some(Self {
+            time_ms: (column * downsample) as f32 * 1000. / sample_rate as f32,
+            amplitude: *samples.get(index)?,
+        })
+    }
+
+    fn db(&self) -> f32 {
+        20. * self.amplitude.abs().log10()
+    }
+
+    /// Time and amplitude from this reading to another one.
+    fn delta(&self, other: &Self) -> Self {
+        Self {
+            time_ms: other.time_ms - self.time_ms,
+            amplitude: other.amplitude - self.amplitude,
+        }
+    }
+}
+
+/// Counts clip events and remembers when the last one happened,
+/// so the scope can flash for a moment after each of them.
+struct ClipDetector {
+    threshold: f32,
+    count: usize,
+    last_clip: Option<std::time::Instant>,
+}
+
+impl Default for ClipDetector {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_CLIP_THRESHOLD,
+            count: 0,
+            last_clip: None,
+        }
+    }
+}
+
+impl ClipDetector {
+    const FLASH_DURATION: std::time::Duration = std::time::Duration::from_millis(250);
+
+    /// Channels with at least one sample above the threshold. Each
+    /// of them counts as a single clip event for these samples.
+    fn detect(&mut self, samples: &[f32], num_channels: usize) -> Vec<usize> {
+        let num_channels = num_channels.max(1);
+        let clipped: Vec<usize> = (0..num_channels)
+            .filter(|&channel| {
+                samples
+                    .iter()
+                    .skip(channel)
+                    .step_by(num_channels)
+                    .any(|sample| sample.abs() > self.threshold)
+            })
+            .collect();
+
+        if !clipped.is_empty() {
+            self.count += clipped.len();
+            self.last_clip = Some(std::time::Instant::now());
+        }
+
+        clipped
+    }
+
+    fn is_flashing(&self) -> bool {
+        self.last_clip
+            .is_some_and(|last_clip| last_clip.elapsed() < Self::FLASH_DURATION)
+    }
+}
+
+/// Single shot capture: waits for the trigger, latches exactly
+/// one window of audio and holds it until it is re-armed.
+struct OneShot {
+    trigger: ScopeTrigger,
+    num_frames: usize,
+    previous: Option<f32>,
+    window: Vec<f32>,
+    captured: Option<AudioBuffer>,
+}
+
+impl Default for OneShot {
+    fn default() -> Self {
+        Self {
+            trigger: Self::DEFAULT_TRIGGER,
+            num_frames: 1024,
+            previous: None,
+            window: vec![],
+            captured: None,
+        }
+    }
+}
+
+impl OneShot {
+    /// Used when no script has set a trigger.
+    const DEFAULT_TRIGGER: ScopeTrigger = ScopeTrigger {
+        level: 0.,
+        edge: dsp::TriggerEdge::Rising,
+    };
+
+    fn feed(&mut self, samples: &[f32], num_channels: usize) {
+        if self.captured.is_some() {
+            return;
+        }
+
+        let num_channels = num_channels.max(1);
+        for frame in samples.chunks_exact(num_channels) {
+            if self.window.is_empty() {
+                let previous = self.previous.replace(frame[0]);
+                let ScopeTrigger { level, edge } = self.trigger;
+                let crossed = previous.is_some_and(|previous| {
+                    dsp::find_trigger(&[previous, frame[0]], level, edge).is_some()
+                });
+
+                if !crossed {
+                    continue;
+                }
+            }
+
+            self.window.extend_from_slice(frame);
+            if self.window.len() >= self.num_frames * num_channels {
+                self.captured = Some(AudioBuffer {
+                    data: std::mem::take(&mut self.window),
+                    num_channels: num_channels as u32,
+                });
+                return;
+            }
+        }
+    }
+}
+
+/// Identity of the audio drawn, from everything it is derived from.
+fn trace_source(inputs: &impl std::hash::Hash) -> u64 {
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    inputs.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Start the buffer at the first trigger crossing of its first channel.
+fn triggered(
+    buffer: &AudioBuffer,
+    trigger: Option<ScopeTrigger>,
+) -> std::borrow::Cow<'_, AudioBuffer> {
+    let num_channels = buffer.num_channels.max(1) as usize;
+    let start = trigger.and_then(|trigger| {
+        let first_channel: Vec<f32> = buffer.data.iter().step_by(num_channels).copied().collect();
+        dsp::find_trigger(&first_channel, trigger.level, trigger.edge)
+    });
+
+    match start {
+        Some(frame) => std::borrow::Cow::Owned(AudioBuffer {
+            data: buffer.data[frame * num_channels..].to_vec(),
+            num_channels: buffer.num_channels,
+        }),
+        None => std::borrow::Cow::Borrowed(buffer),
+    }
+}
+
+/// Frame at which a spectrum window anchored to the trigger starts, the
+/// latest crossing of the first channel followed by a full window of
+/// `fft_size` frames, or `None` to analyse the latest frames instead.
+fn anchored_spectrum_start(
+    buffer: &AudioBuffer,
+    trigger: Option<ScopeTrigger>,
+    fft_size: usize,
+) -> Option<usize> {
+    let ScopeTrigger { level, edge } = trigger?;
+    let num_channels = buffer.num_channels.max(1) as usize;
+    let first_channel: Vec<f32> = buffer.data.iter().step_by(num_channels).copied().collect();
+    let last_start = first_channel.len().checked_sub(fft_size)?;
+
+    (1..=last_start)
+        .rev()
+        .find(|&frame| dsp::find_trigger(&first_channel[frame - 1..=frame], level, edge).is_some())
+}
+
+/// The most recent frames of the buffer, up to `num_frames`.
+fn last_frames(buffer: &AudioBuffer, num_frames: usize) -> AudioBuffer {
+    let num_channels = buffer.num_channels.max(1) as usize;
+    let num_samples = (num_frames * num_channels).min(buffer.data.len());
+    AudioBuffer {
+        data: buffer.data[buffer.data.len() - num_samples..].to_vec(),
+        num_channels: buffer.num_channels,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn seeking_pans_a_clamped_window_across_the_history() {
+        let mut history = History::default();
+        history.push([1., 2., 3., 4.].into_iter(), 1, 3);
+        let live = AudioBuffer::from_deinterleaved(&[[5., 6., 7.]]);
+
+        let mut offset = 0;
+        assert_eq!(history.window(&live, &mut offset, 2).data, [6., 7.]);
+
+        offset = 2;
+        assert_eq!(history.window(&live, &mut offset, 2).data, [4., 5.]);
+
+        // only the latest frames of the history are kept
+        offset = 100;
+        assert_eq!(history.window(&live, &mut offset, 2).data, [2., 3.]);
+        assert_eq!(offset, 4);
+    }
+
+    #[test]
+    fn resuming_snaps_back_to_the_live_audio() {
+        let mut ui = Ui {
+            scope_width: 4,
+            downsample: 8,
+            ..Default::default()
+        };
+
+        ui.on_keypress(KeyCode::Left.into());
+        assert_eq!(ui.seek, None);
+
+        ui.set_paused(true);
+        ui.on_keypress(KeyCode::Left.into());
+        ui.on_keypress(KeyCode::Left.into());
+        ui.on_keypress(KeyCode::Right.into());
+        assert_eq!(ui.seek, Some(8));
+
+        ui.set_paused(false);
+        assert_eq!(ui.seek, None);
+    }
+
+    #[test]
+    fn frozen_snapshot_is_independent_of_the_live_buffer() {
+        let mut ui = Ui::default();
+        let mut live = AudioBuffer::from_deinterleaved(&[[0.1, 0.2, 0.3]]);
+        let expected = live.clone();
+
+        ui.on_keypress(KeyCode::Char('f').into());
+        assert_eq!(*ui.scope_buffer(&live), expected);
+
+        live.data.iter_mut().for_each(|sample| *sample = 1.);
+        live.data.push(1.);
+        assert_eq!(*ui.scope_buffer(&live), expected);
+
+        ui.on_keypress(KeyCode::Char('f').into());
+        assert_eq!(*ui.scope_buffer(&live), live);
+    }
+
+    #[test]
+    fn one_shot_latches_a_single_window_after_the_trigger() {
+        let mut ui = Ui::default();
+        ui.on_keypress(KeyCode::Char('A').into());
+        let one_shot = ui.one_shot.as_mut().unwrap();
+        one_shot.num_frames = 3;
+        one_shot.trigger = ScopeTrigger {
+            level: 0.5,
+            edge: dsp::TriggerEdge::Rising,
+        };
+
+        let live = AudioBuffer::from_deinterleaved(&[[0.0, 0.2], [1.0, 2.0]]);
+        ui.capture_one_shot(&live.data, 2);
+        assert_eq!(*ui.scope_buffer(&live), live);
+
+        let crossing = AudioBuffer::from_deinterleaved(&[[0.6, 0.7], [3.0, 4.0]]);
+        ui.capture_one_shot(&crossing.data, 2);
+        let next = AudioBuffer::from_deinterleaved(&[[0.1, 0.9, 0.1, 0.9], [5.0, 6.0, 7.0, 8.0]]);
+        ui.capture_one_shot(&next.data, 2);
+
+        let expected = AudioBuffer::from_deinterleaved(&[[0.6, 0.7, 0.1], [3.0, 4.0, 5.0]]);
+        assert_eq!(*ui.scope_buffer(&next), expected);
+
+        ui.capture_one_shot(&crossing.data, 2);
+        assert_eq!(*ui.scope_buffer(&next), expected);
+
+        ui.on_keypress(KeyCode::Char('A').into());
+        assert_eq!(*ui.scope_buffer(&next), next);
+    }
+
+    #[test]
+    fn waveform_starts_at_the_trigger_crossing() {
+        let buffer =
+            AudioBuffer::from_deinterleaved(&[[0.0, 0.6, -0.2, 0.8], [1.0, 2.0, 3.0, 4.0]]);
+        let trigger = ScopeTrigger {
+            level: 0.5,
+            edge: dsp::TriggerEdge::Falling,
+        };
+
+        assert_eq!(*triggered(&buffer, None), buffer);
+        assert_eq!(
+            *triggered(&buffer, Some(trigger)),
+            AudioBuffer::from_deinterleaved(&[[-0.2, 0.8], [3.0, 4.0]])
+        );
+    }
+
+    #[test]
+    fn anchored_spectrum_window_starts_at_the_latest_trigger_with_a_full_window() {
+        let buffer =
+            AudioBuffer::from_deinterleaved(&[[0.0, 0.6, 0.0, 0.6, 0.0, 0.0, 0.6, 0.0], [0.0; 8]]);
+        let trigger = ScopeTrigger {
+            level: 0.5,
+            edge: dsp::TriggerEdge::Rising,
+        };
+
+        assert_eq!(anchored_spectrum_start(&buffer, Some(trigger), 4), Some(3));
+        assert_eq!(anchored_spectrum_start(&buffer, Some(trigger), 2), Some(6));
+        assert_eq!(anchored_spectrum_start(&buffer, Some(trigger), 8), None);
+        assert_eq!(anchored_spectrum_start(&buffer, None, 4), None);
+    }
+
+    #[test]
+    fn auto_normalize_follows_the_peak_with_a_slow_release() {
+        let mut follower = PeakFollower::default();
+
+        follower.update(&[0.1, -0.5, 0.25]);
+        assert_eq!(follower.gain(), 2.);
+
+        follower.update(&[0.1]);
+        assert_eq!(follower.peak, 0.5 * PeakFollower::RELEASE);
+
+        for _ in 0..1_000 {
+            follower.update(&[0.]);
+        }
+        assert_eq!(follower.gain(), 1. / PeakFollower::MIN_PEAK);
+    }
+
+    #[test]
+    fn samples_above_the_threshold_register_a_clip() {
+        let mut clips = ClipDetector {
+            threshold: 0.9,
+            ..Default::default()
+        };
+        let buffer = AudioBuffer::from_deinterleaved(&[[0.1, 0.5, -0.2], [0.3, -0.95, 0.2]]);
+
+        assert_eq!(clips.detect(&buffer.data, 2), [1]);
+        assert_eq!(clips.count, 1);
+        assert!(clips.is_flashing());
+
+        assert!(clips.detect(&[0.5, -0.5], 2).is_empty());
+        assert_eq!(clips.count, 1);
+    }
+
+    #[test]
+    fn cursors_read_out_samples_and_their_distance() {
+        let samples = [0.0, 0.5, 0.0, -0.25, 0.0, 1.0];
+        let (downsample, num_columns) = (2, 3);
+
+        let newest = Reading::at(
+            &samples,
+            0,
+            downsample,
+            num_columns,
+            Ui::DEFAULT_SAMPLE_RATE,
+        )
+        .unwrap();
+        assert_eq!(newest.amplitude, 1.);
+        assert_eq!(newest.time_ms, 0.);
+        assert_eq!(newest.db(), 0.);
+
+        let oldest = Reading::at(
+            &samples,
+            2,
+            downsample,
+            num_columns,
+            Ui::DEFAULT_SAMPLE_RATE,
+        )
+        .unwrap();
+        assert_eq!(oldest.amplitude, 0.5);
+        assert_eq!(oldest.time_ms, 4. * 1000. / Ui::DEFAULT_SAMPLE_RATE as f32);
+        assert!((oldest.db() + 6.0206).abs() < 1e-3);
+        assert!(Reading::at(
+            &samples,
+            3,
+            downsample,
+            num_columns,
+            Ui::DEFAULT_SAMPLE_RATE
+        )
+        .is_none());
+
+        let delta = newest.delta(&oldest);
+        assert_eq!(delta.amplitude, -0.5);
+        assert_eq!(delta.time_ms, oldest.time_ms);
+    }
+
+    #[test]
+    fn cursors_read_out_the_channel_of_a_stereo_buffer() {
+        // frames of (left, right), the right channel an octave quieter
+        let drawn = AudioBuffer {
+            data: vec![
+                0.0, 0.0, 0.5, 0.25, 0.0, 0.0, -0.25, -0.125, 0.0, 0.0, 1.0, 0.5,
+            ],
+            num_channels: 2,
+        };
+        let downsample = 2;
+        let num_columns = widgets::scope::num_columns(drawn.num_frames(), downsample, 80);
+        assert_eq!(num_columns, 3);
+
+        let mut measure = Measure::default();
+        measure.clamp(num_columns);
+        let sample_rate = Ui::DEFAULT_SAMPLE_RATE;
+
+        let left = measure.describe(&drawn, 0, downsample, num_columns, sample_rate);
+        assert!(left.contains("0.00 ms 1.000 (0.0 dB)"), "{left}");
+        let right = measure.describe(&drawn, 1, downsample, num_columns, sample_rate);
+        assert!(right.contains("0.00 ms 0.500 (-6.0 dB)"), "{right}");
+
+        measure.move_active(2);
+        let oldest = measure.describe(&drawn, 1, downsample, num_columns, sample_rate);
+        assert!(oldest.contains(" 0.250 (-12.0 dB)"), "{oldest}");
+
+        measure.move_active(1);
+        assert_eq!(measure.cursors, [2]);
+    }
+
+    #[test]
+    fn measure_cursors_stay_on_the_waveform() {
+        let mut ui = Ui::default();
+        ui.on_keypress(KeyCode::Char('c').into());
+
+        let measure = ui.measure.as_mut().unwrap();
+        measure.clamp(3);
+        for _ in 0..5 {
+            ui.on_keypress(KeyCode::Right.into());
+        }
+        assert_eq!(ui.measure.as_ref().unwrap().cursors, [2]);
+
+        ui.on_keypress(KeyCode::Tab.into());
+        ui.on_keypress(KeyCode::Left.into());
+        assert_eq!(ui.measure.as_ref().unwrap().cursors, [2, 1]);
+
+        ui.on_keypress(KeyCode::Tab.into());
+        ui.on_keypress(KeyCode::Char('h').into());
+        assert_eq!(ui.measure.as_ref().unwrap().cursors, [1, 1]);
+
+        ui.on_keypress(KeyCode::Char('c').into());
+        assert!(ui.measure.is_none());
+    }
+
+    #[test]
+    fn mono_sum_combines_all_channels() {
+        let buffer = AudioBuffer::from_deinterleaved(&[[0.5, 0.25], [0.5, -0.25], [1.0, 0.0]]);
+
+        assert_eq!(*MonoSum::Off.apply(&buffer), buffer);
+        assert_eq!(
+            *MonoSum::Sum.apply(&buffer),
+            AudioBuffer::from_deinterleaved(&[[2.0, 0.0]])
+        );
+
+        let average = MonoSum::Average.apply(&buffer);
+        assert_eq!(average.num_channels, 1);
+        assert!((average.data[0] - 2. / 3.).abs() < f32::EPSILON);
+        assert_eq!(average.data[1], 0.);
+    }
+
+    #[test]
+    fn key_hints_follow_the_view_and_come_from_the_usage() {
+        let mut ui = Ui::default();
+        assert_eq!(ui.key_context(), KeyContext::Waveform);
+        let waveform = key_hints(ui.key_context());
+        assert!(waveform.contains(&("c", "toggle measure cursors")));
+        assert!(!waveform.iter().any(|(keys, _)| *keys == "z"));
+
+        ui.scope_mode = ScopeMode::Spectrum;
+        let spectrum = key_hints(ui.key_context());
+        assert!(spectrum.contains(&("z", "halve the spectrum FFT size")));
+        assert!(!spectrum.iter().any(|(keys, _)| *keys == "c"));
+
+        ui.scope_mode = ScopeMode::Waveform;
+        ui.on_keypress(KeyCode::Char('c').into());
+        let measure = key_hints(ui.key_context());
+        assert!(measure.contains(&("<TAB>", "add or switch cursor")));
+        assert_eq!(measure[1], ("<LEFT>, h", "move cursor when measuring"));
+
+        for context in [
+            KeyContext::Waveform,
+            KeyContext::Spectrum,
+            KeyContext::Vector,
+            KeyContext::Sparkline,
+            KeyContext::Measure,
+            KeyContext::Seek,
+        ] {
+            assert_eq!(
+                key_hints(context).len(),
+                context.keys().len(),
+                "{context:?}"
+            );
         }
     }
 }
@@ -74,9 +74,14 @@ pub fn run(
     common_opts: crate::CommonOptions,
 ) -> anyhow::Result<()> {
     if let Some(log_file) = opts.log.or_else(|| crate::locations::log_file("derlink")) {
-        crate::logger::start("derlink", log_file, common_opts.verbose)?;
+        crate::logger::start(
+            "derlink",
+            log_file,
+            common_opts.verbose,
+            common_opts.log_format,
+        )?;
     }
 
     let mut app = TerminalApp::default();
-    crate::app::run(terminal, &mut app, opts.fps.max(1.))
+    crate::app::run(terminal, &mut app, opts.fps.max(1.), &common_opts.limit())
 }
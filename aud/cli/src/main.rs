@@ -5,7 +5,7 @@ mod ui;
 mod utils;
 pub use utils::*;
 
-use clap::{Args, CommandFactory, Parser, Subcommand};
+use clap::{Args, CommandFactory, FromArgMatches, Parser, Subcommand};
 use std::io::Write;
 use utils::terminal::with_terminal;
 
@@ -24,6 +24,44 @@ pub struct CommonOptions {
     /// Log verbosity level
     #[arg(long, default_value_t = false)]
     verbose: bool,
+
+    /// Render without colors. Also disabled when
+    /// `NO_COLOR` is set or `TERM` is dumb
+    #[arg(long, global = true, default_value_t = false)]
+    no_color: bool,
+
+    /// Format of the log file records
+    #[arg(long, global = true, value_enum, default_value_t = logger::LogFormat::Text)]
+    log_format: logger::LogFormat,
+
+    /// Exit after rendering this many frames, e.g. for automated captures
+    #[arg(long, global = true)]
+    once: Option<usize>,
+
+    /// Exit after running for this long, e.g. "10s"
+    #[arg(long, global = true, value_parser = humantime::parse_duration)]
+    duration: Option<std::time::Duration>,
+
+    /// Write the last frame to this file as text when exiting after
+    /// `--once` or `--duration`
+    #[arg(long, global = true)]
+    snapshot: Option<std::path::PathBuf>,
+
+    /// Exit after this many seconds without audio, MIDI or key presses,
+    /// so that a forgotten session does not hold on to the devices
+    #[arg(long, global = true, value_name = "SECS")]
+    idle_timeout: Option<u64>,
+}
+
+impl CommonOptions {
+    fn limit(&self) -> app::Limit {
+        app::Limit {
+            frames: self.once,
+            duration: self.duration,
+            snapshot: self.snapshot.clone(),
+            idle_timeout: self.idle_timeout.map(std::time::Duration::from_secs),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
@@ -63,12 +101,28 @@ impl Completions {
 }
 
 fn main() -> anyhow::Result<()> {
-    let args = Cli::parse();
+    let config = config::Config::load()?;
+    let args = config
+        .apply(Cli::command())
+        .try_get_matches()
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| e.exit());
 
     if let Commands::Completions(ref c) = args.command {
         return c.generate();
     }
 
+    let listed_devices = match args.command {
+        Commands::Midimon(ref opts) => midimon::list_devices(opts)?,
+        Commands::Auscope(ref opts) => auscope::list_devices(opts)?,
+        _ => false,
+    };
+    if listed_devices {
+        return Ok(());
+    }
+
+    ui::theme::set_colors_enabled(!args.opts.no_color && ui::theme::detect_color_support());
+
     let app_result = with_terminal(move |term| match args.command {
         Commands::Midimon(opts) => midimon::run(term, opts, args.opts),
         Commands::Derlink(opts) => derlink::run(term, opts, args.opts),
@@ -1,30 +1,136 @@
 mod ui;
 
-use crate::ui::widgets::midi::MidiMessageString;
+use crate::{
+    labels::Labels,
+    session::{self, Session},
+    ui::widgets::midi::{MessageTemplate, MidiMessageString},
+};
 use aud::{
-    controllers::audio_midi::{AppEvent, AudioMidiController},
+    controllers::audio_midi::{AlertSeverity, AppEvent, AudioMidiController},
+    devices::{self, DeviceWatcher},
     lua::imported,
-    midi::HostedMidiReceiver,
+    midi::{
+        FileMidiInput, HostedMidiReceiver, HostedMidiSender, MessageFilter, MidiReceiving,
+        NoteOffForm, ProgramNames, SysExDump,
+    },
 };
 use ratatui::prelude::*;
 
 struct TerminalApp {
     ui: ui::Ui,
     app: AudioMidiController,
+    program_names: ProgramNames,
+    /// Remember the selected port and script for the next run.
+    saves_session: bool,
 }
 
-impl Default for TerminalApp {
-    fn default() -> Self {
-        let midi_in = Box::<HostedMidiReceiver>::default();
-        let app = AudioMidiController::with_midi(midi_in, imported::midimon::API);
+impl TerminalApp {
+    fn new(midi_in: Box<dyn MidiReceiving>, labels: &Labels) -> Self {
+        let mut app = AudioMidiController::with_midi(midi_in, imported::midimon::API);
+        for (port, label) in labels.ports() {
+            app.midi_mut().set_alias(port.to_owned(), label.to_owned());
+        }
+
         let mut ui = ui::Ui::default();
         ui.update_port_names(app.midi().port_names());
-        Self { ui, app }
+        Self {
+            ui,
+            app,
+            program_names: ProgramNames::default(),
+            saves_session: true,
+        }
+    }
+
+    fn update_session(&self, f: impl FnOnce(&mut Session)) {
+        if self.saves_session {
+            Session::update(f);
+        }
+    }
+
+    /// Reselect the port and script of the last run, if they are still available.
+    fn restore_session(&mut self, session: &Session) {
+        let ports = self.app.midi().port_names().to_vec();
+        if let Some(index) = session::find_by_name(&ports, session.midi_port.as_deref()) {
+            match self.app.midi_mut().connect_to_input_by_index(index) {
+                Ok(()) => self.ui.mark_port_selected(index),
+                Err(e) => log::warn!("failed to reconnect to {} : {e}", ports[index]),
+            }
+        }
+
+        let Some(script) = session.script.as_ref().filter(|script| script.is_file()) else {
+            return;
+        };
+
+        if let Err(e) = self.load_script(script) {
+            log::warn!("failed to reload {} : {e}", script.display());
+        }
+    }
+
+    /// Show the connected port as selected, wherever it is in the list.
+    fn mark_connected_port(&mut self) {
+        let midi = self.app.midi();
+        if let Some(index) = session::find_by_name(midi.port_names(), midi.selected_port_name()) {
+            self.ui.mark_port_selected(index);
+        }
+    }
+
+    /// Close the connection to the selected port, which
+    /// is then not reconnected to on the next run.
+    fn disconnect(&mut self) {
+        self.app.midi_mut().disconnect();
+        self.ui.clear_port_selection();
+        self.update_session(|session| session.midi_port = None);
+    }
+
+    /// Load a script, showing it as selected if it is in the script directory.
+    fn load_script(&mut self, script: &std::path::Path) -> anyhow::Result<()> {
+        self.app.load_script(script)?;
+
+        if let Some(index) = self.ui.scripts().position(script) {
+            self.ui.mark_script_selected(index);
+        }
+
+        Ok(())
+    }
+}
+
+/// Use the user's program names if they have any, General MIDI names otherwise.
+fn load_program_names() -> ProgramNames {
+    let Some(path) = crate::locations::program_names().filter(|path| path.exists()) else {
+        return ProgramNames::default();
+    };
+
+    ProgramNames::from_file(&path).unwrap_or_else(|e| {
+        log::error!("failed to load program names from {} : {e}", path.display());
+        ProgramNames::default()
+    })
+}
+
+/// Use the template passed as an option, or the user's template if they have
+/// one. An invalid template falls back to the default one with a warning.
+fn load_message_template(template: Option<String>) -> (MessageTemplate, Option<String>) {
+    let template = template.or_else(|| {
+        let path = crate::locations::message_format()?;
+        std::fs::read_to_string(path).ok()
+    });
+
+    let Some(template) = template else {
+        return (MessageTemplate::default(), None);
+    };
+
+    match template.trim_end_matches(['\r', '\n']).parse() {
+        Ok(template) => (template, None),
+        Err(e) => {
+            log::warn!("{e}, using the default message template");
+            let warning = format!("{e}\nusing : {}", MessageTemplate::DEFAULT);
+            (MessageTemplate::default(), Some(warning))
+        }
     }
 }
 
 impl crate::app::Base for TerminalApp {
     fn update(&mut self) -> anyhow::Result<crate::app::Flow> {
+        self.app.tick();
         self.app.midi_mut().update();
         self.app.process_engine_events()?;
 
@@ -32,20 +138,39 @@ impl crate::app::Base for TerminalApp {
             return Ok(crate::app::Flow::Exit);
         }
 
+        for request in self.app.take_ui_requests() {
+            self.ui.apply_script_request(request);
+        }
+
         let mut messages: Vec<_> = self
             .app
             .midi_mut()
             .take_messages()
             .iter()
-            .filter_map(|midi| MidiMessageString::new(midi.timestamp, &midi.bytes))
+            .filter_map(|midi| {
+                MidiMessageString::normalized(
+                    midi.timestamp,
+                    &midi.bytes,
+                    &self.program_names,
+                    self.ui.note_off_form(),
+                )
+            })
             .collect();
 
         self.ui.append_messages(&mut messages);
+        self.ui
+            .append_script_messages(self.app.take_script_messages());
+        self.ui.update_params(self.app.params().len());
 
         if self.app.process_file_events()? == AppEvent::ScriptLoaded {
             self.ui.clear_script_cache();
         }
 
+        if self.app.process_device_events()? == AppEvent::DevicesChanged {
+            self.ui.update_port_names(self.app.midi().port_names());
+            self.mark_connected_port();
+        }
+
         Ok(crate::app::Flow::Continue)
     }
 
@@ -60,11 +185,20 @@ impl crate::app::Base for TerminalApp {
             ui::UiEvent::ClearMessages => self.app.midi_mut().clear_messages(),
             ui::UiEvent::Connect(port_index) => {
                 self.app.midi_mut().connect_to_input_by_index(port_index)?;
+                let port = self.app.midi().selected_port_name().map(str::to_owned);
+                self.update_session(|session| session.midi_port = port);
+            }
+            ui::UiEvent::Disconnect => self.disconnect(),
+            ui::UiEvent::Panic => {
+                if let Err(e) = self.app.panic() {
+                    self.ui.show_alert_message(&e.to_string());
+                }
             }
+            ui::UiEvent::AdjustParam { index, steps } => self.app.adjust_param(index, steps),
             ui::UiEvent::LoadScript(script_index) => {
-                if let Some(script_name) = &self.ui.scripts().get(script_index) {
-                    let script = self.ui.script_dir().unwrap().join(script_name);
-                    self.app.load_script(script)?;
+                if let Some(script) = self.ui.scripts().path(script_index) {
+                    self.app.load_script(&script)?;
+                    self.update_session(|session| session.script = Some(script));
                 };
             }
         }
@@ -73,12 +207,21 @@ impl crate::app::Base for TerminalApp {
     }
 
     fn render(&mut self, f: &mut Frame) {
+        let severity = self.app.alert_severity();
         if let Some(alert) = self.app.take_alert() {
-            self.ui.show_alert_message(&alert);
+            let alert = crate::ui::timestamped_alert(&alert);
+            match severity {
+                Some(AlertSeverity::Error) => self.ui.show_alert_message(&alert),
+                _ => self.ui.show_expiring_alert_message(&alert),
+            }
         }
 
         self.ui.render(f, &self.app);
     }
+
+    fn last_input(&self) -> Option<std::time::Instant> {
+        self.app.last_input()
+    }
 }
 
 #[derive(Debug, clap::Parser)]
@@ -95,6 +238,129 @@ pub struct Options {
     /// Path to scripts to view or default script to load
     #[arg(long)]
     script: Option<std::path::PathBuf>,
+
+    /// Milliseconds a script callback may run before it is interrupted
+    #[arg(long, default_value_t = aud::lua::DEFAULT_CALLBACK_TIMEOUT.as_millis() as u64)]
+    script_timeout: u64,
+
+    /// Milliseconds an alert stays up, errors stay until dismissed
+    #[arg(long, default_value_t = aud::controllers::audio_midi::DEFAULT_ALERT_TIMEOUT.as_millis() as u64)]
+    alert_timeout: u64,
+
+    /// Distinct logs or alerts a script may emit per second, repeats are coalesced
+    #[arg(long, default_value_t = aud::controllers::audio_midi::DEFAULT_SCRIPT_MESSAGE_RATE)]
+    script_message_rate: usize,
+
+    /// Connect to this MIDI port on startup, by name or index
+    #[arg(long)]
+    port: Option<String>,
+
+    /// Connect on startup to the first MIDI port matching this glob, e.g.
+    /// "Launchpad Pro*", or this regex when wrapped in slashes, e.g. "/keystep/"
+    #[arg(long, conflicts_with = "port")]
+    port_pattern: Option<String>,
+
+    /// Send the SysEx messages of this `.syx` file to the
+    /// connected port on startup, e.g. to configure the device
+    #[arg(long, conflicts_with = "file")]
+    send_sysex: Option<std::path::PathBuf>,
+
+    /// Load this script on startup. Repeated, the scripts are chained in
+    /// order, each one handling the messages forwarded by the one before
+    #[arg(long)]
+    connect_script: Vec<std::path::PathBuf>,
+
+    /// Only show notes within this inclusive range, e.g. "36-60"
+    #[arg(long, value_parser = parse_note_range)]
+    note_range: Option<(u8, u8)>,
+
+    /// Only show Note Ons with at least this velocity
+    #[arg(long)]
+    min_velocity: Option<u8>,
+
+    /// Start with the MIDI stream paused, until resumed with <SPACE>
+    #[arg(long, default_value_t = false)]
+    paused: bool,
+
+    /// Hold the position of the messages as new ones arrive
+    /// instead of following the latest, toggled with `f`
+    #[arg(long, default_value_t = false)]
+    hold: bool,
+
+    /// Hide every message that is not a note
+    #[arg(long, default_value_t = false)]
+    notes_only: bool,
+
+    /// Write every received message that passes the filter to the log
+    #[arg(long, default_value_t = false)]
+    log_midi: bool,
+
+    /// Template of the message lines, e.g. "{time} {ch} {type} {d1} {d2}".
+    /// Fields are time, type, ch, d1, d2, data and hex. Defaults to
+    /// the template in ~/.aud/midi/format.txt if there is one
+    #[arg(long)]
+    format: Option<String>,
+
+    /// Play this Standard MIDI File instead of listening to a port
+    #[arg(long)]
+    file: Option<std::path::PathBuf>,
+
+    /// Speed at which the file is played, 1 being as recorded
+    #[arg(long, default_value_t = 1.)]
+    speed: f32,
+
+    /// Play the file again once it has ended
+    #[arg(long = "loop", default_value_t = false)]
+    looping: bool,
+
+    /// Show Note Offs as they were sent (raw), as Note Offs (note-off)
+    /// or as Note Ons without velocity (note-on), cycled with `n`
+    #[arg(long, default_value = "raw")]
+    note_off: NoteOffForm,
+
+    /// Also send the Note Offs to the script in the form of `--note-off`
+    #[arg(long, default_value_t = false)]
+    normalize_forwarded: bool,
+
+    /// Messages queued between the MIDI port and the monitor, the
+    /// messages received once it is full are dropped and counted
+    #[arg(long, default_value_t = HostedMidiReceiver::DEFAULT_CAPACITY)]
+    midi_queue: usize,
+
+    /// Print the MIDI input ports with their index and exit
+    #[arg(long, default_value_t = false)]
+    list_devices: bool,
+
+    /// Print the listed ports as JSON
+    #[arg(long, requires = "list_devices", default_value_t = false)]
+    json: bool,
+}
+
+fn parse_note_range(range: &str) -> Result<(u8, u8), String> {
+    let Some((low, high)) = range.split_once('-') else {
+        return Err("Invalid note range syntax. Use low-high".into());
+    };
+
+    let parse = |note: &str| match note.trim().parse::<u8>() {
+        Ok(note) if note < 128 => Ok(note),
+        _ => Err(format!("Invalid note number : {note}")),
+    };
+
+    match (parse(low)?, parse(high)?) {
+        (low, high) if low <= high => Ok((low, high)),
+        _ => Err("Invalid note range, low must not exceed high".into()),
+    }
+}
+
+/// Print the ports instead of running the monitor, returns whether it did.
+pub fn list_devices(opts: &Options) -> anyhow::Result<bool> {
+    if !opts.list_devices {
+        return Ok(false);
+    }
+
+    let ports = aud::midi::list_host_midi_ports()?;
+    crate::listing::print(&crate::listing::midi_ports("input", &ports), opts.json);
+    Ok(true)
 }
 
 pub fn run(
@@ -103,10 +369,53 @@ pub fn run(
     common_opts: crate::CommonOptions,
 ) -> anyhow::Result<()> {
     if let Some(log_file) = opts.log.or_else(|| crate::locations::log_file("midimon")) {
-        crate::logger::start("midimon", log_file, common_opts.verbose)?;
+        crate::logger::start(
+            "midimon",
+            log_file,
+            common_opts.verbose,
+            common_opts.log_format,
+        )?;
     }
 
-    let mut app = TerminalApp::default();
+    let midi_in: Box<dyn MidiReceiving> = match opts.file {
+        Some(ref file) => {
+            let mut input = FileMidiInput::open(file)?;
+            input.set_speed(opts.speed);
+            input.set_looping(opts.looping);
+            Box::new(input)
+        }
+        None => Box::new(HostedMidiReceiver::with_capacity(opts.midi_queue)),
+    };
+
+    let mut app = TerminalApp::new(midi_in, &Labels::load());
+    app.program_names = load_program_names();
+    let (template, warning) = load_message_template(opts.format);
+    app.ui.set_message_template(template);
+    app.ui.set_holding_position(opts.hold);
+    app.ui.set_note_off_form(opts.note_off);
+    if opts.normalize_forwarded {
+        app.app.midi_mut().set_forwarded_note_off(opts.note_off);
+    }
+    if let Some(warning) = warning {
+        app.ui.show_alert_message(&warning);
+    }
+
+    app.app
+        .set_script_timeout(std::time::Duration::from_millis(opts.script_timeout));
+    app.app.set_script_message_rate(opts.script_message_rate);
+    let alert_timeout = std::time::Duration::from_millis(opts.alert_timeout);
+    app.app.set_alert_timeout(alert_timeout);
+    app.ui.set_alert_timeout(alert_timeout);
+    app.app
+        .set_module_paths(crate::locations::lua::lib().into_iter().collect());
+    app.app.midi_mut().set_running(!opts.paused);
+    app.app.midi_mut().set_filter(MessageFilter {
+        note_range: opts.note_range,
+        min_velocity: opts.min_velocity,
+        notes_only: opts.notes_only,
+        ..Default::default()
+    });
+    app.app.midi_mut().set_logging_messages(opts.log_midi);
 
     let scripts = opts
         .script
@@ -114,8 +423,92 @@ pub fn run(
 
     if let Some(script) = scripts {
         log::info!("{:#?}", script.canonicalize()?);
-        app.ui.update_script_dir(script)?;
+        app.ui.update_script_dir(script);
+    }
+
+    if opts.file.is_some() {
+        app.app.midi_mut().connect_to_input_by_index(0)?;
+        app.mark_connected_port();
+    } else {
+        // what is given on the command line is used instead of the last run's
+        let mut session = Session::load();
+        if opts.port.is_some() || opts.port_pattern.is_some() {
+            session.midi_port = None;
+        }
+        if !opts.connect_script.is_empty() {
+            session.script = None;
+        }
+        app.restore_session(&session);
+
+        if let Some(port) = opts.port {
+            app.app
+                .midi_mut()
+                .connect_to_input_by_name_or_index(&port)?;
+            app.mark_connected_port();
+        }
+
+        if let Some(pattern) = opts.port_pattern {
+            app.app.midi_mut().connect_to_input_matching(&pattern)?;
+            app.mark_connected_port();
+        }
+
+        app.app.watch_devices(DeviceWatcher::spawn(
+            devices::DEFAULT_POLL_INTERVAL,
+            aud::midi::list_host_midi_ports,
+        ));
+        app.app.set_midi_output(Box::<HostedMidiSender>::default());
     }
 
-    crate::app::run(terminal, &mut app, opts.fps.max(1.))
+    if let Some(file) = opts.send_sysex {
+        let dump = SysExDump::from_file(&file)?;
+        app.app.send_sysex(&dump)?;
+        log::info!(
+            "sent {} SysEx messages from {}",
+            dump.messages().len(),
+            file.display()
+        );
+    }
+
+    match opts.connect_script.as_slice() {
+        [] => {}
+        [script] => app.load_script(script)?,
+        scripts => {
+            app.app.load_script_chain(scripts)?;
+        }
+    }
+
+    crate::app::run(terminal, &mut app, opts.fps.max(1.), &common_opts.limit())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::harness::{self, MockMidiInput};
+    use crossterm::event::KeyCode;
+
+    #[test]
+    fn ports_are_navigated_and_selected_until_quitting() {
+        let midi_in = MockMidiInput::new(&["keys", "pads"], &[&[0x90, 60, 100]]);
+        let mut app = TerminalApp::new(Box::new(midi_in), &Labels::default());
+        app.saves_session = false;
+        app.app.midi_mut().set_running(true);
+
+        // the script selector has the focus first, and messages go through
+        // the script engine's thread so wait for them to come back
+        let keys = [KeyCode::Char('l'), KeyCode::Char('j'), KeyCode::Enter];
+        let frames = harness::run_until(&mut app, 100, 40, keys, |frame| {
+            frame.contains("NoteOn : chan = 0 | key = 60")
+        })
+        .unwrap();
+
+        assert!(frames.text(1).contains("> keys"), "{}", frames.text(1));
+        assert!(frames.text(2).contains("> pads"), "{}", frames.text(2));
+        assert!(frames.text(2).contains("port : -"), "{}", frames.text(2));
+        let connected = frames.last_text();
+        assert!(connected.contains("port : pads"), "{connected}");
+
+        // no frame is rendered after quitting
+        let frames = harness::run(&mut app, 100, 40, [KeyCode::Char('q')]).unwrap();
+        assert_eq!(frames.len(), 1);
+    }
 }
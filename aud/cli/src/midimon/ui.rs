@@ -1,5 +1,9 @@
 use crate::ui::{components, widgets};
-use aud::{controllers::audio_midi::AudioMidiController, files};
+use aud::{
+    controllers::audio_midi::{AudioMidiController, ScriptMessage},
+    lua::traits::api::UiApiEvent,
+    midi::{NoteOffForm, Transport},
+};
 use crossterm::event::KeyCode;
 use ratatui::prelude::*;
 use std::path::Path;
@@ -9,16 +13,70 @@ const USAGE: &str = r#"
          a : display API
          s : display script
          d : display docs
+         c : clear messages
+         x : toggle hex bytes
+         v : cycle CC changes : off / shown / shown without repeats
+         X : disconnect from port
+         P : MIDI panic, silence all notes of the port
+         e : show / hide script log
+         t : show / hide timing clock messages
+         f : follow the latest messages / hold the position as they arrive
+         n : cycle Note Offs : raw / as Note Off / as Note On without velocity
+         T : show / hide the aud log, paged through with <PGUP> and <PGDOWN>
+    [ or ] : select the previous or next script param
+    - or + : decrease or increase the selected param
    <SPACE> : pause / resume
    <UP>, k : scroll up
  <DOWN>, j : scroll down
+  <PGDOWN> : scroll to older messages, or logs when shown
+    <PGUP> : scroll to newer messages, or logs when shown
+    <HOME> : jump to the latest message, or log when shown
+     <END> : jump to the oldest message, or log when shown
  <LEFT>, h : cycle panes left
 <RIGHT>, l : cycle panes right
-     Enter : confirm selection
+     Enter : connect to port or load script
   <ESC>, q : quit or hide popup
      <C-c> : force quit
 "#;
 
+/// How Control Changes show the change of their controller value.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum DiffMode {
+    #[default]
+    Off,
+    /// Show how much and which way each Control Change moved its controller.
+    Deltas,
+    /// Also hide the Control Changes that repeat the current value.
+    Changes,
+}
+
+impl DiffMode {
+    fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Deltas,
+            Self::Deltas => Self::Changes,
+            Self::Changes => Self::Off,
+        }
+    }
+
+    fn name(self) -> Option<&'static str> {
+        match self {
+            Self::Off => None,
+            Self::Deltas => Some("cc changes"),
+            Self::Changes => Some("cc changes only"),
+        }
+    }
+}
+
+/// Status byte of the timing clock pulses.
+const CLOCK: u8 = 0xF8;
+
+/// Number of script messages kept in the script log.
+const MAX_SCRIPT_LOG_LEN: usize = 500;
+
+/// Clearing more messages than this asks for confirmation first.
+const CLEAR_CONFIRMATION_THRESHOLD: usize = 1_000;
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 enum Popup {
     Usage,
@@ -26,6 +84,8 @@ enum Popup {
     Docs,
     Script,
     Alert,
+    ConfirmClear,
+    Log,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
@@ -38,10 +98,34 @@ pub struct Ui {
     popups: components::Popups<Popup>,
     selectors: components::Selectors<Selector>,
     alert_message: Option<String>,
-    script_dir: Option<std::path::PathBuf>,
-    script_names: Vec<String>,
+    /// How long alerts that are not errors stay up.
+    alert_timeout: std::time::Duration,
+    scripts: components::ScriptBrowser,
     cached_script: Option<String>,
     messages: Vec<widgets::midi::MidiMessageString>,
+    message_template: widgets::midi::MessageTemplate,
+    show_hex: bool,
+    /// Show the clock pulses, which otherwise flood the messages.
+    show_clock: bool,
+    diff: DiffMode,
+    controllers: widgets::midi::ControllerValues,
+    /// Number of the most recent messages scrolled past.
+    message_scroll: usize,
+    /// Keep the view in place as new messages arrive, even when
+    /// showing the latest, instead of following them.
+    hold_position: bool,
+    note_off: NoteOffForm,
+    message_page_size: usize,
+    script_log: Vec<ScriptMessage>,
+    /// Number of the most recent script messages scrolled past,
+    /// when the script log is shown.
+    script_log_scroll: Option<usize>,
+    script_log_page_size: usize,
+    /// Lines of the aud log, read while they are shown.
+    log_tail: components::LogTail,
+    /// Params defined by the script, the one to adjust is the selected one.
+    params: components::Selector,
+    num_params: usize,
 }
 
 impl Default for Ui {
@@ -53,13 +137,30 @@ impl Default for Ui {
                 (Popup::Docs, components::PopupKind::Code),
                 (Popup::Script, components::PopupKind::Code),
                 (Popup::Alert, components::PopupKind::Text),
+                (Popup::ConfirmClear, components::PopupKind::Text),
+                (Popup::Log, components::PopupKind::Log),
             ]),
             selectors: components::Selectors::new(&[Selector::Script, Selector::Port]),
             alert_message: None,
-            script_dir: None,
-            script_names: vec![],
+            alert_timeout: aud::controllers::audio_midi::DEFAULT_ALERT_TIMEOUT,
+            scripts: components::ScriptBrowser::default(),
             cached_script: None,
             messages: vec![],
+            message_template: widgets::midi::MessageTemplate::default(),
+            show_hex: false,
+            show_clock: false,
+            diff: DiffMode::default(),
+            controllers: widgets::midi::ControllerValues::default(),
+            message_scroll: 0,
+            hold_position: false,
+            note_off: NoteOffForm::Raw,
+            message_page_size: 1,
+            script_log: vec![],
+            script_log_scroll: None,
+            script_log_page_size: 1,
+            log_tail: components::LogTail::active(),
+            params: components::Selector::default(),
+            num_params: 0,
         }
     }
 }
@@ -69,49 +170,199 @@ pub enum UiEvent {
     ToggleRunningState,
     ClearMessages,
     Connect(usize),
+    Disconnect,
+    Panic,
     LoadScript(usize),
+    AdjustParam { index: usize, steps: i32 },
     Exit,
 }
 
 impl Ui {
-    pub fn scripts(&self) -> &[String] {
-        self.script_names.as_slice()
-    }
-
-    pub fn script_dir(&self) -> Option<&std::path::PathBuf> {
-        self.script_dir.as_ref()
+    pub fn scripts(&self) -> &components::ScriptBrowser {
+        &self.scripts
     }
 
     pub fn clear_script_cache(&mut self) {
         self.cached_script = None;
     }
 
+    pub fn set_message_template(&mut self, template: widgets::midi::MessageTemplate) {
+        self.message_template = template;
+    }
+
     pub fn append_messages(&mut self, messages: &mut Vec<widgets::midi::MidiMessageString>) {
+        if !self.show_clock {
+            messages.retain(|msg| msg.bytes != [CLOCK]);
+        }
+
+        // values are tracked even when not shown, to be right as soon as they are
+        for msg in messages.iter_mut() {
+            let delta = self.controllers.observe(&msg.bytes);
+            msg.delta = delta.filter(|_| self.diff != DiffMode::Off);
+        }
+
+        if self.diff == DiffMode::Changes {
+            messages.retain(|msg| msg.delta != Some(0));
+        }
+
+        // keep the scrolled view in place while new messages arrive
+        if self.message_scroll > 0 || self.hold_position {
+            self.message_scroll += messages.len();
+        }
         self.messages.append(messages);
     }
 
+    /// Keep the latest script messages, up to the size of the log.
+    pub fn append_script_messages(&mut self, messages: Vec<ScriptMessage>) {
+        if let Some(ref mut scroll) = self.script_log_scroll {
+            if *scroll > 0 {
+                *scroll += messages.len();
+            }
+        }
+
+        self.script_log.extend(messages);
+        let num_overflowing = self.script_log.len().saturating_sub(MAX_SCRIPT_LOG_LEN);
+        self.script_log.drain(..num_overflowing);
+    }
+
+    pub fn set_holding_position(&mut self, hold_position: bool) {
+        self.hold_position = hold_position;
+    }
+
+    /// Form in which Note Offs are displayed.
+    pub fn note_off_form(&self) -> NoteOffForm {
+        self.note_off
+    }
+
+    pub fn set_note_off_form(&mut self, form: NoteOffForm) {
+        self.note_off = form;
+    }
+
+    fn clear_messages(&mut self) {
+        self.messages.clear();
+        self.controllers.clear();
+        self.message_scroll = 0;
+    }
+
+    /// Apply what a script requested through its `ui` table.
+    pub fn apply_script_request(&mut self, request: UiApiEvent) {
+        match request {
+            UiApiEvent::Clear => self.clear_messages(),
+            UiApiEvent::Reset => {
+                if self.popups.is_visible(Popup::Alert) {
+                    self.popups.hide();
+                }
+                self.alert_message = None;
+                self.message_scroll = 0;
+                if self.script_log_scroll.is_some() {
+                    self.script_log_scroll = Some(0);
+                }
+            }
+        }
+    }
+
+    fn toggle_script_log(&mut self) {
+        self.script_log_scroll = match self.script_log_scroll {
+            Some(_) => None,
+            None => Some(0),
+        };
+    }
+
+    /// Scroll the script log when it is shown, the messages otherwise.
+    fn scroll_to(&mut self, scroll: impl Fn(usize, usize, usize) -> usize) {
+        match self.script_log_scroll {
+            Some(current) => {
+                let len = self.script_log.len();
+                let scroll = scroll(current, self.script_log_page_size, len);
+                self.script_log_scroll = Some(scroll.min(len.saturating_sub(1)));
+            }
+            None => self.scroll_messages_to(scroll(
+                self.message_scroll,
+                self.message_page_size,
+                self.messages.len(),
+            )),
+        }
+    }
+
+    fn scroll_messages_to(&mut self, scroll: usize) {
+        self.message_scroll = scroll.min(self.messages.len().saturating_sub(1));
+    }
+
+    /// Follow the number of params defined by the script,
+    /// selecting the first one when they are redefined.
+    pub fn update_params(&mut self, num_params: usize) {
+        if num_params == self.num_params {
+            return;
+        }
+
+        self.num_params = num_params;
+        self.params = components::Selector::with_len(num_params);
+        self.params.confirm_selection();
+    }
+
+    fn select_param(&mut self, select: impl Fn(&mut components::Selector)) {
+        select(&mut self.params);
+        self.params.confirm_selection();
+    }
+
+    fn adjust_param(&self, steps: i32) -> UiEvent {
+        match self.params.selected() {
+            Some(index) => UiEvent::AdjustParam { index, steps },
+            None => UiEvent::Continue,
+        }
+    }
+
     pub fn update_port_names(&mut self, port_names: &[impl AsRef<str>]) {
         if let Some(sel) = self.selectors.get_mut(Selector::Port) {
             *sel = components::Selector::with_len(port_names.len());
         }
     }
 
+    /// Show a port as selected without going through the selector.
+    pub fn mark_port_selected(&mut self, index: usize) {
+        mark_selected(&mut self.selectors, Selector::Port, index);
+    }
+
+    /// Show that no port is connected anymore.
+    pub fn clear_port_selection(&mut self) {
+        if let Some(ports) = self.selectors.get_mut(Selector::Port) {
+            ports.clear_selection();
+        }
+    }
+
+    /// Show a script as selected without going through the selector.
+    pub fn mark_script_selected(&mut self, index: usize) {
+        mark_selected(&mut self.selectors, Selector::Script, index);
+    }
+
     pub fn show_alert_message(&mut self, alert_message: &str) {
         self.popups.show(Popup::Alert);
         self.alert_message = Some(alert_message.into());
     }
 
-    pub fn update_script_dir(&mut self, dir: impl AsRef<Path>) -> anyhow::Result<()> {
-        let dir = dir.as_ref();
-        self.script_names = files::list_with_extension(dir, "lua")?;
-        self.script_dir = Some(dir.into());
+    /// Show an alert that is dismissed once the alert timeout passes.
+    pub fn show_expiring_alert_message(&mut self, alert_message: &str) {
+        let deadline = std::time::Instant::now() + self.alert_timeout;
+        self.popups.show_until(Popup::Alert, deadline);
+        self.alert_message = Some(alert_message.into());
+    }
+
+    pub fn set_alert_timeout(&mut self, timeout: std::time::Duration) {
+        self.alert_timeout = timeout;
+    }
+
+    pub fn update_script_dir(&mut self, dir: impl AsRef<Path>) {
+        self.scripts.update_dir(dir);
         if let Some(sel) = self.selectors.get_mut(Selector::Script) {
-            *sel = components::Selector::with_len(self.script_names.len());
+            *sel = components::Selector::with_len(self.scripts.names().len());
         }
-        Ok(())
     }
 
     pub fn handle_keypress(&mut self, key: crossterm::event::KeyEvent) -> anyhow::Result<UiEvent> {
+        if self.popups.is_visible(Popup::Log) && self.log_tail.on_keypress(key.code) {
+            return Ok(UiEvent::Continue);
+        }
+
         match key.code {
             KeyCode::Char('?') => self.popups.toggle_visible(Popup::Usage),
             KeyCode::Char('a') => self.popups.toggle_visible(Popup::Api),
@@ -124,8 +375,40 @@ impl Ui {
 
                 self.popups.hide()
             }
-            KeyCode::Char('c') => return Ok(UiEvent::ClearMessages),
+            KeyCode::Char('c') => {
+                let is_confirmed = self.popups.is_visible(Popup::ConfirmClear);
+                if self.messages.len() >= CLEAR_CONFIRMATION_THRESHOLD && !is_confirmed {
+                    self.popups.show(Popup::ConfirmClear);
+                    return Ok(UiEvent::Continue);
+                }
+
+                if is_confirmed {
+                    self.popups.hide();
+                }
+
+                self.clear_messages();
+                return Ok(UiEvent::ClearMessages);
+            }
+            KeyCode::Char('x') => self.show_hex = !self.show_hex,
+            KeyCode::Char('X') => return Ok(UiEvent::Disconnect),
+            KeyCode::Char('P') => return Ok(UiEvent::Panic),
+            KeyCode::Char('v') => self.diff = self.diff.next(),
             KeyCode::Char(' ') => return Ok(UiEvent::ToggleRunningState),
+            KeyCode::Char('e') => self.toggle_script_log(),
+            KeyCode::Char('t') => self.show_clock = !self.show_clock,
+            KeyCode::Char('f') => self.hold_position = !self.hold_position,
+            KeyCode::Char('n') => self.note_off = self.note_off.next(),
+            KeyCode::Char('T') => self.popups.toggle_visible(Popup::Log),
+            KeyCode::Char('[') => self.select_param(components::Selector::previous),
+            KeyCode::Char(']') => self.select_param(components::Selector::next),
+            KeyCode::Char('-') => return Ok(self.adjust_param(-1)),
+            KeyCode::Char('+') | KeyCode::Char('=') => return Ok(self.adjust_param(1)),
+            KeyCode::PageDown => self.scroll_to(components::page_forward),
+            KeyCode::PageUp => {
+                self.scroll_to(|scroll, page_size, _| scroll.saturating_sub(page_size))
+            }
+            KeyCode::Home => self.scroll_to(|_, _, _| 0),
+            KeyCode::End => self.scroll_to(|_, _, len| len),
             KeyCode::Left | KeyCode::Char('h') => self.selectors.previous_selector(),
             KeyCode::Right | KeyCode::Char('l') => self.selectors.next_selector(),
             KeyCode::Down | KeyCode::Char('j') => self.selectors.next_item(),
@@ -148,10 +431,18 @@ impl Ui {
     }
 
     pub fn render(&mut self, f: &mut Frame, app: &AudioMidiController) {
+        self.popups.hide_expired(std::time::Instant::now());
         let sections = Layout::default()
             .direction(Direction::Vertical)
             .margin(1)
-            .constraints([Constraint::Min(3), Constraint::Percentage(80)].as_ref())
+            .constraints(
+                [
+                    Constraint::Min(3),
+                    Constraint::Percentage(80),
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
             .split(f.size());
 
         let top_sections = Layout::default()
@@ -160,7 +451,7 @@ impl Ui {
             .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
             .split(sections[0]);
 
-        let has_script_dir = self.script_dir.as_ref().is_some_and(|dir| dir.is_dir());
+        let has_script_dir = self.scripts.is_available();
         let port_selector_section = if has_script_dir {
             top_sections[0]
         } else {
@@ -172,7 +463,7 @@ impl Ui {
             port_selector_section,
             Selector::Port,
             crate::title!("ports"),
-            app.midi().port_names(),
+            &app.midi().display_port_names(),
         );
 
         if has_script_dir {
@@ -180,32 +471,129 @@ impl Ui {
                 f,
                 top_sections[1],
                 Selector::Script,
-                &crate::title!("{}", self.script_dir.as_ref().unwrap().to_string_lossy()),
-                &self.script_names,
+                &crate::title!("{}", self.scripts.dir().unwrap().to_string_lossy()),
+                self.scripts.names(),
             )
         }
 
-        let selected_port_name = match app.midi().selected_port_name() {
-            Some(name) => crate::title!("port : {}", name),
-            None => "".to_owned(),
-        };
-
         let selected_script_name = match app.selected_script() {
             Some(name) => crate::title!("script : {}", name),
             None => "".to_owned(),
         };
 
-        let running_state = if app.midi().is_running() {
-            crate::title!("active")
+        let mut messages_title = match self.show_hex {
+            true => crate::title!("messages : hex").to_owned(),
+            false => crate::title!("messages").to_owned(),
+        };
+
+        if let Some(name) = self.diff.name() {
+            messages_title = format!("{messages_title}─{}", crate::title!("{}", name));
+        }
+
+        if self.note_off != NoteOffForm::Raw {
+            messages_title = format!(
+                "{messages_title}─{}",
+                crate::title!("note off : {}", self.note_off.name())
+            );
+        }
+
+        let scroll_title = match (self.hold_position, self.message_scroll) {
+            (true, 0) => Some(crate::title!("hold").to_owned()),
+            (true, scroll) => Some(crate::title!("hold : {} new below", scroll)),
+            (false, 0) => None,
+            (false, scroll) => Some(crate::title!("scrolled : {}", scroll)),
+        };
+        if let Some(scroll_title) = scroll_title {
+            messages_title = format!("{messages_title}─{scroll_title}");
+        }
+
+        let num_dropped = app.midi().num_dropped_messages();
+        if num_dropped > 0 {
+            messages_title = format!(
+                "{messages_title}─{}",
+                crate::title!("dropped : {}", num_dropped)
+            );
+        }
+
+        let message_sections = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(match self.script_log_scroll {
+                Some(_) => [Constraint::Percentage(60), Constraint::Percentage(40)],
+                None => [Constraint::Percentage(100), Constraint::Length(0)],
+            })
+            .split(sections[1]);
+
+        let message_area = if self.num_params > 0 {
+            let areas = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
+                .split(message_sections[0]);
+
+            let params: Vec<_> = app
+                .params()
+                .iter()
+                .map(|param| format!("{} : {:.3}", param.name, param.value))
+                .collect();
+            self.params
+                .render(f, areas[1], crate::title!("params"), &params, true);
+            areas[0]
         } else {
-            crate::title!("paused")
+            message_sections[0]
         };
 
+        self.message_page_size = components::viewport_height(message_area);
         widgets::midi::render_messages(
             f,
-            &format!("{running_state}─{selected_port_name}─{selected_script_name}"),
+            &messages_title,
             &self.messages,
-            sections[1],
+            message_area,
+            &self.message_template,
+            self.show_hex,
+            self.message_scroll,
+        );
+
+        if let Some(scroll) = self.script_log_scroll {
+            let mut title = crate::title!("script log").to_owned();
+            if scroll > 0 {
+                title = format!("{title}─{}", crate::title!("scrolled : {}", scroll));
+            }
+
+            self.script_log_page_size = components::viewport_height(message_sections[1]);
+            widgets::script_log::render(f, &title, &self.script_log, message_sections[1], scroll);
+        }
+
+        let running_state = if app.midi().is_running() {
+            "active"
+        } else {
+            "paused"
+        };
+
+        let clock = app.midi().clock();
+        let transport = match clock.transport() {
+            Some(Transport::Playing) => "playing",
+            Some(Transport::Stopped) => "stopped",
+            None => "-",
+        };
+        let tempo = clock
+            .bpm()
+            .map_or("-".to_owned(), |bpm| format!("{bpm:.1} bpm"));
+
+        widgets::status::render(
+            f,
+            sections[2],
+            &[
+                running_state.to_owned(),
+                format!("transport : {transport}"),
+                format!("tempo : {tempo}"),
+                format!(
+                    "port : {}",
+                    app.midi()
+                        .selected_port_name()
+                        .map_or("-", |port| app.midi().display_name(port))
+                ),
+                format!("script : {}", app.selected_script().unwrap_or("-".into())),
+                "? : help".to_owned(),
+            ],
         );
 
         self.popups.render(
@@ -225,6 +613,16 @@ impl Ui {
         self.popups
             .render(f, Popup::Usage, crate::title!("usage"), USAGE);
 
+        self.popups.render(
+            f,
+            Popup::ConfirmClear,
+            crate::title!("clear?"),
+            &format!(
+                "       c : clear {} messages\n<ESC>, q : cancel",
+                self.messages.len()
+            ),
+        );
+
         self.popups.render(
             f,
             Popup::Alert,
@@ -232,6 +630,13 @@ impl Ui {
             self.alert_message.as_ref().unwrap_or(&"".to_owned()),
         );
 
+        if self.popups.is_visible(Popup::Log) {
+            self.log_tail.poll();
+            let log_text = self.log_tail.text();
+            self.popups
+                .render(f, Popup::Log, crate::title!("log"), &log_text);
+        }
+
         if !self.popups.is_visible(Popup::Script) {
             self.popups
                 .render(f, Popup::Script, crate::title!(""), "No script loaded");
@@ -254,3 +659,292 @@ impl Ui {
         );
     }
 }
+
+fn mark_selected(selectors: &mut components::Selectors<Selector>, id: Selector, index: usize) {
+    if let Some(selector) = selectors.get_mut(id) {
+        selector.select(index);
+        selector.confirm_selection();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn push_note_ons(ui: &mut Ui, count: usize) {
+        let programs = aud::midi::ProgramNames::default();
+        let mut messages = (0..count)
+            .filter_map(|i| {
+                widgets::midi::MidiMessageString::new(i as u64, &[0x90, 60, 100], &programs)
+            })
+            .collect();
+        ui.append_messages(&mut messages);
+    }
+
+    #[test]
+    fn clearing_empties_the_stored_messages() {
+        let mut ui = Ui::default();
+        push_note_ons(&mut ui, 3);
+
+        let event = ui.handle_keypress(KeyCode::Char('c').into()).unwrap();
+        assert!(matches!(event, UiEvent::ClearMessages));
+        assert!(ui.messages.is_empty());
+    }
+
+    #[test]
+    fn disconnecting_forgets_the_selected_port() {
+        let mut ui = Ui::default();
+        ui.update_port_names(&["dev0", "dev1"]);
+        ui.mark_port_selected(1);
+
+        let event = ui.handle_keypress(KeyCode::Char('X').into()).unwrap();
+        assert!(matches!(event, UiEvent::Disconnect));
+
+        ui.clear_port_selection();
+        let ports = ui.selectors.get_mut(Selector::Port).unwrap();
+        assert_eq!(ports.selected(), None);
+    }
+
+    #[test]
+    fn repeated_controller_values_are_hidden_when_only_showing_changes() {
+        let programs = aud::midi::ProgramNames::default();
+        let push_cc = |ui: &mut Ui, value: u8| {
+            let cc = widgets::midi::MidiMessageString::new(0, &[0xB0, 1, value], &programs);
+            ui.append_messages(&mut cc.into_iter().collect());
+        };
+
+        let mut ui = Ui::default();
+        ui.handle_keypress(KeyCode::Char('v').into()).unwrap();
+        ui.handle_keypress(KeyCode::Char('v').into()).unwrap();
+        for value in [10, 10, 14, 14, 12] {
+            push_cc(&mut ui, value);
+        }
+
+        let deltas: Vec<_> = ui.messages.iter().map(|msg| msg.delta).collect();
+        assert_eq!(deltas, [None, Some(4), Some(-2)]);
+
+        ui.handle_keypress(KeyCode::Char('c').into()).unwrap();
+        push_cc(&mut ui, 12);
+        assert_eq!(ui.messages[0].delta, None);
+    }
+
+    #[test]
+    fn clearing_many_messages_requires_confirmation() {
+        let mut ui = Ui::default();
+        push_note_ons(&mut ui, CLEAR_CONFIRMATION_THRESHOLD);
+
+        let event = ui.handle_keypress(KeyCode::Char('c').into()).unwrap();
+        assert!(matches!(event, UiEvent::Continue));
+        assert!(ui.popups.is_visible(Popup::ConfirmClear));
+        assert_eq!(ui.messages.len(), CLEAR_CONFIRMATION_THRESHOLD);
+
+        ui.handle_keypress(KeyCode::Esc.into()).unwrap();
+        assert!(!ui.popups.is_visible(Popup::ConfirmClear));
+        assert_eq!(ui.messages.len(), CLEAR_CONFIRMATION_THRESHOLD);
+
+        ui.handle_keypress(KeyCode::Char('c').into()).unwrap();
+        let event = ui.handle_keypress(KeyCode::Char('c').into()).unwrap();
+        assert!(matches!(event, UiEvent::ClearMessages));
+        assert!(!ui.popups.is_visible(Popup::ConfirmClear));
+        assert!(ui.messages.is_empty());
+    }
+
+    #[test]
+    fn messages_scroll_by_pages_within_bounds() {
+        let mut ui = Ui {
+            message_page_size: 4,
+            ..Default::default()
+        };
+        push_note_ons(&mut ui, 10);
+
+        ui.handle_keypress(KeyCode::PageDown.into()).unwrap();
+        assert_eq!(ui.message_scroll, 4);
+
+        ui.handle_keypress(KeyCode::PageDown.into()).unwrap();
+        ui.handle_keypress(KeyCode::PageDown.into()).unwrap();
+        assert_eq!(ui.message_scroll, 9);
+
+        ui.handle_keypress(KeyCode::PageUp.into()).unwrap();
+        assert_eq!(ui.message_scroll, 5);
+
+        push_note_ons(&mut ui, 2);
+        assert_eq!(ui.message_scroll, 7);
+
+        ui.handle_keypress(KeyCode::Home.into()).unwrap();
+        assert_eq!(ui.message_scroll, 0);
+
+        ui.handle_keypress(KeyCode::PageUp.into()).unwrap();
+        assert_eq!(ui.message_scroll, 0);
+
+        push_note_ons(&mut ui, 2);
+        assert_eq!(ui.message_scroll, 0);
+
+        ui.handle_keypress(KeyCode::End.into()).unwrap();
+        assert_eq!(ui.message_scroll, 13);
+    }
+
+    #[test]
+    fn scripts_can_clear_the_messages_and_reset_the_display() {
+        let mut ui = Ui::default();
+        push_note_ons(&mut ui, 3);
+        ui.handle_keypress(KeyCode::PageUp.into()).unwrap();
+        ui.show_alert_message("too loud");
+
+        ui.apply_script_request(UiApiEvent::Reset);
+        assert!(!ui.popups.is_visible(Popup::Alert));
+        assert_eq!(ui.alert_message, None);
+        assert_eq!(ui.message_scroll, 0);
+        assert_eq!(ui.messages.len(), 3);
+
+        ui.apply_script_request(UiApiEvent::Clear);
+        assert!(ui.messages.is_empty());
+        ui.apply_script_request(UiApiEvent::Clear);
+        assert!(ui.messages.is_empty());
+    }
+
+    #[test]
+    fn holding_the_position_keeps_the_view_in_place_as_messages_arrive() {
+        let mut ui = Ui::default();
+        push_note_ons(&mut ui, 3);
+        assert_eq!(ui.message_scroll, 0);
+
+        ui.handle_keypress(KeyCode::Char('f').into()).unwrap();
+        push_note_ons(&mut ui, 2);
+        assert_eq!(ui.message_scroll, 2);
+
+        ui.handle_keypress(KeyCode::Home.into()).unwrap();
+        push_note_ons(&mut ui, 4);
+        assert_eq!(ui.message_scroll, 4);
+
+        ui.handle_keypress(KeyCode::Char('f').into()).unwrap();
+        ui.handle_keypress(KeyCode::Home.into()).unwrap();
+        push_note_ons(&mut ui, 1);
+        assert_eq!(ui.message_scroll, 0);
+    }
+
+    #[test]
+    fn script_messages_are_logged_in_order_up_to_the_log_size() {
+        let message = |text: String| ScriptMessage {
+            time: std::time::SystemTime::now(),
+            kind: aud::controllers::audio_midi::ScriptMessageKind::Log,
+            text,
+        };
+
+        let mut ui = Ui::default();
+        ui.handle_keypress(KeyCode::Char('e').into()).unwrap();
+        ui.append_script_messages(vec![message("one".into()), message("two".into())]);
+        ui.append_script_messages(
+            (0..MAX_SCRIPT_LOG_LEN)
+                .map(|i| message(i.to_string()))
+                .collect(),
+        );
+
+        let texts: Vec<_> = ui.script_log.iter().map(|msg| msg.text.as_str()).collect();
+        assert_eq!(texts.len(), MAX_SCRIPT_LOG_LEN);
+        assert_eq!(texts[0], "0");
+        assert_eq!(
+            texts[MAX_SCRIPT_LOG_LEN - 1],
+            (MAX_SCRIPT_LOG_LEN - 1).to_string()
+        );
+
+        ui.handle_keypress(KeyCode::End.into()).unwrap();
+        assert_eq!(ui.script_log_scroll, Some(MAX_SCRIPT_LOG_LEN - 1));
+        assert_eq!(ui.message_scroll, 0);
+    }
+
+    #[test]
+    fn clock_messages_are_hidden_unless_shown() {
+        let programs = aud::midi::ProgramNames::default();
+        let clock = |ui: &mut Ui| {
+            let mut messages = vec![
+                widgets::midi::MidiMessageString::new(0, &[CLOCK], &programs).unwrap(),
+                widgets::midi::MidiMessageString::new(1, &[0x90, 60, 100], &programs).unwrap(),
+            ];
+            ui.append_messages(&mut messages);
+        };
+
+        let mut ui = Ui::default();
+        clock(&mut ui);
+        assert_eq!(ui.messages.len(), 1);
+
+        ui.handle_keypress(KeyCode::Char('t').into()).unwrap();
+        clock(&mut ui);
+        assert_eq!(ui.messages.len(), 3);
+    }
+
+    #[test]
+    fn params_are_adjusted_through_the_selected_one() {
+        let mut ui = Ui::default();
+        let event = ui.handle_keypress(KeyCode::Char('+').into()).unwrap();
+        assert!(matches!(event, UiEvent::Continue));
+
+        ui.update_params(2);
+        ui.handle_keypress(KeyCode::Char(']').into()).unwrap();
+        let event = ui.handle_keypress(KeyCode::Char('-').into()).unwrap();
+        assert!(matches!(
+            event,
+            UiEvent::AdjustParam {
+                index: 1,
+                steps: -1
+            }
+        ));
+
+        ui.handle_keypress(KeyCode::Char(']').into()).unwrap();
+        let event = ui.handle_keypress(KeyCode::Char('+').into()).unwrap();
+        assert!(matches!(event, UiEvent::AdjustParam { index: 0, steps: 1 }));
+    }
+
+    #[test]
+    fn lines_appended_to_the_aud_log_appear_in_the_log_view() {
+        let dir = std::env::temp_dir().join(format!("aud-midimon-log-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aud.log");
+        std::fs::write(&path, "started\n").unwrap();
+
+        let app = AudioMidiController::with_midi(
+            Box::<crate::app::harness::MockMidiInput>::default(),
+            aud::lua::imported::midimon::API,
+        );
+        let mut ui = Ui {
+            log_tail: components::LogTail::new(Some(path.clone())),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(backend::TestBackend::new(100, 30)).unwrap();
+        let mut render = |ui: &mut Ui| {
+            terminal.draw(|f| ui.render(f, &app)).unwrap();
+            crate::app::buffer_text(terminal.backend().buffer())
+        };
+
+        assert!(!render(&mut ui).contains("started"));
+        ui.handle_keypress(KeyCode::Char('T').into()).unwrap();
+        assert!(render(&mut ui).contains("started"));
+
+        let mut log = std::fs::read_to_string(&path).unwrap();
+        log.push_str("script loaded : arp.lua\n");
+        std::fs::write(&path, log).unwrap();
+        let screen = render(&mut ui);
+        assert!(screen.contains("started"), "{screen}");
+        assert!(screen.contains("script loaded : arp.lua"), "{screen}");
+
+        ui.handle_keypress(KeyCode::Char('T').into()).unwrap();
+        assert!(!render(&mut ui).contains("started"));
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn usage_popup_can_be_toggled() {
+        let mut ui = Ui::default();
+        assert!(!ui.popups.is_visible(Popup::Usage));
+
+        ui.handle_keypress(KeyCode::Char('?').into()).unwrap();
+        assert!(ui.popups.is_visible(Popup::Usage));
+
+        ui.handle_keypress(KeyCode::Char('?').into()).unwrap();
+        assert!(!ui.popups.is_visible(Popup::Usage));
+
+        ui.handle_keypress(KeyCode::Char('?').into()).unwrap();
+        ui.handle_keypress(KeyCode::Esc.into()).unwrap();
+        assert!(!ui.popups.is_visible(Popup::Usage));
+    }
+}
@@ -0,0 +1,172 @@
+use crossterm::event::KeyCode;
+use std::{
+    collections::VecDeque,
+    io::{Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+/// Number of the most recent log lines kept.
+const MAX_NUM_LINES: usize = 1_000;
+
+/// Lines scrolled at once with the page keys.
+const PAGE_SIZE: usize = 10;
+
+/// Bytes read back from the end of a log that is already written
+/// when it is first tailed, instead of reading it from the start.
+const MAX_BACKLOG_LEN: u64 = 64 * 1024;
+
+/// Follows a log file, reading the lines appended since it was last polled.
+#[derive(Default)]
+pub struct LogTail {
+    path: Option<PathBuf>,
+    position: Option<u64>,
+    /// Start of a line that is still being written.
+    partial: String,
+    lines: VecDeque<String>,
+    /// Number of the most recent lines scrolled past.
+    scroll: usize,
+}
+
+impl LogTail {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self {
+            path,
+            ..Default::default()
+        }
+    }
+
+    /// Tail the file the app is logging to, if it is logging.
+    pub fn active() -> Self {
+        Self::new(crate::logger::active_file())
+    }
+
+    /// Read the lines appended to the file since the last poll.
+    pub fn poll(&mut self) {
+        if let Err(e) = self.read_appended() {
+            // logging the failure would append to the file failing to be read
+            self.partial.clear();
+            self.lines
+                .push_back(format!("failed to read the log : {e}"));
+            self.path = None;
+        }
+    }
+
+    fn read_appended(&mut self) -> anyhow::Result<()> {
+        let Some(ref path) = self.path else {
+            return Ok(());
+        };
+
+        crate::logger::flush();
+        let mut file = std::fs::File::open(path)?;
+        let len = file.metadata()?.len();
+
+        // the log was truncated or replaced when it got shorter, start over
+        let position = self.position.filter(|&position| position <= len);
+        // skip what would not be kept anyway, e.g. a long backlog
+        let start = position
+            .unwrap_or(0)
+            .max(len.saturating_sub(MAX_BACKLOG_LEN));
+        let is_cut = start > position.unwrap_or(0);
+        if position.is_none() || is_cut {
+            self.partial.clear();
+        }
+
+        file.seek(SeekFrom::Start(start))?;
+        let mut appended = vec![];
+        file.take(len - start).read_to_end(&mut appended)?;
+        self.position = Some(len);
+
+        let text = String::from_utf8_lossy(&appended);
+        let text = match is_cut {
+            // the first line is likely cut
+            true => text.split_once('\n').map_or("", |(_, rest)| rest),
+            false => &text,
+        };
+
+        self.partial.push_str(text);
+        let Some(end) = self.partial.rfind('\n') else {
+            return Ok(());
+        };
+
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        let num_lines = complete.lines().count();
+        if self.scroll > 0 {
+            self.scroll += num_lines;
+        }
+
+        self.lines.extend(complete.lines().map(str::to_owned));
+        let num_overflowing = self.lines.len().saturating_sub(MAX_NUM_LINES);
+        self.lines.drain(..num_overflowing);
+        self.scroll = self.scroll.min(self.lines.len().saturating_sub(1));
+        Ok(())
+    }
+
+    /// Scroll to older lines, given the current scroll and the number of lines.
+    fn scroll_to(&mut self, scroll: impl Fn(usize, usize) -> usize) {
+        let len = self.lines.len();
+        self.scroll = scroll(self.scroll, len).min(len.saturating_sub(1));
+    }
+
+    /// Scroll with the page keys, the older lines being up.
+    /// Returns false if the key was not handled.
+    pub fn on_keypress(&mut self, key: KeyCode) -> bool {
+        match key {
+            KeyCode::PageUp => self.scroll_to(|scroll, _| scroll + PAGE_SIZE),
+            KeyCode::PageDown => self.scroll_to(|scroll, _| scroll.saturating_sub(PAGE_SIZE)),
+            KeyCode::Home => self.scroll_to(|_, len| len),
+            KeyCode::End => self.scroll_to(|_, _| 0),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Lines up to the scrolled one, the most recent last.
+    pub fn text(&self) -> String {
+        let end = self.lines.len() - self.scroll.min(self.lines.len());
+        match end {
+            0 => "no log lines yet".to_owned(),
+            _ => self
+                .lines
+                .range(..end)
+                .fold(String::new(), |text, line| text + line.as_str() + "\n"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn lines_appended_to_the_log_are_read_once_complete() {
+        let dir = std::env::temp_dir().join(format!("aud-log-tail-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("aud.log");
+        std::fs::write(&path, "started\n").unwrap();
+
+        let mut tail = LogTail::new(Some(path.clone()));
+        tail.poll();
+        assert_eq!(tail.text(), "started\n");
+
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "script loaded\nhalf a").unwrap();
+        tail.poll();
+        assert_eq!(tail.text(), "started\nscript loaded\n");
+
+        writeln!(file, " line").unwrap();
+        tail.poll();
+        assert_eq!(tail.text(), "started\nscript loaded\nhalf a line\n");
+
+        assert!(tail.on_keypress(KeyCode::PageUp));
+        assert_eq!(tail.text(), "started\n");
+        assert!(tail.on_keypress(KeyCode::End));
+        assert_eq!(tail.text(), "started\nscript loaded\nhalf a line\n");
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
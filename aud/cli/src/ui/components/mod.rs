@@ -1,7 +1,11 @@
+mod log_tail;
 mod popups;
+mod scripts;
 mod selector;
 mod selectors;
 
+pub use log_tail::*;
 pub use popups::*;
+pub use scripts::*;
 pub use selector::*;
 pub use selectors::*;
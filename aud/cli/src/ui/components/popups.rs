@@ -1,16 +1,20 @@
 use crate::ui::widgets;
 use ratatui::prelude::*;
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, time::Instant};
 
 #[derive(Copy, Clone)]
 pub enum PopupKind {
     Code,
     Text,
+    /// Lines of a log, showing the most recent ones that fit.
+    Log,
 }
 
 pub struct Popups<Key> {
     popups: BTreeMap<Key, PopupKind>,
     visible: Option<Key>,
+    /// When the visible popup hides by itself, if ever.
+    expires_at: Option<Instant>,
 }
 
 impl<Key> Popups<Key>
@@ -26,11 +30,26 @@ where
         Self {
             popups,
             visible: None,
+            expires_at: None,
         }
     }
 
     pub fn show(&mut self, popup: Key) {
         self.visible = Some(popup);
+        self.expires_at = None;
+    }
+
+    /// Show a popup until the deadline, see [`Popups::hide_expired`].
+    pub fn show_until(&mut self, popup: Key, deadline: Instant) {
+        self.visible = Some(popup);
+        self.expires_at = Some(deadline);
+    }
+
+    /// Hide the visible popup if it was shown until a deadline that passed.
+    pub fn hide_expired(&mut self, now: Instant) {
+        if self.expires_at.is_some_and(|deadline| now >= deadline) {
+            self.hide();
+        }
     }
 
     pub fn toggle_visible(&mut self, popup: Key) {
@@ -43,6 +62,7 @@ where
 
     pub fn hide(&mut self) {
         self.visible = None;
+        self.expires_at = None;
     }
 
     pub fn any_visible(&self) -> bool {
@@ -69,6 +89,7 @@ where
         match popup.1 {
             PopupKind::Code => widgets::popup::render_code(f, title, text),
             PopupKind::Text => widgets::popup::render_text(f, title, text),
+            PopupKind::Log => widgets::popup::render_log(f, title, text),
         }
     }
 }
@@ -108,4 +129,23 @@ mod test {
             assert!(!popups.is_visible(popup));
         }
     }
+
+    #[test]
+    fn popups_shown_until_a_deadline_hide_once_it_passes() {
+        let mut popups = Popups::new(&[(Popup::A, PopupKind::Text), (Popup::B, PopupKind::Text)]);
+        let now = Instant::now();
+        let deadline = now + std::time::Duration::from_secs(5);
+
+        popups.show_until(Popup::A, deadline);
+        popups.hide_expired(now);
+        assert!(popups.is_visible(Popup::A));
+        popups.hide_expired(deadline);
+        assert!(!popups.any_visible());
+
+        // another popup shown in the meantime stays
+        popups.show_until(Popup::A, deadline);
+        popups.show(Popup::B);
+        popups.hide_expired(deadline);
+        assert!(popups.is_visible(Popup::B));
+    }
 }
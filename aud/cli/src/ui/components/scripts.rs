@@ -0,0 +1,50 @@
+use std::path::{Path, PathBuf};
+
+/// Lists the Lua scripts found under a directory and its
+/// subdirectories, named by their path relative to it.
+#[derive(Default)]
+pub struct ScriptBrowser {
+    dir: Option<PathBuf>,
+    names: Vec<String>,
+}
+
+impl ScriptBrowser {
+    /// Browse the scripts of a directory. A directory that cannot
+    /// be read, e.g. because it is missing, lists no scripts.
+    pub fn update_dir(&mut self, dir: impl AsRef<Path>) {
+        let dir = dir.as_ref();
+        self.names = match aud::files::list_recursive_with_extension(dir, "lua") {
+            Ok(names) => names,
+            Err(e) => {
+                log::warn!("failed to list scripts in {} : {e}", dir.display());
+                vec![]
+            }
+        };
+        self.dir = Some(dir.into());
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Whether there is a directory to browse scripts from.
+    pub fn is_available(&self) -> bool {
+        self.dir.as_ref().is_some_and(|dir| dir.is_dir())
+    }
+
+    pub fn path(&self, index: usize) -> Option<PathBuf> {
+        Some(self.dir.as_ref()?.join(self.names.get(index)?))
+    }
+
+    /// Index of a script given its full path, if it is browsed.
+    pub fn position(&self, script: &Path) -> Option<usize> {
+        let relative = script.strip_prefix(self.dir.as_ref()?).ok()?;
+        self.names
+            .iter()
+            .position(|name| Path::new(name) == relative)
+    }
+}
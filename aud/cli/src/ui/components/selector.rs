@@ -4,6 +4,8 @@ use ratatui::{
 };
 use std::borrow::Cow;
 
+use crate::ui::theme;
+
 /// Component that can cycles through elements
 /// while retaining a selected index.
 /// It does not own the data it will render,
@@ -21,6 +23,7 @@ pub struct Selector {
     state: ListState,
     selection: Option<usize>,
     len: usize,
+    page_size: usize,
 }
 
 impl Selector {
@@ -32,6 +35,7 @@ impl Selector {
             state,
             selection: None,
             len,
+            page_size: 1,
         }
     }
 
@@ -60,10 +64,39 @@ impl Selector {
         self.state.select(prev);
     }
 
+    /// Move down by the number of items visible on the last render,
+    /// stopping at the last item instead of wrapping around.
+    pub fn next_page(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.select(page_forward(i, self.page_size, self.len));
+        }
+    }
+
+    /// Move up by the number of items visible on the last render,
+    /// stopping at the first item instead of wrapping around.
+    pub fn previous_page(&mut self) {
+        if let Some(i) = self.state.selected() {
+            self.select(i.saturating_sub(self.page_size));
+        }
+    }
+
+    pub fn first(&mut self) {
+        self.select(0);
+    }
+
+    pub fn last(&mut self) {
+        self.select(self.len.saturating_sub(1));
+    }
+
     pub fn confirm_selection(&mut self) {
         self.selection = self.state.selected();
     }
 
+    /// Forget the confirmed selection, the cursor stays where it is.
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
     pub fn select(&mut self, index: usize) {
         if index < self.len {
             self.state.select(Some(index));
@@ -94,7 +127,7 @@ impl Selector {
                     Color::Gray
                 };
 
-                ListItem::new(Span::styled(item, Style::default().fg(color)))
+                ListItem::new(Span::styled(item, theme::style(Style::default().fg(color))))
                     .style(Style::default())
             })
             .collect();
@@ -109,16 +142,27 @@ impl Selector {
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .fg(border_color)
+                    .style(theme::style(Style::default().fg(border_color)))
                     .title(title),
             )
             .highlight_style(Style::default().add_modifier(Modifier::BOLD))
             .highlight_symbol("> ");
 
+        self.page_size = viewport_height(area);
         f.render_stateful_widget(items, area, &mut self.state);
     }
 }
 
+/// Number of rows available inside a bordered area.
+pub fn viewport_height(area: Rect) -> usize {
+    area.height.saturating_sub(2).max(1) as usize
+}
+
+/// Index one page after `index`, clamped to the last of `len` items.
+pub fn page_forward(index: usize, page_size: usize, len: usize) -> usize {
+    (index + page_size).min(len.saturating_sub(1))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -155,6 +199,51 @@ mod test {
         assert_eq!(selector.selected().unwrap(), 0);
     }
 
+    #[test]
+    fn paging_stops_at_the_ends_of_the_list() {
+        let mut selector = Selector::with_len(10);
+        selector.page_size = 4;
+
+        selector.next_page();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 4);
+
+        selector.next_page();
+        selector.next_page();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 9);
+
+        selector.previous_page();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 5);
+
+        selector.previous_page();
+        selector.previous_page();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 0);
+
+        selector.last();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 9);
+
+        selector.first();
+        selector.confirm_selection();
+        assert_eq!(selector.selected().unwrap(), 0);
+    }
+
+    #[test]
+    fn paging_an_empty_list_selects_nothing() {
+        let mut selector = Selector::with_len(0);
+        selector.next_page();
+        selector.last();
+        selector.confirm_selection();
+        assert_eq!(selector.selected(), None);
+
+        assert_eq!(page_forward(0, 4, 0), 0);
+        assert_eq!(viewport_height(Rect::new(0, 0, 10, 1)), 1);
+        assert_eq!(viewport_height(Rect::new(0, 0, 10, 12)), 10);
+    }
+
     #[test]
     fn can_cycle_through_indices_while_retaining_the_selection() {
         let mut selector = Selector::with_len(3);
@@ -66,6 +66,30 @@ where
         };
     }
 
+    pub fn next_page(&mut self) {
+        if let Some(i) = self.focused_index() {
+            self.selectors[i].list.next_page();
+        };
+    }
+
+    pub fn previous_page(&mut self) {
+        if let Some(i) = self.focused_index() {
+            self.selectors[i].list.previous_page();
+        };
+    }
+
+    pub fn first_item(&mut self) {
+        if let Some(i) = self.focused_index() {
+            self.selectors[i].list.first();
+        };
+    }
+
+    pub fn last_item(&mut self) {
+        if let Some(i) = self.focused_index() {
+            self.selectors[i].list.last();
+        };
+    }
+
     pub fn next_selector(&mut self) {
         if self.selectors.is_empty() {
             return;
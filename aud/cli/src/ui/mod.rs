@@ -1,6 +1,18 @@
 pub mod components;
+pub mod theme;
 pub mod widgets;
 
+/// Time of day, in UTC like the log files.
+pub fn time_of_day(time: std::time::SystemTime) -> String {
+    let time = humantime::format_rfc3339_seconds(time).to_string();
+    time.get(11..19).unwrap_or(&time).to_owned()
+}
+
+/// Alert text stamped with the time it is shown at.
+pub fn timestamped_alert(alert: &str) -> String {
+    format!("[ {} ] {alert}", time_of_day(std::time::SystemTime::now()))
+}
+
 #[macro_export]
 macro_rules! title {
     ($fmt:expr) => {
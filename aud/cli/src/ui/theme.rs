@@ -0,0 +1,167 @@
+use ratatui::{prelude::*, symbols::Marker};
+use std::{cell::Cell, io::IsTerminal, sync::RwLock};
+
+thread_local! {
+    /// Set for the thread rendering the app, so that
+    /// tests can each render with or without colors.
+    static COLORS_ENABLED: Cell<bool> = const { Cell::new(true) };
+}
+
+static PALETTE: RwLock<Palette> = RwLock::new(Palette(Vec::new()));
+
+pub const DEFAULT_PALETTE: [Color; 8] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Green,
+    Color::Red,
+    Color::Blue,
+    Color::Gray,
+    Color::LightRed,
+];
+
+/// Glyphs telling channels apart when colors are disabled, with their symbol.
+const CHANNEL_MARKERS: [(Marker, char); 3] =
+    [(Marker::Dot, '•'), (Marker::Block, '█'), (Marker::Bar, '▄')];
+
+pub fn set_colors_enabled(enabled: bool) {
+    COLORS_ENABLED.set(enabled);
+}
+
+pub fn colors_enabled() -> bool {
+    COLORS_ENABLED.get()
+}
+
+/// Colors are disabled when `NO_COLOR` is set, on dumb
+/// terminals and when the output is not a terminal.
+pub fn detect_color_support() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let is_dumb = std::env::var("TERM").is_ok_and(|term| term == "dumb");
+    !no_color && !is_dumb && std::io::stdout().is_terminal()
+}
+
+/// Style to render with, stripped of its colors when they are disabled.
+pub fn style(style: Style) -> Style {
+    with_colors(style, colors_enabled())
+}
+
+/// Marker used to draw charts, braille needs a capable terminal font.
+pub fn marker() -> Marker {
+    match colors_enabled() {
+        true => Marker::Braille,
+        false => Marker::Dot,
+    }
+}
+
+/// Colors of the audio channels in order, cycled through when there
+/// are more channels than colors. The default palette when empty.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Palette(pub Vec<Color>);
+
+impl Palette {
+    pub fn color(&self, channel: usize) -> Color {
+        match self.0.is_empty() {
+            true => DEFAULT_PALETTE[channel % DEFAULT_PALETTE.len()],
+            false => self.0[channel % self.0.len()],
+        }
+    }
+}
+
+/// Parsed from a comma separated list of colors, given by
+/// name, e.g. "cyan", index, e.g. "208", or hex, e.g. "#ff8800".
+impl std::str::FromStr for Palette {
+    type Err = String;
+
+    fn from_str(palette: &str) -> Result<Self, String> {
+        palette
+            .split(',')
+            .map(|color| {
+                color
+                    .trim()
+                    .parse::<Color>()
+                    .map_err(|_| format!("Invalid color : {color}"))
+            })
+            .collect::<Result<_, _>>()
+            .map(Self)
+    }
+}
+
+pub fn set_palette(palette: Palette) {
+    *PALETTE.write().unwrap_or_else(|e| e.into_inner()) = palette;
+}
+
+/// Color of an audio channel in the palette.
+pub fn channel_color(channel: usize) -> Color {
+    PALETTE
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .color(channel)
+}
+
+/// Marker drawing a channel overlaid with others. Without colors the
+/// channels would all look the same, so each gets its own glyph.
+pub fn channel_marker(channel: usize, num_channels: usize, marker: Marker) -> Marker {
+    match colors_enabled() || num_channels < 2 {
+        true => marker,
+        false => CHANNEL_MARKERS[channel % CHANNEL_MARKERS.len()].0,
+    }
+}
+
+/// Name of a channel in a legend, after its glyph when colors are disabled.
+pub fn channel_legend(channel: usize) -> String {
+    match colors_enabled() {
+        true => format!("ch {}", channel + 1),
+        false => {
+            let glyph = CHANNEL_MARKERS[channel % CHANNEL_MARKERS.len()].1;
+            format!("{glyph} ch {}", channel + 1)
+        }
+    }
+}
+
+fn with_colors(style: Style, colors: bool) -> Style {
+    match colors {
+        true => style,
+        false => Style {
+            fg: None,
+            bg: None,
+            ..style
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn disabling_colors_keeps_only_modifiers() {
+        let themed = Style::default()
+            .fg(Color::Cyan)
+            .bg(Color::DarkGray)
+            .add_modifier(Modifier::BOLD);
+
+        assert_eq!(with_colors(themed, true), themed);
+
+        let plain = with_colors(themed, false);
+        assert_eq!(plain.fg, None);
+        assert_eq!(plain.bg, None);
+        assert!(plain.add_modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn channels_get_distinct_colors_from_the_palette_until_it_cycles() {
+        let palette: Palette = "cyan, 208, #ff8800".parse().unwrap();
+        let colors = [Color::Cyan, Color::Indexed(208), Color::Rgb(255, 136, 0)];
+
+        let channels: Vec<_> = (0..3).map(|c| palette.color(c)).collect();
+        assert_eq!(channels, colors);
+        assert_eq!(palette.color(3), Color::Cyan);
+
+        let defaults: std::collections::HashSet<_> = (0..DEFAULT_PALETTE.len())
+            .map(|c| Palette::default().color(c))
+            .collect();
+        assert_eq!(defaults.len(), DEFAULT_PALETTE.len());
+
+        assert!("cyan,not a color".parse::<Palette>().is_err());
+    }
+}
@@ -1,3 +1,5 @@
+use crate::ui::theme;
+use aud::midi::{controller_name, NoteOffForm, ProgramNames};
 use midly::{
     live::{LiveEvent, MtcQuarterFrameMessage, SystemCommon, SystemRealtime},
     MidiMessage,
@@ -6,14 +8,183 @@ use ratatui::{
     prelude::*,
     widgets::{Block, Borders, List, ListItem},
 };
+use std::collections::HashMap;
 
-pub fn render_messages(f: &mut Frame, title: &str, messages: &[MidiMessageString], area: Rect) {
+/// Color of each kind of message, keyed by status byte,
+/// with the channel stripped from channel messages.
+const MESSAGE_COLORS: &[(u8, Color)] = &[
+    (0x80, Color::Green),      // NoteOff
+    (0x90, Color::Green),      // NoteOn
+    (0xA0, Color::LightGreen), // Aftertouch
+    (0xB0, Color::Blue),       // Controller
+    (0xC0, Color::Cyan),       // ProgramChange
+    (0xD0, Color::LightGreen), // ChannelAftertouch
+    (0xE0, Color::Magenta),    // PitchBend
+    (0xF0, Color::Yellow),     // SysEx
+];
+
+/// Color of system common and realtime messages without their own entry.
+const SYSTEM_MESSAGE_COLOR: Color = Color::Gray;
+
+/// Color a message is rendered with, based on its kind.
+pub fn color_for(bytes: &[u8]) -> Color {
+    let Some(&status) = bytes.first() else {
+        return SYSTEM_MESSAGE_COLOR;
+    };
+
+    let kind = match status {
+        0xF0.. => status,
+        _ => status & 0xF0,
+    };
+
+    MESSAGE_COLORS
+        .iter()
+        .find(|(status, _)| *status == kind)
+        .map_or(SYSTEM_MESSAGE_COLOR, |&(_, color)| color)
+}
+
+/// Template of the line a message is rendered as, e.g. the
+/// default `"[ {time} ] : {type} : {data}"`. Fields are :
+/// - `{time}` : timestamp of the message
+/// - `{type}` : kind of message, e.g. "NoteOn"
+/// - `{ch}` : channel, "-" for system messages
+/// - `{d1}`, `{d2}` : first and second data bytes, "-" when absent
+/// - `{data}` : decoded description of the message
+/// - `{hex}` : raw bytes of the message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTemplate {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateField {
+    Time,
+    Type,
+    Channel,
+    Data1,
+    Data2,
+    Data,
+    Hex,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TemplatePart {
+    Text(String),
+    Field(TemplateField),
+}
+
+impl Default for MessageTemplate {
+    fn default() -> Self {
+        Self::DEFAULT.parse().unwrap()
+    }
+}
+
+impl std::str::FromStr for MessageTemplate {
+    type Err = anyhow::Error;
+
+    fn from_str(template: &str) -> Result<Self, Self::Err> {
+        let mut parts = vec![];
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let Some(len) = rest[start..].find('}') else {
+                anyhow::bail!("unclosed field in message template : {template}");
+            };
+
+            if start > 0 {
+                parts.push(TemplatePart::Text(rest[..start].to_owned()));
+            }
+
+            let field = match &rest[start + 1..start + len] {
+                "time" => TemplateField::Time,
+                "type" => TemplateField::Type,
+                "ch" => TemplateField::Channel,
+                "d1" => TemplateField::Data1,
+                "d2" => TemplateField::Data2,
+                "data" => TemplateField::Data,
+                "hex" => TemplateField::Hex,
+                field => anyhow::bail!("unknown field in message template : {{{field}}}"),
+            };
+            parts.push(TemplatePart::Field(field));
+            rest = &rest[start + len + 1..];
+        }
+
+        if rest.contains('}') {
+            anyhow::bail!("unopened field in message template : {template}");
+        }
+
+        if !rest.is_empty() {
+            parts.push(TemplatePart::Text(rest.to_owned()));
+        }
+
+        Ok(Self { parts })
+    }
+}
+
+impl MessageTemplate {
+    pub const DEFAULT: &'static str = "[ {time} ] : {type} : {data}";
+
+    /// Used in place of the template while showing the raw bytes.
+    const HEX: &'static str = "[ {time} ] : {hex}";
+
+    fn hex() -> Self {
+        Self::HEX.parse().unwrap()
+    }
+
+    fn spans(&self, msg: &MidiMessageString, style: Style) -> Vec<Span<'static>> {
+        let color = theme::style(style.fg(color_for(&msg.bytes)));
+        self.parts
+            .iter()
+            .map(|part| match part {
+                TemplatePart::Text(text) => {
+                    Span::styled(text.clone(), theme::style(style.fg(Color::DarkGray)))
+                }
+                TemplatePart::Field(TemplateField::Time) => Span::styled(
+                    field_value(TemplateField::Time, msg),
+                    theme::style(style.fg(Color::Gray)),
+                ),
+                TemplatePart::Field(field) => Span::styled(field_value(*field, msg), color),
+            })
+            .collect()
+    }
+}
+
+fn field_value(field: TemplateField, msg: &MidiMessageString) -> String {
+    let byte = |i: usize| msg.bytes.get(i).map_or("-".to_owned(), u8::to_string);
+
+    match field {
+        TemplateField::Time => msg.timestamp.to_string(),
+        TemplateField::Type => msg.category.clone(),
+        TemplateField::Channel => match msg.bytes.first() {
+            Some(&status) if (0x80..0xF0).contains(&status) => (status & 0x0F).to_string(),
+            _ => "-".to_owned(),
+        },
+        TemplateField::Data1 => byte(1),
+        TemplateField::Data2 => byte(2),
+        TemplateField::Data => msg.data.clone(),
+        TemplateField::Hex => msg.hex(),
+    }
+}
+
+pub fn render_messages(
+    f: &mut Frame,
+    title: &str,
+    messages: &[MidiMessageString],
+    area: Rect,
+    template: &MessageTemplate,
+    hex: bool,
+    scroll: usize,
+) {
     const MAX_NUM_MESSAGES_ON_SCREEN: usize = 128;
 
+    let hex_template = MessageTemplate::hex();
+    let template = if hex { &hex_template } else { template };
+
     let message_list: Vec<ListItem> = messages
         .iter()
         .rev()
         .enumerate()
+        .skip(scroll)
         .take(MAX_NUM_MESSAGES_ON_SCREEN.min(messages.len()))
         .map(|(i, msg)| {
             let style = if i == 0 {
@@ -22,22 +193,18 @@ pub fn render_messages(f: &mut Frame, title: &str, messages: &[MidiMessageString
                 Style::default()
             };
 
-            ListItem::new(vec![Line::from(vec![
-                Span::styled(format!("[ {} ]", msg.timestamp), style.fg(Color::Gray)),
-                Span::styled(" : ", style.fg(Color::DarkGray)),
-                Span::styled(msg.category.clone(), style.fg(Color::Cyan)),
-                Span::styled(" : ", style.fg(Color::DarkGray)),
-                Span::styled(msg.data.clone(), style.fg(Color::Yellow)),
-            ])])
+            let mut spans = template.spans(msg, style);
+            spans.extend(msg.delta.map(|delta| delta_span(delta, style)));
+            ListItem::new(vec![Line::from(spans)])
         })
         .collect();
 
     let list = List::new(message_list)
-        .style(Style::default().fg(Color::Yellow))
+        .style(theme::style(Style::default().fg(Color::Yellow)))
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .style(Style::default().fg(Color::DarkGray))
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
                 .title(Span::styled(
                     title,
                     Style::default().add_modifier(Modifier::BOLD),
@@ -47,14 +214,55 @@ pub fn render_messages(f: &mut Frame, title: &str, messages: &[MidiMessageString
     f.render_widget(list, area);
 }
 
+/// Change of a controller value, drawn with an arrow showing its direction.
+fn delta_span(delta: i16, style: Style) -> Span<'static> {
+    let (arrow, color) = match delta {
+        1.. => ('▲', Color::Green),
+        0 => ('=', Color::DarkGray),
+        _ => ('▼', Color::Red),
+    };
+    Span::styled(format!(" {arrow} {delta:+}"), theme::style(style.fg(color)))
+}
+
+/// Last value of each controller on each channel, to
+/// tell how much a Control Change moved its controller.
+#[derive(Debug, Default)]
+pub struct ControllerValues {
+    last: HashMap<(u8, u8), u8>,
+}
+
+impl ControllerValues {
+    /// Change from the previous value of the controller set by a Control
+    /// Change. `None` for other messages and for the first value seen.
+    pub fn observe(&mut self, bytes: &[u8]) -> Option<i16> {
+        let &[status @ 0xB0..=0xBF, controller, value] = bytes else {
+            return None;
+        };
+
+        let previous = self.last.insert((status & 0x0F, controller), value)?;
+        Some(value as i16 - previous as i16)
+    }
+
+    pub fn clear(&mut self) {
+        self.last.clear();
+    }
+}
+
 pub struct MidiMessageString {
     pub timestamp: u64,
     pub category: String,
     pub data: String,
+    /// Bytes as displayed, which may differ from
+    /// the received ones once their Note Off is normalized.
+    pub bytes: Vec<u8>,
+    /// Bytes as received, shown in hex.
+    pub raw: Vec<u8>,
+    /// Change of the controller value, for Control Changes in diff mode.
+    pub delta: Option<i16>,
 }
 
 impl MidiMessageString {
-    pub fn new(timestamp: u64, bytes: &[u8]) -> Option<Self> {
+    pub fn new(timestamp: u64, bytes: &[u8], programs: &ProgramNames) -> Option<Self> {
         let Ok(event) = LiveEvent::parse(bytes) else {
             return None;
         };
@@ -63,6 +271,9 @@ impl MidiMessageString {
             timestamp,
             category: category.to_string(),
             data: data.to_string(),
+            bytes: bytes.to_vec(),
+            raw: bytes.to_vec(),
+            delta: None,
         };
 
         let str = match event {
@@ -80,9 +291,11 @@ impl MidiMessageString {
                         make("Aftertouch", &format!("key = {key} | vel = {vel}"))
                     }
                     MidiMessage::Controller { controller, value } => {
-                        make("Controller", &format!("cc = {controller} | val = {value}"))
+                        let cc = with_name(controller.as_int(), controller_name);
+                        make("Controller", &format!("cc = {cc} | val = {value}"))
                     }
                     MidiMessage::ProgramChange { program } => {
+                        let program = with_name(program.as_int(), |p| programs.get(p));
                         make("ProgramChange", &format!("program = {program}"))
                     }
                     MidiMessage::ChannelAftertouch { vel } => {
@@ -126,4 +339,155 @@ impl MidiMessageString {
 
         Some(str)
     }
+
+    /// Decode the message with its Note Off in the given form,
+    /// keeping the received bytes for the hex view.
+    pub fn normalized(
+        timestamp: u64,
+        bytes: &[u8],
+        programs: &ProgramNames,
+        note_off: NoteOffForm,
+    ) -> Option<Self> {
+        let mut message = Self::new(timestamp, &note_off.normalize(bytes), programs)?;
+        message.raw = bytes.to_vec();
+        Some(message)
+    }
+
+    /// Raw bytes of the message, e.g. `90 3C 64`.
+    pub fn hex(&self) -> String {
+        self.raw
+            .iter()
+            .map(|byte| format!("{byte:02X}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Format a number followed by its name, or only the number when it has no name.
+fn with_name<'a>(number: u8, name: impl FnOnce(u8) -> Option<&'a str>) -> String {
+    match name(number) {
+        Some(name) => format!("{number} ({name})"),
+        None => number.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    impl MessageTemplate {
+        fn render(&self, msg: &MidiMessageString) -> String {
+            self.spans(msg, Style::default())
+                .into_iter()
+                .map(|span| span.content)
+                .collect()
+        }
+    }
+
+    fn describe(bytes: &[u8], programs: &ProgramNames) -> String {
+        MidiMessageString::new(0, bytes, programs).unwrap().data
+    }
+
+    #[test]
+    fn program_changes_and_controllers_are_decoded_into_names() {
+        let programs = ProgramNames::default();
+        assert_eq!(
+            describe(&[0xC0, 0], &programs),
+            "chan = 0 | program = 0 (Acoustic Grand Piano)"
+        );
+        assert_eq!(
+            describe(&[0xB1, 7, 100], &programs),
+            "chan = 1 | cc = 7 (Volume) | val = 100"
+        );
+        assert_eq!(
+            describe(&[0xB0, 64, 127], &programs),
+            "chan = 0 | cc = 64 (Sustain) | val = 127"
+        );
+        assert_eq!(
+            describe(&[0xB0, 3, 1], &programs),
+            "chan = 0 | cc = 3 | val = 1"
+        );
+    }
+
+    #[test]
+    fn program_changes_use_overridden_names() {
+        let programs = ProgramNames::default().with_overrides("5 Pad").unwrap();
+        assert_eq!(
+            describe(&[0xC2, 5], &programs),
+            "chan = 2 | program = 5 (Pad)"
+        );
+    }
+
+    #[test]
+    fn hex_representation_matches_the_stored_bytes() {
+        let bytes = [0x90, 0x3C, 0x64];
+        let message = MidiMessageString::new(0, &bytes, &ProgramNames::default()).unwrap();
+        assert_eq!(message.bytes, bytes);
+        assert_eq!(message.hex(), "90 3C 64");
+        assert_eq!(message.data, "chan = 0 | key = 60 | vel = 100");
+    }
+
+    #[test]
+    fn normalized_note_offs_are_displayed_in_their_form_but_keep_their_bytes() {
+        let programs = ProgramNames::default();
+        let message =
+            MidiMessageString::normalized(0, &[0x91, 60, 0], &programs, NoteOffForm::NoteOff)
+                .unwrap();
+        assert_eq!(message.category, "NoteOff");
+        assert_eq!(message.bytes, [0x81, 60, 0]);
+        assert_eq!(message.hex(), "91 3C 00");
+    }
+
+    #[test]
+    fn messages_can_be_rendered_through_a_custom_template() {
+        let programs = ProgramNames::default();
+        let note = MidiMessageString::new(42, &[0x93, 60, 100], &programs).unwrap();
+        let clock = MidiMessageString::new(43, &[0xF8], &programs).unwrap();
+
+        let template: MessageTemplate = "{time} {ch} {type} {d1} {d2} ({hex})".parse().unwrap();
+        assert_eq!(template.render(&note), "42 3 NoteOn 60 100 (93 3C 64)");
+        assert_eq!(template.render(&clock), "43 - Clock - - (F8)");
+
+        assert_eq!(
+            MessageTemplate::default().render(&note),
+            "[ 42 ] : NoteOn : chan = 3 | key = 60 | vel = 100"
+        );
+
+        assert!("{time} {velocity}".parse::<MessageTemplate>().is_err());
+        assert!("{time".parse::<MessageTemplate>().is_err());
+        assert!("time}".parse::<MessageTemplate>().is_err());
+    }
+
+    #[test]
+    fn successive_controller_values_produce_their_deltas() {
+        let mut values = ControllerValues::default();
+
+        assert_eq!(values.observe(&[0xB0, 7, 100]), None);
+        assert_eq!(values.observe(&[0xB0, 7, 105]), Some(5));
+        assert_eq!(values.observe(&[0xB0, 7, 98]), Some(-7));
+        assert_eq!(values.observe(&[0xB0, 7, 98]), Some(0));
+
+        // each controller of each channel is tracked on its own
+        assert_eq!(values.observe(&[0xB1, 7, 0]), None);
+        assert_eq!(values.observe(&[0xB0, 1, 64]), None);
+        assert_eq!(values.observe(&[0xB0, 7, 127]), Some(29));
+        assert_eq!(values.observe(&[0x90, 7, 100]), None);
+
+        values.clear();
+        assert_eq!(values.observe(&[0xB0, 7, 0]), None);
+    }
+
+    #[test]
+    fn each_message_kind_has_its_configured_color() {
+        assert_eq!(color_for(&[0x90, 60, 100]), Color::Green);
+        assert_eq!(color_for(&[0x83, 60, 0]), Color::Green);
+        assert_eq!(color_for(&[0xA0, 60, 10]), Color::LightGreen);
+        assert_eq!(color_for(&[0xB5, 7, 100]), Color::Blue);
+        assert_eq!(color_for(&[0xC0, 5]), Color::Cyan);
+        assert_eq!(color_for(&[0xD0, 10]), Color::LightGreen);
+        assert_eq!(color_for(&[0xEF, 0, 64]), Color::Magenta);
+        assert_eq!(color_for(&[0xF0, 0x7E, 0xF7]), Color::Yellow);
+        assert_eq!(color_for(&[0xF8]), SYSTEM_MESSAGE_COLOR);
+        assert_eq!(color_for(&[]), SYSTEM_MESSAGE_COLOR);
+    }
 }
@@ -1,3 +1,7 @@
 pub mod midi;
+pub mod perf;
+pub mod plot;
 pub mod popup;
 pub mod scope;
+pub mod script_log;
+pub mod status;
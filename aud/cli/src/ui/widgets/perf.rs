@@ -0,0 +1,159 @@
+use crate::ui::theme;
+use ratatui::{prelude::*, widgets::*};
+use std::time::{Duration, Instant};
+
+/// Smooths a measurement that is taken on every frame.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MovingAverage {
+    value: Option<f32>,
+}
+
+impl MovingAverage {
+    /// Weight of the newest measurement.
+    const SMOOTHING: f32 = 0.1;
+
+    pub fn update(&mut self, measurement: f32) {
+        self.value = Some(match self.value {
+            Some(value) => value + (measurement - value) * Self::SMOOTHING,
+            None => measurement,
+        });
+    }
+
+    pub fn value(&self) -> Option<f32> {
+        self.value
+    }
+}
+
+/// Timing of the run loop and of the audio it receives,
+/// drawn as a small overlay to tune the refresh rate.
+#[derive(Debug, Default)]
+pub struct PerfStats {
+    frame_interval: MovingAverage,
+    frame_time: MovingAverage,
+    last_frame: Option<Instant>,
+    audio_window: Option<Instant>,
+    audio_frames: usize,
+    audio_rate: usize,
+    buffer_fill: f32,
+    num_sanitized_samples: u64,
+    num_dropped_buffers: u64,
+}
+
+impl PerfStats {
+    /// Call when a frame starts rendering.
+    pub fn begin_frame(&mut self, now: Instant) {
+        if let Some(last) = self.last_frame.replace(now) {
+            self.frame_interval
+                .update(now.duration_since(last).as_secs_f32());
+        }
+    }
+
+    /// Call when a frame has been rendered, with the time it took.
+    pub fn end_frame(&mut self, elapsed: Duration) {
+        self.frame_time.update(elapsed.as_secs_f32());
+    }
+
+    /// Rendered frames per second.
+    pub fn fps(&self) -> f32 {
+        match self.frame_interval.value() {
+            Some(interval) if interval > 0. => 1. / interval,
+            _ => 0.,
+        }
+    }
+
+    /// Count received audio frames, the rate is updated once per second.
+    pub fn record_audio(&mut self, num_frames: usize, now: Instant) {
+        let start = *self.audio_window.get_or_insert(now);
+        self.audio_frames += num_frames;
+
+        let elapsed = now.duration_since(start);
+        if elapsed >= Duration::from_secs(1) {
+            self.audio_rate = (self.audio_frames as f32 / elapsed.as_secs_f32()) as usize;
+            self.audio_frames = 0;
+            self.audio_window = Some(now);
+        }
+    }
+
+    /// Ratio of buffered audio to the audio that can be drawn.
+    pub fn set_buffer_fill(&mut self, fill: f32) {
+        self.buffer_fill = fill;
+    }
+
+    /// Number of NaN and infinite samples replaced with silence so far.
+    pub fn set_num_sanitized_samples(&mut self, num_samples: u64) {
+        self.num_sanitized_samples = num_samples;
+    }
+
+    /// Number of buffers dropped on a full queue so far.
+    pub fn set_num_dropped_buffers(&mut self, num_buffers: u64) {
+        self.num_dropped_buffers = num_buffers;
+    }
+
+    fn lines(&self) -> Vec<String> {
+        let frame_ms = self.frame_time.value().unwrap_or_default() * 1000.;
+        let mut lines = vec![
+            format!("fps    : {:.1}", self.fps()),
+            format!("frame  : {frame_ms:.2} ms"),
+            format!("audio  : {} fr/s", self.audio_rate),
+            format!("buffer : {:.0} %", self.buffer_fill * 100.),
+        ];
+        if self.num_sanitized_samples > 0 {
+            lines.push(format!("nan    : {} smp", self.num_sanitized_samples));
+        }
+        if self.num_dropped_buffers > 0 {
+            lines.push(format!("drops  : {} buf", self.num_dropped_buffers));
+        }
+        lines
+    }
+}
+
+/// Draw the stats in the top right corner of an area.
+pub fn render(f: &mut Frame, area: Rect, stats: &PerfStats) {
+    let lines = stats.lines();
+    let width = lines
+        .iter()
+        .map(|line| line.len())
+        .max()
+        .unwrap_or_default() as u16
+        + 2;
+    let height = lines.len() as u16 + 2;
+    let corner = Rect {
+        x: area.right().saturating_sub(width + 1),
+        y: area.y + 1,
+        width: width.min(area.width),
+        height: height.min(area.height),
+    };
+
+    let text = Paragraph::new(lines.into_iter().map(Line::from).collect::<Vec<_>>())
+        .style(theme::style(Style::default().fg(Color::Gray)))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(theme::style(Style::default().fg(Color::DarkGray))),
+        );
+
+    f.render_widget(Clear, corner);
+    f.render_widget(text, corner);
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fps_follows_the_moving_average_of_frame_intervals() {
+        let mut stats = PerfStats::default();
+        assert_eq!(stats.fps(), 0.);
+
+        let start = Instant::now();
+        for frame in 0..100 {
+            stats.begin_frame(start + Duration::from_millis(20 * frame));
+        }
+        assert!((stats.fps() - 50.).abs() < 0.01);
+
+        let last = start + Duration::from_millis(20 * 99);
+        stats.begin_frame(last + Duration::from_millis(120));
+        let smoothed = 1. / (0.02 + (0.12 - 0.02) * MovingAverage::SMOOTHING);
+        assert!((stats.fps() - smoothed).abs() < 0.01);
+    }
+}
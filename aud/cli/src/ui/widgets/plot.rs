@@ -0,0 +1,272 @@
+use ratatui::style::Color;
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Size of a lane of the exported image, in pixels.
+const LANE_WIDTH: f64 = 960.;
+const LANE_HEIGHT: f64 = 240.;
+const MARGIN: f64 = 16.;
+const TITLE_HEIGHT: f64 = 24.;
+
+/// A channel drawn in a lane, named when it needs a legend.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trace {
+    pub name: Option<String>,
+    pub color: Color,
+    pub points: Vec<(f64, f64)>,
+}
+
+/// An area of the plot with its own axes, e.g. a channel of the stacked
+/// scope. Scattered points are drawn as dots rather than joined.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lane {
+    pub label: Option<String>,
+    pub traces: Vec<Trace>,
+    pub x_bounds: [f64; 2],
+    pub y_bounds: [f64; 2],
+    pub scatter: bool,
+}
+
+/// What the scope shows, to export as an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Plot {
+    pub title: String,
+    pub lanes: Vec<Lane>,
+}
+
+impl Plot {
+    /// Draw the lanes stacked under the title as an SVG document.
+    pub fn to_svg(&self) -> String {
+        let width = LANE_WIDTH + 2. * MARGIN;
+        let height =
+            TITLE_HEIGHT + self.lanes.len().max(1) as f64 * (LANE_HEIGHT + MARGIN) + MARGIN;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="monospace" font-size="12">"#
+        );
+        let _ = writeln!(svg, r#"<rect width="100%" height="100%" fill="black"/>"#);
+        let _ = writeln!(
+            svg,
+            r#"<text x="{MARGIN}" y="{}" fill="gray">{}</text>"#,
+            TITLE_HEIGHT - 6.,
+            escape_xml(&self.title)
+        );
+
+        for (i, lane) in self.lanes.iter().enumerate() {
+            let top = TITLE_HEIGHT + i as f64 * (LANE_HEIGHT + MARGIN);
+            draw_lane(&mut svg, lane, MARGIN, top);
+        }
+
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Write the plot to a file, only SVG can be exported.
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if !is_svg(path) {
+            anyhow::bail!("Cannot export {}, only SVG is supported", path.display());
+        }
+        std::fs::write(path, self.to_svg())?;
+        Ok(())
+    }
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("svg"))
+}
+
+/// Validate the file an image is exported to, given on the command line.
+pub fn parse_image_path(path: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(path);
+    match is_svg(&path) {
+        true => Ok(path),
+        false => Err(format!(
+            "Cannot export {}, only SVG is supported",
+            path.display()
+        )),
+    }
+}
+
+fn draw_lane(svg: &mut String, lane: &Lane, left: f64, top: f64) {
+    let [x_min, x_max] = lane.x_bounds;
+    let [y_min, y_max] = lane.y_bounds;
+    let x_span = (x_max - x_min).max(f64::EPSILON);
+    let y_span = (y_max - y_min).max(f64::EPSILON);
+    let to_pixel = |(x, y): (f64, f64)| {
+        let y = y.clamp(y_min, y_max);
+        (
+            left + (x - x_min) / x_span * LANE_WIDTH,
+            top + (y_max - y) / y_span * LANE_HEIGHT,
+        )
+    };
+
+    let _ = writeln!(
+        svg,
+        r#"<rect x="{left}" y="{top}" width="{LANE_WIDTH}" height="{LANE_HEIGHT}" fill="none" stroke="dimgray"/>"#
+    );
+    if y_min < 0. && y_max > 0. {
+        let (_, zero) = to_pixel((x_min, 0.));
+        let _ = writeln!(
+            svg,
+            r#"<line x1="{left}" y1="{zero:.2}" x2="{}" y2="{zero:.2}" stroke="dimgray" stroke-dasharray="4 4"/>"#,
+            left + LANE_WIDTH
+        );
+    }
+    if let Some(ref label) = lane.label {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" fill="gray">{}</text>"#,
+            left + 4.,
+            top + 14.,
+            escape_xml(label)
+        );
+    }
+
+    for trace in &lane.traces {
+        let color = svg_color(trace.color);
+        let points = trace.points.iter().copied().map(to_pixel);
+        match lane.scatter {
+            true => {
+                for (x, y) in points {
+                    let _ = writeln!(
+                        svg,
+                        r#"<circle cx="{x:.2}" cy="{y:.2}" r="1" fill="{color}"/>"#
+                    );
+                }
+            }
+            false => {
+                let points: Vec<String> = points.map(|(x, y)| format!("{x:.2},{y:.2}")).collect();
+                let _ = writeln!(
+                    svg,
+                    r#"<polyline points="{}" fill="none" stroke="{color}"/>"#,
+                    points.join(" ")
+                );
+            }
+        }
+    }
+
+    let legend = lane
+        .traces
+        .iter()
+        .filter_map(|trace| Some((trace.name.as_ref()?, svg_color(trace.color))));
+    for (i, (name, color)) in legend.enumerate() {
+        let _ = writeln!(
+            svg,
+            r#"<text x="{}" y="{}" fill="{color}" text-anchor="end">{}</text>"#,
+            left + LANE_WIDTH - 4.,
+            top + 14. * (i + 1) as f64,
+            escape_xml(name)
+        );
+    }
+}
+
+/// Color of the SVG for a terminal color, terminals being free to
+/// pick their own shades of the named ones.
+fn svg_color(color: Color) -> String {
+    let name = match color {
+        Color::Rgb(r, g, b) => return format!("#{r:02x}{g:02x}{b:02x}"),
+        Color::Black => "black",
+        Color::Red => "red",
+        Color::Green => "green",
+        Color::Yellow => "gold",
+        Color::Blue => "royalblue",
+        Color::Magenta => "magenta",
+        Color::Cyan => "cyan",
+        Color::Gray => "silver",
+        Color::DarkGray => "dimgray",
+        Color::LightRed => "salmon",
+        Color::LightGreen => "lightgreen",
+        Color::LightYellow => "lightyellow",
+        Color::LightBlue => "lightskyblue",
+        Color::LightMagenta => "violet",
+        Color::LightCyan => "lightcyan",
+        Color::White | Color::Reset | Color::Indexed(_) => "white",
+    };
+    name.to_owned()
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Image file named after the time of the export, in the working directory.
+pub fn default_path(now: SystemTime) -> PathBuf {
+    PathBuf::from(format!("auscope-{}.svg", file_timestamp(now)))
+}
+
+/// Time in a form that can be part of a file name on any system,
+/// e.g. `1970-01-01T00-01-30Z`, which sorts in chronological order.
+pub fn file_timestamp(now: SystemTime) -> String {
+    humantime::format_rfc3339_seconds(now)
+        .to_string()
+        .replace(':', "-")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quick_xml::events::Event;
+
+    fn elements(svg: &str) -> Vec<String> {
+        let mut reader = quick_xml::Reader::from_str(svg);
+        let mut names = vec![];
+        loop {
+            match reader.read_event().unwrap() {
+                Event::Start(element) | Event::Empty(element) => {
+                    names.push(element.name().as_ref().to_owned())
+                }
+                Event::Eof => break,
+                _ => (),
+            }
+        }
+        names
+    }
+
+    #[test]
+    fn default_paths_are_timestamped() {
+        let now = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(90);
+        assert_eq!(
+            default_path(now),
+            PathBuf::from("auscope-1970-01-01T00-01-30Z.svg")
+        );
+    }
+
+    #[test]
+    fn only_svg_files_can_be_saved() {
+        let plot = Plot {
+            title: "scope".into(),
+            lanes: vec![],
+        };
+        let path = std::env::temp_dir().join(format!("aud-export-{}.png", std::process::id()));
+        assert!(plot.save(&path).is_err());
+        assert!(!path.exists());
+
+        assert_eq!(
+            parse_image_path("scope.SVG"),
+            Ok(PathBuf::from("scope.SVG"))
+        );
+        assert!(parse_image_path("scope.png").is_err());
+        assert!(parse_image_path("scope").is_err());
+    }
+
+    #[test]
+    fn titles_are_escaped() {
+        let plot = Plot {
+            title: "<left & right>".into(),
+            lanes: vec![],
+        };
+        let svg = plot.to_svg();
+        assert!(svg.contains("&lt;left &amp; right&gt;"));
+        assert_eq!(elements(&svg), ["svg", "rect", "text"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::ui::theme;
 use ratatui::{prelude::*, widgets::*};
 use syntect::{easy, highlighting, parsing, util};
 
@@ -27,7 +28,7 @@ pub fn render_text(f: &mut Frame, title: &str, text: &str) {
         .collect();
 
     let text = Paragraph::new(lines)
-        .style(Style::default().fg(Color::Yellow))
+        .style(theme::style(Style::default().fg(Color::Yellow)))
         .alignment(Alignment::Left);
 
     const MARGIN: usize = 4;
@@ -45,6 +46,25 @@ pub fn render_text(f: &mut Frame, title: &str, text: &str) {
     f.render_widget(text.block(block), area);
 }
 
+/// Draw the last lines of a log that fit, the most recent at the bottom.
+pub fn render_log(f: &mut Frame, title: &str, log_text: &str) {
+    let (block, area) = setup_popup(f, title, 80, 80);
+    let num_visible = area.height.saturating_sub(2) as usize;
+
+    let lines: Vec<_> = log_text.lines().collect();
+    let lines: Vec<_> = lines[lines.len().saturating_sub(num_visible)..]
+        .iter()
+        .map(|line| Line::from(line.to_string()))
+        .collect();
+
+    let text = Paragraph::new(lines)
+        .style(theme::style(Style::default().fg(Color::Gray)))
+        .alignment(Alignment::Left);
+
+    f.render_widget(Clear, area);
+    f.render_widget(text.block(block), area);
+}
+
 fn get_highlighted_code<'a>(
     h: &'a mut easy::HighlightLines,
     code: &'a str,
@@ -64,7 +84,10 @@ fn get_highlighted_code<'a>(
         let ranges: Vec<(highlighting::Style, &str)> = h.highlight_line(line, ps).unwrap();
         for (style, text) in ranges {
             let fg_color = Color::Rgb(style.foreground.r, style.foreground.g, style.foreground.b);
-            spans.push(Span::styled(text, Style::default().fg(fg_color)));
+            spans.push(Span::styled(
+                text,
+                theme::style(Style::default().fg(fg_color)),
+            ));
         }
         spans.push(Span::raw("\n"));
         highlighted_text.push(ListItem::new(Line::from(spans)));
@@ -82,7 +105,7 @@ fn setup_popup<'a>(
     let block = Block::default()
         .title(title)
         .borders(Borders::ALL)
-        .set_style(Style::default().gray());
+        .set_style(theme::style(Style::default().gray()));
 
     let y_border_percentage = (100 - height_precentage.min(100)) / 2;
     let y_constraints = Layout::default()
@@ -1,16 +1,8 @@
+use super::plot;
+use crate::ui::theme;
 use aud::{audio::AudioBuffer, dsp};
 use ratatui::{prelude::*, widgets::*};
-
-const COLORS: [Color; 8] = [
-    Color::Cyan,
-    Color::Yellow,
-    Color::Magenta,
-    Color::Green,
-    Color::Red,
-    Color::Blue,
-    Color::Gray,
-    Color::LightRed,
-];
+use std::collections::VecDeque;
 
 type SamplePoint = (f64, f64);
 type SamplePoints = Vec<SamplePoint>;
@@ -21,7 +13,7 @@ fn prepare_audio_data(
     num_samples_to_render: usize,
     gain: f32,
 ) -> Vec<SamplePoints> {
-    let num_channels = audio.num_channels.min(1) as usize;
+    let num_channels = audio.num_channels.max(1) as usize;
     let audio = dsp::deinterleave(&audio.data, num_channels);
     let mut channels = Vec::<SamplePoints>::with_capacity(num_channels);
     for chan in audio {
@@ -29,7 +21,6 @@ fn prepare_audio_data(
             .iter()
             .take(num_samples_to_render * downsample)
             .rev()
-            .step_by(num_channels)
             .step_by(downsample)
             .enumerate()
             .map(|(i, &sample)| (i as f64, (sample * gain) as f64))
@@ -39,51 +30,1720 @@ fn prepare_audio_data(
     channels
 }
 
-fn create_datasets(data: &[SamplePoints]) -> Vec<Dataset> {
+/// Draw the minimum and maximum of the samples of each column, in the
+/// order they came in, so that peaks between strides are not missed.
+fn prepare_decimated_audio_data(
+    audio: &AudioBuffer,
+    downsample: usize,
+    num_samples_to_render: usize,
+    gain: f32,
+) -> Vec<SamplePoints> {
+    let num_channels = audio.num_channels.max(1) as usize;
+    let downsample = downsample.max(1);
+    dsp::deinterleave(&audio.data, num_channels)
+        .iter()
+        .map(|chan| {
+            let samples: Vec<f32> = chan
+                .iter()
+                .take(num_samples_to_render * downsample)
+                .rev()
+                .copied()
+                .collect();
+
+            samples
+                .chunks(downsample)
+                .enumerate()
+                .flat_map(|(i, column)| {
+                    let position = |pick: fn(f32, f32) -> bool| {
+                        (0..column.len())
+                            .reduce(|a, b| if pick(column[b], column[a]) { b } else { a })
+                            .unwrap_or(0)
+                    };
+                    let (min, max) = (position(|a, b| a < b), position(|a, b| a > b));
+                    let (first, second) = (min.min(max), min.max(max));
+                    [column[first], column[second]].map(|sample| (i as f64, (sample * gain) as f64))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// How the samples of each column are picked when a
+/// column stands for more than one sample.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Decimation {
+    /// Their minimum and maximum, which shows every peak.
+    #[default]
+    MinMax,
+    /// The first of them, which is lighter to draw.
+    Stride,
+}
+
+impl Decimation {
+    pub fn next(self) -> Self {
+        match self {
+            Self::MinMax => Self::Stride,
+            Self::Stride => Self::MinMax,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::MinMax => "min/max",
+            Self::Stride => "stride",
+        }
+    }
+}
+
+/// How the samples of the scope are drawn, from
+/// the densest to the most readable at a glance.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LineStyle {
+    #[default]
+    Braille,
+    Line,
+    Points,
+    Bars,
+}
+
+impl LineStyle {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Braille => Self::Line,
+            Self::Line => Self::Points,
+            Self::Points => Self::Bars,
+            Self::Bars => Self::Braille,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Braille => "braille",
+            Self::Line => "line",
+            Self::Points => "points",
+            Self::Bars => "bars",
+        }
+    }
+
+    fn marker(self) -> Marker {
+        match self {
+            Self::Braille | Self::Line => theme::marker(),
+            Self::Points => Marker::Dot,
+            Self::Bars => Marker::Block,
+        }
+    }
+
+    /// Exported images join the points of every style but this one,
+    /// braille being too fine to tell apart from a line.
+    fn is_scattered(self) -> bool {
+        self == Self::Points
+    }
+
+    fn graph_type(self) -> GraphType {
+        match self {
+            Self::Braille | Self::Points => GraphType::Scatter,
+            Self::Line | Self::Bars => GraphType::Line,
+        }
+    }
+
+    /// Bars are drawn as a line going from the
+    /// baseline up to each point and back down.
+    fn shape(self, data: Vec<SamplePoints>) -> Vec<SamplePoints> {
+        if self != Self::Bars {
+            return data;
+        }
+
+        data.into_iter()
+            .map(|points| {
+                points
+                    .into_iter()
+                    .flat_map(|(x, y)| [(x, 0.), (x, y), (x, 0.)])
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Horizontal reference lines drawn behind the waveform,
+/// at fractions of the full scale amplitude.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Grid {
+    #[default]
+    Off,
+    /// Only the zero line.
+    Center,
+    /// Lines at 0, ±0.5 and ±1.
+    Halves,
+    /// Lines at every quarter of the full scale.
+    Quarters,
+}
+
+impl Grid {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Off => Self::Center,
+            Self::Center => Self::Halves,
+            Self::Halves => Self::Quarters,
+            Self::Quarters => Self::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Off => "no grid",
+            Self::Center => "center",
+            Self::Halves => "grid 1/2",
+            Self::Quarters => "grid 1/4",
+        }
+    }
+
+    /// Amplitudes at which to draw a line.
+    fn levels(self) -> Vec<f64> {
+        let num_steps = match self {
+            Self::Off => return vec![],
+            Self::Center => return vec![0.],
+            Self::Halves => 2,
+            Self::Quarters => 4,
+        };
+
+        (-num_steps..=num_steps)
+            .map(|step| step as f64 / num_steps as f64)
+            .collect()
+    }
+
+    /// Rows of an area `height` cells high at which the lines
+    /// are drawn, along with the amplitude of each line.
+    fn rows(self, height: u16) -> Vec<(u16, f64)> {
+        if height == 0 {
+            return vec![];
+        }
+
+        let last_row = (height - 1) as f64;
+        let mut rows: Vec<_> = self
+            .levels()
+            .into_iter()
+            .map(|level| (((1. - level) / 2. * last_row).round() as u16, level))
+            .collect();
+        // on short areas several levels can land on the same row,
+        // which is then drawn as the line closest to zero
+        rows.sort_by(|a, b| a.0.cmp(&b.0).then(a.1.abs().total_cmp(&b.1.abs())));
+        rows.dedup_by_key(|&mut (row, _)| row);
+        rows
+    }
+
+    /// Draw the lines straight into the buffer, the chart drawn
+    /// afterwards only overwrites the cells it puts a point in.
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        for (row, level) in self.rows(area.height) {
+            let (symbol, color) = match level == 0. {
+                true => (symbols::line::HORIZONTAL, Color::DarkGray),
+                false => ("┈", Color::Indexed(236)),
+            };
+
+            for x in area.left()..area.right() {
+                buf.get_mut(x, area.top() + row)
+                    .set_symbol(symbol)
+                    .set_style(theme::style(Style::default().fg(color)));
+            }
+        }
+    }
+}
+
+/// How the audio is scaled and drawn in the scope.
+pub struct ScopeView {
+    pub downsample: usize,
+    pub gain: f32,
+    pub style: LineStyle,
+    pub decimation: Decimation,
+    pub grid: Grid,
+    /// Columns at which to draw vertical measurement cursors.
+    pub cursors: Vec<usize>,
+    /// Identifies the audio drawn, the trace is only cached when it is known.
+    pub source: Option<u64>,
+}
+
+/// Everything a prepared trace depends on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TraceKey {
+    source: u64,
+    width: usize,
+    downsample: usize,
+    gain: f32,
+    decimation: Decimation,
+    style: LineStyle,
+}
+
+/// Keeps the last prepared trace, so that it is not prepared again
+/// every frame while nothing it depends on changes, e.g. when paused.
+#[derive(Default)]
+pub struct TraceCache {
+    key: Option<TraceKey>,
+    trace: Vec<SamplePoints>,
+}
+
+impl TraceCache {
+    fn get_or_prepare(
+        &mut self,
+        key: TraceKey,
+        prepare: impl FnOnce() -> Vec<SamplePoints>,
+    ) -> &[SamplePoints] {
+        if self.key != Some(key) {
+            self.trace = prepare();
+            self.key = Some(key);
+        }
+        &self.trace
+    }
+}
+
+/// Number of columns a waveform of `num_samples` samples is drawn over.
+pub fn num_columns(num_samples: usize, downsample: usize, width: usize) -> usize {
+    (num_samples / downsample.max(1)).min(width)
+}
+
+/// Number of columns the waveform can be drawn over inside
+/// the bordered block of an area, at the current area size.
+pub fn drawable_width(area: Rect) -> usize {
+    block("", false).inner(area).width as usize
+}
+
+/// Index of the sample drawn at a column of the waveform,
+/// the most recent sample being drawn in the first column.
+pub fn sample_at_column(column: usize, downsample: usize, num_columns: usize) -> Option<usize> {
+    (column < num_columns).then(|| (num_columns - column) * downsample - 1)
+}
+
+fn create_cursor_datasets(cursors: &[SamplePoints]) -> Vec<Dataset<'_>> {
+    cursors
+        .iter()
+        .map(|points| {
+            Dataset::default()
+                .marker(theme::marker())
+                .graph_type(GraphType::Line)
+                .style(theme::style(Style::default().fg(Color::White)))
+                .data(points)
+        })
+        .collect()
+}
+
+/// Number of previous traces kept by default in persistence mode.
+pub const DEFAULT_PERSISTENCE_DEPTH: usize = 8;
+
+/// Portion of its brightness a trace keeps each frame it ages.
+const PERSISTENCE_DECAY: f32 = 0.7;
+
+/// Keeps the last rendered traces to draw them fading
+/// out behind the current one, like phosphor persistence.
+pub struct Persistence {
+    depth: usize,
+    history: VecDeque<Vec<SamplePoints>>,
+}
+
+impl Persistence {
+    pub fn new(depth: usize) -> Self {
+        Self {
+            depth,
+            history: VecDeque::with_capacity(depth),
+        }
+    }
+
+    /// Keep a rendered trace, dropping the ones older than the depth.
+    fn push(&mut self, trace: Vec<SamplePoints>) {
+        self.history.push_front(trace);
+        self.history.truncate(self.depth);
+    }
+}
+
+/// Brightness of a trace rendered `age` frames ago.
+fn persistence_intensity(age: usize) -> f32 {
+    PERSISTENCE_DECAY.powi(age as i32)
+}
+
+/// Grayscale color from the 256 color palette for a brightness in `0..=1`.
+fn persistence_color(intensity: f32) -> Color {
+    const GRAYSCALE_START: f32 = 232.;
+    const GRAYSCALE_LEN: f32 = 23.;
+    Color::Indexed((GRAYSCALE_START + intensity.clamp(0., 1.) * GRAYSCALE_LEN).round() as u8)
+}
+
+fn create_persistence_datasets(persistence: &Persistence, style: LineStyle) -> Vec<Dataset<'_>> {
+    // oldest first so that newer traces are drawn over them
+    persistence
+        .history
+        .iter()
+        .enumerate()
+        .rev()
+        .flat_map(|(i, trace)| {
+            let color = persistence_color(persistence_intensity(i + 1));
+            trace.iter().map(move |points| {
+                Dataset::default()
+                    .marker(style.marker())
+                    .graph_type(style.graph_type())
+                    .style(theme::style(Style::default().fg(color)))
+                    .data(points)
+            })
+        })
+        .collect()
+}
+
+/// A dataset per channel, in the color of the channel. Overlaid
+/// channels are named so that the chart draws a legend for them.
+fn create_datasets(data: &[SamplePoints], style: LineStyle) -> Vec<Dataset<'_>> {
     data.iter()
         .enumerate()
         .map(|(i, points)| {
-            Dataset::default()
-                .name(i.to_string())
-                .marker(symbols::Marker::Braille)
-                .style(Style::default().fg(COLORS[i % COLORS.len()]))
-                .data(points)
+            let dataset = Dataset::default()
+                .marker(theme::channel_marker(i, data.len(), style.marker()))
+                .graph_type(style.graph_type())
+                .style(theme::style(Style::default().fg(theme::channel_color(i))))
+                .data(points);
+            match data.len() > 1 {
+                true => dataset.name(theme::channel_legend(i)),
+                false => dataset,
+            }
         })
         .collect()
 }
 
+/// Bordered block around the scope, drawn in red to flag an alert.
+pub fn block(title: &str, alert: bool) -> Block<'_> {
+    let color = if alert { Color::Red } else { Color::DarkGray };
+
+    Block::default()
+        .title(title)
+        .borders(Borders::ALL)
+        .style(theme::style(Style::default().fg(color)))
+}
+
+/// The trace of each channel drawn over `width` columns.
+fn prepare_trace(audio: &AudioBuffer, view: &ScopeView, width: usize) -> Vec<SamplePoints> {
+    let num_samples_to_render = num_columns(audio.num_frames(), view.downsample, width);
+    let prepare = match view.decimation {
+        Decimation::MinMax if view.downsample > 1 => prepare_decimated_audio_data,
+        _ => prepare_audio_data,
+    };
+    view.style.shape(prepare(
+        audio,
+        view.downsample,
+        num_samples_to_render,
+        view.gain,
+    ))
+}
+
 pub fn render(
     f: &mut Frame,
     area: Rect,
-    title: &str,
+    block: Block,
     audio: &AudioBuffer,
-    downsample: usize,
-    gain: f32,
+    view: &ScopeView,
+    persistence: Option<&mut Persistence>,
+    cache: Option<&mut TraceCache>,
 ) {
-    let width = f.size().width as usize;
-    let num_samples_to_render = (audio.num_frames() / downsample).min(width);
-    let data = prepare_audio_data(audio, downsample, num_samples_to_render, gain);
+    let inner = block.inner(area);
+    let width = inner.width as usize;
+    let prepare_trace = || prepare_trace(audio, view, width);
+
+    let key = view.source.map(|source| TraceKey {
+        source,
+        width,
+        downsample: view.downsample,
+        gain: view.gain,
+        decimation: view.decimation,
+        style: view.style,
+    });
+    let mut prepared = None;
+    let data = match (cache, key) {
+        (Some(cache), Some(key)) => cache.get_or_prepare(key, prepare_trace),
+        _ => prepared.insert(prepare_trace()).as_slice(),
+    };
+
+    let mut datasets = match persistence {
+        Some(ref persistence) => create_persistence_datasets(persistence, view.style),
+        None => vec![],
+    };
+    datasets.extend(create_datasets(data, view.style));
 
-    let datasets = create_datasets(&data);
+    let cursors: Vec<SamplePoints> = view
+        .cursors
+        .iter()
+        .map(|&column| vec![(column as f64, -1.), (column as f64, 1.)])
+        .collect();
+    datasets.extend(create_cursor_datasets(&cursors));
+
+    view.grid.render(inner, f.buffer_mut());
 
     let chart = Chart::new(datasets)
-        .block(
-            Block::default()
-                .title(title.dark_gray())
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::DarkGray)),
-        )
+        .block(block)
         .x_axis(
             Axis::default()
-                .style(Style::default().fg(Color::DarkGray))
-                .bounds([0., f.size().width as f64]),
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
+                .bounds([0., width as f64]),
         )
         .y_axis(
             Axis::default()
-                .style(Style::default().fg(Color::DarkGray))
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
                 .labels(vec!["-1 ".bold(), "――".into(), " 1 ".bold()])
                 .bounds([-1.0, 1.0]),
         );
 
     f.render_widget(chart, area);
+
+    if let Some(persistence) = persistence {
+        persistence.push(data.to_vec());
+    }
+}
+
+/// Smallest height of a lane in the stacked layout, borders included.
+const MIN_LANE_HEIGHT: u16 = 4;
+
+/// Split an area into stacked lanes, one per channel,
+/// with as many channels as fit at the minimum height.
+pub fn lane_areas(area: Rect, num_channels: usize) -> Vec<Rect> {
+    let max_lanes = (area.height / MIN_LANE_HEIGHT).max(1) as usize;
+    let num_lanes = num_channels.min(max_lanes);
+    if num_lanes == 0 {
+        return vec![];
+    }
+
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(vec![Constraint::Ratio(1, num_lanes as u32); num_lanes])
+        .split(area)
+        .to_vec()
+}
+
+/// Draw each channel in its own lane, starting from `first_channel`.
+/// Lanes are titled with the label of their channel, or its number
+/// if `labels` has none for it.
+pub fn render_stacked(
+    f: &mut Frame,
+    area: Rect,
+    block: Block,
+    audio: &AudioBuffer,
+    view: &ScopeView,
+    first_channel: usize,
+    labels: &[String],
+) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let channels = audio.deinterleave();
+    let channels = channels.get(first_channel..).unwrap_or_default();
+    let lanes = lane_areas(inner, channels.len());
+
+    for (i, (lane, channel)) in lanes.into_iter().zip(channels).enumerate() {
+        let channel_index = first_channel + i;
+        let label = match labels.get(channel_index) {
+            Some(label) => crate::title!("{}", label),
+            None => crate::title!("ch {}", channel_index + 1),
+        };
+        render(
+            f,
+            lane,
+            block_with_label(&label),
+            &AudioBuffer::from_deinterleaved(&[channel]),
+            view,
+            None,
+            None,
+        );
+    }
+}
+
+fn block_with_label(label: &str) -> Block<'_> {
+    Block::default()
+        .title(label)
+        .borders(Borders::TOP)
+        .style(theme::style(Style::default().fg(Color::DarkGray)))
+}
+
+/// Number of frames transformed to draw the spectrum by default.
+pub const DEFAULT_FFT_SIZE: usize = 512;
+const MIN_FFT_SIZE: usize = 64;
+const MAX_FFT_SIZE: usize = 8192;
+
+/// Validate a transform size given on the command line.
+pub fn parse_fft_size(size: &str) -> Result<usize, String> {
+    match size.trim().parse::<usize>() {
+        Ok(size) if size.is_power_of_two() && (MIN_FFT_SIZE..=MAX_FFT_SIZE).contains(&size) => {
+            Ok(size)
+        }
+        _ => Err(format!(
+            "Invalid FFT size : {size}, use a power of two from {MIN_FFT_SIZE} to {MAX_FFT_SIZE}"
+        )),
+    }
+}
+
+/// Share of a transform window that overlaps with the next one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Overlap {
+    None,
+    #[default]
+    Half,
+    ThreeQuarters,
+}
+
+impl Overlap {
+    pub fn next(self) -> Self {
+        match self {
+            Self::None => Self::Half,
+            Self::Half => Self::ThreeQuarters,
+            Self::ThreeQuarters => Self::None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "0%",
+            Self::Half => "50%",
+            Self::ThreeQuarters => "75%",
+        }
+    }
+
+    /// Frames between the start of two windows of `fft_size` frames.
+    fn hop(self, fft_size: usize) -> usize {
+        match self {
+            Self::None => fft_size,
+            Self::Half => fft_size / 2,
+            Self::ThreeQuarters => fft_size / 4,
+        }
+    }
+}
+
+/// Trade off between the frequency resolution of the
+/// spectrum and how quickly it follows the audio.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpectrumSettings {
+    /// Frames per transform, a power of two.
+    pub fft_size: usize,
+    pub overlap: Overlap,
+    /// Analyse the window starting at the trigger crossing, when
+    /// there is one, for a spectrum of periodic signals that smears less.
+    pub anchored: bool,
+}
+
+impl Default for SpectrumSettings {
+    fn default() -> Self {
+        Self {
+            fft_size: DEFAULT_FFT_SIZE,
+            overlap: Overlap::default(),
+            anchored: false,
+        }
+    }
+}
+
+impl SpectrumSettings {
+    /// Double or halve the transform size.
+    pub fn adjust_fft_size(&mut self, increase: bool) {
+        self.fft_size = match increase {
+            true => self.fft_size * 2,
+            false => self.fft_size / 2,
+        }
+        .clamp(MIN_FFT_SIZE, MAX_FFT_SIZE);
+    }
+
+    /// Frames averaged into one spectrum, the latest two windows worth.
+    fn num_frames(&self) -> usize {
+        self.fft_size * 2
+    }
+}
+
+/// Weight of a new spectrum in the smoothed spectrum by default.
+pub const DEFAULT_SPECTRUM_SMOOTHING: f32 = 0.3;
+
+/// Portion of its level a held peak keeps each frame.
+const PEAK_HOLD_DECAY: f32 = 0.97;
+
+/// Validate a smoothing coefficient given on the command line.
+pub fn parse_spectrum_smoothing(coefficient: &str) -> Result<f32, String> {
+    match coefficient.trim().parse::<f32>() {
+        Ok(coefficient) if coefficient > 0. && coefficient <= 1. => Ok(coefficient),
+        _ => Err(format!(
+            "Invalid smoothing : {coefficient}, use a weight above 0 and up to 1"
+        )),
+    }
+}
+
+/// How the spectrum follows the audio from one frame to the next.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SpectrumResponse {
+    #[default]
+    Instant,
+    Smoothed,
+    /// Smoothed, with the peak of each bin held and slowly decaying.
+    PeakHold,
+}
+
+impl SpectrumResponse {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Instant => Self::Smoothed,
+            Self::Smoothed => Self::PeakHold,
+            Self::PeakHold => Self::Instant,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Instant => "instant",
+            Self::Smoothed => "smoothed",
+            Self::PeakHold => "peak hold",
+        }
+    }
+}
+
+/// Exponential moving average of each bin of the spectrum
+/// over the frames, along with the peak each bin reached.
+#[derive(Debug, Clone)]
+pub struct SpectrumSmoothing {
+    pub response: SpectrumResponse,
+    /// Weight of a new frame in the average, 1 follows the audio instantly.
+    coefficient: f32,
+    averages: Vec<Vec<f32>>,
+    peaks: Vec<Vec<f32>>,
+}
+
+impl Default for SpectrumSmoothing {
+    fn default() -> Self {
+        Self::new(DEFAULT_SPECTRUM_SMOOTHING)
+    }
+}
+
+impl SpectrumSmoothing {
+    pub fn new(coefficient: f32) -> Self {
+        Self {
+            response: SpectrumResponse::default(),
+            coefficient,
+            averages: vec![],
+            peaks: vec![],
+        }
+    }
+
+    /// Switch to the next response, starting over from the next frame.
+    pub fn cycle_response(&mut self) {
+        self.response = self.response.next();
+        self.averages.clear();
+        self.peaks.clear();
+    }
+
+    /// Blend a frame of magnitudes, per channel and bin, into the averages
+    /// and the peaks. They start over from the frame when the number of
+    /// channels or bins changed, e.g. with the transform size.
+    fn update(&mut self, frame: &[Vec<f32>]) {
+        let is_same_shape = self.averages.len() == frame.len()
+            && self
+                .averages
+                .iter()
+                .zip(frame)
+                .all(|(averages, magnitudes)| averages.len() == magnitudes.len());
+
+        if !is_same_shape {
+            self.averages = frame.to_vec();
+            self.peaks = frame.to_vec();
+            return;
+        }
+
+        let bins = self
+            .averages
+            .iter_mut()
+            .zip(self.peaks.iter_mut())
+            .zip(frame);
+        for ((averages, peaks), magnitudes) in bins {
+            for ((average, peak), &magnitude) in averages.iter_mut().zip(peaks).zip(magnitudes) {
+                *average += self.coefficient * (magnitude - *average);
+                *peak = (*peak * PEAK_HOLD_DECAY).max(magnitude);
+            }
+        }
+    }
+
+    /// Replace the magnitudes of the spectrum with their averages,
+    /// returning the held peaks if they are drawn.
+    fn apply(&mut self, data: &mut [SamplePoints]) -> Vec<SamplePoints> {
+        if self.response == SpectrumResponse::Instant {
+            return vec![];
+        }
+
+        let frame: Vec<Vec<f32>> = data
+            .iter()
+            .map(|points| {
+                points
+                    .iter()
+                    .map(|&(_, magnitude)| magnitude as f32)
+                    .collect()
+            })
+            .collect();
+        self.update(&frame);
+
+        for (points, averages) in data.iter_mut().zip(&self.averages) {
+            for ((_, magnitude), &average) in points.iter_mut().zip(averages) {
+                *magnitude = average as f64;
+            }
+        }
+
+        if self.response != SpectrumResponse::PeakHold {
+            return vec![];
+        }
+
+        data.iter()
+            .zip(&self.peaks)
+            .map(|(points, peaks)| {
+                points
+                    .iter()
+                    .zip(peaks)
+                    .map(|(&(frequency, _), &peak)| (frequency, peak as f64))
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Average the spectrum of the overlapping windows over the latest frames.
+/// Windows are zero-padded when fewer frames than the transform size are buffered.
+fn prepare_spectrum_data(
+    audio: &AudioBuffer,
+    settings: SpectrumSettings,
+    sample_rate: usize,
+) -> Vec<SamplePoints> {
+    let num_channels = audio.num_channels.max(1) as usize;
+    let num_frames = (audio.data.len() / num_channels).min(settings.num_frames());
+    let start = audio.data.len() - num_frames * num_channels;
+    let fft_size = settings.fft_size;
+    let hop = settings.overlap.hop(fft_size).max(1);
+
+    dsp::deinterleave(&audio.data[start..], num_channels)
+        .iter()
+        .map(|chan| {
+            let window_starts: Vec<usize> = match chan.len().checked_sub(fft_size) {
+                Some(last) => (0..=last).step_by(hop).collect(),
+                None => vec![0],
+            };
+
+            let mut magnitudes = vec![0.; fft_size / 2];
+            for &start in &window_starts {
+                let mut window = chan[start..]
+                    .iter()
+                    .take(fft_size)
+                    .copied()
+                    .collect::<Vec<_>>();
+                window.resize(fft_size, 0.);
+
+                for (sum, magnitude) in magnitudes.iter_mut().zip(dsp::magnitude_spectrum(&window))
+                {
+                    *sum += magnitude / window_starts.len() as f32;
+                }
+            }
+
+            magnitudes
+                .into_iter()
+                .enumerate()
+                .map(|(bin, magnitude)| {
+                    let frequency = dsp::bin_frequency(bin, fft_size, sample_rate);
+                    (frequency as f64, magnitude as f64)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// How the spectrum is computed and drawn.
+pub struct SpectrumView<'a> {
+    pub style: LineStyle,
+    pub settings: SpectrumSettings,
+    pub smoothing: &'a mut SpectrumSmoothing,
+    pub sample_rate: usize,
+}
+
+pub fn render_spectrum(
+    f: &mut Frame,
+    area: Rect,
+    title: &str,
+    audio: &AudioBuffer,
+    view: SpectrumView,
+) {
+    let SpectrumView {
+        style,
+        settings,
+        smoothing,
+        sample_rate,
+    } = view;
+    let mut data = prepare_spectrum_data(audio, settings, sample_rate);
+    let peaks = smoothing.apply(&mut data);
+    let data = style.shape(data);
+    let nyquist = sample_rate as f64 / 2.;
+    let frequency_label = |frequency: f64| match frequency >= 1000. {
+        true => format!(" {:.1}k ", frequency / 1000.),
+        false => format!(" {frequency:.0} "),
+    };
+
+    let mut datasets: Vec<_> = peaks
+        .iter()
+        .map(|points| {
+            Dataset::default()
+                .marker(theme::marker())
+                .graph_type(GraphType::Scatter)
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
+                .data(points)
+        })
+        .collect();
+    datasets.extend(create_datasets(&data, style));
+
+    let chart = Chart::new(datasets)
+        .block(block(title, false))
+        .x_axis(
+            Axis::default()
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
+                .labels(
+                    [0., nyquist / 2., nyquist]
+                        .map(|frequency| frequency_label(frequency).bold())
+                        .to_vec(),
+                )
+                .bounds([0., nyquist]),
+        )
+        .y_axis(
+            Axis::default()
+                .style(theme::style(Style::default().fg(Color::DarkGray)))
+                .labels(vec![" 0 ".bold(), " 1 ".bold()])
+                .bounds([0., 1.]),
+        );
+
+    f.render_widget(chart, area);
+}
+
+/// Channels drawn against each other on the axes of the vectorscope.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelPair {
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Default for ChannelPair {
+    fn default() -> Self {
+        Self { x: 0, y: 1 }
+    }
+}
+
+impl ChannelPair {
+    /// Move the X axis to the next channel, skipping the one on the Y axis.
+    pub fn next_x(self, num_channels: usize) -> Self {
+        Self {
+            x: next_free_channel(self.x, self.y, num_channels),
+            ..self
+        }
+    }
+
+    /// Move the Y axis to the next channel, skipping the one on the X axis.
+    pub fn next_y(self, num_channels: usize) -> Self {
+        Self {
+            y: next_free_channel(self.y, self.x, num_channels),
+            ..self
+        }
+    }
+
+    /// The pair to draw for a number of channels, falling back to the
+    /// first two channels if a channel is missing or both are the same.
+    /// There is none with fewer than two channels.
+    pub fn clamp(self, num_channels: usize) -> Option<Self> {
+        if num_channels < 2 {
+            return None;
+        }
+
+        match self.x.max(self.y) < num_channels && self.x != self.y {
+            true => Some(self),
+            false => Some(Self::default()),
+        }
+    }
+
+    pub fn name(&self) -> String {
+        format!("x : ch {} / y : ch {}", self.x + 1, self.y + 1)
+    }
+}
+
+/// Channel after `channel` that is not `other`, wrapping around.
+fn next_free_channel(channel: usize, other: usize, num_channels: usize) -> usize {
+    if num_channels < 2 {
+        return channel;
+    }
+
+    let next = (channel + 1) % num_channels;
+    match next == other {
+        true => (next + 1) % num_channels,
+        false => next,
+    }
+}
+
+/// A point per frame, the sample of the X channel against the sample of the Y channel.
+fn prepare_vector_data(audio: &AudioBuffer, pair: ChannelPair, gain: f32) -> SamplePoints {
+    let num_channels = audio.num_channels.max(1) as usize;
+    audio
+        .data
+        .chunks_exact(num_channels)
+        .filter_map(|frame| Some((*frame.get(pair.x)?, *frame.get(pair.y)?)))
+        .map(|(x, y)| ((x * gain) as f64, (y * gain) as f64))
+        .collect()
+}
+
+/// Correlation below which the channels are taken to be out of phase,
+/// mostly cancelling each other out when summed to mono.
+pub const PHASE_WARNING_CORRELATION: f32 = -0.5;
+
+/// Correlation of the channels of the pair, see [`dsp::correlation`].
+/// There is none with fewer than two channels.
+pub fn pair_correlation(audio: &AudioBuffer, pair: ChannelPair) -> Option<f32> {
+    let num_channels = audio.num_channels.max(1) as usize;
+    let pair = pair.clamp(num_channels)?;
+    let channels = dsp::deinterleave(&audio.data, num_channels);
+    Some(dsp::correlation(&channels[pair.x], &channels[pair.y]))
+}
+
+/// A meter from -1 to +1 marking the correlation, followed by its value,
+/// e.g. `-1 ────┼──●─ +1 +0.60`. Only the value fits in narrow areas.
+fn correlation_meter(correlation: f32, width: usize) -> String {
+    let readout = format!("{correlation:+.2}");
+    let Some(num_columns) = width.checked_sub(readout.len() + 7).filter(|&n| n >= 3) else {
+        return readout;
+    };
+
+    // an odd number of columns has a center
+    let last = (num_columns - 1) / 2 * 2;
+    let marker = ((correlation.clamp(-1., 1.) + 1.) / 2. * last as f32).round() as usize;
+    let meter: String = (0..=last)
+        .map(|column| match column {
+            column if column == marker => '●',
+            column if column == last / 2 => '┼',
+            _ => '─',
+        })
+        .collect();
+    format!("-1 {meter} +1 {readout}")
+}
+
+/// Draw the correlation meter on the first row of the area, in red
+/// once the channels are out of phase.
+fn render_correlation(f: &mut Frame, area: Rect, correlation: f32) {
+    let color = match correlation {
+        c if c < PHASE_WARNING_CORRELATION => Color::Red,
+        c if c < 0. => Color::Yellow,
+        _ => Color::Green,
+    };
+    let meter = correlation_meter(correlation, area.width as usize);
+    let style = theme::style(Style::default().fg(color));
+    f.buffer_mut().set_string(area.x, area.y, meter, style);
+}
+
+/// Draw a pair of channels against each other, one point per frame,
+/// above the meter of their correlation.
+pub fn render_vector(
+    f: &mut Frame,
+    area: Rect,
+    block: Block,
+    audio: &AudioBuffer,
+    pair: ChannelPair,
+    gain: f32,
+) {
+    let num_channels = audio.num_channels.max(1) as usize;
+    let Some(pair) = pair.clamp(num_channels) else {
+        let text = Paragraph::new("the vectorscope needs at least two channels")
+            .style(theme::style(Style::default().fg(Color::DarkGray)))
+            .block(block);
+        f.render_widget(text, area);
+        return;
+    };
+
+    let points = prepare_vector_data(audio, pair, gain);
+    let dataset = Dataset::default()
+        .marker(theme::marker())
+        .graph_type(GraphType::Scatter)
+        .style(theme::style(Style::default().fg(theme::channel_color(0))))
+        .data(&points);
+
+    let axis = |label: String| {
+        Axis::default()
+            .title(label)
+            .style(theme::style(Style::default().fg(Color::DarkGray)))
+            .bounds([-1., 1.])
+    };
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+    let sections = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .split(inner);
+
+    let chart = Chart::new(vec![dataset])
+        .x_axis(axis(format!("ch {}", pair.x + 1)))
+        .y_axis(axis(format!("ch {}", pair.y + 1)));
+    f.render_widget(chart, sections[0]);
+
+    if let Some(correlation) = pair_correlation(audio, pair) {
+        render_correlation(f, sections[1], correlation);
+    }
+}
+
+/// Glyphs of a sparkline, from the lowest level to the highest.
+const SPARKLINE_GLYPHS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Glyph drawn for a sample, -1 being empty and 1 being a full block.
+fn sparkline_glyph(sample: f32) -> char {
+    let level = (sample.clamp(-1., 1.) + 1.) / 2. * (SPARKLINE_GLYPHS.len() - 1) as f32;
+    SPARKLINE_GLYPHS[level.round() as usize]
+}
+
+/// The sample of largest magnitude among those of each column,
+/// so that peaks are not missed.
+fn sparkline_peaks(samples: &[f32], width: usize) -> Vec<f32> {
+    if samples.is_empty() || width == 0 {
+        return vec![];
+    }
+
+    let samples_per_column = samples.len().div_ceil(width);
+    samples
+        .chunks(samples_per_column)
+        .map(|column| {
+            column
+                .iter()
+                .copied()
+                .fold(0f32, |peak, sample| match sample.abs() > peak.abs() {
+                    true => sample,
+                    false => peak,
+                })
+        })
+        .collect()
+}
+
+/// A glyph per column, drawing the peak of the column.
+fn prepare_sparkline(samples: &[f32], width: usize, gain: f32) -> String {
+    sparkline_peaks(samples, width)
+        .into_iter()
+        .map(|peak| sparkline_glyph(peak * gain))
+        .collect()
+}
+
+/// Draw a channel of the audio on the first row of the area, to embed a
+/// tiny scope in a status bar. Nothing is drawn if the channel is missing.
+pub fn render_sparkline(f: &mut Frame, area: Rect, audio: &AudioBuffer, channel: usize, gain: f32) {
+    let channels = audio.deinterleave();
+    let Some(samples) = channels.get(channel) else {
+        return;
+    };
+
+    let line = prepare_sparkline(samples, area.width as usize, gain);
+    let style = theme::style(Style::default().fg(theme::channel_color(channel)));
+    f.buffer_mut().set_string(area.x, area.y, line, style);
+}
+
+/// Draw each channel as a sparkline on its own row, after its label,
+/// with as many channels as fit in the area.
+pub fn render_sparklines(
+    f: &mut Frame,
+    area: Rect,
+    block: Block,
+    audio: &AudioBuffer,
+    gain: f32,
+    labels: &[String],
+) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let num_channels = audio.num_channels.max(1) as usize;
+    let label_width = (0..num_channels)
+        .map(|channel| sparkline_label(labels, channel).chars().count())
+        .max()
+        .unwrap_or_default() as u16
+        + 1;
+
+    for (channel, y) in (0..num_channels).zip(inner.top()..inner.bottom()) {
+        let label = sparkline_label(labels, channel);
+        let style = theme::style(Style::default().fg(Color::DarkGray));
+        f.buffer_mut()
+            .set_stringn(inner.x, y, label, inner.width as usize, style);
+
+        let line = Rect::new(
+            inner.x + label_width,
+            y,
+            inner.width.saturating_sub(label_width),
+            1,
+        );
+        if line.width > 0 {
+            render_sparkline(f, line, audio, channel, gain);
+        }
+    }
+}
+
+fn sparkline_label(labels: &[String], channel: usize) -> String {
+    labels
+        .get(channel)
+        .cloned()
+        .unwrap_or_else(|| format!("ch {}", channel + 1))
+}
+
+/// Traces of the exported image, in the colors and with
+/// the legend of the datasets of [`create_datasets`].
+fn plot_traces(data: Vec<SamplePoints>) -> Vec<plot::Trace> {
+    let num_traces = data.len();
+    data.into_iter()
+        .enumerate()
+        .map(|(i, points)| plot::Trace {
+            name: (num_traces > 1).then(|| theme::channel_legend(i)),
+            color: theme::channel_color(i),
+            points,
+        })
+        .collect()
+}
+
+/// The waveform drawn by [`render`] over `width` columns, to export it.
+pub fn waveform_lane(audio: &AudioBuffer, view: &ScopeView, width: usize) -> plot::Lane {
+    plot::Lane {
+        label: None,
+        traces: plot_traces(prepare_trace(audio, view, width)),
+        x_bounds: [0., width as f64],
+        y_bounds: [-1., 1.],
+        scatter: view.style.is_scattered(),
+    }
+}
+
+/// The lanes drawn by [`render_stacked`], to export them.
+pub fn stacked_lanes(
+    audio: &AudioBuffer,
+    view: &ScopeView,
+    width: usize,
+    first_channel: usize,
+    labels: &[String],
+) -> Vec<plot::Lane> {
+    let channels = audio.deinterleave();
+    let channels = channels.get(first_channel..).unwrap_or_default();
+    channels
+        .iter()
+        .enumerate()
+        .map(|(i, channel)| plot::Lane {
+            label: Some(sparkline_label(labels, first_channel + i)),
+            ..waveform_lane(&AudioBuffer::from_deinterleaved(&[channel]), view, width)
+        })
+        .collect()
+}
+
+/// The spectrum drawn by [`render_spectrum`], to export it. The smoothing
+/// is updated as if the spectrum was drawn, export with a copy of it.
+pub fn spectrum_lane(audio: &AudioBuffer, view: SpectrumView) -> plot::Lane {
+    let mut data = prepare_spectrum_data(audio, view.settings, view.sample_rate);
+    let peaks = view.smoothing.apply(&mut data);
+    let mut traces: Vec<_> = peaks
+        .into_iter()
+        .map(|points| plot::Trace {
+            name: None,
+            color: Color::DarkGray,
+            points,
+        })
+        .collect();
+    traces.extend(plot_traces(view.style.shape(data)));
+
+    plot::Lane {
+        label: None,
+        traces,
+        x_bounds: [0., view.sample_rate as f64 / 2.],
+        y_bounds: [0., 1.],
+        scatter: view.style.is_scattered(),
+    }
+}
+
+/// The points drawn by [`render_vector`], to export them.
+/// There are none with fewer than two channels.
+pub fn vector_lane(audio: &AudioBuffer, pair: ChannelPair, gain: f32) -> plot::Lane {
+    let num_channels = audio.num_channels.max(1) as usize;
+    let traces = match pair.clamp(num_channels) {
+        Some(pair) => vec![plot::Trace {
+            name: None,
+            color: theme::channel_color(0),
+            points: prepare_vector_data(audio, pair, gain),
+        }],
+        None => vec![],
+    };
+
+    plot::Lane {
+        label: pair
+            .clamp(num_channels)
+            .map(|pair| format!("ch {} / ch {}", pair.x + 1, pair.y + 1)),
+        traces,
+        x_bounds: [-1., 1.],
+        y_bounds: [-1., 1.],
+        scatter: true,
+    }
+}
+
+/// The sparklines drawn by [`render_sparklines`] over `width`
+/// columns, a lane per channel joining the peak of each column.
+pub fn sparkline_lanes(
+    audio: &AudioBuffer,
+    gain: f32,
+    labels: &[String],
+    width: usize,
+) -> Vec<plot::Lane> {
+    audio
+        .deinterleave()
+        .iter()
+        .enumerate()
+        .map(|(channel, samples)| {
+            let points = sparkline_peaks(samples, width)
+                .into_iter()
+                .enumerate()
+                .map(|(column, peak)| (column as f64, (peak * gain) as f64))
+                .collect();
+            plot::Lane {
+                label: Some(sparkline_label(labels, channel)),
+                traces: vec![plot::Trace {
+                    name: None,
+                    color: theme::channel_color(channel),
+                    points,
+                }],
+                x_bounds: [0., width as f64],
+                y_bounds: [-1., 1.],
+                scatter: false,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn the_trace_is_prepared_again_only_when_an_input_changes() {
+        let key = TraceKey {
+            source: 1,
+            width: 80,
+            downsample: 16,
+            gain: 1.,
+            decimation: Decimation::MinMax,
+            style: LineStyle::Braille,
+        };
+        let mut cache = TraceCache::default();
+        let mut num_prepared = 0;
+        let mut prepare = |key: TraceKey| {
+            cache.get_or_prepare(key, || {
+                num_prepared += 1;
+                vec![vec![(0., key.gain as f64)]]
+            });
+        };
+
+        prepare(key);
+        prepare(key);
+        prepare(TraceKey { gain: 2., ..key });
+        prepare(TraceKey { gain: 2., ..key });
+        prepare(TraceKey {
+            source: 2,
+            gain: 2.,
+            ..key
+        });
+        prepare(TraceKey { width: 100, ..key });
+        assert_eq!(num_prepared, 4);
+        assert_eq!(cache.trace, [vec![(0., 1.)]]);
+    }
+
+    #[test]
+    fn spectrum_bins_follow_the_chosen_fft_size() {
+        let sample_rate = 48_000;
+        let sine: Vec<f32> = (0..4_096)
+            .map(|n| (std::f32::consts::TAU * 3_000. * n as f32 / sample_rate as f32).sin())
+            .collect();
+        let audio = AudioBuffer::from_deinterleaved(&[sine]);
+        let peak = |points: &SamplePoints| {
+            points.iter().copied().fold(
+                (0., 0.),
+                |peak, point| if point.1 > peak.1 { point } else { peak },
+            )
+        };
+
+        let mut settings = SpectrumSettings::default();
+        let points = &prepare_spectrum_data(&audio, settings, sample_rate)[0];
+        assert_eq!(points.len(), 256);
+        assert_eq!(points[1].0, 93.75);
+        assert_eq!(peak(points).0, 3_000.);
+
+        settings.adjust_fft_size(true);
+        let points = &prepare_spectrum_data(&audio, settings, sample_rate)[0];
+        assert_eq!(points.len(), 512);
+        assert_eq!(points[1].0, 46.875);
+        assert_eq!(peak(points).0, 3_000.);
+
+        // fewer buffered frames than the transform size are zero-padded
+        settings.adjust_fft_size(true);
+        settings.adjust_fft_size(true);
+        settings.adjust_fft_size(true);
+        assert_eq!(settings.fft_size, 8_192);
+        let points = &prepare_spectrum_data(&audio, settings, sample_rate)[0];
+        assert_eq!(points.len(), 4_096);
+    }
+
+    #[test]
+    fn fft_sizes_must_be_powers_of_two() {
+        assert_eq!(parse_fft_size("1024"), Ok(1024));
+        assert!(parse_fft_size("1000").is_err());
+        assert!(parse_fft_size("32").is_err());
+        assert!(parse_fft_size("big").is_err());
+    }
+
+    #[test]
+    fn gain_is_applied_to_the_rendered_samples() {
+        let audio = AudioBuffer::from_deinterleaved(&[[0.1, -0.2, 0.3, -0.4]]);
+
+        let points = prepare_audio_data(&audio, 1, 4, 2.);
+        let samples: Vec<f64> = points[0].iter().map(|&(_, sample)| sample).collect();
+        assert_eq!(samples, [-0.8, 0.6, -0.4, 0.2].map(|s: f32| s as f64));
+    }
+
+    #[test]
+    fn min_max_decimation_keeps_the_spikes_that_stride_sampling_misses() {
+        let mut samples = [0.; 16];
+        samples[5] = 1.;
+        samples[10] = -0.5;
+        let audio = AudioBuffer::from_deinterleaved(&[samples]);
+
+        let strided = &prepare_audio_data(&audio, 4, 4, 1.)[0];
+        assert!(strided.iter().all(|&(_, sample)| sample == 0.));
+
+        let decimated = &prepare_decimated_audio_data(&audio, 4, 4, 1.)[0];
+        assert_eq!(
+            decimated,
+            &[
+                (0., 0.),
+                (0., 0.),
+                (1., 0.),
+                (1., -0.5),
+                (2., 0.),
+                (2., 1.),
+                (3., 0.),
+                (3., 0.),
+            ]
+        );
+    }
+
+    /// Draw a rising ramp filling a small area with no borders or axes.
+    fn render_ramp(style: LineStyle) -> Vec<String> {
+        let ramp = style.shape(vec![vec![(0., 0.), (1., 1.), (2., 2.), (3., 3.)]]);
+        let chart = Chart::new(create_datasets(&ramp, style))
+            .x_axis(Axis::default().bounds([0., 3.]))
+            .y_axis(Axis::default().bounds([0., 3.]));
+
+        let mut terminal = Terminal::new(backend::TestBackend::new(4, 4)).unwrap();
+        terminal.draw(|f| f.render_widget(chart, f.size())).unwrap();
+
+        let buffer = terminal.backend().buffer();
+        (0..4)
+            .map(|y| {
+                (0..4)
+                    .map(|x| buffer.get(x, y).symbol().to_owned())
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn each_line_style_draws_a_ramp_with_its_own_cells() {
+        theme::set_colors_enabled(true);
+
+        let cases = [
+            (LineStyle::Braille, ["   ⠈", "  ⠂ ", " ⠄  ", "⡀   "]),
+            (LineStyle::Line, ["  ⢀⠎", " ⢀⠎ ", " ⡎  ", "⡜   "]),
+            (LineStyle::Points, ["   •", "  • ", " •  ", "•   "]),
+            (LineStyle::Bars, ["   █", "  ██", " ███", "████"]),
+        ];
+
+        for (style, expected) in cases {
+            assert_eq!(render_ramp(style), expected, "{style:?}");
+        }
+    }
+
+    #[test]
+    fn stacked_lanes_are_titled_with_their_label_or_number() {
+        let audio = AudioBuffer::from_deinterleaved(&[[0.; 8], [0.; 8], [0.; 8]]);
+        let view = ScopeView {
+            downsample: 1,
+            gain: 1.,
+            style: LineStyle::default(),
+            decimation: Decimation::default(),
+            grid: Grid::default(),
+            cursors: vec![],
+            source: None,
+        };
+
+        let mut terminal = Terminal::new(backend::TestBackend::new(16, 9)).unwrap();
+        terminal
+            .draw(|f| {
+                let labels = ["Kick".to_owned(), "Snare".to_owned()];
+                render_stacked(f, f.size(), Block::default(), &audio, &view, 1, &labels)
+            })
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let row = |y| {
+            (0..16)
+                .map(|x| buffer.get(x, y).symbol())
+                .collect::<String>()
+        };
+        let titles: Vec<_> = (0..9).map(row).filter(|row| row.contains('˧')).collect();
+        assert_eq!(titles.len(), 2);
+        assert!(titles[0].contains("Snare"), "{titles:?}");
+        assert!(titles[1].contains("ch 3"), "{titles:?}");
+    }
+
+    #[test]
+    fn overlaid_channels_are_drawn_in_their_own_color_with_a_legend() {
+        theme::set_colors_enabled(true);
+
+        let audio = AudioBuffer::from_deinterleaved(&[[0.5; 64], [-0.5; 64], [0.; 64]]);
+        let view = ScopeView {
+            downsample: 1,
+            gain: 1.,
+            style: LineStyle::default(),
+            decimation: Decimation::default(),
+            grid: Grid::default(),
+            cursors: vec![],
+            source: None,
+        };
+
+        let mut terminal = Terminal::new(backend::TestBackend::new(64, 24)).unwrap();
+        terminal
+            .draw(|f| render(f, f.size(), Block::default(), &audio, &view, None, None))
+            .unwrap();
+
+        let buffer = terminal.backend().buffer();
+        let text: String = buffer
+            .content()
+            .chunks(64)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>() + "\n")
+            .collect();
+        for channel in 1..=3 {
+            assert!(text.contains(&format!("ch {channel}")), "{text}");
+        }
+
+        let colors: std::collections::HashSet<_> = buffer
+            .content()
+            .iter()
+            .filter(|cell| cell.symbol().starts_with(|c| ('⠁'..='⣿').contains(&c)))
+            .map(|cell| cell.fg)
+            .collect();
+        let palette: std::collections::HashSet<_> = (0..3).map(theme::channel_color).collect();
+        assert_eq!(colors, palette);
+    }
+
+    #[test]
+    fn overlaid_channels_get_their_own_glyph_without_colors() {
+        theme::set_colors_enabled(false);
+
+        let audio = AudioBuffer::from_deinterleaved(&[[0.5; 64], [-0.5; 64], [0.; 64]]);
+        let view = ScopeView {
+            downsample: 1,
+            gain: 1.,
+            style: LineStyle::default(),
+            decimation: Decimation::default(),
+            grid: Grid::default(),
+            cursors: vec![],
+            source: None,
+        };
+
+        let mut terminal = Terminal::new(backend::TestBackend::new(64, 24)).unwrap();
+        terminal
+            .draw(|f| render(f, f.size(), Block::default(), &audio, &view, None, None))
+            .unwrap();
+        theme::set_colors_enabled(true);
+
+        let buffer = terminal.backend().buffer();
+        let rows: Vec<String> = buffer
+            .content()
+            .chunks(64)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect();
+        let text = rows.join("\n");
+        assert!(buffer.content().iter().all(|cell| cell.fg == Color::Reset));
+
+        // each channel is a flat line of its glyph, named after it in the legend
+        for (channel, glyph) in ["•", "█", "▄"].into_iter().enumerate() {
+            let legend = format!("{glyph} ch {}", channel + 1);
+            assert!(text.contains(&legend), "{text}");
+            let line = rows
+                .iter()
+                .filter(|row| !row.contains(&legend))
+                .any(|row| row.matches(glyph).count() > 32);
+            assert!(line, "{text}");
+        }
+    }
+
+    #[test]
+    fn vector_pairs_cycle_without_sharing_a_channel() {
+        let pair = ChannelPair::default();
+        assert_eq!(pair.next_x(4), ChannelPair { x: 2, y: 1 });
+        assert_eq!(pair.next_y(4), ChannelPair { x: 0, y: 2 });
+        assert_eq!(pair.next_x(2), pair);
+
+        let pair = ChannelPair { x: 3, y: 0 };
+        assert_eq!(pair.next_x(4), ChannelPair { x: 1, y: 0 });
+        assert_eq!(pair.next_y(4), ChannelPair { x: 3, y: 1 });
+
+        assert_eq!(pair.clamp(4), Some(pair));
+        assert_eq!(pair.clamp(2), Some(ChannelPair::default()));
+        assert_eq!(
+            ChannelPair { x: 1, y: 1 }.clamp(4),
+            Some(ChannelPair::default())
+        );
+        assert_eq!(pair.clamp(1), None);
+    }
+
+    #[test]
+    fn vector_points_map_the_chosen_channels_to_the_axes() {
+        let audio =
+            AudioBuffer::from_deinterleaved(&[[0.1, 0.2], [0.3, 0.4], [0.5, 0.6], [0.7, 0.8]]);
+        let pair = ChannelPair { x: 3, y: 1 };
+
+        let points: Vec<_> = prepare_vector_data(&audio, pair, 2.)
+            .into_iter()
+            .map(|(x, y)| ((x * 10.).round(), (y * 10.).round()))
+            .collect();
+        assert_eq!(points, [(14., 6.), (16., 8.)]);
+    }
+
+    #[test]
+    fn gridlines_are_drawn_on_the_rows_of_their_amplitude() {
+        let rows_drawn = |grid: Grid, height: u16| {
+            let area = Rect::new(0, 0, 4, height);
+            let mut buffer = Buffer::empty(area);
+            grid.render(area, &mut buffer);
+            (0..height)
+                .filter(|&y| buffer.get(0, y).symbol() != " ")
+                .collect::<Vec<_>>()
+        };
+
+        assert!(rows_drawn(Grid::Off, 9).is_empty());
+        assert_eq!(rows_drawn(Grid::Center, 9), [4]);
+        assert_eq!(rows_drawn(Grid::Halves, 9), [0, 2, 4, 6, 8]);
+        assert_eq!(rows_drawn(Grid::Quarters, 9), [0, 1, 2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(rows_drawn(Grid::Halves, 21), [0, 5, 10, 15, 20]);
+        assert_eq!(rows_drawn(Grid::Quarters, 3), [0, 1, 2]);
+    }
+
+    #[test]
+    fn cursor_columns_map_to_the_drawn_samples() {
+        let audio = AudioBuffer::from_deinterleaved(&[[0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7]]);
+        let columns = num_columns(audio.data.len(), 2, 80);
+        assert_eq!(columns, 4);
+        assert_eq!(num_columns(audio.data.len(), 2, 3), 3);
+
+        let points = &prepare_audio_data(&audio, 2, columns, 1.)[0];
+        for (column, &(_, sample)) in points.iter().enumerate() {
+            let index = sample_at_column(column, 2, columns).unwrap();
+            assert_eq!(audio.data[index] as f64, sample);
+        }
+
+        assert_eq!(sample_at_column(0, 2, columns), Some(7));
+        assert_eq!(sample_at_column(3, 2, columns), Some(1));
+        assert_eq!(sample_at_column(4, 2, columns), None);
+    }
+
+    #[test]
+    fn the_number_of_drawn_samples_follows_the_area_width() {
+        let audio = AudioBuffer::from_deinterleaved(&[vec![0.5; 1_000]]);
+        let num_drawn = |width| {
+            let area = Rect::new(0, 0, width, 10);
+            num_columns(audio.num_frames(), 1, drawable_width(area))
+        };
+
+        assert_eq!(num_drawn(40), 38);
+        assert_eq!(num_drawn(80), 78);
+    }
+
+    #[test]
+    fn stacked_lanes_share_the_area_up_to_their_minimum_height() {
+        let area = Rect::new(0, 10, 80, 20);
+
+        let lanes = lane_areas(area, 4);
+        assert_eq!(lanes.len(), 4);
+        assert!(lanes.iter().all(|lane| lane.height == 5));
+        assert_eq!(lanes[0].y, 10);
+        assert_eq!(lanes[3].y, 25);
+
+        let lanes = lane_areas(area, 16);
+        assert_eq!(lanes.len(), 20 / MIN_LANE_HEIGHT as usize);
+        assert!(lanes.iter().all(|lane| lane.height == MIN_LANE_HEIGHT));
+
+        assert_eq!(lane_areas(Rect::new(0, 0, 80, 2), 3).len(), 1);
+        assert!(lane_areas(area, 0).is_empty());
+    }
+
+    #[test]
+    fn persisted_traces_fade_out_with_age() {
+        assert_eq!(persistence_intensity(0), 1.);
+        for age in 1..DEFAULT_PERSISTENCE_DEPTH {
+            let intensity = persistence_intensity(age);
+            assert!((intensity - persistence_intensity(age - 1) * PERSISTENCE_DECAY).abs() < 1e-6);
+        }
+
+        assert_eq!(persistence_color(1.), Color::Indexed(255));
+        assert_eq!(persistence_color(0.), Color::Indexed(232));
+        assert_eq!(persistence_color(2.), Color::Indexed(255));
+    }
+
+    #[test]
+    fn persistence_keeps_up_to_its_depth_of_traces() {
+        let mut persistence = Persistence::new(2);
+        for i in 0..4 {
+            persistence.push(vec![vec![(0., i as f64)]]);
+        }
+
+        let traces: Vec<_> = persistence
+            .history
+            .iter()
+            .map(|trace| trace[0][0].1)
+            .collect();
+        assert_eq!(traces, [3., 2.]);
+        assert_eq!(
+            create_persistence_datasets(&persistence, LineStyle::default()).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn a_ramp_climbs_through_every_sparkline_glyph() {
+        let ramp: Vec<f32> = (0..9).map(|i| i as f32 / 4. - 1.).collect();
+        assert_eq!(prepare_sparkline(&ramp, 9, 1.), " ▁▂▃▄▅▆▇█");
+
+        assert_eq!(sparkline_glyph(-2.), ' ');
+        assert_eq!(sparkline_glyph(2.), '█');
+        assert_eq!(prepare_sparkline(&ramp, 9, 0.), "▄▄▄▄▄▄▄▄▄");
+        assert_eq!(prepare_sparkline(&[], 3, 1.), "");
+    }
+
+    #[test]
+    fn the_correlation_meter_marks_the_correlation_around_its_center() {
+        assert_eq!(correlation_meter(0., 20), "-1 ───●─── +1 +0.00");
+        assert_eq!(correlation_meter(1., 20), "-1 ───┼──● +1 +1.00");
+        assert_eq!(correlation_meter(-1., 20), "-1 ●──┼─── +1 -1.00");
+        assert_eq!(correlation_meter(0.5, 21), "-1 ────┼─●── +1 +0.50");
+        assert_eq!(correlation_meter(-0.25, 8), "-0.25");
+    }
+
+    #[test]
+    fn each_bin_of_the_spectrum_is_smoothed_over_the_frames() {
+        let mut smoothing = SpectrumSmoothing::new(0.5);
+        smoothing.update(&[vec![1., 0.]]);
+        assert_eq!(smoothing.averages, [[1., 0.]]);
+
+        smoothing.update(&[vec![0., 1.]]);
+        assert_eq!(smoothing.averages, [[0.5, 0.5]]);
+        assert_eq!(smoothing.peaks, [[PEAK_HOLD_DECAY, 1.]]);
+
+        smoothing.update(&[vec![0., 1.]]);
+        assert_eq!(smoothing.averages, [[0.25, 0.75]]);
+        assert_eq!(smoothing.peaks, [[PEAK_HOLD_DECAY * PEAK_HOLD_DECAY, 1.]]);
+
+        // a new transform size starts over
+        smoothing.update(&[vec![0.2, 0.4, 0.6]]);
+        assert_eq!(smoothing.averages, [[0.2, 0.4, 0.6]]);
+
+        let mut data = vec![vec![(0., 0.), (10., 0.), (20., 0.)]];
+        assert!(smoothing.apply(&mut data).is_empty());
+        assert_eq!(data, [[(0., 0.), (10., 0.), (20., 0.)]]);
+
+        smoothing.cycle_response();
+        smoothing.cycle_response();
+        assert_eq!(smoothing.response, SpectrumResponse::PeakHold);
+        let peaks = smoothing.apply(&mut data);
+        assert_eq!(peaks, data);
+    }
 }
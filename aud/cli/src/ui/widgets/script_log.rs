@@ -0,0 +1,58 @@
+use crate::ui::theme;
+use aud::controllers::audio_midi::{ScriptMessage, ScriptMessageKind};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Borders, List, ListItem},
+};
+
+fn color_for(kind: ScriptMessageKind) -> Color {
+    match kind {
+        ScriptMessageKind::Log => Color::Gray,
+        ScriptMessageKind::Alert => Color::Yellow,
+        ScriptMessageKind::Error => Color::Red,
+    }
+}
+
+fn label_for(kind: ScriptMessageKind) -> &'static str {
+    match kind {
+        ScriptMessageKind::Log => "log",
+        ScriptMessageKind::Alert => "alert",
+        ScriptMessageKind::Error => "error",
+    }
+}
+
+/// Draw the script messages, the most recent first,
+/// skipping the `scroll` most recent ones.
+pub fn render(f: &mut Frame, title: &str, messages: &[ScriptMessage], area: Rect, scroll: usize) {
+    let num_visible = area.height as usize;
+
+    let items: Vec<ListItem> = messages
+        .iter()
+        .rev()
+        .skip(scroll)
+        .take(num_visible)
+        .map(|msg| {
+            let color = theme::style(Style::default().fg(color_for(msg.kind)));
+            ListItem::new(Line::from(vec![
+                Span::styled(
+                    format!("[ {} ]", crate::ui::time_of_day(msg.time)),
+                    theme::style(Style::default().fg(Color::DarkGray)),
+                ),
+                Span::styled(format!(" {} : ", label_for(msg.kind)), color),
+                Span::styled(msg.text.clone(), color),
+            ]))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(theme::style(Style::default().fg(Color::DarkGray)))
+            .title(Span::styled(
+                title,
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+    );
+
+    f.render_widget(list, area);
+}
@@ -0,0 +1,18 @@
+use ratatui::{prelude::*, widgets::*};
+
+/// Render a single line of status items separated by a divider.
+pub fn render(f: &mut Frame, area: Rect, items: &[String]) {
+    let mut spans = vec![];
+
+    for (i, item) in items.iter().filter(|item| !item.is_empty()).enumerate() {
+        if i > 0 {
+            spans.push(Span::styled(" │ ", Style::default().fg(Color::DarkGray)));
+        }
+        spans.push(Span::styled(
+            item.as_str(),
+            Style::default().fg(Color::Gray),
+        ));
+    }
+
+    f.render_widget(Paragraph::new(Line::from(spans)), area);
+}
@@ -1,6 +1,12 @@
+#[cfg(test)]
+pub mod harness;
+
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
 use ratatui::prelude::*;
-use std::time::{Duration, Instant};
+use std::{
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 pub enum Flow {
     Continue,
@@ -21,51 +27,329 @@ pub trait Base {
 
     /// Render the terminal UI frame
     fn render(&mut self, frame: &mut Frame);
+
+    /// When the app last received audio or MIDI, for the idle timeout.
+    fn last_input(&self) -> Option<Instant> {
+        None
+    }
+
+    /// Called once when the app stops, whether it quit,
+    /// was interrupted or failed. Finalize any file output here.
+    fn on_shutdown(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Stops the app by itself, for captures without anyone at the keyboard.
+#[derive(Debug, Default, Clone)]
+pub struct Limit {
+    /// Number of frames after which the app exits.
+    pub frames: Option<usize>,
+    /// Time after which the app exits.
+    pub duration: Option<Duration>,
+    /// File the last frame is written to as text, once a limit is reached.
+    pub snapshot: Option<PathBuf>,
+    /// Time without audio, MIDI or key presses after which the app exits.
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Limit {
+    fn is_reached(&self, num_frames: usize, elapsed: Duration) -> bool {
+        self.frames.is_some_and(|frames| num_frames >= frames)
+            || self.duration.is_some_and(|duration| elapsed >= duration)
+    }
+}
+
+/// Tells when neither the devices nor the user did anything for a while.
+#[derive(Debug)]
+struct IdleTimer {
+    timeout: Duration,
+    last_activity: Instant,
 }
 
+impl IdleTimer {
+    fn new(timeout: Duration, now: Instant) -> Self {
+        Self {
+            timeout,
+            last_activity: now,
+        }
+    }
+
+    fn record_activity(&mut self, at: Instant) {
+        self.last_activity = self.last_activity.max(at);
+    }
+
+    fn is_idle(&self, now: Instant) -> bool {
+        now.saturating_duration_since(self.last_activity) >= self.timeout
+    }
+}
+
+/// Run the app until it exits, then shut it down
+/// and flush the logs before the terminal is restored.
 pub fn run(
     terminal: &mut Terminal<impl Backend>,
     app: &mut impl Base,
     fps: f32,
+    limit: &Limit,
+) -> anyhow::Result<()> {
+    run_and_shut_down(terminal, app, fps, limit, read_terminal_event)
+}
+
+fn run_and_shut_down(
+    terminal: &mut Terminal<impl Backend>,
+    app: &mut impl Base,
+    fps: f32,
+    limit: &Limit,
+    next_event: impl FnMut(Duration) -> anyhow::Result<Option<Event>>,
+) -> anyhow::Result<()> {
+    let result = run_loop(terminal, app, fps, limit, next_event);
+    let shutdown = app.on_shutdown();
+    crate::logger::flush();
+    result.and(shutdown)
+}
+
+/// Wait up to `timeout` for the next terminal event.
+fn read_terminal_event(timeout: Duration) -> anyhow::Result<Option<Event>> {
+    match crossterm::event::poll(timeout)? {
+        true => Ok(Some(crossterm::event::read()?)),
+        false => Ok(None),
+    }
+}
+
+fn run_loop(
+    terminal: &mut Terminal<impl Backend>,
+    app: &mut impl Base,
+    fps: f32,
+    limit: &Limit,
+    mut next_event: impl FnMut(Duration) -> anyhow::Result<Option<Event>>,
 ) -> anyhow::Result<()> {
     terminal.clear()?;
 
     let tick_rate = Duration::from_millis((1000. / fps) as u64);
-    let mut last_tick = Instant::now();
+    let start = Instant::now();
+    let mut last_tick = start;
+    let mut num_frames = 0;
+    let mut idle = limit
+        .idle_timeout
+        .map(|timeout| IdleTimer::new(timeout, start));
 
     loop {
+        if limit.is_reached(num_frames, start.elapsed()) {
+            return finish(terminal, app, limit);
+        }
+
         terminal.draw(|f| app.render(f))?;
 
         let timeout = tick_rate
             .checked_sub(last_tick.elapsed())
             .unwrap_or_else(|| Duration::from_secs(0));
 
-        if crossterm::event::poll(timeout)? {
-            if let Event::Key(key) = crossterm::event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => {
-                            return Ok(())
-                        }
-                        _ => match app.on_keypress(key)? {
-                            Flow::Continue => (),
-                            Flow::Loop => continue,
-                            Flow::Exit => break,
-                        },
-                    }
+        if let Some(event) = next_event(timeout)? {
+            if let (Some(idle), Event::Key(_)) = (idle.as_mut(), &event) {
+                idle.record_activity(Instant::now());
+            }
+
+            match event {
+                Event::Key(key) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('c') if key.modifiers == KeyModifiers::CONTROL => break,
+                    _ => match app.on_keypress(key)? {
+                        Flow::Continue => (),
+                        Flow::Loop => continue,
+                        Flow::Exit => break,
+                    },
+                },
+                // redraw at the new size straight away rather than on the next tick
+                Event::Resize(..) => {
+                    terminal.autoresize()?;
+                    continue;
                 }
+                _ => (),
             }
         }
 
         if last_tick.elapsed() >= tick_rate {
             last_tick = Instant::now();
+            num_frames += 1;
             match app.update()? {
                 Flow::Continue => (),
                 Flow::Loop => continue,
                 Flow::Exit => break,
             }
+
+            if let Some(idle) = idle.as_mut() {
+                if let Some(at) = app.last_input() {
+                    idle.record_activity(at);
+                }
+                if idle.is_idle(Instant::now()) {
+                    log::info!(
+                        "exiting after {}s without audio, MIDI or key presses",
+                        idle.timeout.as_secs()
+                    );
+                    break;
+                }
+            }
         }
     }
 
     Ok(())
 }
+
+/// Render the last frame, keeping a copy of it if a snapshot was requested.
+fn finish(
+    terminal: &mut Terminal<impl Backend>,
+    app: &mut impl Base,
+    limit: &Limit,
+) -> anyhow::Result<()> {
+    let frame = terminal.draw(|f| app.render(f))?;
+
+    if let Some(ref path) = limit.snapshot {
+        std::fs::write(path, buffer_text(frame.buffer))?;
+    }
+
+    Ok(())
+}
+
+/// Text of a rendered frame, one line per row, without the styling.
+pub fn buffer_text(buffer: &buffer::Buffer) -> String {
+    let width = buffer.area.width.max(1) as usize;
+    buffer
+        .content
+        .chunks(width)
+        .map(|row| {
+            let line: String = row.iter().map(|cell| cell.symbol()).collect();
+            format!("{}\n", line.trim_end())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Default)]
+    struct ExitingApp {
+        fails: bool,
+        num_shutdowns: usize,
+    }
+
+    impl Base for ExitingApp {
+        fn update(&mut self) -> anyhow::Result<Flow> {
+            match self.fails {
+                true => anyhow::bail!("update failed"),
+                false => Ok(Flow::Exit),
+            }
+        }
+
+        fn render(&mut self, _: &mut Frame) {}
+
+        fn on_shutdown(&mut self) -> anyhow::Result<()> {
+            self.num_shutdowns += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn apps_are_shut_down_once_when_the_run_loop_ends() {
+        let mut terminal = Terminal::new(backend::TestBackend::new(16, 4)).unwrap();
+        let no_input = |timeout| {
+            std::thread::sleep(timeout);
+            Ok(None)
+        };
+
+        let mut app = ExitingApp::default();
+        run_and_shut_down(&mut terminal, &mut app, 1_000., &Limit::default(), no_input).unwrap();
+        assert_eq!(app.num_shutdowns, 1);
+
+        let mut app = ExitingApp {
+            fails: true,
+            ..Default::default()
+        };
+        let result =
+            run_and_shut_down(&mut terminal, &mut app, 1_000., &Limit::default(), no_input);
+        assert_eq!(result.unwrap_err().to_string(), "update failed");
+        assert_eq!(app.num_shutdowns, 1);
+    }
+
+    #[derive(Default)]
+    struct CountingApp {
+        num_updates: usize,
+    }
+
+    impl Base for CountingApp {
+        fn update(&mut self) -> anyhow::Result<Flow> {
+            self.num_updates += 1;
+            Ok(Flow::Continue)
+        }
+
+        fn render(&mut self, f: &mut Frame) {
+            let text = format!("updates : {}", self.num_updates);
+            f.render_widget(ratatui::widgets::Paragraph::new(text), f.size());
+        }
+    }
+
+    #[test]
+    fn the_loop_ends_after_the_configured_number_of_frames() {
+        let mut terminal = Terminal::new(backend::TestBackend::new(16, 2)).unwrap();
+        let mut app = CountingApp::default();
+        let snapshot = std::env::temp_dir().join(format!("aud-snapshot-{}", std::process::id()));
+        let limit = Limit {
+            frames: Some(3),
+            snapshot: Some(snapshot.clone()),
+            ..Default::default()
+        };
+
+        let no_input = |timeout| {
+            std::thread::sleep(timeout);
+            Ok(None)
+        };
+        run_loop(&mut terminal, &mut app, 1_000., &limit, no_input).unwrap();
+        assert_eq!(app.num_updates, 3);
+
+        let text = std::fs::read_to_string(&snapshot).unwrap();
+        let _ = std::fs::remove_file(snapshot);
+        assert_eq!(text, "updates : 3\n\n");
+    }
+
+    #[test]
+    fn the_app_is_idle_once_the_timeout_passes_without_activity() {
+        let start = Instant::now();
+        let mut idle = IdleTimer::new(Duration::from_secs(10), start);
+
+        assert!(!idle.is_idle(start + Duration::from_secs(9)));
+        assert!(idle.is_idle(start + Duration::from_secs(10)));
+
+        idle.record_activity(start + Duration::from_secs(8));
+        assert!(!idle.is_idle(start + Duration::from_secs(17)));
+        assert!(idle.is_idle(start + Duration::from_secs(18)));
+
+        // input seen on an earlier frame does not wind the timer back
+        idle.record_activity(start + Duration::from_secs(2));
+        assert!(!idle.is_idle(start + Duration::from_secs(17)));
+    }
+
+    #[test]
+    fn the_loop_ends_when_the_app_stays_idle() {
+        let mut terminal = Terminal::new(backend::TestBackend::new(16, 2)).unwrap();
+        let mut app = CountingApp::default();
+        let limit = Limit {
+            idle_timeout: Some(Duration::from_millis(20)),
+            frames: Some(10_000),
+            ..Default::default()
+        };
+
+        let no_input = |timeout| {
+            std::thread::sleep(timeout);
+            Ok(None)
+        };
+        let start = Instant::now();
+        run_loop(&mut terminal, &mut app, 1_000., &limit, no_input).unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed >= Duration::from_millis(20),
+            "ended after {elapsed:?}"
+        );
+        assert!(elapsed < Duration::from_secs(5), "ended after {elapsed:?}");
+        assert!(app.num_updates < 10_000);
+    }
+}
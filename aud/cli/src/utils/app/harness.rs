@@ -0,0 +1,273 @@
+//! Drives apps through the run loop on a [`TestBackend`], with mock
+//! audio and MIDI sources, to test them end to end without hardware
+//! or a real terminal.
+
+use super::{buffer_text, run_loop, Base, Limit};
+use aud::{audio::*, midi::MidiData};
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{backend::TestBackend, buffer::Buffer, prelude::*};
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Frames produced on every update by a connected [`MockAudioSource`].
+pub const NUM_FRAMES: u32 = 16;
+
+/// Key that no app handles, pressed to let frames go by.
+pub const IDLE: KeyCode = KeyCode::Null;
+
+/// Time a [`run_until`] waits for the expected frame before failing.
+pub const WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Press `keys` one at a time, one per frame, and return the frames
+/// rendered along the way. The first frame is rendered before any key
+/// is pressed, and one more after each key unless the app exits.
+/// Once all the keys are pressed, the loop is interrupted with CTRL+C.
+pub fn run(
+    app: &mut impl Base,
+    width: u16,
+    height: u16,
+    keys: impl IntoIterator<Item = impl Into<KeyEvent>>,
+) -> anyhow::Result<Frames> {
+    run_until(app, width, height, keys, |_| true)
+}
+
+/// Like [`run`], but once all the keys are pressed, let frames go by
+/// until the text of the last one is `is_expected`, e.g. to wait for
+/// messages coming back from the script engine's thread. Fails if no
+/// such frame is rendered within [`WAIT_TIMEOUT`].
+pub fn run_until(
+    app: &mut impl Base,
+    width: u16,
+    height: u16,
+    keys: impl IntoIterator<Item = impl Into<KeyEvent>>,
+    is_expected: impl Fn(&str) -> bool,
+) -> anyhow::Result<Frames> {
+    let mut terminal = Terminal::new(TestBackend::new(width, height))?;
+    let frames = Rc::new(RefCell::new(vec![]));
+    let mut recorder = Recorder {
+        app,
+        frames: frames.clone(),
+    };
+
+    let mut keys: VecDeque<KeyEvent> = keys.into_iter().map(Into::into).collect();
+    let interrupt = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    let mut deadline = None;
+    // wait out the tick so that the app updates between two keys
+    let next_event = |timeout| {
+        std::thread::sleep(timeout);
+        if let Some(key) = keys.pop_front() {
+            return Ok(Some(Event::Key(key)));
+        }
+
+        let frames = frames.borrow();
+        if frames
+            .last()
+            .is_none_or(|frame| is_expected(&buffer_text(frame)))
+        {
+            return Ok(Some(Event::Key(interrupt)));
+        }
+
+        if *deadline.get_or_insert_with(|| Instant::now() + WAIT_TIMEOUT) < Instant::now() {
+            anyhow::bail!(
+                "timed out waiting for the expected frame, the last one was :\n{}",
+                frames.last().map(buffer_text).unwrap_or_default()
+            );
+        }
+        Ok(Some(Event::Key(IDLE.into())))
+    };
+
+    run_loop(
+        &mut terminal,
+        &mut recorder,
+        1_000.,
+        &Limit::default(),
+        next_event,
+    )?;
+    Ok(Frames(frames.take()))
+}
+
+/// Every frame rendered during a [`run`], in order.
+pub struct Frames(Vec<Buffer>);
+
+impl std::ops::Deref for Frames {
+    type Target = [Buffer];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Frames {
+    /// Text of a frame, one line per row, without the styling.
+    pub fn text(&self, index: usize) -> String {
+        buffer_text(&self.0[index])
+    }
+
+    pub fn last_text(&self) -> String {
+        self.text(self.len() - 1)
+    }
+}
+
+/// Keeps a copy of every frame the app renders.
+struct Recorder<'a, A> {
+    app: &'a mut A,
+    frames: Rc<RefCell<Vec<Buffer>>>,
+}
+
+impl<A: Base> Base for Recorder<'_, A> {
+    fn update(&mut self) -> anyhow::Result<super::Flow> {
+        self.app.update()
+    }
+
+    fn on_keypress(&mut self, key: KeyEvent) -> anyhow::Result<super::Flow> {
+        self.app.on_keypress(key)
+    }
+
+    fn render(&mut self, frame: &mut Frame) {
+        self.app.render(frame);
+        self.frames.borrow_mut().push(frame.buffer_mut().clone());
+    }
+
+    fn last_input(&self) -> Option<std::time::Instant> {
+        self.app.last_input()
+    }
+}
+
+/// Audio source with a fixed set of devices, producing
+/// a buffer of [`NUM_FRAMES`] on every update once connected.
+pub struct MockAudioSource {
+    pub devices: Vec<AudioDevice>,
+    connection: Option<AudioDeviceConnection>,
+}
+
+impl MockAudioSource {
+    pub fn new(names: &[&str]) -> Self {
+        Self {
+            devices: names
+                .iter()
+                .enumerate()
+                .map(|(index, name)| AudioDevice {
+                    name: name.to_string(),
+                    num_channels: 2,
+                    index,
+                })
+                .collect(),
+            connection: None,
+        }
+    }
+}
+
+impl AudioInterface for MockAudioSource {
+    fn is_accessible(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    fn list_audio_devices(&self) -> &[AudioDevice] {
+        &self.devices
+    }
+
+    fn connect_to_audio_device(
+        &mut self,
+        audio_device: &AudioDevice,
+        channel_selection: AudioChannelSelection,
+    ) -> anyhow::Result<()> {
+        if !audio_device.supports_channels(&channel_selection) {
+            anyhow::bail!("unsupported channels : {channel_selection:?}");
+        }
+
+        self.connection = Some(AudioDeviceConnection {
+            device: audio_device.clone(),
+            channels: channel_selection,
+            sample_rate: 48_000,
+        });
+        Ok(())
+    }
+
+    fn disconnect_from_audio_device(&mut self) {
+        self.connection = None;
+    }
+
+    fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
+        self.connection.as_ref()
+    }
+
+    fn process_audio_events(&mut self) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+impl AudioProviding for MockAudioSource {
+    fn retrieve_audio_buffer(&mut self) -> AudioBuffer {
+        match self.connection {
+            Some(ref connection) => {
+                AudioBuffer::with_frames(NUM_FRAMES, connection.channels.count() as u32)
+            }
+            None => AudioBuffer::default(),
+        }
+    }
+}
+
+/// MIDI source with a fixed set of ports, producing
+/// the queued messages once connected and running.
+#[derive(Default)]
+pub struct MockMidiInput {
+    pub ports: Vec<String>,
+    pub messages: Vec<MidiData>,
+    connected_port: Option<String>,
+    is_active: bool,
+}
+
+impl MockMidiInput {
+    pub fn new(ports: &[&str], messages: &[&[u8]]) -> Self {
+        Self {
+            ports: ports.iter().map(|port| port.to_string()).collect(),
+            messages: messages
+                .iter()
+                .enumerate()
+                .map(|(i, bytes)| MidiData {
+                    timestamp: i as u64,
+                    bytes: bytes.to_vec(),
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+}
+
+impl aud::midi::MidiReceiving for MockMidiInput {
+    fn is_midi_stream_active(&self) -> bool {
+        self.is_active
+    }
+
+    fn set_midi_stream_active(&mut self, should_be_active: bool) {
+        self.is_active = should_be_active;
+    }
+
+    fn list_midi_devices(&self) -> anyhow::Result<Vec<String>> {
+        Ok(self.ports.clone())
+    }
+
+    fn connect_to_midi_device(&mut self, device_name: &str) -> anyhow::Result<()> {
+        if !self.ports.iter().any(|port| port == device_name) {
+            anyhow::bail!("no MIDI device : {device_name}");
+        }
+
+        self.connected_port = Some(device_name.to_owned());
+        Ok(())
+    }
+
+    fn disconnect_from_midi_device(&mut self) {
+        self.connected_port = None;
+    }
+
+    fn produce_midi_messages(&mut self) -> Vec<MidiData> {
+        match self.connected_port.is_some() && self.is_active {
+            true => std::mem::take(&mut self.messages),
+            false => vec![],
+        }
+    }
+}
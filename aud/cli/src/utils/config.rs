@@ -0,0 +1,141 @@
+//! Defaults of the command line options, stored in `~/.aud/config.toml`, e.g.
+//!
+//! ```toml
+//! fps = 60
+//! palette = "cyan,magenta"
+//! audio_queue = 128
+//! midi_port = "Launchpad Pro MK3"
+//! ```
+//!
+//! Options given on the command line take precedence over the file,
+//! which takes precedence over the built-in defaults. Options an app
+//! does not have are ignored by it. Key bindings are not configurable
+//! yet, they are not command line options.
+
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// `--fps` of every app.
+    pub fps: Option<f32>,
+    /// `--palette` of auscope.
+    pub palette: Option<String>,
+    /// `--audio-queue` of auscope.
+    pub audio_queue: Option<usize>,
+    /// `--midi-queue` of midimon.
+    pub midi_queue: Option<usize>,
+    /// `--device` of auscope.
+    pub audio_device: Option<String>,
+    /// `--port` of midimon.
+    pub midi_port: Option<String>,
+    /// `--connect-script` of midimon.
+    pub script: Option<PathBuf>,
+}
+
+impl Config {
+    /// Load the user's config, or the built-in defaults if there is none.
+    pub fn load() -> anyhow::Result<Self> {
+        let Some(path) = crate::locations::config().filter(|path| path.exists()) else {
+            return Ok(Self::default());
+        };
+
+        Self::from_file(&path)
+            .map_err(|e| anyhow::anyhow!("Invalid config {} : {e}", path.display()))
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+
+    /// Make the values of the file the defaults of the command's
+    /// options, so that those given on the command line override them.
+    pub fn apply(&self, mut command: clap::Command) -> clap::Command {
+        for app in ["midimon", "derlink", "auscope"] {
+            command = command.mut_subcommand(app, |app| {
+                with_default(app, "fps", self.fps.map(|fps| fps.to_string()))
+            });
+        }
+
+        command
+            .mut_subcommand("auscope", |auscope| {
+                let auscope = with_default(auscope, "palette", self.palette.clone());
+                let auscope = with_default(
+                    auscope,
+                    "audio_queue",
+                    self.audio_queue.map(|capacity| capacity.to_string()),
+                );
+                with_default(auscope, "device", self.audio_device.clone())
+            })
+            .mut_subcommand("midimon", |midimon| {
+                let midimon = with_default(
+                    midimon,
+                    "midi_queue",
+                    self.midi_queue.map(|capacity| capacity.to_string()),
+                );
+                let midimon = with_default(midimon, "port", self.midi_port.clone());
+                with_default(
+                    midimon,
+                    "connect_script",
+                    self.script
+                        .as_ref()
+                        .map(|script| script.display().to_string()),
+                )
+            })
+    }
+}
+
+fn with_default(command: clap::Command, arg: &str, value: Option<String>) -> clap::Command {
+    match value {
+        Some(value) => command.mut_arg(arg, |arg| arg.default_value(value)),
+        None => command,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use clap::CommandFactory;
+
+    fn matches(config: &Config, args: &[&str]) -> clap::ArgMatches {
+        config
+            .apply(crate::Cli::command())
+            .try_get_matches_from(args)
+            .unwrap()
+    }
+
+    #[test]
+    fn file_values_are_used_unless_given_on_the_command_line() {
+        let config = Config::parse("fps = 60\nmidi_queue = 16\nmidi_port = \"keys\"\n").unwrap();
+        assert_eq!(config.fps, Some(60.));
+        assert_eq!(config.audio_queue, None);
+
+        let args = matches(&config, &["aud", "midimon"]);
+        let midimon = args.subcommand_matches("midimon").unwrap();
+        assert_eq!(midimon.get_one::<f32>("fps"), Some(&60.));
+        assert_eq!(midimon.get_one::<usize>("midi_queue"), Some(&16));
+        assert_eq!(midimon.get_one::<String>("port").unwrap(), "keys");
+
+        let args = matches(
+            &config,
+            &["aud", "midimon", "--fps", "24", "--port", "pads"],
+        );
+        let midimon = args.subcommand_matches("midimon").unwrap();
+        assert_eq!(midimon.get_one::<f32>("fps"), Some(&24.));
+        assert_eq!(midimon.get_one::<usize>("midi_queue"), Some(&16));
+        assert_eq!(midimon.get_one::<String>("port").unwrap(), "pads");
+
+        let args = matches(&Config::default(), &["aud", "auscope"]);
+        let auscope = args.subcommand_matches("auscope").unwrap();
+        assert_eq!(auscope.get_one::<f32>("fps"), Some(&30.));
+        assert_eq!(auscope.get_one::<String>("device"), None);
+
+        assert!(Config::parse("fsp = 60").is_err());
+        assert!(Config::parse("fps = \"fast\"").is_err());
+    }
+}
@@ -0,0 +1,112 @@
+//! Names given by the user to audio channels and MIDI ports, stored in
+//! `~/.aud/labels.txt` with one `<source> = <label>` entry per line.
+//!
+//! Audio channels are numbered from 1 after the name of their device,
+//! e.g. `Scarlett 2i2 USB/1 = Kick`, any other source is a MIDI port,
+//! e.g. `Launchpad Pro MK3 = Pads`. Empty lines and lines starting
+//! with `#` are ignored.
+
+use std::{collections::HashMap, path::Path};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Labels {
+    channels: HashMap<(String, usize), String>,
+    ports: HashMap<String, String>,
+}
+
+impl Labels {
+    /// Load the user's labels, or none if they have not set any.
+    pub fn load() -> Self {
+        let Some(path) = crate::locations::labels().filter(|path| path.exists()) else {
+            return Self::default();
+        };
+
+        Self::from_file(&path).unwrap_or_else(|e| {
+            log::error!("failed to load labels from {} : {e}", path.display());
+            Self::default()
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    fn parse(contents: &str) -> anyhow::Result<Self> {
+        let mut labels = Self::default();
+        let lines = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        for line in lines {
+            let Some((source, label)) = line.split_once('=') else {
+                anyhow::bail!("Invalid label entry : {line}");
+            };
+
+            let (source, label) = (source.trim(), label.trim().to_owned());
+            if source.is_empty() || label.is_empty() {
+                anyhow::bail!("Invalid label entry : {line}");
+            }
+
+            let channel = source
+                .rsplit_once('/')
+                .and_then(|(device, channel)| Some((device, channel.trim().parse().ok()?)));
+
+            match channel {
+                Some((_, 0)) => anyhow::bail!("Invalid channel number, they start at 1 : {line}"),
+                Some((device, channel)) => {
+                    labels
+                        .channels
+                        .insert((device.trim().to_owned(), channel - 1), label);
+                }
+                None => {
+                    labels.ports.insert(source.to_owned(), label);
+                }
+            }
+        }
+
+        Ok(labels)
+    }
+
+    /// Label of a channel of a device, counted from 0, or its number if it has none.
+    pub fn channel(&self, device: Option<&str>, channel: usize) -> String {
+        device
+            .and_then(|device| self.channels.get(&(device.to_owned(), channel)))
+            .cloned()
+            .unwrap_or_else(|| format!("ch {}", channel + 1))
+    }
+
+    /// Every labelled MIDI port, by port name.
+    pub fn ports(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.ports
+            .iter()
+            .map(|(port, label)| (port.as_str(), label.as_str()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn configured_labels_are_used_and_others_default_to_numbers() {
+        let labels = Labels::parse(
+            "# studio\n\nScarlett 2i2 USB/1 = Kick\n Scarlett 2i2 USB / 2 =  Snare top \nLaunchpad Pro MK3 = Pads\n",
+        )
+        .unwrap();
+
+        assert_eq!(labels.channel(Some("Scarlett 2i2 USB"), 0), "Kick");
+        assert_eq!(labels.channel(Some("Scarlett 2i2 USB"), 1), "Snare top");
+        assert_eq!(labels.channel(Some("Scarlett 2i2 USB"), 2), "ch 3");
+        assert_eq!(labels.channel(Some("MacBook Pro Microphone"), 0), "ch 1");
+        assert_eq!(labels.channel(None, 1), "ch 2");
+        assert_eq!(
+            labels.ports().collect::<Vec<_>>(),
+            [("Launchpad Pro MK3", "Pads")]
+        );
+
+        assert!(Labels::parse("Scarlett 2i2 USB/0 = Nope").is_err());
+        assert!(Labels::parse("Nope").is_err());
+        assert!(Labels::parse("Launchpad Pro MK3 =").is_err());
+    }
+}
@@ -0,0 +1,144 @@
+use crate::logger::escape_json;
+use aud::audio::HostAudioDevice;
+
+/// A device printed by `--list-devices`, to find the name or index to
+/// connect to. Only audio devices have a channel count and sample rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedDevice {
+    pub kind: &'static str,
+    pub index: usize,
+    pub name: String,
+    pub num_channels: Option<usize>,
+    pub sample_rate: Option<u32>,
+}
+
+pub fn audio_devices(kind: &'static str, devices: &[HostAudioDevice]) -> Vec<ListedDevice> {
+    devices
+        .iter()
+        .map(|host| ListedDevice {
+            kind,
+            index: host.device.index,
+            name: host.device.name.clone(),
+            num_channels: Some(host.device.num_channels),
+            sample_rate: Some(host.sample_rate),
+        })
+        .collect()
+}
+
+pub fn midi_ports(kind: &'static str, port_names: &[String]) -> Vec<ListedDevice> {
+    port_names
+        .iter()
+        .enumerate()
+        .map(|(index, name)| ListedDevice {
+            kind,
+            index,
+            name: name.clone(),
+            num_channels: None,
+            sample_rate: None,
+        })
+        .collect()
+}
+
+/// One device per line, e.g. `input  0 : Microphone : 1 ch : 48000 Hz`.
+pub fn to_text(devices: &[ListedDevice]) -> String {
+    if devices.is_empty() {
+        return "no devices found\n".to_owned();
+    }
+
+    let kind_width = devices.iter().map(|d| d.kind.len()).max().unwrap_or(0);
+    let mut text = String::new();
+    for device in devices {
+        let mut line = format!(
+            "{:kind_width$} {} : {}",
+            device.kind, device.index, device.name
+        );
+        if let Some(num_channels) = device.num_channels {
+            line.push_str(&format!(" : {num_channels} ch"));
+        }
+        if let Some(sample_rate) = device.sample_rate {
+            line.push_str(&format!(" : {sample_rate} Hz"));
+        }
+        text.push_str(line.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+/// A JSON array of the devices, leaving out the fields they do not have.
+pub fn to_json(devices: &[ListedDevice]) -> String {
+    let objects: Vec<String> = devices
+        .iter()
+        .map(|device| {
+            let mut object = format!(
+                r#"{{"kind":"{}","index":{},"name":"{}""#,
+                escape_json(device.kind),
+                device.index,
+                escape_json(&device.name)
+            );
+            if let Some(num_channels) = device.num_channels {
+                object.push_str(&format!(r#","channels":{num_channels}"#));
+            }
+            if let Some(sample_rate) = device.sample_rate {
+                object.push_str(&format!(r#","sample_rate":{sample_rate}"#));
+            }
+            object.push('}');
+            object
+        })
+        .collect();
+
+    format!("[{}]\n", objects.join(","))
+}
+
+/// Print the devices to stdout, as text or JSON.
+pub fn print(devices: &[ListedDevice], json: bool) {
+    match json {
+        true => print!("{}", to_json(devices)),
+        false => print!("{}", to_text(devices)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::app::harness::{MockAudioSource, MockMidiInput};
+    use aud::{audio::AudioInterface, midi::MidiReceiving};
+
+    #[test]
+    fn devices_of_a_mock_host_are_listed_as_text_and_json() {
+        let source = MockAudioSource::new(&["mic", "interface \"usb\""]);
+        let inputs: Vec<_> = source
+            .list_audio_devices()
+            .iter()
+            .map(|device| HostAudioDevice {
+                device: device.clone(),
+                sample_rate: 48_000,
+            })
+            .collect();
+        let ports = MockMidiInput::new(&["keys", "pads"], &[])
+            .list_midi_devices()
+            .unwrap();
+
+        let mut devices = audio_devices("input", &inputs);
+        devices.extend(midi_ports("midi", &ports));
+
+        assert_eq!(
+            to_text(&devices),
+            "input 0 : mic : 2 ch : 48000 Hz\n\
+             input 1 : interface \"usb\" : 2 ch : 48000 Hz\n\
+             midi  0 : keys\n\
+             midi  1 : pads\n"
+        );
+        assert_eq!(to_text(&[]), "no devices found\n");
+
+        let json: serde_json::Value = serde_json::from_str(&to_json(&devices)).unwrap();
+        assert_eq!(json.as_array().unwrap().len(), 4);
+        assert_eq!(json[1]["kind"], "input");
+        assert_eq!(json[1]["index"], 1);
+        assert_eq!(json[1]["name"], "interface \"usb\"");
+        assert_eq!(json[1]["channels"], 2);
+        assert_eq!(json[1]["sample_rate"], 48_000);
+        assert_eq!(json[3]["name"], "pads");
+        assert!(json[3].get("channels").is_none());
+        assert_eq!(to_json(&[]), "[]\n");
+    }
+}
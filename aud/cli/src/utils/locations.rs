@@ -3,6 +3,12 @@
 /// .
 /// ├── bin
 /// │  └── aud
+/// ├── config.toml
+/// ├── labels.txt
+/// ├── midi
+/// │  ├── format.txt
+/// │  └── programs.txt
+/// ├── session
 /// ├── log
 /// │  └── aud.log
 /// └── lua
@@ -30,6 +36,35 @@ pub fn lua() -> Option<PathBuf> {
     Some(aud()?.join("lua"))
 }
 
+pub fn midi() -> Option<PathBuf> {
+    Some(aud()?.join("midi"))
+}
+
+/// User overrides of the General MIDI program names.
+pub fn program_names() -> Option<PathBuf> {
+    Some(midi()?.join("programs.txt"))
+}
+
+/// Template of the lines midimon renders messages with.
+pub fn message_format() -> Option<PathBuf> {
+    Some(midi()?.join("format.txt"))
+}
+
+/// Defaults of the command line options.
+pub fn config() -> Option<PathBuf> {
+    Some(aud()?.join("config.toml"))
+}
+
+/// Names given to audio channels and MIDI ports.
+pub fn labels() -> Option<PathBuf> {
+    Some(aud()?.join("labels.txt"))
+}
+
+/// Selections remembered between runs.
+pub fn session() -> Option<PathBuf> {
+    Some(aud()?.join("session"))
+}
+
 pub fn log() -> Option<PathBuf> {
     Some(aud()?.join("log"))
 }
@@ -41,12 +76,14 @@ pub fn log_file(name: &str) -> Option<std::path::PathBuf> {
 pub mod lua {
     use super::*;
 
-    pub fn lib() -> Option<PathBuf> {
-        Some(lua()?.join("examples"))
+    pub fn api() -> Option<PathBuf> {
+        Some(lua()?.join("api"))
     }
 
-    pub fn api() -> Option<PathBuf> {
-        Some(lua()?.join("examples"))
+    /// Shared modules scripts can `require`, installed next to the API
+    /// and named after their directory, e.g. `require("aud.midi")`.
+    pub fn lib() -> Option<PathBuf> {
+        Some(api()?.parent()?.to_path_buf())
     }
 
     pub fn examples() -> Option<PathBuf> {
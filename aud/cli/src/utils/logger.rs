@@ -1,19 +1,47 @@
 use std::{
-    path::Path,
+    path::{Path, PathBuf},
     sync::{
         atomic::{AtomicBool, Ordering},
-        Once,
+        Once, OnceLock,
     },
 };
 
 static INIT: Once = Once::new();
 static IS_INITIALIZED: AtomicBool = AtomicBool::new(false);
+static LOG_FILE: OnceLock<PathBuf> = OnceLock::new();
 
 pub fn is_active() -> bool {
     IS_INITIALIZED.load(Ordering::SeqCst)
 }
 
-pub fn start(id: &str, file: impl AsRef<Path>, verbose: bool) -> anyhow::Result<()> {
+/// File the logs are written to, once the logger is started.
+pub fn active_file() -> Option<PathBuf> {
+    LOG_FILE.get().cloned()
+}
+
+/// Write out any buffered log records.
+pub fn flush() {
+    if is_active() {
+        log::logger().flush();
+    }
+}
+
+/// How log records are written to the log file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human readable lines
+    #[default]
+    Text,
+    /// Newline-delimited JSON objects, for log tooling
+    Json,
+}
+
+pub fn start(
+    id: &str,
+    file: impl AsRef<Path>,
+    verbose: bool,
+    format: LogFormat,
+) -> anyhow::Result<()> {
     let level = if verbose {
         log::LevelFilter::Trace
     } else {
@@ -30,14 +58,16 @@ pub fn start(id: &str, file: impl AsRef<Path>, verbose: bool) -> anyhow::Result<
         .format(move |out, msg, record| {
             let time = humantime::format_rfc3339_seconds(std::time::SystemTime::now());
 
-            if cfg!(debug_assertions) {
+            if format == LogFormat::Json {
                 out.finish(format_args!(
-                    "[ {id} ] : [ {time} ] : [ {} {} ] : {msg}",
-                    record.target(),
-                    record.level(),
+                    "{}",
+                    json_line(&id, &time.to_string(), record.level(), record.target(), msg)
                 ))
             } else {
-                out.finish(format_args!("[ {id} ] : [ {time} ] : {msg}"))
+                out.finish(format_args!(
+                    "{}",
+                    text_line(&id, &time.to_string(), record.level(), record.target(), msg)
+                ))
             }
         })
         .level(level)
@@ -48,6 +78,116 @@ pub fn start(id: &str, file: impl AsRef<Path>, verbose: bool) -> anyhow::Result<
 
     log::trace!("started");
 
+    let _ = LOG_FILE.set(file.as_ref().to_owned());
     INIT.call_once(|| IS_INITIALIZED.store(true, Ordering::SeqCst));
     Ok(())
 }
+
+/// Format a record as a human readable line. Lines logged
+/// by scripts are prefixed with the script they come from.
+fn text_line(
+    id: &str,
+    time: &str,
+    level: log::Level,
+    target: &str,
+    message: &std::fmt::Arguments,
+) -> String {
+    if let Some(script) = aud::lua::script_from_log_target(target) {
+        return format!("[ {id} ] : [ {time} ] : [ script {script} ] : {message}");
+    }
+
+    if cfg!(debug_assertions) {
+        format!("[ {id} ] : [ {time} ] : [ {target} {level} ] : {message}")
+    } else {
+        format!("[ {id} ] : [ {time} ] : {message}")
+    }
+}
+
+/// Format a record as a single line JSON object. Lines logged
+/// by scripts carry the name of the script they come from.
+fn json_line(
+    id: &str,
+    time: &str,
+    level: log::Level,
+    target: &str,
+    message: &std::fmt::Arguments,
+) -> String {
+    let mut line = format!(
+        r#"{{"id":"{}","timestamp":"{}","level":"{}","target":"{}","message":"{}""#,
+        escape_json(id),
+        escape_json(time),
+        level,
+        escape_json(target),
+        escape_json(&message.to_string()),
+    );
+
+    if let Some(script) = aud::lua::script_from_log_target(target) {
+        line.push_str(&format!(r#","script":"{}""#, escape_json(script)));
+    }
+
+    line.push('}');
+    line
+}
+
+pub fn escape_json(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn script_lines_carry_the_script_name_and_time() {
+        let target = aud::lua::script_log_target("arp.lua");
+        let line = text_line(
+            "midimon:42",
+            "2024-01-01T00:00:00Z",
+            log::Level::Info,
+            &target,
+            &format_args!("step 3"),
+        );
+
+        assert_eq!(
+            line,
+            "[ midimon:42 ] : [ 2024-01-01T00:00:00Z ] : [ script arp.lua ] : step 3"
+        );
+    }
+
+    #[test]
+    fn json_lines_carry_the_record_fields() {
+        let target = aud::lua::script_log_target("arp.lua");
+        let line = json_line(
+            "midimon:42",
+            "2024-01-01T00:00:00Z",
+            log::Level::Info,
+            &target,
+            &format_args!("said \"hi\"\n"),
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["id"], "midimon:42");
+        assert_eq!(json["timestamp"], "2024-01-01T00:00:00Z");
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["target"], target.as_str());
+        assert_eq!(json["message"], "said \"hi\"\n");
+        assert_eq!(json["script"], "arp.lua");
+
+        let line = json_line("id", "now", log::Level::Warn, "aud", &format_args!("\u{1}"));
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["message"], "\u{1}");
+        assert!(json.get("script").is_none());
+    }
+}
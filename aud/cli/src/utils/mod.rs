@@ -1,4 +1,8 @@
 pub mod app;
+pub mod config;
+pub mod labels;
+pub mod listing;
 pub mod locations;
 pub mod logger;
+pub mod session;
 pub mod terminal;
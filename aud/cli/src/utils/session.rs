@@ -0,0 +1,127 @@
+//! Selections remembered between runs, stored in `~/.aud/session`
+//! with one `key = value` entry per line.
+
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub audio_device: Option<String>,
+    pub midi_port: Option<String>,
+    pub script: Option<PathBuf>,
+}
+
+impl Session {
+    /// Load the saved session, or an empty one if there is none.
+    pub fn load() -> Self {
+        let Some(path) = crate::locations::session().filter(|path| path.exists()) else {
+            return Self::default();
+        };
+
+        Self::from_file(&path).unwrap_or_else(|e| {
+            log::error!("failed to load session from {} : {e}", path.display());
+            Self::default()
+        })
+    }
+
+    /// Modify the saved session, keeping the entries of other apps.
+    pub fn update(modify: impl FnOnce(&mut Self)) {
+        let Some(path) = crate::locations::session() else {
+            return;
+        };
+
+        let mut session = Self::load();
+        modify(&mut session);
+
+        if let Err(e) = session.to_file(&path) {
+            log::error!("failed to save session to {} : {e}", path.display());
+        }
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Ok(Self::parse(&std::fs::read_to_string(path)?))
+    }
+
+    pub fn to_file(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, self.to_string())?;
+        Ok(())
+    }
+
+    fn parse(contents: &str) -> Self {
+        let mut session = Self::default();
+
+        for (key, value) in contents.lines().filter_map(|line| line.split_once('=')) {
+            let value = value.trim().to_owned();
+            match key.trim() {
+                "audio_device" => session.audio_device = Some(value),
+                "midi_port" => session.midi_port = Some(value),
+                "script" => session.script = Some(value.into()),
+                key => log::warn!("unknown session entry : {key}"),
+            }
+        }
+
+        session
+    }
+}
+
+impl std::fmt::Display for Session {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(ref device) = self.audio_device {
+            writeln!(f, "audio_device = {device}")?;
+        }
+
+        if let Some(ref port) = self.midi_port {
+            writeln!(f, "midi_port = {port}")?;
+        }
+
+        if let Some(ref script) = self.script {
+            writeln!(f, "script = {}", script.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Index of a saved selection in the currently available names.
+pub fn find_by_name(names: &[impl AsRef<str>], saved: Option<&str>) -> Option<usize> {
+    let saved = saved?;
+    names.iter().position(|name| name.as_ref() == saved)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn session_can_round_trip_through_a_file() {
+        let path = std::env::temp_dir()
+            .join(format!("aud-{}", std::process::id()))
+            .join("session");
+
+        let session = Session {
+            audio_device: Some("MacBook Pro Microphone".to_owned()),
+            midi_port: Some("IAC Driver Bus 1".to_owned()),
+            script: Some("/home/me/.aud/lua/examples/midimon/log.lua".into()),
+        };
+
+        session.to_file(&path).unwrap();
+        assert_eq!(Session::from_file(&path).unwrap(), session);
+
+        Session::default().to_file(&path).unwrap();
+        assert_eq!(Session::from_file(&path).unwrap(), Session::default());
+
+        std::fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn saved_selections_are_found_by_name() {
+        let ports = ["dev0", "dev1", "dev2"];
+        assert_eq!(find_by_name(&ports, Some("dev1")), Some(1));
+        assert_eq!(find_by_name(&ports, Some("unplugged")), None);
+        assert_eq!(find_by_name(&ports, None), None);
+    }
+}
@@ -12,8 +12,9 @@ where
 {
     let mut terminal = acquire()?;
     set_panic_hook();
-    f(&mut terminal)?;
-    release()
+    let result = f(&mut terminal);
+    release()?;
+    result
 }
 
 fn acquire() -> anyhow::Result<CrossTerminal> {
@@ -33,20 +34,86 @@ fn release() -> anyhow::Result<()> {
     Ok(())
 }
 
+type PanicHook = Box<dyn Fn(&std::panic::PanicHookInfo) + Send + Sync + 'static>;
+
 fn set_panic_hook() {
-    let original_hook = std::panic::take_hook();
-    std::panic::set_hook(Box::new(move |panic| {
-        // our Lua Runtime might panic, which is handled in "gracefully" in the app.
-        // however, that panic will still trigger the global panic handler
-        // so we need to specifically filter out panics originating from
-        // the file that triggers the panic
-        if let Some(location) = panic.location() {
-            if location.file() == "src/lua/engine.rs" {
-                return;
-            }
+    std::panic::set_hook(restoring_panic_hook(release, std::panic::take_hook()));
+}
+
+/// Restore the terminal and log the panic before handing it over
+/// to `next`, so that the backtrace is printed on a usable terminal.
+fn restoring_panic_hook(
+    restore: impl Fn() -> anyhow::Result<()> + Send + Sync + 'static,
+    next: PanicHook,
+) -> PanicHook {
+    Box::new(move |panic| {
+        let Some(report) = prepare_report(panic.payload(), panic.location(), &restore) else {
+            return;
+        };
+
+        if super::logger::is_active() {
+            log::error!("{report}");
+            super::logger::flush();
         }
 
-        release().unwrap();
-        original_hook(panic);
-    }));
+        next(panic);
+    })
+}
+
+/// Restore the terminal and describe the panic, or `None`
+/// if the panic is not to be reported.
+fn prepare_report(
+    payload: &(dyn std::any::Any + Send),
+    location: Option<&std::panic::Location>,
+    restore: &dyn Fn() -> anyhow::Result<()>,
+) -> Option<String> {
+    // our Lua Runtime might panic, which is handled in "gracefully" in the app.
+    // however, that panic will still trigger the global panic handler
+    // so we need to specifically filter out panics originating from
+    // the file that triggers the panic
+    if location.is_some_and(|location| location.file().ends_with("src/lua/engine.rs")) {
+        return None;
+    }
+
+    if let Err(e) = restore() {
+        eprintln!("failed to restore the terminal : {e}");
+    }
+
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>");
+
+    Some(match location {
+        Some(location) => format!("panicked at {location}:\n{message}"),
+        None => format!("panicked:\n{message}"),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn the_terminal_is_restored_when_the_panic_is_reported() {
+        let is_restored = Cell::new(false);
+        let restore = || {
+            is_restored.set(true);
+            Ok(())
+        };
+
+        let location = std::panic::Location::caller();
+        let report = prepare_report(&"device enumeration failed", Some(location), &restore);
+
+        assert!(is_restored.get());
+        assert_eq!(
+            report.unwrap(),
+            format!("panicked at {location}:\ndevice enumeration failed")
+        );
+
+        let report = prepare_report(&"failed".to_owned(), None, &|| anyhow::bail!("no terminal"));
+        assert_eq!(report.unwrap(), "panicked:\nfailed");
+    }
 }
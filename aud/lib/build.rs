@@ -17,6 +17,11 @@ fn generate_c_header() {
         .with_pragma_once(false)
         .with_include_guard("AUD_LIB_BINDINGS")
         .with_no_includes()
+        // defaults of the command line options, not part of the FFI,
+        // associated constants are excluded by their name alone
+        .exclude_item("DEFAULT_SEED")
+        .exclude_item("DEFAULT_CAPACITY")
+        .exclude_item("DEFAULT_SCRIPT_MESSAGE_RATE")
         .generate()
         .expect("Unable to generate C header")
         .write_to_file(HEADER_PATH);
@@ -20,8 +20,22 @@ fn setup_logger() -> Result<(), fern::InitError> {
     Ok(())
 }
 
+/// Channels to send, in order, e.g. `2,0`. All of them if not given.
+fn parse_channel_map() -> anyhow::Result<Option<Vec<usize>>> {
+    let Some(channels) = std::env::args().nth(1) else {
+        return Ok(None);
+    };
+
+    let channels = channels
+        .split(',')
+        .map(|channel| channel.trim().parse())
+        .collect::<Result<_, _>>()?;
+    Ok(Some(channels))
+}
+
 fn main() -> anyhow::Result<()> {
     setup_logger()?;
+    let channel_map = parse_channel_map()?;
 
     let sockets = Sockets {
         socket: UdpSocket::bind("127.0.0.1:8081").unwrap(),
@@ -42,6 +56,11 @@ fn main() -> anyhow::Result<()> {
 
     log::info!("connected to audio device");
 
+    if let Some(channels) = channel_map {
+        let num_channels = tx.connected_audio_device().unwrap().channels.count();
+        tx.set_channel_map(ChannelMap::new(channels, num_channels)?);
+    }
+
     while tx.is_accessible() {
         tx.process_audio_events().unwrap();
     }
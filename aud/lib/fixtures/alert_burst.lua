@@ -0,0 +1,3 @@
+for _ = 1, 12 do
+    alert("boom")
+end
@@ -0,0 +1,3 @@
+function on_midi(_, msg)
+    alert(string.format("%s:%d:%d:%d:%d", msg.type, msg.channel, msg.note, msg.velocity, #msg.bytes))
+end
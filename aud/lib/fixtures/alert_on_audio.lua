@@ -0,0 +1,3 @@
+function on_audio(device_name, buffer)
+    alert("on_audio:" .. device_name .. ":" .. #buffer .. ":" .. #buffer[1])
+end
@@ -0,0 +1,3 @@
+function on_disconnect(device_name)
+    alert("disconnected from " .. device_name)
+end
@@ -0,0 +1,3 @@
+function on_audio(device_name, buffer)
+    alert(tostring(audio.rms(1)))
+end
@@ -0,0 +1,3 @@
+function on_start()
+    midi.alias("dev0", "Keys")
+end
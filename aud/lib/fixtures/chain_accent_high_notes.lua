@@ -0,0 +1,6 @@
+function on_midi(_, msg)
+    if msg.type == "note_on" and msg.note >= 72 then
+        msg[3] = math.min(msg.velocity * 2, 127)
+    end
+    return msg
+end
@@ -0,0 +1,6 @@
+function on_midi(_, msg)
+    if msg.type == "note_on" then
+        msg[2] = msg.note + 12
+    end
+    return msg
+end
@@ -0,0 +1,5 @@
+function on_midi(_, msg)
+    if msg.type == "control_change" then
+        ui.clear()
+    end
+end
@@ -0,0 +1 @@
+connect("*1", {})
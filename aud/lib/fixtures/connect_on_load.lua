@@ -0,0 +1 @@
+connect("mic #2", { 0 })
@@ -0,0 +1,8 @@
+function on_start()
+    params.define("threshold", 0.5, 0, 1)
+    params.define("inverted", 1, 2, 0)
+end
+
+function on_param(name, value)
+    alert(name .. ":" .. value .. ":" .. params.get(name))
+end
@@ -0,0 +1,3 @@
+function on_midi(device_name, msg)
+    error("cannot handle " .. device_name)
+end
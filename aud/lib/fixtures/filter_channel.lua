@@ -0,0 +1 @@
+filter.channels({ 1 })
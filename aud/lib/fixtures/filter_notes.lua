@@ -0,0 +1 @@
+filter.notes(50, 80)
@@ -0,0 +1,3 @@
+function on_start()
+    while true do end
+end
@@ -0,0 +1,3 @@
+log("one")
+alert("two")
+log("three")
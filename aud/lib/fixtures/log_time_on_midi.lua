@@ -0,0 +1,4 @@
+function on_midi(device_name, msg)
+    local now = time.now()
+    log(string.format("%d %f %f", time.frames(), now, time.now()))
+end
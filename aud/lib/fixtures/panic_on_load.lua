@@ -0,0 +1,2 @@
+midi.panic()
+alert("panicked")
@@ -0,0 +1,3 @@
+local before = midi.selected()
+connect("dev1", {})
+alert(tostring(before) .. " -> " .. tostring(midi.selected()) .. ", " .. tostring(audio.selected()))
@@ -0,0 +1,3 @@
+local helper = require("sibling_helper")
+
+alert(helper.greeting())
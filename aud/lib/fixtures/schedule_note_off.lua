@@ -0,0 +1,5 @@
+function on_midi(device_name, msg)
+    if msg.type == "note_on" then
+        midi.send_after(50, { 0x80, msg.note, 0 })
+    end
+end
@@ -0,0 +1,5 @@
+midi.send_sysex({ 0x7E, 0x7F, 0x06, 0x01 })
+
+if not midi.send_sysex({ 0x90, 0x3C, 0x7F }) then
+    alert("rejected")
+end
@@ -0,0 +1,10 @@
+function on_start()
+    scope.set_mode("spectrum")
+    scope.set_mode("unknown")
+    scope.set_trigger(0.5, "falling")
+    scope.set_trigger(0.1, "sideways")
+    scope.zoom(20)
+    scope.zoom(-1)
+    scope.zoom(0 / 0)
+    scope.zoom(1e300)
+end
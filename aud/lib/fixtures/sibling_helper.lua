@@ -0,0 +1,7 @@
+local M = {}
+
+function M.greeting()
+    return "hello from a sibling"
+end
+
+return M
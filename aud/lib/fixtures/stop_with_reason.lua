@@ -0,0 +1,3 @@
+function on_connect(device)
+    stop("done")
+end
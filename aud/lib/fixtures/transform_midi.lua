@@ -0,0 +1,10 @@
+function on_midi(_, msg)
+    if msg.note == 60 then
+        msg[2] = msg.note + 12
+        return msg
+    elseif msg.note == 61 then
+        return nil
+    elseif msg.note == 62 then
+        return true, { bytes = { 0x80, msg.note, 0 } }
+    end
+end
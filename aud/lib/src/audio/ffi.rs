@@ -126,12 +126,13 @@ impl aud_transmitter_initializer_t {
             unsafe { slice::from_raw_parts(self.sources, self.num_sources as usize) };
         let mut devices = Vec::with_capacity(self.num_sources as usize);
 
-        for source in sources_slice {
+        for (index, source) in sources_slice.iter().enumerate() {
             let name_cstr = unsafe { CStr::from_ptr(source.name) };
             match name_cstr.to_str() {
                 Ok(name) => devices.push(AudioDevice {
                     name: name.to_owned(),
                     num_channels: source.num_channels as usize,
+                    index,
                 }),
                 Err(_) => return Err(FfiAudioTransmitterResult::FailedToParseAudioSource),
             }
@@ -0,0 +1,316 @@
+use super::*;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Plays a WAV file as if it was an audio input device, streaming
+/// its frames in real time, or faster or slower, for offline analysis.
+pub struct FileAudioInput {
+    samples: Vec<f32>,
+    sample_rate: u32,
+    devices: Vec<AudioDevice>,
+    connected_device: Option<AudioDeviceConnection>,
+    audio: AudioBuffer,
+    /// Next frame of the file to play.
+    position: usize,
+    /// Fraction of a frame left over from the previous update.
+    remainder: f64,
+    last_update: Option<Instant>,
+    speed: f32,
+    looping: bool,
+    paused: bool,
+}
+
+impl FileAudioInput {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let wav = Wav::parse(&std::fs::read(path)?)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Ok(Self {
+            devices: vec![AudioDevice {
+                name,
+                num_channels: wav.num_channels,
+                index: 0,
+            }],
+            samples: wav.samples,
+            sample_rate: wav.sample_rate,
+            connected_device: None,
+            audio: AudioBuffer::default(),
+            position: 0,
+            remainder: 0.,
+            last_update: None,
+            speed: 1.,
+            looping: false,
+            paused: false,
+        })
+    }
+
+    /// Play the file faster, above 1, or slower, below 1, than real time.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.);
+    }
+
+    /// Start over from the beginning of the file once it has been played.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    fn num_file_channels(&self) -> usize {
+        self.devices[0].num_channels.max(1)
+    }
+
+    fn num_file_frames(&self) -> usize {
+        self.samples.len() / self.num_file_channels()
+    }
+
+    /// Play the frames of the file that fit in the elapsed time.
+    fn advance(&mut self, elapsed: Duration) {
+        let Some(ref connection) = self.connected_device else {
+            return;
+        };
+
+        let frames =
+            elapsed.as_secs_f64() * self.sample_rate as f64 * self.speed as f64 + self.remainder;
+        self.remainder = frames.fract();
+
+        let channels = connection.channels.as_vec();
+        let num_channels = self.num_file_channels();
+        self.audio.num_channels = channels.len() as u32;
+
+        for _ in 0..frames as usize {
+            if self.position >= self.num_file_frames() {
+                // an empty file has no frame to loop back to
+                if !self.looping || self.num_file_frames() == 0 {
+                    break;
+                }
+                self.position = 0;
+            }
+
+            let frame = &self.samples[self.position * num_channels..][..num_channels];
+            self.audio
+                .data
+                .extend(channels.iter().map(|&channel| frame[channel]));
+            self.position += 1;
+        }
+    }
+}
+
+impl AudioInterface for FileAudioInput {
+    fn is_accessible(&self) -> bool {
+        self.connected_device.is_some()
+    }
+
+    fn list_audio_devices(&self) -> &[AudioDevice] {
+        self.devices.as_slice()
+    }
+
+    fn connect_to_audio_device(
+        &mut self,
+        audio_device: &AudioDevice,
+        channel_selection: AudioChannelSelection,
+    ) -> anyhow::Result<()> {
+        if !self.devices.contains(audio_device) {
+            anyhow::bail!("Unknown audio file : {}", audio_device.name);
+        }
+
+        if !audio_device.supports_channels(&channel_selection) {
+            anyhow::bail!("Invalid channel selection : {channel_selection:?}");
+        }
+
+        self.connected_device = Some(AudioDeviceConnection {
+            device: audio_device.clone(),
+            channels: channel_selection,
+            sample_rate: self.sample_rate,
+        });
+        self.audio = AudioBuffer::default();
+        self.position = 0;
+        self.remainder = 0.;
+        self.last_update = None;
+        Ok(())
+    }
+
+    fn disconnect_from_audio_device(&mut self) {
+        self.connected_device = None;
+        self.audio = AudioBuffer::default();
+    }
+
+    fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
+        self.connected_device.as_ref()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn process_audio_events(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let elapsed = self
+            .last_update
+            .replace(now)
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+
+        if !self.paused {
+            self.advance(elapsed);
+        }
+        Ok(())
+    }
+}
+
+impl AudioProviding for FileAudioInput {
+    fn retrieve_audio_buffer(&mut self) -> AudioBuffer {
+        let num_channels = self.audio.num_channels;
+        std::mem::replace(&mut self.audio, AudioBuffer::with_length(0, num_channels))
+    }
+}
+
+/// Interleaved samples of a WAV file, as written by [`WavRecorder`].
+pub(crate) struct Wav {
+    pub samples: Vec<f32>,
+    pub num_channels: usize,
+    pub sample_rate: u32,
+}
+
+impl Wav {
+    pub(crate) const PCM: u16 = 1;
+    pub(crate) const FLOAT: u16 = 3;
+    const EXTENSIBLE: u16 = 0xFFFE;
+
+    pub fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            anyhow::bail!("Not a WAV file");
+        }
+
+        let u16_at = |chunk: &[u8], at: usize| u16::from_le_bytes([chunk[at], chunk[at + 1]]);
+        let u32_at = |chunk: &[u8], at: usize| {
+            u32::from_le_bytes([chunk[at], chunk[at + 1], chunk[at + 2], chunk[at + 3]])
+        };
+
+        let mut format = None;
+        let mut data = None;
+        let mut chunks = &bytes[12..];
+        while chunks.len() >= 8 {
+            let size = u32_at(chunks, 4) as usize;
+            let body = &chunks[8..(8 + size).min(chunks.len())];
+            match &chunks[0..4] {
+                b"fmt " if body.len() >= 16 => format = Some(body),
+                b"data" => data = Some(body),
+                _ => {}
+            }
+            // chunks are padded to an even size
+            chunks = &chunks[(8 + size + size % 2).min(chunks.len())..];
+        }
+
+        let (Some(format), Some(data)) = (format, data) else {
+            anyhow::bail!("WAV file without a format or data chunk");
+        };
+
+        let mut tag = u16_at(format, 0);
+        if tag == Self::EXTENSIBLE && format.len() >= 26 {
+            tag = u16_at(format, 24);
+        }
+        let num_channels = u16_at(format, 2) as usize;
+        let sample_rate = u32_at(format, 4);
+        let bits = u16_at(format, 14);
+
+        let width = bits as usize / 8;
+        if num_channels == 0 || width == 0 {
+            anyhow::bail!("Invalid WAV format");
+        }
+
+        let decode: fn(&[u8]) -> f32 = match (tag, bits) {
+            (Self::PCM, 8) => |s| (s[0] as f32 - 128.) / 128.,
+            (Self::PCM, 16) => |s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.,
+            (Self::PCM, 24) => {
+                |s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8388608.
+            }
+            (Self::PCM, 32) => {
+                |s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2147483648.
+            }
+            (Self::FLOAT, 32) => |s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]),
+            _ => anyhow::bail!("Unsupported WAV format {tag} with {bits} bits"),
+        };
+
+        let frame_size = width * num_channels;
+        let samples = data[..data.len() - data.len() % frame_size]
+            .chunks_exact(width)
+            .map(decode)
+            .collect();
+
+        Ok(Self {
+            samples,
+            num_channels,
+            sample_rate,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Record a 16-bit PCM WAV file of interleaved samples.
+    fn write_wav(path: &Path, samples: &[i16], num_channels: u16, sample_rate: u32) {
+        let samples: Vec<f32> = samples
+            .iter()
+            .map(|&sample| sample as f32 / i16::MAX as f32)
+            .collect();
+        let mut recorder =
+            WavRecorder::create(path, num_channels, sample_rate, WavSampleFormat::Int16).unwrap();
+        recorder.write_interleaved(&samples).unwrap();
+        recorder.finalize().unwrap();
+    }
+
+    #[test]
+    fn frames_of_a_wav_file_are_played_in_real_time() {
+        let samples: Vec<i16> = (0..200).map(|i| i * 100).collect();
+        let path = std::env::temp_dir().join(format!("aud-play-{}.wav", std::process::id()));
+        write_wav(&path, &samples, 2, 1_000);
+
+        let input = FileAudioInput::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mut input = input.unwrap();
+
+        let device = input.list_audio_devices()[0].clone();
+        assert_eq!(device.num_channels, 2);
+        input
+            .connect_to_audio_device(&device, AudioChannelSelection::Mono(1))
+            .unwrap();
+        assert_eq!(input.connected_audio_device().unwrap().sample_rate, 1_000);
+
+        input.advance(Duration::from_millis(10));
+        let audio = input.retrieve_audio_buffer();
+        assert_eq!(audio.num_channels, 1);
+        let expected: Vec<f32> = (0..10).map(|i| (i * 200 + 100) as f32 / 32768.).collect();
+        assert_eq!(audio.data, expected);
+
+        input.advance(Duration::from_secs(1));
+        assert_eq!(input.retrieve_audio_buffer().data.len(), 90);
+
+        input.set_looping(true);
+        input.advance(Duration::from_millis(5));
+        assert_eq!(input.retrieve_audio_buffer().data[0], 100. / 32768.);
+    }
+
+    #[test]
+    fn an_empty_wav_file_plays_nothing_when_looping() {
+        let path = std::env::temp_dir().join(format!("aud-play-empty-{}.wav", std::process::id()));
+        write_wav(&path, &[], 1, 1_000);
+
+        let input = FileAudioInput::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        let mut input = input.unwrap();
+
+        let device = input.list_audio_devices()[0].clone();
+        input
+            .connect_to_audio_device(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        input.set_looping(true);
+        input.advance(Duration::from_millis(10));
+        assert!(input.retrieve_audio_buffer().data.is_empty());
+    }
+}
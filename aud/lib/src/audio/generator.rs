@@ -0,0 +1,332 @@
+use super::*;
+use std::time::{Duration, Instant};
+
+/// Shape of a generated signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Waveform {
+    Sine,
+    Square,
+    /// White noise, the frequency is ignored.
+    Noise,
+    /// Sine rising exponentially from 20 Hz up
+    /// to the frequency, then starting over.
+    Sweep,
+}
+
+/// Signal to generate, parsed from `<waveform>[:<frequency>[:<amplitude>]]`,
+/// e.g. `sine:440` or `sweep:20000:0.25`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Signal {
+    pub waveform: Waveform,
+    pub frequency: f32,
+    pub amplitude: f32,
+}
+
+impl Signal {
+    const DEFAULT_FREQUENCY: f32 = 440.;
+    /// Below full scale, not to be taken for clipping.
+    const DEFAULT_AMPLITUDE: f32 = 0.5;
+    /// Frequency at which a sweep starts.
+    const SWEEP_START: f32 = 20.;
+    /// Time taken by a sweep to reach its frequency.
+    const SWEEP_DURATION: Duration = Duration::from_secs(2);
+}
+
+impl std::str::FromStr for Signal {
+    type Err = anyhow::Error;
+
+    fn from_str(signal: &str) -> anyhow::Result<Self> {
+        let mut fields = signal.split(':').map(str::trim);
+
+        let waveform = match fields.next() {
+            Some("sine") => Waveform::Sine,
+            Some("square") => Waveform::Square,
+            Some("noise") => Waveform::Noise,
+            Some("sweep") => Waveform::Sweep,
+            _ => anyhow::bail!("Invalid signal : {signal}, use sine, square, noise or sweep"),
+        };
+
+        let mut number = |name: &str, default: f32| match fields.next() {
+            Some(field) => match field.parse::<f32>() {
+                Ok(value) if value.is_finite() && value > 0. => Ok(value),
+                _ => anyhow::bail!("Invalid signal {name} : {field}"),
+            },
+            None => Ok(default),
+        };
+
+        let frequency = number("frequency", Self::DEFAULT_FREQUENCY)?;
+        let amplitude = number("amplitude", Self::DEFAULT_AMPLITUDE)?;
+        if fields.next().is_some() {
+            anyhow::bail!("Invalid signal : {signal}, use <waveform>[:<frequency>[:<amplitude>]]");
+        }
+
+        Ok(Self {
+            waveform,
+            frequency,
+            amplitude,
+        })
+    }
+}
+
+/// Generates a signal as if it was an audio input device, in real time,
+/// to try out the app without hardware. Generated noise only depends
+/// on its seed, so that two runs with the same seed see the same audio.
+pub struct SignalAudioInput {
+    signal: Signal,
+    sample_rate: u32,
+    devices: Vec<AudioDevice>,
+    connected_device: Option<AudioDeviceConnection>,
+    audio: AudioBuffer,
+    /// Position within the current period of the signal, from 0 to 1.
+    phase: f64,
+    /// Frames generated since the current sweep started.
+    sweep_position: u64,
+    /// State of the noise generator, never 0.
+    noise_state: u64,
+    /// Fraction of a frame left over from the previous update.
+    remainder: f64,
+    last_update: Option<Instant>,
+    paused: bool,
+}
+
+impl SignalAudioInput {
+    const SAMPLE_RATE: u32 = 48_000;
+    pub const DEFAULT_SEED: u64 = 0x5EED;
+    const NUM_CHANNELS: usize = 2;
+    /// Frames generated by an update at most, so that a late update,
+    /// e.g. after the app was suspended, does not generate them all.
+    const MAX_BUFFER_FRAMES: usize = 4096;
+
+    pub fn new(signal: Signal) -> Self {
+        let name = match signal.waveform {
+            Waveform::Noise => "noise".to_owned(),
+            waveform => format!("{waveform:?} {} Hz", signal.frequency).to_lowercase(),
+        };
+
+        let mut input = Self {
+            signal,
+            sample_rate: Self::SAMPLE_RATE,
+            devices: vec![AudioDevice {
+                name,
+                num_channels: Self::NUM_CHANNELS,
+                index: 0,
+            }],
+            connected_device: None,
+            audio: AudioBuffer::default(),
+            phase: 0.,
+            sweep_position: 0,
+            noise_state: 0,
+            remainder: 0.,
+            last_update: None,
+            paused: false,
+        };
+        input.set_seed(Self::DEFAULT_SEED);
+        input
+    }
+
+    /// Start the noise over from this seed.
+    pub fn set_seed(&mut self, seed: u64) {
+        // xorshift is stuck at 0, which is not a valid state
+        self.noise_state = seed.max(1);
+    }
+
+    fn next_noise(&mut self) -> f32 {
+        let mut x = self.noise_state;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.noise_state = x;
+        // the top 24 bits are exact in an f32, spread over [-1, 1)
+        (x >> 40) as f32 / (1u64 << 23) as f32 - 1.
+    }
+
+    fn frequency(&self) -> f64 {
+        let Signal { frequency, .. } = self.signal;
+        match self.signal.waveform {
+            Waveform::Sweep => {
+                let duration = Signal::SWEEP_DURATION.as_secs_f64() * self.sample_rate as f64;
+                let progress = self.sweep_position as f64 / duration;
+                let start = Signal::SWEEP_START.min(frequency) as f64;
+                start * (frequency as f64 / start).powf(progress)
+            }
+            _ => frequency as f64,
+        }
+    }
+
+    fn next_sample(&mut self) -> f32 {
+        let sample = match self.signal.waveform {
+            Waveform::Sine | Waveform::Sweep => (std::f64::consts::TAU * self.phase).sin() as f32,
+            Waveform::Square if self.phase < 0.5 => 1.,
+            Waveform::Square => -1.,
+            Waveform::Noise => self.next_noise(),
+        };
+
+        self.phase = (self.phase + self.frequency() / self.sample_rate as f64).fract();
+        if self.signal.waveform == Waveform::Sweep {
+            let duration = Signal::SWEEP_DURATION.as_secs_f64() * self.sample_rate as f64;
+            self.sweep_position = (self.sweep_position + 1) % duration as u64;
+        }
+
+        sample * self.signal.amplitude
+    }
+
+    /// Generate frames, the same sample on every selected channel.
+    fn generate(&mut self, num_frames: usize) {
+        let Some(ref connection) = self.connected_device else {
+            return;
+        };
+
+        let num_channels = connection.channels.count();
+        self.audio.num_channels = num_channels as u32;
+        for _ in 0..num_frames {
+            let sample = self.next_sample();
+            self.audio
+                .data
+                .extend(std::iter::repeat_n(sample, num_channels));
+        }
+    }
+
+    /// Generate the frames that fit in the elapsed time, up to a buffer.
+    fn advance(&mut self, elapsed: Duration) {
+        let frames = elapsed.as_secs_f64() * self.sample_rate as f64 + self.remainder;
+        if frames >= Self::MAX_BUFFER_FRAMES as f64 {
+            self.remainder = 0.;
+            self.generate(Self::MAX_BUFFER_FRAMES);
+            return;
+        }
+
+        self.remainder = frames.fract();
+        self.generate(frames as usize);
+    }
+}
+
+impl AudioInterface for SignalAudioInput {
+    fn is_accessible(&self) -> bool {
+        self.connected_device.is_some()
+    }
+
+    fn list_audio_devices(&self) -> &[AudioDevice] {
+        self.devices.as_slice()
+    }
+
+    fn connect_to_audio_device(
+        &mut self,
+        audio_device: &AudioDevice,
+        channel_selection: AudioChannelSelection,
+    ) -> anyhow::Result<()> {
+        if !self.devices.contains(audio_device) {
+            anyhow::bail!("Unknown signal : {}", audio_device.name);
+        }
+
+        if !audio_device.supports_channels(&channel_selection) {
+            anyhow::bail!("Invalid channel selection : {channel_selection:?}");
+        }
+
+        self.connected_device = Some(AudioDeviceConnection {
+            device: audio_device.clone(),
+            channels: channel_selection,
+            sample_rate: self.sample_rate,
+        });
+        self.audio = AudioBuffer::default();
+        self.remainder = 0.;
+        self.last_update = None;
+        Ok(())
+    }
+
+    fn disconnect_from_audio_device(&mut self) {
+        self.connected_device = None;
+        self.audio = AudioBuffer::default();
+    }
+
+    fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
+        self.connected_device.as_ref()
+    }
+
+    fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    fn process_audio_events(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let elapsed = self
+            .last_update
+            .replace(now)
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+
+        if !self.paused {
+            self.advance(elapsed);
+        }
+        Ok(())
+    }
+}
+
+impl AudioProviding for SignalAudioInput {
+    fn retrieve_audio_buffer(&mut self) -> AudioBuffer {
+        let num_channels = self.audio.num_channels;
+        std::mem::replace(&mut self.audio, AudioBuffer::with_length(0, num_channels))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn generate(input: &mut SignalAudioInput, num_frames: usize) -> Vec<f32> {
+        let device = input.list_audio_devices()[0].clone();
+        input
+            .connect_to_audio_device(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        input.generate(num_frames);
+        input.retrieve_audio_buffer().data
+    }
+
+    #[test]
+    fn generated_sine_peaks_at_its_frequency_in_the_spectrum() {
+        const FFT_SIZE: usize = 4096;
+        let sample_rate = SignalAudioInput::SAMPLE_RATE as usize;
+
+        let mut input = SignalAudioInput::new("sine:440".parse().unwrap());
+        let samples = generate(&mut input, FFT_SIZE);
+        assert_eq!(samples.len(), FFT_SIZE);
+        assert!(samples.iter().all(|s| s.abs() <= Signal::DEFAULT_AMPLITUDE));
+
+        let spectrum = crate::dsp::magnitude_spectrum(&samples);
+        let peak = (0..spectrum.len())
+            .max_by(|&a, &b| spectrum[a].total_cmp(&spectrum[b]))
+            .unwrap();
+        let peak_frequency = crate::dsp::bin_frequency(peak, FFT_SIZE, sample_rate);
+        assert!((peak_frequency - 440.).abs() < sample_rate as f32 / FFT_SIZE as f32);
+    }
+
+    #[test]
+    fn noise_only_depends_on_its_seed() {
+        let noise = |seed| {
+            let mut input = SignalAudioInput::new("noise:1:0.25".parse().unwrap());
+            input.set_seed(seed);
+            generate(&mut input, 64)
+        };
+
+        assert_eq!(noise(7), noise(7));
+        assert_ne!(noise(7), noise(8));
+        assert!(noise(7).iter().all(|s| s.abs() <= 0.25));
+
+        assert!("triangle:440".parse::<Signal>().is_err());
+        assert!("sine:-1".parse::<Signal>().is_err());
+        assert!("sine:440:1:2".parse::<Signal>().is_err());
+    }
+
+    #[test]
+    fn a_late_update_generates_a_buffer_at_most() {
+        let mut input = SignalAudioInput::new("sine:440".parse().unwrap());
+        generate(&mut input, 0);
+
+        input.advance(Duration::from_millis(10));
+        assert_eq!(input.retrieve_audio_buffer().data.len(), 480);
+
+        input.advance(Duration::from_secs(3_600));
+        assert_eq!(
+            input.retrieve_audio_buffer().data.len(),
+            SignalAudioInput::MAX_BUFFER_FRAMES
+        );
+    }
+}
@@ -1,41 +1,146 @@
 use std::collections::HashSet;
 
 use super::*;
+use crate::drops::DropCounter;
 use cpal::{traits::*, FromSample, Sample, SizedSample};
-use crossbeam::channel::{Receiver, Sender};
+use crossbeam::channel::{Receiver, Sender, TrySendError};
 
 pub struct HostAudioInput {
     host: cpal::Host,
     sender: Sender<AudioBuffer>,
     receiver: Receiver<AudioBuffer>,
+    error_sender: Sender<cpal::StreamError>,
+    error_receiver: Receiver<cpal::StreamError>,
     stream: AudioStream,
     devices: Vec<AudioDevice>,
     connected_device: Option<AudioDeviceConnection>,
+    channel_mismatch: Option<ChannelMismatch>,
     audio: AudioBuffer,
+    drops: DropCounter,
+    /// Buffers queued at [`HostAudioInput::REFERENCE_SAMPLE_RATE`].
+    capacity: usize,
+}
+
+fn list_input_devices(host: &cpal::Host) -> Vec<AudioDevice> {
+    match host.input_devices() {
+        Ok(devices) => devices
+            .enumerate()
+            .filter_map(|(index, device)| AudioDevice::try_from_input(index, device))
+            .collect(),
+        Err(err) => {
+            log::error!("Failed to get input devices: {}", err);
+            vec![]
+        }
+    }
+}
+
+/// Names of the input devices of the system's default audio host.
+/// This does not need a [`HostAudioInput`], so it can be polled from any thread.
+pub fn list_host_audio_devices() -> anyhow::Result<Vec<String>> {
+    Ok(cpal::default_host()
+        .input_devices()?
+        .filter_map(|device| device.name().ok())
+        .collect())
+}
+
+/// A device of the system's default audio host along with its default sample rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostAudioDevice {
+    pub device: AudioDevice,
+    pub sample_rate: u32,
+}
+
+/// Input and output devices of the system's default audio host, in the
+/// order and with the indices [`HostAudioInput`] and [`HostAudioOutput`] list them.
+pub fn describe_host_audio_devices() -> anyhow::Result<(Vec<HostAudioDevice>, Vec<HostAudioDevice>)>
+{
+    let host = cpal::default_host();
+    let describe = |index, device: cpal::Device, config: cpal::SupportedStreamConfig| {
+        Some(HostAudioDevice {
+            sample_rate: config.sample_rate().0,
+            device: AudioDevice::try_from_config(index, device.name().ok()?, config),
+        })
+    };
+
+    let inputs = host
+        .input_devices()?
+        .enumerate()
+        .filter_map(|(index, device)| {
+            let config = device.default_input_config().ok()?;
+            describe(index, device, config)
+        })
+        .collect();
+    let outputs = host
+        .output_devices()?
+        .enumerate()
+        .filter_map(|(index, device)| {
+            let config = device.default_output_config().ok()?;
+            describe(index, device, config)
+        })
+        .collect();
+
+    Ok((inputs, outputs))
 }
 
 impl Default for HostAudioInput {
     fn default() -> Self {
-        let (sender, receiver) = crossbeam::channel::bounded(128);
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
+    }
+}
+
+impl HostAudioInput {
+    /// Buffers queued between the audio thread and the app.
+    pub const DEFAULT_CAPACITY: usize = 128;
+    /// Sample rate the capacity is given for.
+    const REFERENCE_SAMPLE_RATE: u32 = 48_000;
+
+    /// Queue up to `capacity` buffers, at least one, between the audio thread
+    /// and the app. The buffers captured once it is full are dropped and counted.
+    ///
+    /// Devices deliver their buffers more often at higher sample rates, the
+    /// queue grows with the rate of the device to hold as long a time of audio.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, receiver) = crossbeam::channel::bounded(capacity.max(1));
+        let (error_sender, error_receiver) = crossbeam::channel::bounded(16);
         let host = cpal::default_host();
-        let devices = match host.input_devices() {
-            Ok(devices) => devices.filter_map(AudioDevice::try_from_input).collect(),
-            Err(err) => {
-                log::error!("Failed to get input devices: {}", err);
-                vec![]
-            }
-        };
+        let devices = list_input_devices(&host);
 
         Self {
             stream: AudioStream::default(),
             sender,
             receiver,
+            error_sender,
+            error_receiver,
             devices,
             audio: AudioBuffer::default(),
             connected_device: None,
+            channel_mismatch: None,
             host,
+            drops: DropCounter::default(),
+            capacity: capacity.max(1),
         }
     }
+
+    /// Capacity of the queue of a device running at `sample_rate`,
+    /// never less than the capacity at the reference rate.
+    pub fn capacity_for(capacity: usize, sample_rate: u32) -> usize {
+        let scaled = (capacity as u64 * sample_rate as u64)
+            .div_ceil(Self::REFERENCE_SAMPLE_RATE as u64) as usize;
+        scaled.max(capacity).max(1)
+    }
+
+    /// Replace the queue with one sized for the sample rate,
+    /// closing the stream sending to the current one.
+    fn resize_queue(&mut self, sample_rate: u32) {
+        let capacity = Self::capacity_for(self.capacity, sample_rate);
+        if self.sender.capacity() == Some(capacity) {
+            return;
+        }
+
+        self.stream = AudioStream::default();
+        log::info!("queueing up to {capacity} audio buffers at {sample_rate} Hz");
+        (self.sender, self.receiver) = crossbeam::channel::bounded(capacity);
+    }
 }
 
 impl AudioInterface for HostAudioInput {
@@ -53,32 +158,62 @@ impl AudioInterface for HostAudioInput {
             return Ok(());
         }
 
-        self.stream = self
-            .host
-            .input_devices()?
-            .find(|device| device.name().ok().as_deref() == Some(&audio_device.name))
-            .map(|device| {
-                AudioStream::open_input(self.sender.clone(), &device, channel_selection.clone())
-            })
-            .ok_or_else(|| anyhow::anyhow!("No audio input device selected"))??;
+        let device = find_device(self.host.input_devices()?, audio_device)
+            .ok_or_else(|| anyhow::anyhow!("No audio input device selected"))?;
+
+        self.resize_queue(device.default_input_config()?.sample_rate().0);
+        self.stream = AudioStream::open_input(
+            self.sender.clone(),
+            self.drops.clone(),
+            self.error_sender.clone(),
+            &device,
+            channel_selection.clone(),
+        )?;
+
+        let config = self.stream.config.as_ref().unwrap();
+        self.channel_mismatch = detect_channel_mismatch(audio_device, config);
+        if let Some(mismatch) = self.channel_mismatch {
+            log::warn!("{} is {mismatch}", audio_device.name);
+        }
 
         self.connected_device = Some(AudioDeviceConnection {
             device: audio_device.to_owned(),
             channels: channel_selection,
-            sample_rate: self.stream.config.as_ref().unwrap().sample_rate.0,
+            sample_rate: config.sample_rate.0,
         });
 
         Ok(())
     }
 
+    fn disconnect_from_audio_device(&mut self) {
+        self.stream = AudioStream::default();
+        self.connected_device = None;
+        self.channel_mismatch = None;
+        self.audio = AudioBuffer::default();
+        self.receiver.try_iter().for_each(drop);
+    }
+
     fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
         self.connected_device.as_ref()
     }
 
+    fn channel_mismatch(&self) -> Option<ChannelMismatch> {
+        self.channel_mismatch
+    }
+
+    fn num_dropped_buffers(&self) -> u64 {
+        self.drops.count()
+    }
+
     fn list_audio_devices(&self) -> &[AudioDevice] {
         self.devices.as_slice()
     }
 
+    fn refresh_audio_devices(&mut self) {
+        self.devices = list_input_devices(&self.host);
+    }
+
+    /// Fails with the latest stream error reported by CPAL, if any.
     fn process_audio_events(&mut self) -> anyhow::Result<()> {
         for mut buffer in self.receiver.try_iter() {
             if buffer.num_channels != self.audio.num_channels {
@@ -87,6 +222,11 @@ impl AudioInterface for HostAudioInput {
                 self.audio.data.append(&mut buffer.data);
             }
         }
+
+        if let Some(e) = self.error_receiver.try_iter().last() {
+            anyhow::bail!("audio input stream error : {e}");
+        }
+
         Ok(())
     }
 }
@@ -111,7 +251,10 @@ impl Default for HostAudioOutput {
         let (sender, receiver) = crossbeam::channel::bounded(16);
         let host = cpal::default_host();
         let devices = match host.output_devices() {
-            Ok(devices) => devices.filter_map(AudioDevice::try_from_output).collect(),
+            Ok(devices) => devices
+                .enumerate()
+                .filter_map(|(index, device)| AudioDevice::try_from_output(index, device))
+                .collect(),
             Err(err) => {
                 log::error!("Failed to get output devices: {}", err);
                 vec![]
@@ -144,10 +287,7 @@ impl AudioInterface for HostAudioOutput {
             return Ok(());
         }
 
-        self.stream = self
-            .host
-            .output_devices()?
-            .find(|device| device.name().ok().as_deref() == Some(&audio_device.name))
+        self.stream = find_device(self.host.output_devices()?, audio_device)
             .map(|device| {
                 AudioStream::open_output(self.receiver.clone(), &device, channel_selection.clone())
             })
@@ -181,28 +321,59 @@ impl AudioConsuming for HostAudioOutput {
 }
 
 impl AudioDevice {
-    fn try_from_output(device: cpal::Device) -> Option<Self> {
+    fn try_from_output(index: usize, device: cpal::Device) -> Option<Self> {
         Some(Self::try_from_config(
+            index,
             device.name().ok()?,
             device.default_output_config().ok()?,
         ))
     }
 
-    fn try_from_input(device: cpal::Device) -> Option<Self> {
+    fn try_from_input(index: usize, device: cpal::Device) -> Option<Self> {
         Some(Self::try_from_config(
+            index,
             device.name().ok()?,
             device.default_input_config().ok()?,
         ))
     }
 
-    fn try_from_config(name: String, config: cpal::SupportedStreamConfig) -> Self {
+    fn try_from_config(index: usize, name: String, config: cpal::SupportedStreamConfig) -> Self {
         Self {
             name,
             num_channels: config.channels() as usize,
+            index,
         }
     }
 }
 
+/// Find a listed device by its index, so that devices sharing a name
+/// are told apart, or by its name alone if the devices changed since.
+fn find_device(
+    devices: impl Iterator<Item = cpal::Device>,
+    audio_device: &AudioDevice,
+) -> Option<cpal::Device> {
+    let mut named: Vec<_> = devices
+        .enumerate()
+        .filter(|(_, device)| device.name().ok().as_deref() == Some(&audio_device.name))
+        .collect();
+
+    let position = named
+        .iter()
+        .position(|(index, _)| *index == audio_device.index)
+        .unwrap_or(0);
+
+    (position < named.len()).then(|| named.swap_remove(position).1)
+}
+
+/// The stream is read with the channels of its config, so a device
+/// advertising others may have its channels mixed up or missing.
+fn detect_channel_mismatch(
+    audio_device: &AudioDevice,
+    config: &cpal::StreamConfig,
+) -> Option<ChannelMismatch> {
+    ChannelMismatch::detect(audio_device, config.channels as usize)
+}
+
 #[derive(Default)]
 struct AudioStream {
     stream: Option<cpal::Stream>,
@@ -224,22 +395,24 @@ impl AudioStream {
 
     fn open_input(
         tx: Sender<AudioBuffer>,
+        drops: DropCounter,
+        err: Sender<cpal::StreamError>,
         dev: &cpal::Device,
         sel: AudioChannelSelection,
     ) -> anyhow::Result<Self> {
         let (config, sample_format) = setup_preferred_stream_config(dev.default_input_config()?);
 
         let stream = match sample_format {
-            cpal::SampleFormat::I8 => read::<i8>(tx, dev, &config, sel),
-            cpal::SampleFormat::I16 => read::<i16>(tx, dev, &config, sel),
-            cpal::SampleFormat::I32 => read::<i32>(tx, dev, &config, sel),
-            cpal::SampleFormat::I64 => read::<i64>(tx, dev, &config, sel),
-            cpal::SampleFormat::U8 => read::<u8>(tx, dev, &config, sel),
-            cpal::SampleFormat::U16 => read::<u16>(tx, dev, &config, sel),
-            cpal::SampleFormat::U32 => read::<u32>(tx, dev, &config, sel),
-            cpal::SampleFormat::U64 => read::<u64>(tx, dev, &config, sel),
-            cpal::SampleFormat::F32 => read::<f32>(tx, dev, &config, sel),
-            cpal::SampleFormat::F64 => read::<f64>(tx, dev, &config, sel),
+            cpal::SampleFormat::I8 => read::<i8>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::I16 => read::<i16>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::I32 => read::<i32>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::I64 => read::<i64>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::U8 => read::<u8>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::U16 => read::<u16>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::U32 => read::<u32>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::U64 => read::<u64>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::F32 => read::<f32>(tx, drops, err, dev, &config, sel),
+            cpal::SampleFormat::F64 => read::<f64>(tx, drops, err, dev, &config, sel),
             sample_format => anyhow::bail!("Unsupported sample format '{sample_format}'"),
         }?;
 
@@ -303,6 +476,8 @@ fn setup_preferred_stream_config(
 
 fn read<T>(
     sender: Sender<AudioBuffer>,
+    drops: DropCounter,
+    error_sender: Sender<cpal::StreamError>,
     device: &cpal::Device,
     config: &cpal::StreamConfig,
     selection: AudioChannelSelection,
@@ -312,6 +487,7 @@ where
 {
     let enqueue_audio_input_data = make_audio_buffer_enqueueing_function::<T>(
         sender,
+        drops,
         config.channels as usize,
         selection.as_vec(),
     );
@@ -319,7 +495,7 @@ where
     let stream = device.build_input_stream(
         config,
         move |data: &[T], _| enqueue_audio_input_data(data),
-        move |e| log::error!("an error occurred in audio input stream: {e}"),
+        make_stream_error_handler(error_sender),
         None,
     )?;
 
@@ -352,8 +528,20 @@ where
     Ok(stream)
 }
 
+/// Forward CPAL stream errors out of the audio
+/// thread so the owner can observe and react to them.
+fn make_stream_error_handler(sender: Sender<cpal::StreamError>) -> impl FnMut(cpal::StreamError) {
+    move |e| {
+        log::error!("an error occurred in audio input stream: {e}");
+        if let Err(e) = sender.try_send(e) {
+            log::error!("failed to push stream error out of CPAL: {}", e);
+        }
+    }
+}
+
 fn make_audio_buffer_enqueueing_function<T>(
     sender: Sender<AudioBuffer>,
+    drops: DropCounter,
     num_input_channels: usize,
     selected_channels: Vec<usize>,
 ) -> impl Fn(&[T])
@@ -379,8 +567,8 @@ where
             }
         }
 
-        if let Err(e) = sender.try_send(buffer) {
-            log::error!("failed to push audio out of CPAL: {}", e);
+        if let Err(TrySendError::Disconnected(_)) = drops.try_send(&sender, buffer) {
+            log::error!("failed to push audio out of CPAL: disconnected");
         }
     }
 }
@@ -445,7 +633,12 @@ pub fn test_make_audio_buffer_enqueueing_function(
     num_input_channels: usize,
     selected_channels: Vec<usize>,
 ) -> impl Fn(&[f32]) {
-    make_audio_buffer_enqueueing_function::<f32>(sender, num_input_channels, selected_channels)
+    make_audio_buffer_enqueueing_function::<f32>(
+        sender,
+        DropCounter::default(),
+        num_input_channels,
+        selected_channels,
+    )
 }
 
 #[cfg(feature = "bench")]
@@ -461,6 +654,67 @@ pub fn test_make_audio_dequeing_function(
 mod test {
     use super::*;
 
+    #[test]
+    fn the_queue_grows_with_the_sample_rate() {
+        let capacity = HostAudioInput::DEFAULT_CAPACITY;
+        assert_eq!(HostAudioInput::capacity_for(capacity, 48_000), capacity);
+        assert_eq!(HostAudioInput::capacity_for(capacity, 44_100), capacity);
+        assert_eq!(HostAudioInput::capacity_for(capacity, 96_000), capacity * 2);
+        assert_eq!(
+            HostAudioInput::capacity_for(capacity, 192_000),
+            capacity * 4
+        );
+        assert_eq!(HostAudioInput::capacity_for(3, 50_000), 4);
+        assert_eq!(HostAudioInput::capacity_for(0, 0), 1);
+
+        let mut input = HostAudioInput::with_capacity(capacity);
+        input.resize_queue(192_000);
+        assert_eq!(input.sender.capacity(), Some(capacity * 4));
+        input.resize_queue(48_000);
+        assert_eq!(input.sender.capacity(), Some(capacity));
+    }
+
+    #[test]
+    fn stream_errors_are_reported_when_processing_audio_events() {
+        let mut input = HostAudioInput::default();
+        input.process_audio_events().unwrap();
+
+        let mut report_error = make_stream_error_handler(input.error_sender.clone());
+        report_error(cpal::StreamError::DeviceNotAvailable);
+
+        let error = input.process_audio_events().unwrap_err();
+        let expected = cpal::StreamError::DeviceNotAvailable.to_string();
+        assert!(error.to_string().contains(&expected), "{error}");
+
+        input.process_audio_events().unwrap();
+    }
+
+    #[test]
+    fn streams_with_other_channels_than_their_device_are_detected() {
+        let device = AudioDevice {
+            name: "Aggregate Device".into(),
+            num_channels: 2,
+            index: 0,
+        };
+        let config = |channels| cpal::StreamConfig {
+            channels,
+            sample_rate: cpal::SampleRate(48_000),
+            buffer_size: cpal::BufferSize::Default,
+        };
+
+        assert_eq!(detect_channel_mismatch(&device, &config(2)), None);
+
+        let mismatch = detect_channel_mismatch(&device, &config(6)).unwrap();
+        assert_eq!(
+            mismatch,
+            ChannelMismatch {
+                advertised: 2,
+                streamed: 6
+            }
+        );
+        assert_eq!(mismatch.to_string(), "streaming 6 of 2 advertised channels");
+    }
+
     fn assign_channel_index_to_each_sample(buffer: &mut AudioBuffer) {
         for frame in buffer.data.chunks_mut(buffer.num_channels as usize) {
             for (chan, value) in frame.iter_mut().enumerate() {
@@ -494,6 +748,7 @@ mod test {
                 let channels = AudioChannelSelection::Mono(selected_channel);
                 let process = make_audio_buffer_enqueueing_function::<f32>(
                     sender,
+                    DropCounter::default(),
                     num_channels,
                     channels.as_vec(),
                 );
@@ -524,6 +779,7 @@ mod test {
                     let channels = AudioChannelSelection::Range(start..end).as_vec();
                     let process = make_audio_buffer_enqueueing_function::<f32>(
                         sender,
+                        DropCounter::default(),
                         num_channels,
                         channels.clone(),
                     );
@@ -546,6 +802,28 @@ mod test {
         }
     }
 
+    #[test]
+    fn audio_enqueued_on_a_full_channel_is_counted_as_dropped() {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        let drops = DropCounter::default();
+        let process = make_audio_buffer_enqueueing_function::<f32>(
+            sender,
+            drops.clone(),
+            2,
+            AudioChannelSelection::Mono(0).as_vec(),
+        );
+
+        let buffer = AudioBuffer::with_frames(32, 2);
+        for _ in 0..3 {
+            process(&buffer.data);
+        }
+        assert_eq!(drops.count(), 2);
+        assert_eq!(receiver.try_iter().count(), 1);
+
+        process(&buffer.data);
+        assert_eq!(drops.count(), 2);
+    }
+
     #[test]
     fn can_dequeue_audio_into_an_output_buffer_for_a_mono_channel() {
         const MAX_NUM_CHANNELS: usize = 10;
@@ -8,6 +8,11 @@ pub trait AudioInterface {
     /// Lists available audio devices that this source can connect to.
     fn list_audio_devices(&self) -> &[AudioDevice];
 
+    /// Enumerate the available audio devices again, after devices
+    /// were plugged in or out. Sources with a fixed set of devices
+    /// do not need to do anything.
+    fn refresh_audio_devices(&mut self) {}
+
     /// Attempts to establish a connection to a specified audio device for audio retrieval.
     fn connect_to_audio_device(
         &mut self,
@@ -15,9 +20,34 @@ pub trait AudioInterface {
         channel_selection: AudioChannelSelection,
     ) -> anyhow::Result<()>;
 
+    /// Close the connection to the current device, if any.
+    fn disconnect_from_audio_device(&mut self) {}
+
+    /// Whether the device was lost and is being connected to again.
+    /// Only remote sources lose their device this way.
+    fn is_reconnecting(&self) -> bool {
+        false
+    }
+
     /// Retrieve the currently connected audio device
     fn connected_audio_device(&self) -> Option<&AudioDeviceConnection>;
 
+    /// Channels of the connected stream, if they differ from the ones the
+    /// device advertised, as aggregate and virtual devices sometimes do.
+    fn channel_mismatch(&self) -> Option<ChannelMismatch> {
+        None
+    }
+
+    /// Stop producing audio until unpaused. Live sources
+    /// cannot be paused so they do not need to do anything.
+    fn set_paused(&mut self, _paused: bool) {}
+
+    /// Buffers dropped so far because they arrived faster than they were
+    /// retrieved. Only sources fed from another thread can drop any.
+    fn num_dropped_buffers(&self) -> u64 {
+        0
+    }
+
     /// Process internal messages, this may include fetching
     /// or pushing audio to the underlying `AudioDevice`
     fn process_audio_events(&mut self) -> anyhow::Result<()>;
@@ -150,10 +180,39 @@ impl AudioBuffer {
         crate::dsp::deinterleave(&self.data, self.num_channels as usize)
     }
 
+    /// Append the samples of another buffer, taking on its number of channels.
+    ///
+    /// When the number of channels changes, the frames already in this buffer
+    /// keep the channels they share with the new layout, channels that were
+    /// not there before are silent in them.
+    pub fn append(&mut self, other: &mut Self) {
+        if self.num_channels != other.num_channels {
+            self.set_num_channels(other.num_channels);
+        }
+
+        self.data.append(&mut other.data);
+    }
+
+    fn set_num_channels(&mut self, num_channels: u32) {
+        let (old, new) = (self.num_channels as usize, num_channels as usize);
+        self.num_channels = num_channels;
+
+        if old == 0 || new == 0 {
+            self.data.clear();
+            return;
+        }
+
+        self.data = self
+            .data
+            .chunks_exact(old)
+            .flat_map(|frame| (0..new).map(|chan| frame.get(chan).copied().unwrap_or(0.)))
+            .collect();
+    }
+
     /// Number of "frames" in this interleaved buffer. This is effectively
     /// the same as "number of samples per channel" for this buffer.
     pub fn num_frames(&self) -> usize {
-        self.data.len() / self.num_channels.min(1) as usize
+        self.data.len() / self.num_channels.max(1) as usize
     }
 }
 
@@ -161,9 +220,30 @@ impl AudioBuffer {
 pub struct AudioDevice {
     pub name: String,
     pub num_channels: usize,
+    /// Position of the device when the devices were listed,
+    /// which tells apart devices that share the same name.
+    #[serde(default)]
+    pub index: usize,
 }
 
 impl AudioDevice {
+    /// Names that tell the devices apart, where devices sharing
+    /// a name are suffixed with their rank, e.g. "Mic #2".
+    pub fn unique_names(devices: &[Self]) -> Vec<String> {
+        let count = |name: &str| devices.iter().filter(|dev| dev.name == name).count();
+        devices
+            .iter()
+            .enumerate()
+            .map(|(i, dev)| match count(&dev.name) {
+                1 => dev.name.clone(),
+                _ => {
+                    let rank = devices[..=i].iter().filter(|d| d.name == dev.name).count();
+                    format!("{} #{rank}", dev.name)
+                }
+            })
+            .collect()
+    }
+
     /// Check if the requested channel selection is viable for this device
     pub fn supports_channels(&self, selection: &AudioChannelSelection) -> bool {
         let chans = 0..self.num_channels;
@@ -177,6 +257,33 @@ impl AudioDevice {
     }
 }
 
+/// A stream opened with another number of channels
+/// than its device advertised when it was listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChannelMismatch {
+    pub advertised: usize,
+    pub streamed: usize,
+}
+
+impl ChannelMismatch {
+    pub fn detect(device: &AudioDevice, num_stream_channels: usize) -> Option<Self> {
+        (device.num_channels != num_stream_channels).then_some(Self {
+            advertised: device.num_channels,
+            streamed: num_stream_channels,
+        })
+    }
+}
+
+impl std::fmt::Display for ChannelMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "streaming {} of {} advertised channels",
+            self.streamed, self.advertised
+        )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct AudioDeviceConnection {
     pub device: AudioDevice,
@@ -219,10 +326,96 @@ impl AudioChannelSelection {
     }
 }
 
+/// Selects which channels of a buffer are kept, and in which order.
+///
+/// For example a map of `[2, 0]` builds a stereo buffer from
+/// the third and first channels of the source buffer.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChannelMap {
+    /// Source channel of each output channel,
+    /// `None` keeps all channels as they are.
+    sources: Option<Vec<usize>>,
+}
+
+impl ChannelMap {
+    /// Create a map over buffers with `num_channels` channels.
+    /// Fails if any source channel is out of range.
+    pub fn new(sources: Vec<usize>, num_channels: usize) -> anyhow::Result<Self> {
+        if sources.is_empty() {
+            anyhow::bail!("Channel map needs at least one channel");
+        }
+
+        if let Some(source) = sources.iter().find(|&&source| source >= num_channels) {
+            anyhow::bail!("Channel {source} is out of range for {num_channels} channels");
+        }
+
+        Ok(Self {
+            sources: Some(sources),
+        })
+    }
+
+    /// Keep all the channels of a buffer in their original order.
+    pub fn identity() -> Self {
+        Self::default()
+    }
+
+    /// Build the interleaved buffer with the mapped channels.
+    pub fn apply(&self, buffer: &AudioBuffer) -> anyhow::Result<AudioBuffer> {
+        let Some(ref sources) = self.sources else {
+            return Ok(buffer.clone());
+        };
+
+        let num_channels = buffer.num_channels as usize;
+        if let Some(source) = sources.iter().find(|&&source| source >= num_channels) {
+            anyhow::bail!("Channel {source} is out of range for {num_channels} channels");
+        }
+
+        Ok(AudioBuffer {
+            data: buffer
+                .data
+                .chunks_exact(num_channels)
+                .flat_map(|frame| sources.iter().map(|&source| frame[source]))
+                .collect(),
+            num_channels: sources.len() as u32,
+        })
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
+    #[test]
+    fn appending_a_different_channel_count_keeps_the_shared_channels() {
+        // each sample is 10 x frame + channel
+        let frames = |start: usize, num_frames: usize, num_channels: usize| AudioBuffer {
+            data: (start..start + num_frames)
+                .flat_map(|frame| (0..num_channels).map(move |chan| (frame * 10 + chan) as f32))
+                .collect(),
+            num_channels: num_channels as u32,
+        };
+
+        let mut buffer = AudioBuffer::with_length(0, 2);
+        buffer.append(&mut frames(0, 2, 2));
+        buffer.append(&mut frames(2, 1, 4));
+        #[rustfmt::skip]
+        let expected = [
+            0., 1., 0., 0.,
+            10., 11., 0., 0.,
+            20., 21., 22., 23.,
+        ];
+        assert_eq!(buffer.num_channels, 4);
+        assert_eq!(buffer.data, expected);
+
+        buffer.append(&mut frames(3, 1, 2));
+        assert_eq!(buffer.num_channels, 2);
+        assert_eq!(buffer.data, [0., 1., 10., 11., 20., 21., 30., 31.]);
+
+        buffer.append(&mut frames(4, 1, 4));
+        assert_eq!(buffer.data.len() % 4, 0);
+        assert_eq!(buffer.deinterleave()[3], [0., 0., 0., 0., 43.]);
+    }
+
     #[test]
     fn mono_channels_can_be_selected() {
         use AudioChannelSelection::*;
@@ -230,6 +423,7 @@ mod test {
         let dev = AudioDevice {
             name: String::default(),
             num_channels: 1,
+            index: 0,
         };
         assert!(dev.supports_channels(&Mono(0)));
 
@@ -238,6 +432,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn devices_sharing_a_name_are_told_apart() {
+        let devices: Vec<_> = ["mic", "line", "mic"]
+            .iter()
+            .enumerate()
+            .map(|(index, name)| AudioDevice {
+                name: name.to_string(),
+                num_channels: 1,
+                index,
+            })
+            .collect();
+
+        assert_eq!(
+            AudioDevice::unique_names(&devices),
+            ["mic #1", "line", "mic #2"]
+        );
+    }
+
     #[test]
     fn range_of_channels_can_be_selected() {
         use AudioChannelSelection::*;
@@ -246,6 +458,7 @@ mod test {
         let dev = AudioDevice {
             name: String::default(),
             num_channels: NUM_CHANNELS,
+            index: 0,
         };
 
         assert!(dev.supports_channels(&Range(0..NUM_CHANNELS)));
@@ -260,9 +473,38 @@ mod test {
         let dev = AudioDevice {
             name: String::default(),
             num_channels: NUM_CHANNELS,
+            index: 0,
         };
 
         assert!(dev.supports_channels(&Range(0..NUM_CHANNELS)));
         assert!(!dev.supports_channels(&Range(NUM_CHANNELS..NUM_CHANNELS * 2)));
     }
+
+    #[test]
+    fn channel_map_reorders_and_drops_channels() {
+        let buffer = AudioBuffer {
+            data: vec![0., 1., 2., 3., 10., 11., 12., 13.],
+            num_channels: 4,
+        };
+
+        let map = ChannelMap::new(vec![2, 0], 4).unwrap();
+        assert_eq!(
+            map.apply(&buffer).unwrap(),
+            AudioBuffer {
+                data: vec![2., 0., 12., 10.],
+                num_channels: 2,
+            }
+        );
+
+        assert_eq!(ChannelMap::identity().apply(&buffer).unwrap(), buffer);
+    }
+
+    #[test]
+    fn channel_map_rejects_out_of_range_channels() {
+        assert!(ChannelMap::new(vec![0, 4], 4).is_err());
+        assert!(ChannelMap::new(vec![], 4).is_err());
+
+        let map = ChannelMap::new(vec![3], 4).unwrap();
+        assert!(map.apply(&AudioBuffer::with_frames(4, 2)).is_err());
+    }
 }
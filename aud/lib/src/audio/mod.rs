@@ -1,10 +1,18 @@
 #[cfg(feature = "ffi")]
 mod ffi;
 
+mod file;
+mod generator;
 mod host;
 mod interface;
+mod monitor;
 mod net;
+mod record;
 
+pub use file::*;
+pub use generator::*;
 pub use host::*;
 pub use interface::*;
+pub use monitor::*;
 pub use net::*;
+pub use record::*;
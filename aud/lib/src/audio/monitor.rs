@@ -0,0 +1,235 @@
+use super::*;
+use crate::dsp::LinearResampler;
+use cpal::{traits::*, FromSample, SizedSample};
+use crossbeam::queue::ArrayQueue;
+use std::{sync::Arc, time::Duration};
+
+/// Longest time audio can wait in the ring before being played.
+const MAX_LATENCY: Duration = Duration::from_millis(500);
+
+/// Lock-free ring of interleaved frames, filled by the capture side and
+/// emptied by the playback callback. Frames are written and read whole,
+/// so the channels stay aligned as long as there is a single writer and
+/// a single reader.
+#[derive(Clone)]
+pub struct MonitorRing {
+    samples: Arc<ArrayQueue<f32>>,
+    num_channels: usize,
+}
+
+impl MonitorRing {
+    pub fn new(num_frames: usize, num_channels: usize) -> Self {
+        let num_channels = num_channels.max(1);
+        Self {
+            samples: Arc::new(ArrayQueue::new(num_frames.max(1) * num_channels)),
+            num_channels,
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    pub fn num_frames(&self) -> usize {
+        self.samples.len() / self.num_channels
+    }
+
+    /// Write the frames that fit, returning how many were dropped
+    /// because the playback side did not keep up.
+    pub fn push(&self, frames: &[f32]) -> usize {
+        let mut num_dropped = 0;
+        for frame in frames.chunks_exact(self.num_channels) {
+            if self.samples.capacity() - self.samples.len() < self.num_channels {
+                num_dropped += 1;
+                continue;
+            }
+
+            for &sample in frame {
+                let _ = self.samples.push(sample);
+            }
+        }
+        num_dropped
+    }
+
+    /// Fill the output with the oldest frames, padding with silence
+    /// when the ring runs dry. Returns the number of frames read.
+    pub fn pop_into(&self, output: &mut [f32]) -> usize {
+        let mut num_read = 0;
+        for frame in output.chunks_exact_mut(self.num_channels) {
+            if self.samples.len() < self.num_channels {
+                frame.fill(0.);
+                continue;
+            }
+
+            for sample in frame.iter_mut() {
+                *sample = self.samples.pop().unwrap_or(0.);
+            }
+            num_read += 1;
+        }
+        num_read
+    }
+}
+
+/// Whether playing the input on the output would capture it again,
+/// such as when monitoring a loopback device on the device it loops.
+pub fn would_feed_back(input_name: &str, output_name: &str) -> bool {
+    let input_name = input_name.to_lowercase();
+    let output_name = output_name.to_lowercase();
+    input_name == output_name || input_name == format!("monitor of {output_name}")
+}
+
+/// Plays the captured audio back on the default output device,
+/// so that what is being scoped can be heard.
+pub struct AudioMonitor {
+    ring: MonitorRing,
+    output_name: String,
+    output_rate: u32,
+    gain: f32,
+    /// Resampler in use along with the input sample rate it converts from.
+    resampler: Option<(u32, LinearResampler)>,
+    _stream: cpal::Stream,
+}
+
+impl AudioMonitor {
+    /// Open the default output device, playing
+    /// the captured audio after a `delay`.
+    pub fn open_default(delay: Duration) -> anyhow::Result<Self> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| anyhow::anyhow!("No audio output device available"))?;
+
+        let config = device.default_output_config()?;
+        let sample_format = config.sample_format();
+        let config: cpal::StreamConfig = config.into();
+        let output_rate = config.sample_rate.0;
+
+        let to_frames = |duration: Duration| (duration.as_secs_f64() * output_rate as f64) as usize;
+        let delay = delay.min(MAX_LATENCY);
+        let ring = MonitorRing::new(to_frames(MAX_LATENCY + delay), config.channels as usize);
+        ring.push(&vec![0.; to_frames(delay) * ring.num_channels()]);
+
+        let stream = match sample_format {
+            cpal::SampleFormat::I8 => play::<i8>(ring.clone(), &device, &config),
+            cpal::SampleFormat::I16 => play::<i16>(ring.clone(), &device, &config),
+            cpal::SampleFormat::I32 => play::<i32>(ring.clone(), &device, &config),
+            cpal::SampleFormat::I64 => play::<i64>(ring.clone(), &device, &config),
+            cpal::SampleFormat::U8 => play::<u8>(ring.clone(), &device, &config),
+            cpal::SampleFormat::U16 => play::<u16>(ring.clone(), &device, &config),
+            cpal::SampleFormat::U32 => play::<u32>(ring.clone(), &device, &config),
+            cpal::SampleFormat::U64 => play::<u64>(ring.clone(), &device, &config),
+            cpal::SampleFormat::F32 => play::<f32>(ring.clone(), &device, &config),
+            cpal::SampleFormat::F64 => play::<f64>(ring.clone(), &device, &config),
+            sample_format => anyhow::bail!("Unsupported sample format '{sample_format}'"),
+        }?;
+
+        Ok(Self {
+            ring,
+            output_name: device.name()?,
+            output_rate,
+            gain: 1.,
+            resampler: None,
+            _stream: stream,
+        })
+    }
+
+    pub fn output_name(&self) -> &str {
+        &self.output_name
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain;
+    }
+
+    /// Queue captured audio, recorded at `sample_rate`, for playback.
+    pub fn push(&mut self, audio: &AudioBuffer, sample_rate: u32) {
+        let num_channels = audio.num_channels.max(1) as usize;
+        let mut data = audio.data.clone();
+
+        if sample_rate != self.output_rate {
+            let resampler = match self.resampler {
+                Some((rate, ref mut resampler))
+                    if rate == sample_rate && resampler.num_channels() == num_channels =>
+                {
+                    resampler
+                }
+                _ => {
+                    let resampler =
+                        LinearResampler::new(sample_rate, self.output_rate, num_channels);
+                    &mut self.resampler.insert((sample_rate, resampler)).1
+                }
+            };
+            data = resampler.process(&data);
+        }
+
+        let frames = remap_channels(&data, num_channels, self.ring.num_channels(), self.gain);
+        let num_dropped = self.ring.push(&frames);
+        if num_dropped > 0 {
+            log::warn!("dropped {num_dropped} monitored frames");
+        }
+    }
+}
+
+/// Spread the input channels over the output ones, repeating
+/// the last input channel when there are more output channels.
+fn remap_channels(data: &[f32], num_inputs: usize, num_outputs: usize, gain: f32) -> Vec<f32> {
+    data.chunks_exact(num_inputs)
+        .flat_map(|frame| (0..num_outputs).map(move |chan| frame[chan.min(num_inputs - 1)] * gain))
+        .collect()
+}
+
+fn play<T>(
+    ring: MonitorRing,
+    device: &cpal::Device,
+    config: &cpal::StreamConfig,
+) -> anyhow::Result<cpal::Stream>
+where
+    T: SizedSample + FromSample<f32> + 'static,
+{
+    let mut frames = vec![];
+    let stream = device.build_output_stream(
+        config,
+        move |data: &mut [T], _| {
+            frames.resize(data.len(), 0.);
+            ring.pop_into(&mut frames);
+            for (output, &sample) in data.iter_mut().zip(&frames) {
+                *output = T::from_sample(sample);
+            }
+        },
+        move |e| log::error!("an error occurred in the audio monitor stream: {e}"),
+        None,
+    )?;
+
+    stream.play()?;
+    log::trace!("started audio monitor stream");
+    Ok(stream)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frames_are_handed_whole_from_capture_to_playback() {
+        let ring = MonitorRing::new(4, 2);
+
+        let capture = {
+            let ring = ring.clone();
+            std::thread::spawn(move || ring.push(&[1., -1., 2., -2., 3., -3.]))
+        };
+        assert_eq!(capture.join().unwrap(), 0);
+        assert_eq!(ring.num_frames(), 3);
+
+        assert_eq!(ring.push(&[4., -4., 5., -5.]), 1);
+
+        let mut output = [9.; 10];
+        assert_eq!(ring.pop_into(&mut output), 4);
+        assert_eq!(output, [1., -1., 2., -2., 3., -3., 4., -4., 0., 0.]);
+    }
+
+    #[test]
+    fn monitoring_a_loopback_of_the_output_would_feed_back() {
+        assert!(would_feed_back("Speakers", "speakers"));
+        assert!(would_feed_back("Monitor of Speakers", "Speakers"));
+        assert!(!would_feed_back("Microphone", "Speakers"));
+    }
+}
@@ -1,6 +1,68 @@
 use super::*;
 use crate::comms::*;
 use crossbeam::channel::{Receiver, Sender};
+use std::time::{Duration, Instant};
+
+/// How a `RemoteAudioReceiver` gets the audio flowing again
+/// once the remote has been silent for too long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Silence after which the remote is considered lost.
+    pub timeout: Duration,
+    /// Delay between the first two retries, doubled after each failed retry.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Retries after which the receiver gives up and reports an error.
+    pub max_retries: usize,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(1),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+            max_retries: 8,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay after a number of failed retries.
+    fn backoff(&self, num_retries: usize) -> Duration {
+        let factor = 1u32 << num_retries.saturating_sub(1).min(16);
+        self.initial_backoff
+            .saturating_mul(factor)
+            .min(self.max_backoff)
+    }
+}
+
+/// Error returned once the remote stayed silent through every retry.
+/// The receiver then stops retrying until a device is requested again.
+#[derive(Debug)]
+pub struct RemoteUnreachable(pub usize);
+
+impl std::fmt::Display for RemoteUnreachable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "remote audio unreachable after {} retries", self.0)
+    }
+}
+
+impl std::error::Error for RemoteUnreachable {}
+
+/// State of the audio stream coming from the remote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Link {
+    /// No device was requested from the remote.
+    Idle,
+    /// The remote has answered recently.
+    Streaming { last_response: Instant },
+    /// The remote has been silent, the device is requested again at `next_retry`.
+    Connecting {
+        num_retries: usize,
+        next_retry: Instant,
+    },
+}
 
 /// `RemoteAudioReceiver` acts as a facade to a remote `AudioProviding` struct,
 /// proxying the audio data to the local `AudioConsumer`.
@@ -16,6 +78,9 @@ pub struct RemoteAudioReceiver<AudioConsumer: AudioConsuming> {
     packets: AudioPacketSequence,
     audio_consumer: AudioConsumer,
     connected_device: Option<AudioDeviceConnection>,
+    requested: Option<(AudioDevice, AudioChannelSelection)>,
+    link: Link,
+    retry_policy: RetryPolicy,
     _handle: SocketCommunicator,
 }
 
@@ -38,6 +103,9 @@ impl<AudioConsumer: AudioConsuming> RemoteAudioReceiver<AudioConsumer> {
             audio_consumer,
             packets: AudioPacketSequence::default(),
             connected_device: None,
+            requested: None,
+            link: Link::Idle,
+            retry_policy: RetryPolicy::default(),
             _handle: SocketCommunicator::launch(
                 sockets,
                 Events {
@@ -47,6 +115,69 @@ impl<AudioConsumer: AudioConsuming> RemoteAudioReceiver<AudioConsumer> {
             ),
         })
     }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.retry_policy = retry_policy;
+    }
+
+    fn mark_streaming(&mut self, now: Instant) {
+        if self.requested.is_none() {
+            return;
+        }
+
+        if self.is_reconnecting() {
+            log::info!("remote audio reconnected");
+        }
+        self.link = Link::Streaming { last_response: now };
+    }
+
+    fn request_device(&self) -> anyhow::Result<()> {
+        let Some((ref device, ref channels)) = self.requested else {
+            return Ok(());
+        };
+
+        self.sender.send(AudioRequest::Connect {
+            device: device.clone(),
+            channels: channels.clone(),
+        })?;
+        Ok(())
+    }
+
+    /// Request the device again, backing off further after each
+    /// retry, until the remote answers or the retries run out.
+    fn retry_when_silent(&mut self, now: Instant) -> anyhow::Result<()> {
+        let policy = self.retry_policy;
+        match self.link {
+            Link::Streaming { last_response } if now - last_response > policy.timeout => {
+                log::warn!("remote audio silent for {:?}, reconnecting", policy.timeout);
+                self.is_remote_accessible = false;
+                self.link = Link::Connecting {
+                    num_retries: 0,
+                    next_retry: now,
+                };
+                self.retry_when_silent(now)
+            }
+            Link::Connecting {
+                num_retries,
+                next_retry,
+            } if now >= next_retry => {
+                if num_retries >= policy.max_retries {
+                    self.link = Link::Idle;
+                    return Err(RemoteUnreachable(num_retries).into());
+                }
+
+                // audio from before the drop would be mixed with the new stream
+                self.packets = AudioPacketSequence::default();
+                self.request_device()?;
+                self.link = Link::Connecting {
+                    num_retries: num_retries + 1,
+                    next_retry: now + policy.backoff(num_retries + 1),
+                };
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 impl<AudioConsumer: AudioConsuming> AudioInterface for RemoteAudioReceiver<AudioConsumer> {
@@ -68,19 +199,37 @@ impl<AudioConsumer: AudioConsuming> AudioInterface for RemoteAudioReceiver<Audio
         channel_selection: AudioChannelSelection,
     ) -> anyhow::Result<()> {
         self.is_remote_accessible = false;
-        self.sender.send(AudioRequest::Connect {
-            device: audio_device.clone(),
-            channels: channel_selection.clone(),
-        })?;
+        self.requested = Some((audio_device.clone(), channel_selection));
+        self.request_device()?;
+        // the first answer is awaited as long as any later one
+        self.link = Link::Connecting {
+            num_retries: 0,
+            next_retry: Instant::now() + self.retry_policy.timeout,
+        };
         Ok(())
     }
 
+    fn disconnect_from_audio_device(&mut self) {
+        self.requested = None;
+        self.link = Link::Idle;
+    }
+
+    fn is_reconnecting(&self) -> bool {
+        matches!(self.link, Link::Connecting { num_retries, .. } if num_retries > 0)
+    }
+
     fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
         self.connected_device.as_ref()
     }
 
     fn process_audio_events(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
         while let Ok(event) = self.receiver.try_recv() {
+            // listing devices says nothing about whether the audio still flows
+            if !matches!(event, AudioResponse::Devices(_)) {
+                self.mark_streaming(now);
+            }
+
             match event {
                 AudioResponse::Connected(dev) => {
                     self.is_remote_accessible = true;
@@ -102,7 +251,7 @@ impl<AudioConsumer: AudioConsuming> AudioInterface for RemoteAudioReceiver<Audio
             self.audio_consumer.consume_audio_buffer(buffer)?;
         }
 
-        Ok(())
+        self.retry_when_silent(now)
     }
 }
 
@@ -118,6 +267,7 @@ pub struct RemoteAudioTransmitter<AudioProvider> {
     responses: Sender<AudioResponse>,
     sequence: AudioPacketSequenceBuilder,
     connected_device: Option<AudioDeviceConnection>,
+    channel_map: ChannelMap,
     _handle: SocketCommunicator,
 }
 
@@ -141,6 +291,7 @@ where
             responses: response_tx,
             sequence: AudioPacketSequenceBuilder::default(),
             connected_device: None,
+            channel_map: ChannelMap::identity(),
             _handle: SocketCommunicator::launch(
                 sockets,
                 Events {
@@ -151,12 +302,27 @@ where
         })
     }
 
+    /// Select which of the provided channels are sent, and in which order.
+    pub fn set_channel_map(&mut self, channel_map: ChannelMap) {
+        self.channel_map = channel_map;
+    }
+
     fn purge_audio_cache(&mut self) {
         let _ = self.audio_provider.retrieve_audio_buffer();
     }
 
     fn try_send_audio(&mut self) {
-        let buffer = self.audio_provider.retrieve_audio_buffer();
+        let buffer = match self
+            .channel_map
+            .apply(&self.audio_provider.retrieve_audio_buffer())
+        {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                log::error!("Failed to map audio channels : {e}");
+                return;
+            }
+        };
+
         for packet in self.sequence.from_buffer(&buffer).into_packets() {
             if let Err(e) = self.responses.try_send(AudioResponse::Audio(packet)) {
                 log::error!("Failed to pass audio response to socket tasks : {e}");
@@ -242,7 +408,10 @@ where
 mod test {
     use super::*;
     use crate::comms::test::{MockSocket, ADDR};
-    use std::sync::{Arc, Mutex};
+    use std::sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, Mutex,
+    };
 
     #[derive(Default)]
     struct MockConsumer {
@@ -265,14 +434,17 @@ mod test {
             AudioDevice {
                 name: "a".to_owned(),
                 num_channels: 1,
+                index: 0,
             },
             AudioDevice {
                 name: "b".to_owned(),
                 num_channels: 2,
+                index: 1,
             },
             AudioDevice {
                 name: "c".to_owned(),
                 num_channels: 3,
+                index: 2,
             },
         ];
 
@@ -337,6 +509,268 @@ mod test {
         assert_eq!(audio_recv.list_audio_devices(), expected_device_list);
     }
 
+    /// Remote streaming audio once a device is requested, until it goes offline.
+    #[derive(Default)]
+    struct FlakyRemote {
+        is_online: AtomicBool,
+        is_streaming: AtomicBool,
+        num_requests: AtomicUsize,
+        num_packets: AtomicU64,
+    }
+
+    impl FlakyRemote {
+        fn online() -> Arc<Self> {
+            let remote = Arc::new(Self::default());
+            remote.is_online.store(true, Ordering::SeqCst);
+            remote
+        }
+
+        fn go_offline(&self) {
+            self.is_online.store(false, Ordering::SeqCst);
+            self.is_streaming.store(false, Ordering::SeqCst);
+        }
+
+        fn socket(self: &Arc<Self>) -> Sockets<MockSocket> {
+            let on_send = {
+                let remote = self.clone();
+                move |buf: &[u8]| {
+                    if let Ok(AudioRequest::Connect { .. }) = AudioRequest::deserialized(buf) {
+                        remote.num_requests.fetch_add(1, Ordering::SeqCst);
+                        let is_online = remote.is_online.load(Ordering::SeqCst);
+                        remote.is_streaming.store(is_online, Ordering::SeqCst);
+                    }
+                    Ok(buf.len())
+                }
+            };
+
+            let on_recv = {
+                let remote = self.clone();
+                move |buf: &mut [u8]| {
+                    std::thread::sleep(std::time::Duration::from_millis(1));
+                    if !remote.is_streaming.load(Ordering::SeqCst) {
+                        return Ok((0, ADDR));
+                    }
+
+                    let index = remote.num_packets.fetch_add(1, Ordering::SeqCst);
+                    let packet = AudioPacket::new(index, &[0.5; 64], 1);
+                    let response = AudioResponse::Audio(packet).serialize().unwrap();
+                    buf[..response.len()].copy_from_slice(&response);
+                    Ok((response.len(), ADDR))
+                }
+            };
+
+            Sockets {
+                socket: MockSocket::with_hooks(on_send, on_recv),
+                target: ADDR,
+            }
+        }
+    }
+
+    fn flaky_receiver(
+        remote: &Arc<FlakyRemote>,
+        max_retries: usize,
+    ) -> (RemoteAudioReceiver<MockConsumer>, Arc<AtomicUsize>) {
+        let num_frames = Arc::new(AtomicUsize::new(0));
+        let consumer = MockConsumer {
+            on_consume: Some(Box::new({
+                let num_frames = num_frames.clone();
+                move |buffer: AudioBuffer| {
+                    num_frames.fetch_add(buffer.num_frames(), Ordering::SeqCst);
+                    Ok(())
+                }
+            })),
+        };
+
+        let mut audio_recv = RemoteAudioReceiver::new(consumer, remote.socket()).unwrap();
+        audio_recv.set_retry_policy(RetryPolicy {
+            timeout: Duration::from_millis(50),
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(40),
+            max_retries,
+        });
+
+        let device = AudioDevice {
+            name: "remote".to_owned(),
+            num_channels: 1,
+            index: 0,
+        };
+        audio_recv
+            .connect_to_audio_device(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        (audio_recv, num_frames)
+    }
+
+    /// Process events until `is_done` or a timeout, returning false on timeout.
+    fn process_until(
+        audio_recv: &mut RemoteAudioReceiver<MockConsumer>,
+        is_done: impl Fn(&RemoteAudioReceiver<MockConsumer>) -> bool,
+    ) -> bool {
+        let start = Instant::now();
+        while !is_done(audio_recv) {
+            if start.elapsed() > Duration::from_secs(2) {
+                return false;
+            }
+            audio_recv.process_audio_events().unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        true
+    }
+
+    #[test]
+    fn receiver_resumes_the_audio_once_a_dropped_remote_is_back() {
+        let remote = FlakyRemote::online();
+        let (mut audio_recv, num_frames) = flaky_receiver(&remote, 100);
+        let frames = || num_frames.load(Ordering::SeqCst);
+
+        assert!(process_until(&mut audio_recv, |_| frames() > 0));
+        assert!(!audio_recv.is_reconnecting());
+
+        remote.go_offline();
+        assert!(process_until(&mut audio_recv, |rx| rx.is_reconnecting()));
+
+        let num_frames_before = frames();
+        remote.is_online.store(true, Ordering::SeqCst);
+        assert!(process_until(&mut audio_recv, |rx| {
+            !rx.is_reconnecting() && frames() > num_frames_before
+        }));
+        assert!(remote.num_requests.load(Ordering::SeqCst) >= 2);
+    }
+
+    #[test]
+    fn receiver_reports_a_remote_that_never_comes_back() {
+        let remote = FlakyRemote::online();
+        remote.go_offline();
+        let (mut audio_recv, _) = flaky_receiver(&remote, 3);
+
+        let start = Instant::now();
+        let error = loop {
+            assert!(start.elapsed() < Duration::from_secs(2), "never gave up");
+            if let Err(e) = audio_recv.process_audio_events() {
+                break e;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+
+        assert!(error.downcast_ref::<RemoteUnreachable>().is_some());
+        assert_eq!(
+            error.to_string(),
+            "remote audio unreachable after 3 retries"
+        );
+        assert_eq!(remote.num_requests.load(Ordering::SeqCst), 4);
+        assert!(!audio_recv.is_reconnecting());
+
+        // the retries stop at the cap
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_millis(200) {
+            audio_recv.process_audio_events().unwrap();
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        assert_eq!(remote.num_requests.load(Ordering::SeqCst), 4);
+    }
+
+    #[test]
+    fn retries_back_off_up_to_a_maximum() {
+        let policy = RetryPolicy {
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(500),
+            ..Default::default()
+        };
+        let backoffs: Vec<_> = (1..=5).map(|n| policy.backoff(n).as_millis()).collect();
+        assert_eq!(backoffs, [100, 200, 400, 500, 500]);
+    }
+
+    /// Provider of a single 4 channel buffer.
+    struct QuadProvider {
+        devices: Vec<AudioDevice>,
+        buffer: Option<AudioBuffer>,
+    }
+
+    impl AudioProviding for QuadProvider {
+        fn retrieve_audio_buffer(&mut self) -> AudioBuffer {
+            self.buffer.take().unwrap_or_default()
+        }
+    }
+
+    impl AudioInterface for QuadProvider {
+        fn is_accessible(&self) -> bool {
+            true
+        }
+
+        fn list_audio_devices(&self) -> &[AudioDevice] {
+            &self.devices
+        }
+
+        fn connect_to_audio_device(
+            &mut self,
+            _: &AudioDevice,
+            _: AudioChannelSelection,
+        ) -> anyhow::Result<()> {
+            Ok(())
+        }
+
+        fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
+            None
+        }
+
+        fn process_audio_events(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn transmitter_sends_the_mapped_channels() {
+        let packets = Arc::new(Mutex::new(vec![]));
+        let socket = MockSocket::with_hooks(
+            {
+                let packets = packets.clone();
+                move |buf: &[u8]| {
+                    if let Ok(AudioResponse::Audio(packet)) = AudioResponse::deserialized(buf) {
+                        packets.lock().unwrap().push(packet);
+                    }
+                    Ok(buf.len())
+                }
+            },
+            |_: &mut [u8]| {
+                std::thread::sleep(Duration::from_millis(1));
+                Ok((0, ADDR))
+            },
+        );
+
+        let provider = QuadProvider {
+            devices: vec![],
+            buffer: Some(AudioBuffer {
+                data: vec![0., 1., 2., 3., 10., 11., 12., 13.],
+                num_channels: 4,
+            }),
+        };
+        let mut audio_tx = RemoteAudioTransmitter::new(
+            provider,
+            Sockets {
+                socket,
+                target: ADDR,
+            },
+        )
+        .unwrap();
+        audio_tx.set_channel_map(ChannelMap::new(vec![2, 0], 4).unwrap());
+        audio_tx.process_audio_events().unwrap();
+
+        let start = Instant::now();
+        while packets.lock().unwrap().is_empty() {
+            assert!(start.elapsed() < Duration::from_secs(2), "no audio sent");
+            std::thread::sleep(Duration::from_millis(5));
+        }
+
+        let packets = packets.lock().unwrap();
+        assert_eq!(packets.len(), 1);
+        assert_eq!(
+            packets[0].buffer,
+            AudioBuffer {
+                data: vec![2., 0., 12., 10.],
+                num_channels: 2,
+            }
+        );
+    }
+
     #[test]
     fn receiver_can_fetch_audio_buffers() {}
 
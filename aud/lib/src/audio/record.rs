@@ -0,0 +1,271 @@
+use super::Wav;
+use std::{
+    fs::File,
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+/// Encoding of the samples written to a WAV file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum WavSampleFormat {
+    /// 16-bit integer PCM, clipping the samples to [-1, 1].
+    #[default]
+    Int16,
+    /// 32-bit float, written as they are.
+    Float32,
+}
+
+impl WavSampleFormat {
+    fn tag(self) -> u16 {
+        match self {
+            Self::Int16 => Wav::PCM,
+            Self::Float32 => Wav::FLOAT,
+        }
+    }
+
+    fn num_bits(self) -> u16 {
+        match self {
+            Self::Int16 => 16,
+            Self::Float32 => 32,
+        }
+    }
+
+    fn encode(self, sample: f32, out: &mut Vec<u8>) {
+        match self {
+            Self::Int16 => {
+                let sample = (sample.clamp(-1., 1.) * i16::MAX as f32).round() as i16;
+                out.extend(sample.to_le_bytes());
+            }
+            Self::Float32 => out.extend(sample.to_le_bytes()),
+        }
+    }
+}
+
+impl std::str::FromStr for WavSampleFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(format: &str) -> anyhow::Result<Self> {
+        match format {
+            "16" | "int16" => Ok(Self::Int16),
+            "32f" | "float32" => Ok(Self::Float32),
+            _ => anyhow::bail!("Invalid WAV sample format : {format}, use int16 or float32"),
+        }
+    }
+}
+
+/// Writes audio to a WAV file as it is recorded.
+///
+/// The audio is given one buffer per channel and written interleaved,
+/// one frame after the other with the channels in order within each
+/// frame, e.g. `[left0, right0, left1, right1, ...]` for stereo, as
+/// players expect it. The sizes of the file are only known once it is
+/// [`finalize`](Self::finalize)d, players may reject the file until then.
+///
+/// The sizes are 32-bit, so the recording stops once the audio reaches
+/// 4 GiB, the frames that do not fit are dropped and an error returned.
+pub struct WavRecorder {
+    file: BufWriter<File>,
+    format: WavSampleFormat,
+    num_channels: u16,
+    num_frames: u32,
+}
+
+impl WavRecorder {
+    const HEADER_LEN: u32 = 44;
+
+    pub fn create(
+        path: impl AsRef<Path>,
+        num_channels: u16,
+        sample_rate: u32,
+        format: WavSampleFormat,
+    ) -> anyhow::Result<Self> {
+        if num_channels == 0 {
+            anyhow::bail!("Cannot record a WAV file without channels");
+        }
+
+        let mut recorder = Self {
+            file: BufWriter::new(File::create(path)?),
+            format,
+            num_channels,
+            num_frames: 0,
+        };
+        recorder.write_header(sample_rate)?;
+        Ok(recorder)
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.num_channels as usize
+    }
+
+    pub fn num_frames(&self) -> u32 {
+        self.num_frames
+    }
+
+    /// Append the samples of each channel. Channels of different lengths
+    /// are padded with silence up to the longest, see [`crate::dsp::interleave`].
+    pub fn write_channels(&mut self, channels: &[impl AsRef<[f32]>]) -> anyhow::Result<()> {
+        if channels.len() != self.num_channels() {
+            anyhow::bail!(
+                "Cannot record {} channels to a WAV file of {}",
+                channels.len(),
+                self.num_channels
+            );
+        }
+
+        self.write_interleaved(&crate::dsp::interleave(channels))
+    }
+
+    /// Append frames of interleaved samples, as they are laid out in the file.
+    pub fn write_interleaved(&mut self, samples: &[f32]) -> anyhow::Result<()> {
+        if !samples.len().is_multiple_of(self.num_channels()) {
+            anyhow::bail!(
+                "Cannot record {} samples to a WAV file of {} channels",
+                samples.len(),
+                self.num_channels
+            );
+        }
+
+        let num_frames = samples.len() / self.num_channels();
+        let num_written = num_frames.min((self.max_frames() - self.num_frames) as usize);
+        let samples = &samples[..num_written * self.num_channels()];
+
+        let mut bytes = Vec::with_capacity(num_written * self.frame_size() as usize);
+        for &sample in samples {
+            self.format.encode(sample, &mut bytes);
+        }
+
+        self.file.write_all(&bytes)?;
+        self.num_frames += num_written as u32;
+        if num_written < num_frames {
+            anyhow::bail!("WAV file is full, it cannot hold more than 4 GiB of audio");
+        }
+        Ok(())
+    }
+
+    /// Write the sizes of the recorded audio, completing the file.
+    pub fn finalize(mut self) -> anyhow::Result<()> {
+        let data_len = self.num_frames.checked_mul(self.frame_size());
+        let Some((data_len, riff_len)) =
+            data_len.and_then(|len| Some((len, len.checked_add(Self::HEADER_LEN - 8)?)))
+        else {
+            anyhow::bail!("Too many frames for a WAV file : {}", self.num_frames);
+        };
+
+        self.file.seek(SeekFrom::Start(4))?;
+        self.file.write_all(&riff_len.to_le_bytes())?;
+        self.file.seek(SeekFrom::Start(40))?;
+        self.file.write_all(&data_len.to_le_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+
+    /// Bytes of a frame, one sample of every channel.
+    fn frame_size(&self) -> u32 {
+        self.num_channels as u32 * self.format.num_bits() as u32 / 8
+    }
+
+    /// Frames that fit in the 32-bit sizes of the file.
+    fn max_frames(&self) -> u32 {
+        (u32::MAX - (Self::HEADER_LEN - 8)) / self.frame_size()
+    }
+
+    fn write_header(&mut self, sample_rate: u32) -> anyhow::Result<()> {
+        let frame_size = self.frame_size();
+        let mut header = Vec::with_capacity(Self::HEADER_LEN as usize);
+        header.extend(b"RIFF");
+        header.extend((Self::HEADER_LEN - 8).to_le_bytes());
+        header.extend(b"WAVEfmt ");
+        header.extend(16u32.to_le_bytes());
+        header.extend(self.format.tag().to_le_bytes());
+        header.extend(self.num_channels.to_le_bytes());
+        let Some(byte_rate) = sample_rate.checked_mul(frame_size) else {
+            anyhow::bail!("Invalid sample rate for a WAV file : {sample_rate}");
+        };
+        header.extend(sample_rate.to_le_bytes());
+        header.extend(byte_rate.to_le_bytes());
+        header.extend((frame_size as u16).to_le_bytes());
+        header.extend(self.format.num_bits().to_le_bytes());
+        header.extend(b"data");
+        header.extend(0u32.to_le_bytes());
+        self.file.write_all(&header)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn record(format: WavSampleFormat, channels: &[Vec<f32>]) -> Vec<u8> {
+        let path =
+            std::env::temp_dir().join(format!("aud-record-{format:?}-{}.wav", std::process::id()));
+
+        let mut recorder = WavRecorder::create(&path, 2, 1_000, format).unwrap();
+        recorder.write_channels(channels).unwrap();
+        assert!(recorder.write_channels(&channels[..1]).is_err());
+        recorder.finalize().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        bytes
+    }
+
+    #[test]
+    fn channels_are_interleaved_and_converted_to_the_sample_format() {
+        // the right channel is short of a sample, recorded as silence
+        let channels = vec![vec![0.5, -1., 2.], vec![0.25, 1.]];
+
+        let bytes = record(WavSampleFormat::Int16, &channels);
+        assert_eq!(bytes.len(), 44 + 3 * 2 * 2);
+        assert_eq!(&bytes[4..8], &(36u32 + 12).to_le_bytes());
+        assert_eq!(&bytes[40..44], &12u32.to_le_bytes());
+        let wav = crate::audio::Wav::parse(&bytes).unwrap();
+        assert_eq!((wav.num_channels, wav.sample_rate), (2, 1_000));
+        let samples: Vec<f32> = [16384, 8192, -32767, 32767, 32767, 0]
+            .iter()
+            .map(|&sample| sample as f32 / 32768.)
+            .collect();
+        assert_eq!(wav.samples, samples);
+
+        let bytes = record(WavSampleFormat::Float32, &channels);
+        assert_eq!(&bytes[40..44], &24u32.to_le_bytes());
+        let wav = crate::audio::Wav::parse(&bytes).unwrap();
+        assert_eq!(wav.samples, [0.5, 0.25, -1., 1., 2., 0.]);
+    }
+
+    #[test]
+    fn interleaved_samples_are_written_in_whole_frames() {
+        let path =
+            std::env::temp_dir().join(format!("aud-record-frames-{}.wav", std::process::id()));
+        let mut recorder = WavRecorder::create(&path, 2, 1_000, WavSampleFormat::Float32).unwrap();
+        recorder.write_interleaved(&[0.5, 0.25, -1., 1.]).unwrap();
+        assert!(recorder.write_interleaved(&[0.5]).is_err());
+        assert_eq!(recorder.num_frames(), 2);
+        recorder.finalize().unwrap();
+
+        let wav = crate::audio::Wav::parse(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(wav.samples, [0.5, 0.25, -1., 1.]);
+    }
+
+    #[test]
+    fn the_recording_stops_once_the_sizes_would_overflow() {
+        let path = std::env::temp_dir().join(format!("aud-record-full-{}.wav", std::process::id()));
+        let mut recorder = WavRecorder::create(&path, 2, 1_000, WavSampleFormat::Int16).unwrap();
+        let max_frames = recorder.max_frames();
+        recorder.num_frames = max_frames - 1;
+
+        assert!(recorder.write_interleaved(&[0.; 4]).is_err());
+        assert_eq!(recorder.num_frames(), max_frames);
+        assert!(recorder.write_interleaved(&[0.; 2]).is_err());
+        assert_eq!(recorder.num_frames(), max_frames);
+        recorder.finalize().unwrap();
+
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let data_len = u32::from_le_bytes(bytes[40..44].try_into().unwrap());
+        let riff_len = u32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(data_len, max_frames * 4);
+        assert_eq!(riff_len as u64, data_len as u64 + 36);
+    }
+}
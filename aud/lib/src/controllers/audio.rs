@@ -1,8 +1,17 @@
 use crate::{
-    audio::{AudioBuffer, AudioChannelSelection, AudioDevice, AudioInterface, AudioProviding},
+    audio::{
+        would_feed_back, AudioBuffer, AudioChannelSelection, AudioDevice, AudioInterface,
+        AudioMonitor, AudioProviding, ChannelMismatch, RemoteUnreachable,
+    },
+    drops::DropWarning,
+    dsp::{self, LinearResampler},
     lua::{HostEvent, ScriptController},
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    cell::RefCell,
+    rc::Rc,
+    time::{Duration, Instant},
+};
 
 pub trait AudioProvider: AudioProviding + AudioInterface {}
 
@@ -12,18 +21,55 @@ pub struct AudioProviderController {
     receiver: Box<dyn AudioProvider>,
     script: Rc<RefCell<ScriptController>>,
     buffer: AudioBuffer,
+    /// Changes whenever the buffer may have changed.
+    buffer_version: u64,
     selected_device: Option<AudioDevice>,
+    /// Rank of the selected device among those sharing its name,
+    /// which still holds once the devices are listed again.
+    selected_rank: usize,
     selected_channels: Option<AudioChannelSelection>,
+    analysis_rate: Option<u32>,
+    /// Resampler in use along with the device sample rate it converts from.
+    resampler: Option<(u32, LinearResampler)>,
+    monitor: Option<AudioMonitor>,
+    is_running: bool,
+    /// NaN and infinite samples received and replaced with silence.
+    num_sanitized_samples: u64,
+    drop_warning: DropWarning,
+    /// Frames skipped so far to catch up with the device.
+    num_skipped_frames: u64,
+    skip_warning: DropWarning,
+    last_audible: Option<Instant>,
 }
 
 impl AudioProviderController {
+    /// Level below which audio is taken for silence, -60 dBFS.
+    const AUDIBLE_THRESHOLD: f32 = 0.001;
+    /// Most audio kept to be drawn, older frames are
+    /// skipped rather than shown ever later.
+    pub const MAX_LAG: Duration = Duration::from_secs(1);
+
     pub fn new(receiver: Box<dyn AudioProvider>, script: Rc<RefCell<ScriptController>>) -> Self {
         Self {
             buffer: AudioBuffer::default(),
+            buffer_version: 0,
             receiver,
             script,
             selected_device: None,
+            selected_rank: 0,
             selected_channels: None,
+            analysis_rate: None,
+            resampler: None,
+            monitor: None,
+            is_running: true,
+            num_sanitized_samples: 0,
+            drop_warning: DropWarning::new("audio buffers").with_hint(
+                "increase the capacity of the audio queue or lower the sample rate of the device",
+            ),
+            num_skipped_frames: 0,
+            skip_warning: DropWarning::new("audio frames")
+                .with_reason("to catch up with the device"),
+            last_audible: None,
         }
     }
 
@@ -31,14 +77,114 @@ impl AudioProviderController {
         self.receiver.list_audio_devices()
     }
 
+    /// Names that tell apart the devices, even those sharing a name.
+    pub fn device_names(&self) -> Vec<String> {
+        AudioDevice::unique_names(self.devices())
+    }
+
+    /// Unique name of the selected device.
+    pub fn selected_device_name(&self) -> Option<String> {
+        let selected = self.selected_device.as_ref()?;
+        let (index, _) = self
+            .devices()
+            .iter()
+            .enumerate()
+            .filter(|(_, dev)| dev.name == selected.name)
+            .nth(self.selected_rank)?;
+        self.device_names().into_iter().nth(index)
+    }
+
+    /// List the devices again, after devices were plugged in or out.
+    pub fn refresh_devices(&mut self) {
+        self.receiver.refresh_audio_devices();
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.is_running
+    }
+
+    /// Stop or resume capturing audio. Live audio received while stopped
+    /// is discarded, while recorded audio is paused until resumed.
+    pub fn set_running(&mut self, should_run: bool) {
+        self.is_running = should_run;
+        self.receiver.set_paused(!should_run);
+    }
+
     pub fn buffer(&self) -> &AudioBuffer {
         &self.buffer
     }
 
     pub fn buffer_mut(&mut self) -> &mut AudioBuffer {
+        self.buffer_version += 1;
         &mut self.buffer
     }
 
+    /// Version of the buffer, to tell whether it changed since it was last drawn.
+    pub fn buffer_version(&self) -> u64 {
+        self.buffer_version
+    }
+
+    /// Convert the incoming audio to this sample rate before buffering it,
+    /// so that it is analysed the same way whatever the device sample rate.
+    pub fn set_analysis_rate(&mut self, sample_rate: Option<u32>) {
+        self.analysis_rate = sample_rate;
+        self.resampler = None;
+    }
+
+    /// Play the incoming audio back through a monitor, or stop
+    /// monitoring. Fails if the monitor would capture its own output.
+    pub fn set_monitor(&mut self, monitor: Option<AudioMonitor>) -> anyhow::Result<()> {
+        if let (Some(device), Some(monitor)) = (&self.selected_device, &monitor) {
+            if would_feed_back(&device.name, monitor.output_name()) {
+                anyhow::bail!("monitoring {} on itself would feed back", device.name);
+            }
+        }
+
+        self.monitor = monitor;
+        Ok(())
+    }
+
+    /// Whether the source lost the device and is connecting to it again.
+    pub fn is_reconnecting(&self) -> bool {
+        self.receiver.is_reconnecting()
+    }
+
+    /// Sample rate of the buffered audio, the analysis rate if it is resampled.
+    pub fn sample_rate(&self) -> Option<u32> {
+        let connection = self.receiver.connected_audio_device()?;
+        Some(self.analysis_rate.unwrap_or(connection.sample_rate))
+    }
+
+    /// Channels of the connected stream, if they differ from the device's.
+    pub fn channel_mismatch(&self) -> Option<ChannelMismatch> {
+        self.receiver.channel_mismatch()
+    }
+
+    /// Number of NaN and infinite samples replaced with silence so far.
+    pub fn num_sanitized_samples(&self) -> u64 {
+        self.num_sanitized_samples
+    }
+
+    /// When audio above [`Self::AUDIBLE_THRESHOLD`] was last received.
+    pub fn last_audible_input(&self) -> Option<Instant> {
+        self.last_audible
+    }
+
+    /// Buffers dropped so far because the app fell behind the device.
+    pub fn num_dropped_buffers(&self) -> u64 {
+        self.receiver.num_dropped_buffers()
+    }
+
+    /// Frames skipped so far because more than [`Self::MAX_LAG`]
+    /// of audio was waiting to be drawn.
+    pub fn num_skipped_frames(&self) -> u64 {
+        self.num_skipped_frames
+    }
+
+    pub fn is_monitoring(&self) -> bool {
+        self.monitor.is_some()
+    }
+
     pub fn selected_device(&self) -> Option<&AudioDevice> {
         self.selected_device.as_ref()
     }
@@ -47,17 +193,147 @@ impl AudioProviderController {
         self.selected_channels.as_ref()
     }
 
+    /// Fetch the latest audio from the provider, transfer
+    /// it to the engine and append it to the local buffer.
+    ///
+    /// Empty buffers are dropped and NaN or infinite samples,
+    /// which some drivers emit, are replaced with silence.
+    ///
+    /// If the provider reports an error, the device is
+    /// reconnected and the error is returned to the caller.
+    /// A remote that gave up retrying is only reconnected
+    /// to when the device is selected again.
     pub fn update(&mut self) -> anyhow::Result<()> {
-        self.receiver.process_audio_events()?;
+        if let Err(e) = self.receiver.process_audio_events() {
+            if e.downcast_ref::<RemoteUnreachable>().is_some() {
+                log::error!("{e}");
+            } else {
+                log::error!("{e}, reconnecting");
+                self.reconnect()?;
+            }
+            return Err(e);
+        }
+
+        self.drop_warning
+            .check(self.receiver.num_dropped_buffers(), Instant::now());
+
         let mut audio = self.receiver.retrieve_audio_buffer();
-        if self.buffer.num_channels != audio.num_channels {
-            self.buffer = audio;
-        } else {
-            self.buffer.data.append(&mut audio.data);
+        if !self.is_running || audio.data.is_empty() {
+            return Ok(());
+        }
+
+        self.sanitize(&mut audio);
+        if audio
+            .data
+            .iter()
+            .any(|sample| sample.abs() > Self::AUDIBLE_THRESHOLD)
+        {
+            self.last_audible = Some(Instant::now());
+        }
+
+        self.monitor(&audio);
+        self.resample(&mut audio);
+        self.send_audio_to_script(&audio);
+
+        if !audio.data.is_empty() || audio.num_channels != self.buffer.num_channels {
+            self.buffer_version += 1;
         }
+        self.buffer.append(&mut audio);
         Ok(())
     }
 
+    /// Only keep the latest [`Self::MAX_LAG`] of the buffered audio,
+    /// once the new audio was recorded, so that drawing it does not
+    /// lag ever further behind the device.
+    pub fn skip_backlog(&mut self) {
+        let Some(sample_rate) = self.sample_rate() else {
+            return;
+        };
+
+        let num_channels = self.buffer.num_channels.max(1) as usize;
+        let max_frames = (Self::MAX_LAG.as_secs_f64() * sample_rate as f64) as usize;
+        let num_frames = self.buffer.data.len() / num_channels;
+        if num_frames <= max_frames {
+            return;
+        }
+
+        let num_skipped = num_frames - max_frames;
+        self.buffer.data.drain(..num_skipped * num_channels);
+        self.buffer_version += 1;
+        self.num_skipped_frames += num_skipped as u64;
+        self.skip_warning
+            .check(self.num_skipped_frames, Instant::now());
+    }
+
+    fn sanitize(&mut self, audio: &mut AudioBuffer) {
+        let num_sanitized = dsp::zero_non_finite(&mut audio.data);
+        if num_sanitized == 0 {
+            return;
+        }
+
+        self.num_sanitized_samples += num_sanitized as u64;
+        log::warn!(
+            "replaced {num_sanitized} non-finite samples with silence, {} so far",
+            self.num_sanitized_samples
+        );
+    }
+
+    fn monitor(&mut self, audio: &AudioBuffer) {
+        let (Some(monitor), Some(connection)) =
+            (&mut self.monitor, self.receiver.connected_audio_device())
+        else {
+            return;
+        };
+
+        if would_feed_back(&connection.device.name, monitor.output_name()) {
+            log::warn!(
+                "stopped monitoring {} to avoid feedback",
+                connection.device.name
+            );
+            self.monitor = None;
+            return;
+        }
+
+        monitor.push(audio, connection.sample_rate);
+    }
+
+    fn resample(&mut self, audio: &mut AudioBuffer) {
+        let Some(target_rate) = self.analysis_rate else {
+            return;
+        };
+
+        let source_rate = match self.receiver.connected_audio_device() {
+            Some(connection) if connection.sample_rate != target_rate => connection.sample_rate,
+            _ => return,
+        };
+
+        let num_channels = audio.num_channels.max(1) as usize;
+        let resampler = match self.resampler {
+            Some((rate, ref mut resampler))
+                if rate == source_rate && resampler.num_channels() == num_channels =>
+            {
+                resampler
+            }
+            _ => {
+                let resampler = LinearResampler::new(source_rate, target_rate, num_channels);
+                &mut self.resampler.insert((source_rate, resampler)).1
+            }
+        };
+
+        audio.data = resampler.process(&audio.data);
+    }
+
+    fn send_audio_to_script(&self, audio: &AudioBuffer) {
+        let script = self.script.borrow();
+        if audio.data.is_empty() || script.path().is_none() {
+            return;
+        }
+
+        if let Err(e) = script.try_send(HostEvent::Audio(audio.clone())) {
+            log::error!("Failed to send audio to Lua Runtime : {e}");
+        }
+    }
+
     pub fn reconnect(&mut self) -> anyhow::Result<()> {
         if self.selected_device().is_some() && self.selected_channels().is_some() {
             self.connect_to_input(
@@ -78,28 +354,69 @@ impl AudioProviderController {
         };
 
         self.buffer.data.clear();
+        self.buffer_version += 1;
+        self.resampler = None;
         self.receiver
             .connect_to_audio_device(audio_device, channel_selection.clone())?;
         self.selected_channels = Some(channel_selection);
 
-        if let Err(e) = self
-            .script
-            .borrow()
-            .try_send(HostEvent::Connect(audio_device.name.clone()))
-        {
+        let name = self
+            .selected_device_name()
+            .unwrap_or_else(|| audio_device.name.clone());
+
+        if let Err(e) = self.script.borrow().try_send(HostEvent::Connect(name)) {
             log::error!("Failed to send device connected event to runtime : {e}");
         }
 
         Ok(())
     }
 
+    /// Close the stream of the selected device and drop its buffered audio,
+    /// the script is notified through its `on_disconnect` hook.
+    pub fn disconnect(&mut self) {
+        self.receiver.disconnect_from_audio_device();
+        self.selected_channels = None;
+        self.buffer = AudioBuffer::default();
+        self.buffer_version += 1;
+        self.resampler = None;
+
+        if self.selected_device.take().is_none() {
+            return;
+        }
+
+        if let Err(e) = self.script.borrow().try_send(HostEvent::Disconnect) {
+            log::error!("Failed to send device disconnected event to runtime : {e}");
+        }
+    }
+
     pub fn connect_to_input(
         &mut self,
         audio_device: &AudioDevice,
         channel_selection: AudioChannelSelection,
     ) -> anyhow::Result<()> {
+        self.selected_rank = self
+            .devices()
+            .iter()
+            .take_while(|&dev| dev != audio_device)
+            .filter(|dev| dev.name == audio_device.name)
+            .count();
         self.selected_device = Some(audio_device.clone());
         self.buffer.num_channels = channel_selection.count() as u32;
         self.update_channel_selection(channel_selection)
     }
+
+    /// Switch to the device at `index` in the list of devices,
+    /// dropping the audio buffered from the previous device.
+    pub fn connect_to_input_by_index(
+        &mut self,
+        index: usize,
+        channel_selection: AudioChannelSelection,
+    ) -> anyhow::Result<()> {
+        let Some(audio_device) = self.devices().get(index) else {
+            anyhow::bail!("invalid device selection : {index}");
+        };
+
+        let audio_device = audio_device.clone();
+        self.connect_to_input(&audio_device, channel_selection)
+    }
 }
@@ -4,28 +4,116 @@ use super::{
 };
 use crate::{
     audio::{AudioChannelSelection, HostAudioInput},
-    lua::{traits::api::*, HostEvent, LuaEngineEvent, ScriptController, ScriptEvent},
-    midi::{HostedMidiReceiver, MidiReceiving},
+    devices::DeviceWatcher,
+    lua::{
+        script_log_target, traits::api::*, HostEvent, LuaEngineEvent, ScriptController,
+        ScriptEvent, ScriptLoadResult,
+    },
+    midi::{HostedMidiReceiver, MessageFilter, MidiData, MidiProducing, MidiReceiving, SysExDump},
 };
+use crossbeam::channel::Receiver;
 use std::{
     cell::RefCell,
+    collections::VecDeque,
     path::{Path, PathBuf},
     rc::Rc,
+    time::{Duration, Instant, SystemTime},
 };
 
+/// Default time after which alerts that are not errors are dismissed.
+pub const DEFAULT_ALERT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of distinct logs or alerts a script may emit per second.
+pub const DEFAULT_SCRIPT_MESSAGE_RATE: usize = 20;
+
+/// Number of script messages kept until the app takes them.
+pub(crate) const MAX_SCRIPT_MESSAGES: usize = 1_000;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum AppEvent {
     Continue,
     Stopping,
     ScriptCrash,
     ScriptLoaded,
+    ScriptError,
+    DevicesChanged,
+}
+
+/// Display settings of the scope, driven by scripts.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ScopeSettings {
+    pub mode: ScopeMode,
+    pub trigger: Option<ScopeTrigger>,
+    /// Duration of audio the scope should display.
+    pub window: Option<std::time::Duration>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertSeverity {
+    Info,
+    Error,
+}
+
+/// Log, alert or error of a script, kept for the app to show
+/// a history of them alongside the latest alert.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptMessage {
+    pub time: SystemTime,
+    pub kind: ScriptMessageKind,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScriptMessageKind {
+    Log,
+    Alert,
+    Error,
+}
+
+/// Message pushed back to the app, dismissed by the app or once it expires.
+#[derive(Debug, Clone, PartialEq)]
+struct Alert {
+    message: String,
+    severity: AlertSeverity,
+    raised_at: Instant,
+}
+
+impl Alert {
+    fn new(message: String, severity: AlertSeverity) -> Self {
+        Self {
+            message,
+            severity,
+            raised_at: Instant::now(),
+        }
+    }
+
+    /// Errors never expire, they stay until they are dismissed.
+    fn has_expired(&self, timeout: Duration, now: Instant) -> bool {
+        self.severity != AlertSeverity::Error && now.duration_since(self.raised_at) >= timeout
+    }
 }
 
 pub struct AudioMidiController {
     audio: AudioProviderController,
     midi: MidiReceiverController,
+    midi_out: Option<Box<dyn MidiProducing>>,
     script: Rc<RefCell<ScriptController>>,
-    alert_message: Option<String>,
+    alert: Option<Alert>,
+    alert_timeout: Duration,
+    scope: ScopeSettings,
+    device_watcher: Option<DeviceWatcher>,
+    logs: MessageThrottle,
+    alerts: MessageThrottle,
+    script_messages: VecDeque<ScriptMessage>,
+    ui_requests: Vec<UiApiEvent>,
+    params: ScriptParams,
+    /// Filter the app set, until the loaded script changed it.
+    app_filter: Option<MessageFilter>,
+    script_timeout: Duration,
+    /// Set once the engine crashed, until it is restarted by loading a script.
+    is_engine_down: bool,
+    started_at: Instant,
+    num_frames: u64,
 }
 
 impl AudioMidiController {
@@ -39,8 +127,22 @@ impl AudioMidiController {
         Self {
             audio: AudioProviderController::new(audio_receiver, script.clone()),
             midi: MidiReceiverController::new(midi_receiver, script.clone()),
+            midi_out: None,
             script,
-            alert_message: None,
+            alert: None,
+            alert_timeout: DEFAULT_ALERT_TIMEOUT,
+            scope: ScopeSettings::default(),
+            device_watcher: None,
+            logs: MessageThrottle::new(DEFAULT_SCRIPT_MESSAGE_RATE),
+            alerts: MessageThrottle::new(DEFAULT_SCRIPT_MESSAGE_RATE),
+            script_messages: VecDeque::new(),
+            ui_requests: vec![],
+            params: ScriptParams::default(),
+            app_filter: None,
+            script_timeout: crate::lua::DEFAULT_CALLBACK_TIMEOUT,
+            is_engine_down: false,
+            started_at: Instant::now(),
+            num_frames: 0,
         }
     }
 
@@ -72,12 +174,202 @@ impl AudioMidiController {
         &mut self.midi
     }
 
+    /// When audible audio or a MIDI message was last received.
+    pub fn last_input(&self) -> Option<Instant> {
+        self.audio
+            .last_audible_input()
+            .max(self.midi.last_message_received())
+    }
+
+    pub fn scope(&self) -> &ScopeSettings {
+        &self.scope
+    }
+
+    /// Refresh the devices whenever the watcher notices a change.
+    pub fn watch_devices(&mut self, watcher: DeviceWatcher) {
+        self.device_watcher = Some(watcher);
+    }
+
+    /// Refresh the devices if they changed since the last call, and
+    /// let the script discover the devices of the list that changed.
+    pub fn process_device_events(&mut self) -> anyhow::Result<AppEvent> {
+        let Some(_) = self
+            .device_watcher
+            .as_ref()
+            .and_then(DeviceWatcher::try_recv_change)
+        else {
+            return Ok(AppEvent::Continue);
+        };
+
+        let port_names = self.midi.port_names().to_vec();
+        self.midi.refresh_ports();
+        if self.midi.port_names() != port_names {
+            self.send_midi_port_discovery()?;
+        }
+
+        let devices = self.audio.devices().to_vec();
+        self.audio.refresh_devices();
+        if self.audio.devices() != devices {
+            self.send_audio_device_discovery();
+        }
+
+        Ok(AppEvent::DevicesChanged)
+    }
+
+    /// Directories of shared modules scripts can `require`, after
+    /// their own directory. Applies from the next script load.
+    pub fn set_module_paths(&mut self, module_paths: Vec<PathBuf>) {
+        self.script.borrow_mut().set_module_paths(module_paths);
+    }
+
+    /// Set how long a script callback may run before the engine interrupts it.
+    pub fn set_script_timeout(&mut self, timeout: std::time::Duration) {
+        self.script_timeout = timeout;
+        let event = HostEvent::SetCallbackTimeout(timeout);
+        if let Err(e) = self.script.borrow().try_send(event) {
+            log::error!("failed to send script timeout : {e}");
+        }
+    }
+
+    /// Advance the clock scripts read with `time.now()` and
+    /// `time.frames()`, once per frame of the host loop.
+    pub fn tick(&mut self) {
+        self.num_frames += 1;
+        let clock = Clock {
+            seconds: self.started_at.elapsed().as_secs_f64(),
+            frames: self.num_frames,
+        };
+
+        if let Err(e) = self.script.borrow().try_send(HostEvent::Tick(clock)) {
+            log::error!("failed to send clock : {e}");
+        }
+    }
+
+    /// Output through which SysEx is sent to the connected MIDI device.
+    pub fn set_midi_output(&mut self, output: Box<dyn MidiProducing>) {
+        self.midi_out = Some(output);
+    }
+
+    /// Send SysEx to the output of the connected MIDI
+    /// device, i.e. the output port sharing its name.
+    pub fn send_sysex(&mut self, dump: &SysExDump) -> anyhow::Result<()> {
+        let Some(ref mut output) = self.midi_out else {
+            anyhow::bail!("no MIDI output to send SysEx to");
+        };
+
+        let Some(device) = self.midi.selected_port_name() else {
+            anyhow::bail!("no MIDI device connected to send SysEx to");
+        };
+
+        dump.send(output.as_mut(), device)
+    }
+
+    /// Silence the connected MIDI device: release the notes it was seen
+    /// holding, then send All Notes Off and All Sound Off on every channel.
+    pub fn panic(&mut self) -> anyhow::Result<()> {
+        let Some(ref mut output) = self.midi_out else {
+            anyhow::bail!("no MIDI output to send the panic to");
+        };
+
+        let Some(device) = self.midi.selected_port_name() else {
+            anyhow::bail!("no MIDI device connected to send the panic to");
+        };
+
+        let messages: Vec<_> = self
+            .midi
+            .held_notes()
+            .panic_messages()
+            .into_iter()
+            .map(|bytes| MidiData {
+                timestamp: 0,
+                bytes,
+            })
+            .collect();
+
+        output.send_midi_messages(device, &messages)?;
+        self.midi.held_notes_mut().clear();
+        Ok(())
+    }
+
+    /// Limit the number of distinct logs and alerts a script may emit per second.
+    /// Repeated messages are coalesced and never count towards the limit.
+    pub fn set_script_message_rate(&mut self, max_per_second: usize) {
+        self.logs = MessageThrottle::new(max_per_second);
+        self.alerts = MessageThrottle::new(max_per_second);
+    }
+
+    /// Set how long alerts that are not errors stay before they are dismissed.
+    pub fn set_alert_timeout(&mut self, timeout: Duration) {
+        self.alert_timeout = timeout;
+    }
+
     pub fn take_alert(&mut self) -> Option<String> {
-        self.alert_message.take()
+        self.alert.take().map(|alert| alert.message)
     }
 
+    /// Script messages received since the last call, oldest first.
+    pub fn take_script_messages(&mut self) -> Vec<ScriptMessage> {
+        self.script_messages.drain(..).collect()
+    }
+
+    /// Requests of the script to reset the display since the last call,
+    /// for the app to apply before showing the messages taken after them.
+    pub fn take_ui_requests(&mut self) -> Vec<UiApiEvent> {
+        std::mem::take(&mut self.ui_requests)
+    }
+
+    pub fn alert_severity(&self) -> Option<AlertSeverity> {
+        self.alert.as_ref().map(|alert| alert.severity)
+    }
+
+    /// Dismiss the alert if it has been up for longer than the alert timeout.
+    pub fn expire_alert(&mut self, now: Instant) {
+        if self
+            .alert
+            .as_ref()
+            .is_some_and(|alert| alert.has_expired(self.alert_timeout, now))
+        {
+            self.alert = None;
+        }
+    }
+
+    /// Parameters the loaded script exposes, in the order they were defined.
+    pub fn params(&self) -> &[ScriptParam] {
+        self.params.as_slice()
+    }
+
+    /// Move a parameter by a number of steps and
+    /// deliver its new value to the script.
+    pub fn adjust_param(&mut self, index: usize, steps: i32) {
+        let Some(param) = self.params.as_slice().get(index) else {
+            return;
+        };
+
+        let name = param.name.clone();
+        let value = param.value + param.step() * steps as f64;
+        let Some(value) = self.params.set(&name, value) else {
+            return;
+        };
+
+        if let Err(e) = self
+            .script
+            .borrow()
+            .try_send(HostEvent::SetParam { name, value })
+        {
+            log::error!("failed to send param event : {e}");
+        }
+    }
+
+    /// Name of the loaded script, followed by the names of the scripts chained after it.
     pub fn selected_script(&self) -> Option<String> {
-        self.script.borrow().name().map(str::to_owned)
+        let script = self.script.borrow();
+        let chained = script
+            .chained_paths()
+            .iter()
+            .filter_map(|path| path.file_name()?.to_str());
+        Some(chained.fold(script.name()?.to_owned(), |names, name| {
+            format!("{names} → {name}")
+        }))
     }
 
     pub fn loaded_script_path(&self) -> Option<PathBuf> {
@@ -86,8 +378,66 @@ impl AudioMidiController {
 
     /// Send a script to be loaded by the scripting engine. This function does not block.
     pub fn load_script(&mut self, script_path: impl AsRef<Path>) -> anyhow::Result<AppEvent> {
+        self.restart_engine_if_down();
         self.script.borrow_mut().load(script_path)?;
+        self.params.clear();
+        self.restore_app_filter();
+        self.rediscover_devices()?;
+        Ok(AppEvent::Continue)
+    }
+
+    /// Send scripts to be loaded one after the other by the scripting engine,
+    /// each handling the MIDI messages forwarded by the one before.
+    /// This function does not block.
+    pub fn load_script_chain(&mut self, scripts: &[impl AsRef<Path>]) -> anyhow::Result<AppEvent> {
+        self.restart_engine_if_down();
+        self.script.borrow_mut().load_chain(scripts)?;
+        self.params.clear();
+        self.restore_app_filter();
+        self.rediscover_devices()?;
+        Ok(AppEvent::Continue)
+    }
+
+    /// Send a script to be loaded by the scripting engine without blocking.
+    /// The returned receiver resolves with the outcome of the load.
+    pub fn load_script_with_result(
+        &mut self,
+        script_path: impl AsRef<Path>,
+    ) -> anyhow::Result<Receiver<ScriptLoadResult>> {
+        self.restart_engine_if_down();
+        let result = self.script.borrow_mut().load_with_result(script_path)?;
+        self.params.clear();
+        self.restore_app_filter();
+        self.rediscover_devices()?;
+        Ok(result)
+    }
+
+    /// Start a fresh scripting engine in place of the current one, which
+    /// is terminated, with the same settings and no script loaded.
+    pub fn restart_engine(&mut self) {
+        self.script.borrow_mut().restart_engine();
+        self.set_script_timeout(self.script_timeout);
+        self.is_engine_down = false;
+
+        if self.alert_severity() == Some(AlertSeverity::Error) {
+            self.alert = None;
+        }
+    }
+
+    fn restart_engine_if_down(&mut self) {
+        if self.is_engine_down {
+            log::info!("restarting the scripting engine");
+            self.restart_engine();
+        }
+    }
+
+    /// Whether the engine crashed and a script must be loaded to restart it.
+    pub fn is_engine_down(&self) -> bool {
+        self.is_engine_down
+    }
 
+    /// Let a newly loaded script know about the devices already in use.
+    fn rediscover_devices(&mut self) -> anyhow::Result<()> {
         if self.midi.selected_port_name().is_some() {
             self.send_midi_port_discovery()?;
             self.midi.reconnect()?;
@@ -98,7 +448,7 @@ impl AudioMidiController {
             self.audio.reconnect()?;
         }
 
-        Ok(AppEvent::Continue)
+        Ok(())
     }
 
     /// Load a script and block until the script has been loaded by the engine.
@@ -107,14 +457,30 @@ impl AudioMidiController {
         script_path: impl AsRef<Path>,
         timeout: std::time::Duration,
     ) -> anyhow::Result<()> {
-        self.load_script(script_path)?;
+        self.load_script_chain_sync(&[script_path], timeout)
+    }
+
+    /// Load a chain of scripts and block until the engine has loaded all of them.
+    pub fn load_script_chain_sync(
+        &mut self,
+        scripts: &[impl AsRef<Path>],
+        timeout: std::time::Duration,
+    ) -> anyhow::Result<()> {
+        self.load_script_chain(scripts)?;
         let start = std::time::Instant::now();
-        while self.process_script_events()? != AppEvent::ScriptLoaded {
-            if start.elapsed() > timeout {
-                anyhow::bail!("Failed to load script in time");
+        loop {
+            match self.process_script_events()? {
+                AppEvent::ScriptLoaded => return Ok(()),
+                AppEvent::ScriptError => anyhow::bail!(
+                    "Failed to load script : {}",
+                    self.alert
+                        .as_ref()
+                        .map_or("", |alert| alert.message.as_str())
+                ),
+                _ if start.elapsed() > timeout => anyhow::bail!("Failed to load script in time"),
+                _ => {}
             }
         }
-        Ok(())
     }
 
     /// Block while waiting for the script to push an alert back to the app.
@@ -125,7 +491,7 @@ impl AudioMidiController {
         let start = std::time::Instant::now();
         while start.elapsed() < timeout {
             let _ = self.process_script_events()?;
-            if self.alert_message.is_some() {
+            if self.alert.is_some() {
                 return Ok(self.take_alert());
             }
         }
@@ -136,7 +502,15 @@ impl AudioMidiController {
     /// This processes all the available events unless the engine:
     /// - requests to stop the application
     /// - has just loaded a script
+    /// - has failed to load a script
     pub fn process_script_events(&mut self) -> anyhow::Result<AppEvent> {
+        self.expire_alert(Instant::now());
+        let app_event = self.process_available_script_events();
+        self.flush_repeated_messages();
+        app_event
+    }
+
+    fn process_available_script_events(&mut self) -> anyhow::Result<AppEvent> {
         loop {
             let event = {
                 match self.script.borrow().try_recv() {
@@ -144,18 +518,51 @@ impl AudioMidiController {
                     Err(_) => break,
                 }
             };
-            self.process_script_event(event)?;
+
+            let app_event = self.process_script_event(event)?;
+            if app_event != AppEvent::Continue {
+                return Ok(app_event);
+            }
         }
         Ok(AppEvent::Continue)
     }
 
     fn process_script_event(&mut self, event: ScriptEvent) -> anyhow::Result<AppEvent> {
         match event {
-            ScriptEvent::Loaded => return Ok(AppEvent::ScriptLoaded),
+            ScriptEvent::Loaded { modules } => {
+                self.script.borrow_mut().watch_modules(modules);
+                return Ok(AppEvent::ScriptLoaded);
+            }
+            ScriptEvent::Error(message) => {
+                self.record_script_message(ScriptMessageKind::Error, &message);
+                self.alert = Some(Alert::new(message, AlertSeverity::Error));
+                return Ok(AppEvent::ScriptError);
+            }
             ScriptEvent::Log(request) => self.handle_lua_log_request(request),
             ScriptEvent::Midi(message) => self.midi.push_message(message),
             ScriptEvent::Connect(request) => self.handle_lua_connect_request(request)?,
             ScriptEvent::Control(request) => return Ok(self.handle_lua_control_request(request)),
+            ScriptEvent::Scope(request) => self.handle_lua_scope_request(request),
+            ScriptEvent::Alias(AliasApiEvent { device, alias }) => {
+                self.midi.set_alias(device, alias)
+            }
+            ScriptEvent::Selection(request) => self.handle_lua_selection_request(request),
+            ScriptEvent::Panic(PanicApiEvent) => {
+                if let Err(e) = self.panic() {
+                    log::error!("failed to send midi panic : {e}");
+                }
+            }
+            ScriptEvent::Ui(request) => self.handle_lua_ui_request(request),
+            ScriptEvent::Filter(request) => self.handle_lua_filter_request(request),
+            ScriptEvent::SysEx(SysExApiEvent { message }) => {
+                if let Err(e) = self.send_sysex(&message.into()) {
+                    log::error!("failed to send sysex : {e}");
+                }
+            }
+            ScriptEvent::Param(ParamApiEvent::Define(param)) => self.params.define(param),
+            ScriptEvent::Param(ParamApiEvent::Set { name, value }) => {
+                self.params.set(&name, value);
+            }
         }
         Ok(AppEvent::Continue)
     }
@@ -166,23 +573,46 @@ impl AudioMidiController {
             return Ok(AppEvent::Continue);
         }
 
-        let script_path = self.script.borrow().path().cloned();
-        if let Some(path) = script_path {
-            self.load_script(path)
-        } else {
-            Ok(AppEvent::Continue)
+        let scripts: Vec<_> = {
+            let script = self.script.borrow();
+            let chained_paths = script.chained_paths().iter();
+            script
+                .path()
+                .into_iter()
+                .chain(chained_paths)
+                .cloned()
+                .collect()
+        };
+        if scripts.is_empty() {
+            return Ok(AppEvent::Continue);
         }
+
+        self.load_script_chain(&scripts)?;
+        Ok(AppEvent::ScriptLoaded)
     }
 
     /// Process all the available engine events without blocking.
+    ///
+    /// A crashed engine is reported with an alert that stays
+    /// until a script is loaded again, which restarts the engine.
     pub fn process_engine_events(&mut self) -> anyhow::Result<AppEvent> {
+        let mut has_crashed = !self.is_engine_down && !self.script.borrow().is_engine_running();
+
         while let Ok(event) = self.script.borrow().try_recv_engine_events() {
             match event {
-                LuaEngineEvent::Panicked => return Ok(AppEvent::ScriptCrash),
+                LuaEngineEvent::Panicked => has_crashed = true,
                 LuaEngineEvent::Terminated => log::info!("Lua Engine terminated"),
             }
         }
-        Ok(AppEvent::Continue)
+
+        if !has_crashed {
+            return Ok(AppEvent::Continue);
+        }
+
+        self.is_engine_down = true;
+        let message = "The scripting engine crashed, load a script to restart it".to_owned();
+        self.alert = Some(Alert::new(message, AlertSeverity::Error));
+        Ok(AppEvent::ScriptCrash)
     }
 
     fn handle_lua_connect_request(&mut self, request: ConnectionApiEvent) -> anyhow::Result<()> {
@@ -196,41 +626,176 @@ impl AudioMidiController {
             return Ok(());
         }
 
-        let device = self
+        let audio_device = self
             .audio
-            .devices()
+            .device_names()
             .iter()
-            .find(|dev| dev.name == *device)
+            .position(|name| name == device)
+            .and_then(|index| self.audio.devices().get(index))
             .cloned();
 
-        if let Some(device) = device {
+        if let Some(audio_device) = audio_device {
             let channels = self
                 .audio
                 .selected_channels()
                 .unwrap_or(&AudioChannelSelection::Mono(0));
 
-            self.audio.connect_to_input(&device, channels.clone())?;
+            self.audio
+                .connect_to_input(&audio_device, channels.clone())?;
+            return Ok(());
+        }
+
+        // names differ between hosts, so scripts may give a pattern instead
+        if let Err(e) = self.midi.connect_to_input_matching(device) {
+            log::warn!("script failed to connect : {e}");
         }
 
         Ok(())
     }
 
     fn handle_lua_control_request(&mut self, request: ControlFlowApiEvent) -> AppEvent {
+        let (app_event, action, reason) = match request {
+            ControlFlowApiEvent::Pause(reason) => {
+                self.midi.set_running(false);
+                (AppEvent::Continue, "paused", reason)
+            }
+            ControlFlowApiEvent::Resume(reason) => {
+                self.midi.set_running(true);
+                (AppEvent::Continue, "resumed", reason)
+            }
+            ControlFlowApiEvent::Stop(reason) => (AppEvent::Stopping, "stopped", reason),
+        };
+
+        if let Some(reason) = reason {
+            let message = format!("script {action} : {reason}");
+            log::info!("{message}");
+            self.alert = Some(Alert::new(message, AlertSeverity::Info));
+        }
+
+        app_event
+    }
+
+    fn handle_lua_selection_request(&self, request: SelectionApiEvent) {
+        let selected = match request.kind {
+            DeviceKind::Midi => self.midi.selected_port_name().map(str::to_owned),
+            DeviceKind::Audio => self.audio.selected_device_name(),
+        };
+
+        if let Err(e) = request.reply.try_send(selected) {
+            log::error!("failed to reply to selection request : {e}");
+        }
+    }
+
+    /// Narrow down the messages kept for the monitor, leaving
+    /// the rest of the filter as the app configured it. Scripts
+    /// still receive every message, filtered or not.
+    fn handle_lua_filter_request(&mut self, request: FilterApiEvent) {
+        let mut filter = self.midi.filter().clone();
+        let app_filter = self.app_filter.get_or_insert_with(|| filter.clone());
+
+        match request {
+            FilterApiEvent::Channels(channels) => filter.channels = channels,
+            FilterApiEvent::Types(types) => filter.types = types,
+            FilterApiEvent::Notes(range) => {
+                // never keeps notes outside of the range of the app
+                filter.note_range = match (app_filter.note_range, range) {
+                    (Some((app_low, app_high)), Some((low, high))) => {
+                        Some((app_low.max(low), app_high.min(high)))
+                    }
+                    (app_range, range) => range.or(app_range),
+                }
+            }
+        }
+        self.midi.set_filter(filter);
+    }
+
+    /// Filters do not outlive the script that set them.
+    fn restore_app_filter(&mut self) {
+        if let Some(filter) = self.app_filter.take() {
+            self.midi.set_filter(filter);
+        }
+    }
+
+    /// Clear what is held here, the app clears what it displays.
+    fn handle_lua_ui_request(&mut self, request: UiApiEvent) {
+        match request {
+            UiApiEvent::Clear => {
+                self.midi.clear_messages();
+                if !self.audio.buffer().data.is_empty() {
+                    self.audio.buffer_mut().data.clear();
+                }
+            }
+            UiApiEvent::Reset => self.alert = None,
+        }
+
+        if self.ui_requests.last() != Some(&request) {
+            self.ui_requests.push(request);
+        }
+    }
+
+    fn handle_lua_scope_request(&mut self, request: ScopeApiEvent) {
         match request {
-            ControlFlowApiEvent::Pause => self.midi.set_running(false),
-            ControlFlowApiEvent::Resume => self.midi.set_running(true),
-            ControlFlowApiEvent::Stop => return AppEvent::Stopping,
+            ScopeApiEvent::SetMode(mode) => self.scope.mode = mode,
+            ScopeApiEvent::SetTrigger(trigger) => self.scope.trigger = Some(trigger),
+            ScopeApiEvent::Zoom(window) => self.scope.window = Some(window),
         }
-        AppEvent::Continue
     }
 
     fn handle_lua_log_request(&mut self, request: LogApiEvent) {
+        let now = Instant::now();
         match request {
-            LogApiEvent::Log(msg) => log::info!("{msg}"),
-            LogApiEvent::Alert(msg) => self.alert_message = Some(msg),
+            LogApiEvent::Log(msg) => {
+                for msg in self.logs.push(msg, now) {
+                    self.log_script_message(msg);
+                }
+            }
+            LogApiEvent::Alert(msg) => {
+                for msg in self.alerts.push(msg, now) {
+                    self.alert_script_message(msg);
+                }
+            }
         }
     }
 
+    /// Report the repeats of the last log and alert coalesced so far.
+    fn flush_repeated_messages(&mut self) {
+        if let Some(msg) = self.logs.flush() {
+            self.log_script_message(msg);
+        }
+        if let Some(msg) = self.alerts.flush() {
+            self.alert_script_message(msg);
+        }
+    }
+
+    /// Log target of the messages of the loaded script, named after the
+    /// script alone, the chain of [`Self::selected_script`] is only shown.
+    fn script_log_target(&self) -> String {
+        script_log_target(self.script.borrow().name().unwrap_or_default())
+    }
+
+    fn log_script_message(&mut self, msg: String) {
+        log::info!(target: &self.script_log_target(), "{msg}");
+        self.record_script_message(ScriptMessageKind::Log, &msg);
+    }
+
+    fn alert_script_message(&mut self, msg: String) {
+        log::info!(target: &self.script_log_target(), "alert : {msg}");
+        self.record_script_message(ScriptMessageKind::Alert, &msg);
+        self.alert = Some(Alert::new(msg, AlertSeverity::Info));
+    }
+
+    fn record_script_message(&mut self, kind: ScriptMessageKind, text: &str) {
+        if self.script_messages.len() == MAX_SCRIPT_MESSAGES {
+            self.script_messages.pop_front();
+        }
+
+        self.script_messages.push_back(ScriptMessage {
+            time: SystemTime::now(),
+            kind,
+            text: text.to_owned(),
+        });
+    }
+
     fn send_midi_port_discovery(&mut self) -> anyhow::Result<()> {
         let ports = self.midi.port_names().to_vec();
         if let Err(e) = self.script.borrow().try_send(HostEvent::Discover(ports)) {
@@ -240,15 +805,82 @@ impl AudioMidiController {
     }
 
     fn send_audio_device_discovery(&mut self) {
-        let devices = self
-            .audio
-            .devices()
-            .iter()
-            .map(|dev| dev.name.clone())
-            .collect();
-
+        let devices = self.audio.device_names();
         if let Err(e) = self.script.borrow().try_send(HostEvent::Discover(devices)) {
             log::error!("failed to send discovery event : {e}");
         }
     }
 }
+
+/// Protects the app from scripts flooding it with messages.
+/// Consecutive identical messages are coalesced into a single
+/// one suffixed with their count, e.g. "boom x12", and distinct
+/// messages above the rate limit are dropped.
+struct MessageThrottle {
+    max_per_second: usize,
+    window_start: Option<Instant>,
+    num_in_window: usize,
+    num_dropped: usize,
+    last: Option<String>,
+    num_repeats: usize,
+    num_reported: usize,
+}
+
+impl MessageThrottle {
+    fn new(max_per_second: usize) -> Self {
+        Self {
+            max_per_second,
+            window_start: None,
+            num_in_window: 0,
+            num_dropped: 0,
+            last: None,
+            num_repeats: 0,
+            num_reported: 0,
+        }
+    }
+
+    /// Messages to emit now, in order.
+    fn push(&mut self, msg: String, now: Instant) -> Vec<String> {
+        if self.last.as_ref() == Some(&msg) {
+            self.num_repeats += 1;
+            return vec![];
+        }
+
+        let mut emitted: Vec<String> = self.flush().into_iter().collect();
+        self.last = Some(msg.clone());
+        self.num_repeats = 1;
+        self.num_reported = 1;
+
+        let is_in_window = matches!(
+            self.window_start,
+            Some(start) if now.duration_since(start) < Duration::from_secs(1)
+        );
+        if !is_in_window {
+            if self.num_dropped > 0 {
+                log::warn!("dropped {} script messages", self.num_dropped);
+            }
+            self.window_start = Some(now);
+            self.num_in_window = 0;
+            self.num_dropped = 0;
+        }
+
+        if self.num_in_window < self.max_per_second {
+            self.num_in_window += 1;
+            emitted.push(msg);
+        } else {
+            self.num_dropped += 1;
+        }
+        emitted
+    }
+
+    /// The last message with its count, if it was repeated since last reported.
+    fn flush(&mut self) -> Option<String> {
+        if self.num_repeats == self.num_reported {
+            return None;
+        }
+
+        self.num_reported = self.num_repeats;
+        let last = self.last.as_deref()?;
+        Some(format!("{last} x{}", self.num_repeats))
+    }
+}
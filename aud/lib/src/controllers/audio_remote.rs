@@ -3,7 +3,7 @@ use crossbeam::channel;
 use crate::{
     audio::{
         AudioBuffer, AudioChannelSelection, AudioConsuming, AudioDevice, AudioDeviceConnection,
-        AudioInterface, AudioProviding, RemoteAudioReceiver,
+        AudioInterface, AudioProviding, ChannelMismatch, RemoteAudioReceiver, RetryPolicy,
     },
     comms::{SocketInterface, Sockets},
 };
@@ -42,6 +42,10 @@ impl RemoteAudioProvider {
             receiver,
         })
     }
+
+    pub fn set_retry_policy(&mut self, retry_policy: RetryPolicy) {
+        self.interface.set_retry_policy(retry_policy);
+    }
 }
 
 impl AudioProviding for RemoteAudioProvider {
@@ -68,10 +72,22 @@ impl AudioInterface for RemoteAudioProvider {
             .connect_to_audio_device(audio_device, channel_selection)
     }
 
+    fn disconnect_from_audio_device(&mut self) {
+        self.interface.disconnect_from_audio_device()
+    }
+
+    fn is_reconnecting(&self) -> bool {
+        self.interface.is_reconnecting()
+    }
+
     fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
         self.interface.connected_audio_device()
     }
 
+    fn channel_mismatch(&self) -> Option<ChannelMismatch> {
+        self.interface.channel_mismatch()
+    }
+
     fn process_audio_events(&mut self) -> anyhow::Result<()> {
         self.interface.process_audio_events()
     }
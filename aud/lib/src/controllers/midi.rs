@@ -1,8 +1,25 @@
 use crate::{
+    drops::DropWarning,
     lua::{HostEvent, ScriptController},
-    midi::{MidiData, MidiReceiving},
+    midi::{
+        HeldNotes, MessageFilter, MidiClock, MidiData, MidiFields, MidiReceiving, NoteOffForm,
+        PortPattern,
+    },
 };
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, collections::HashMap, rc::Rc, time::Instant};
+
+/// Log target of the received messages, when they are logged.
+pub const MIDI_LOG_TARGET: &str = "midi";
+
+/// Line a received message is logged with.
+pub fn log_line(message: &MidiData) -> String {
+    format!(
+        "{} us : {} : {:02X?}",
+        message.timestamp,
+        MidiFields::decode(&message.bytes),
+        message.bytes
+    )
+}
 
 pub struct MidiReceiverController {
     receiver: Box<dyn MidiReceiving>,
@@ -10,6 +27,13 @@ pub struct MidiReceiverController {
     port_names: Vec<String>,
     selected_port_name: Option<String>,
     messages: Vec<MidiData>,
+    filter: MessageFilter,
+    aliases: HashMap<String, String>,
+    clock: MidiClock,
+    held_notes: HeldNotes,
+    log_messages: bool,
+    drop_warning: DropWarning,
+    last_message: Option<Instant>,
 }
 
 impl MidiReceiverController {
@@ -20,6 +44,14 @@ impl MidiReceiverController {
             script,
             selected_port_name: None,
             messages: vec![],
+            filter: MessageFilter::default(),
+            aliases: HashMap::new(),
+            clock: MidiClock::default(),
+            held_notes: HeldNotes::default(),
+            log_messages: false,
+            drop_warning: DropWarning::new("midi messages")
+                .with_hint("increase the capacity of the MIDI queue"),
+            last_message: None,
         }
     }
 
@@ -35,14 +67,84 @@ impl MidiReceiverController {
         self.port_names.as_slice()
     }
 
+    /// List the ports again, after ports were plugged in or out.
+    pub fn refresh_ports(&mut self) {
+        match self.receiver.list_midi_devices() {
+            Ok(port_names) => self.port_names = port_names,
+            Err(e) => log::error!("failed to list midi ports : {e}"),
+        }
+    }
+
     pub fn selected_port_name(&self) -> Option<&str> {
         self.selected_port_name.as_deref()
     }
 
+    /// Display a port under another name. Connecting
+    /// to the port still uses its real name.
+    pub fn set_alias(&mut self, port_name: String, display_name: String) {
+        self.aliases.insert(port_name, display_name);
+    }
+
+    /// Name to display for a port, its alias if it has one.
+    pub fn display_name<'a>(&'a self, port_name: &'a str) -> &'a str {
+        self.aliases
+            .get(port_name)
+            .map_or(port_name, String::as_str)
+    }
+
+    /// Names of all the ports as they should be displayed.
+    pub fn display_port_names(&self) -> Vec<String> {
+        self.port_names
+            .iter()
+            .map(|name| self.display_name(name).to_owned())
+            .collect()
+    }
+
+    /// Transport and tempo of the clock received on the connected port.
+    pub fn clock(&self) -> &MidiClock {
+        &self.clock
+    }
+
+    /// Notes received on the connected port that were not released yet.
+    pub fn held_notes(&self) -> &HeldNotes {
+        &self.held_notes
+    }
+
+    pub fn held_notes_mut(&mut self) -> &mut HeldNotes {
+        &mut self.held_notes
+    }
+
+    pub fn filter(&self) -> &MessageFilter {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: MessageFilter) {
+        self.filter = filter;
+    }
+
+    /// Write every message that passes the filter to the log as it is received.
+    pub fn set_logging_messages(&mut self, should_log: bool) {
+        self.log_messages = should_log;
+    }
+
+    /// Store a message for the monitor if it passes the filter.
     pub fn push_message(&mut self, message: MidiData) {
+        if !self.filter.accepts(&message.bytes) {
+            return;
+        }
+
+        if self.log_messages {
+            log::info!(target: MIDI_LOG_TARGET, "{}", log_line(&message));
+        }
+
         self.messages.push(message)
     }
 
+    /// Messages stored for the monitor, not taken yet.
+    pub fn messages(&self) -> &[MidiData] {
+        &self.messages
+    }
+
     pub fn clear_messages(&mut self) {
         self.messages.clear();
     }
@@ -51,9 +153,41 @@ impl MidiReceiverController {
         std::mem::take(&mut self.messages)
     }
 
+    /// Form of the Note Offs handed to the script, the messages it lets
+    /// through and the monitored ones keep the bytes as received.
+    pub fn set_forwarded_note_off(&mut self, form: NoteOffForm) {
+        let event = HostEvent::SetForwardedNoteOff(form);
+        if let Err(e) = self.script.borrow().try_send(event) {
+            log::error!("failed to send the forwarded Note Off form : {e}");
+        }
+    }
+
+    /// When a message other than a system realtime one was last
+    /// received, whether it passed the filter or not.
+    pub fn last_message_received(&self) -> Option<Instant> {
+        self.last_message
+    }
+
+    /// Messages dropped so far because the app fell behind the device.
+    pub fn num_dropped_messages(&self) -> u64 {
+        self.receiver.num_dropped_messages()
+    }
+
     /// Transfer all received MIDI messages to the engine.
     pub fn update(&mut self) {
-        for msg in self.receiver.produce_midi_messages() {
+        self.drop_warning
+            .check(self.receiver.num_dropped_messages(), Instant::now());
+
+        let messages = self.receiver.produce_midi_messages();
+        // a running clock or active sensing does not mean anyone is playing
+        let is_realtime = |msg: &MidiData| matches!(msg.bytes.first(), Some(0xF8..=0xFF));
+        if messages.iter().any(|msg| !is_realtime(msg)) {
+            self.last_message = Some(Instant::now());
+        }
+
+        for msg in messages {
+            self.clock.observe(&msg);
+            self.held_notes.observe(&msg);
             if let Err(e) = self.script.borrow().try_send(HostEvent::Midi(msg)) {
                 log::error!("Failed to send midi to Lua Runtime : {e}");
             }
@@ -77,6 +211,59 @@ impl MidiReceiverController {
         self.connect_to_input_by_index(index)
     }
 
+    /// Connect to a port given either its name or its index.
+    pub fn connect_to_input_by_name_or_index(&mut self, port: &str) -> anyhow::Result<()> {
+        let index = match self.port_names.iter().position(|name| name == port) {
+            Some(index) => index,
+            None => match port.parse::<usize>() {
+                Ok(index) if index < self.port_names.len() => index,
+                _ => anyhow::bail!(
+                    "port not found : {port}, available ports are : {}",
+                    self.available_ports()
+                ),
+            },
+        };
+
+        self.connect_to_input_by_index(index)
+    }
+
+    /// Connect to the first port matching a glob or a regular expression,
+    /// see [`PortPattern`]. The other matching ports are logged.
+    pub fn connect_to_input_matching(&mut self, pattern: &str) -> anyhow::Result<()> {
+        let pattern: PortPattern = pattern.parse()?;
+        let matches = pattern.find(&self.port_names);
+
+        let Some(&index) = matches.first() else {
+            anyhow::bail!(
+                "no port matches : {pattern}, available ports are : {}",
+                self.available_ports()
+            );
+        };
+
+        if matches.len() > 1 {
+            let names: Vec<&str> = matches
+                .iter()
+                .map(|&index| self.port_names[index].as_str())
+                .collect();
+            log::warn!(
+                "several ports match {pattern} : {}, connecting to {}",
+                names.join(", "),
+                names[0]
+            );
+        }
+
+        self.connect_to_input_by_index(index)
+    }
+
+    fn available_ports(&self) -> String {
+        self.port_names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| format!("[{i}] {name}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
     pub fn connect_to_input_by_index(&mut self, index: usize) -> anyhow::Result<()> {
         let Some(port_name) = self.port_names.get(index) else {
             anyhow::bail!("invalid port selection : {index}");
@@ -85,9 +272,29 @@ impl MidiReceiverController {
         let port_name = port_name.to_owned();
         self.connect_to_input_unchecked(port_name)?;
         self.clear_messages();
+        self.clock = MidiClock::default();
+        self.held_notes.clear();
         Ok(())
     }
 
+    /// Close the connection to the selected port and stop the stream,
+    /// the script is notified through its `on_disconnect` hook.
+    pub fn disconnect(&mut self) {
+        self.receiver.disconnect_from_midi_device();
+        self.receiver.set_midi_stream_active(false);
+        self.clear_messages();
+        self.clock = MidiClock::default();
+        self.held_notes.clear();
+
+        if self.selected_port_name.take().is_none() {
+            return;
+        }
+
+        if let Err(e) = self.script.borrow().try_send(HostEvent::Disconnect) {
+            log::error!("Failed to send device disconnected event to runtime : {e}");
+        }
+    }
+
     fn connect_to_input_unchecked(&mut self, port_name: String) -> anyhow::Result<()> {
         self.receiver.connect_to_midi_device(&port_name)?;
         self.selected_port_name = Some(port_name.clone());
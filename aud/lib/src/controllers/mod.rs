@@ -6,17 +6,36 @@ pub mod midi;
 
 #[cfg(test)]
 mod test {
-    use super::audio_midi::{AppEvent, AudioMidiController};
-    use crate::midi::{MidiData, MidiReceiving};
-    use std::time::Duration;
+    use super::audio_midi::{AlertSeverity, AppEvent, AudioMidiController, ScriptMessageKind};
+    use crate::{
+        audio::{
+            AudioBuffer, AudioChannelSelection, AudioDevice, AudioDeviceConnection, AudioInterface,
+            AudioProviding,
+        },
+        dsp::TriggerEdge,
+        lua::{
+            imported,
+            traits::api::{ScopeMode, UiApiEvent},
+            ScriptLoadResult,
+        },
+        midi::{MidiData, MidiProducing, MidiReceiving, SysExDump},
+    };
+    use std::{
+        cell::{Cell, RefCell},
+        rc::Rc,
+        time::Duration,
+    };
 
     const MIDI_DEVICES: &[&str] = &["dev0", "dev1", "dev2"];
     const MIDI_BYTES: &[u8] = &[1, 2, 3];
+    const AUDIO_DEVICES: &[&str] = &["audio0", "audio1"];
+    const AUDIO_NUM_FRAMES: u32 = 64;
     const TIMEOUT: Duration = Duration::from_millis(500);
 
     #[derive(Default)]
     struct MockMidiHost {
         is_active: bool,
+        messages: Vec<Vec<u8>>,
     }
 
     impl MidiReceiving for MockMidiHost {
@@ -38,10 +57,144 @@ mod test {
         }
 
         fn produce_midi_messages(&mut self) -> Vec<MidiData> {
-            vec![MidiData {
-                timestamp: 1111,
-                bytes: MIDI_BYTES.into(),
-            }]
+            if self.messages.is_empty() {
+                return vec![MidiData {
+                    timestamp: 1111,
+                    bytes: MIDI_BYTES.into(),
+                }];
+            }
+
+            self.messages
+                .iter()
+                .map(|bytes| MidiData {
+                    timestamp: 1111,
+                    bytes: bytes.clone(),
+                })
+                .collect()
+        }
+    }
+
+    /// Records what is sent along with the device it is sent to.
+    #[derive(Default)]
+    struct MockMidiOutput {
+        sent: Rc<RefCell<Vec<(String, Vec<u8>)>>>,
+    }
+
+    impl MidiProducing for MockMidiOutput {
+        fn send_midi_messages(
+            &mut self,
+            device: &str,
+            messages: &[MidiData],
+        ) -> anyhow::Result<()> {
+            let mut sent = self.sent.borrow_mut();
+            sent.extend(
+                messages
+                    .iter()
+                    .map(|m| (device.to_owned(), m.bytes.clone())),
+            );
+            Ok(())
+        }
+    }
+
+    struct MockAudioHost {
+        devices: Vec<AudioDevice>,
+        connection: Option<AudioDeviceConnection>,
+        stream_error: Rc<RefCell<Option<anyhow::Error>>>,
+        num_connections: Rc<Cell<usize>>,
+        /// Buffer produced on the next retrieval instead of the generated one.
+        next_buffer: Rc<RefCell<Option<AudioBuffer>>>,
+        /// Devices listed on the next refresh.
+        next_devices: Rc<RefCell<Option<Vec<AudioDevice>>>>,
+        sample_rate: u32,
+    }
+
+    impl Default for MockAudioHost {
+        fn default() -> Self {
+            Self {
+                devices: AUDIO_DEVICES
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| AudioDevice {
+                        name: name.to_string(),
+                        num_channels: 2,
+                        index,
+                    })
+                    .collect(),
+                connection: None,
+                stream_error: Rc::default(),
+                num_connections: Rc::default(),
+                next_buffer: Rc::default(),
+                next_devices: Rc::default(),
+                sample_rate: 48_000,
+            }
+        }
+    }
+
+    impl AudioInterface for MockAudioHost {
+        fn is_accessible(&self) -> bool {
+            self.connection.is_some()
+        }
+
+        fn list_audio_devices(&self) -> &[AudioDevice] {
+            self.devices.as_slice()
+        }
+
+        fn refresh_audio_devices(&mut self) {
+            if let Some(devices) = self.next_devices.borrow_mut().take() {
+                self.devices = devices;
+            }
+        }
+
+        fn connect_to_audio_device(
+            &mut self,
+            audio_device: &AudioDevice,
+            channel_selection: AudioChannelSelection,
+        ) -> anyhow::Result<()> {
+            assert!(self.devices.contains(audio_device));
+            self.num_connections.set(self.num_connections.get() + 1);
+            self.connection = Some(AudioDeviceConnection {
+                device: audio_device.clone(),
+                channels: channel_selection,
+                sample_rate: self.sample_rate,
+            });
+            Ok(())
+        }
+
+        fn disconnect_from_audio_device(&mut self) {
+            self.connection = None;
+        }
+
+        fn connected_audio_device(&self) -> Option<&AudioDeviceConnection> {
+            self.connection.as_ref()
+        }
+
+        fn process_audio_events(&mut self) -> anyhow::Result<()> {
+            match self.stream_error.borrow_mut().take() {
+                Some(e) => Err(e),
+                None => Ok(()),
+            }
+        }
+    }
+
+    impl AudioProviding for MockAudioHost {
+        fn retrieve_audio_buffer(&mut self) -> AudioBuffer {
+            if let Some(buffer) = self.next_buffer.borrow_mut().take() {
+                return buffer;
+            }
+
+            match self.connection {
+                Some(ref connection) => {
+                    let mut buffer = AudioBuffer::with_frames(
+                        AUDIO_NUM_FRAMES,
+                        connection.channels.count() as u32,
+                    );
+                    for (i, sample) in buffer.data.iter_mut().enumerate() {
+                        *sample = (i as f32 * 0.1).sin();
+                    }
+                    buffer
+                }
+                None => AudioBuffer::default(),
+            }
         }
     }
 
@@ -67,6 +220,23 @@ mod test {
         assert_eq!(app.take_alert().unwrap(), "loaded");
     }
 
+    #[test]
+    fn a_burst_of_identical_alerts_is_coalesced() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        let script = crate::test::fixture("alert_burst.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        let start = std::time::Instant::now();
+        let mut alerts = vec![];
+        while start.elapsed() < std::time::Duration::from_millis(100) {
+            assert_eq!(app.process_script_events().unwrap(), AppEvent::Continue);
+            alerts.extend(app.take_alert());
+        }
+
+        assert_eq!(alerts, ["boom x12"]);
+    }
+
     #[test]
     fn can_call_into_scripts_through_hooks() {
         let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
@@ -112,15 +282,1055 @@ mod test {
     }
 
     #[test]
-    fn does_not_panic_when_an_invalid_script_crashes_the_engine() {
+    fn scripts_receive_decoded_midi_fields() {
+        let midi = MockMidiHost {
+            messages: vec![vec![0x92, 60, 100]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let script = crate::test::fixture("alert_midi_fields.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            "note_on:2:60:100:3"
+        );
+    }
+
+    #[test]
+    fn realtime_messages_do_not_count_as_midi_activity() {
+        let midi = MockMidiHost {
+            messages: vec![vec![0xF8], vec![0xFE]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), "");
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        assert_eq!(app.midi().last_message_received(), None);
+    }
+
+    #[test]
+    fn only_scripts_receive_normalized_note_offs() {
+        let midi = MockMidiHost {
+            messages: vec![vec![0x92, 60, 0]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let script = crate::test::fixture("alert_midi_fields.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut()
+            .set_forwarded_note_off(crate::midi::NoteOffForm::NoteOff);
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            "note_off:2:60:0:3"
+        );
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.is_empty() && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages().into_iter().map(|m| m.bytes));
+        }
+        assert_eq!(messages, [vec![0x92, 60, 0]]);
+    }
+
+    #[test]
+    fn scripts_can_transform_drop_and_expand_midi_messages() {
+        let midi = MockMidiHost {
+            messages: vec![
+                vec![0x90, 60, 100],
+                vec![0x90, 61, 100],
+                vec![0x90, 62, 100],
+                vec![0x90, 63, 100],
+            ],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let script = crate::test::fixture("transform_midi.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 4 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages().into_iter().map(|m| m.bytes));
+        }
+
+        assert_eq!(
+            messages,
+            [
+                vec![0x90, 72, 100],
+                vec![0x90, 62, 100],
+                vec![0x80, 62, 0],
+                vec![0x90, 63, 100],
+            ]
+        );
+    }
+
+    #[test]
+    fn chained_scripts_transform_messages_in_order() {
+        let midi = MockMidiHost {
+            messages: vec![vec![0x90, 60, 50], vec![0x80, 60, 0]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let scripts = [
+            crate::test::fixture("chain_raise_octave.lua"),
+            crate::test::fixture("chain_accent_high_notes.lua"),
+        ];
+        app.load_script_chain_sync(&scripts, TIMEOUT).unwrap();
+        assert_eq!(
+            app.selected_script().unwrap(),
+            "chain_raise_octave.lua → chain_accent_high_notes.lua"
+        );
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 2 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages().into_iter().map(|m| m.bytes));
+        }
+
+        // the octave is raised first, so the note is accented after
+        assert_eq!(messages, [vec![0x90, 72, 100], vec![0x80, 60, 0]]);
+
+        let scripts = [scripts[0].clone(), crate::test::fixture("invalid.lua")];
+        let error = app.load_script_chain_sync(&scripts, TIMEOUT).unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("script 2 of the chain, invalid.lua"));
+    }
+
+    #[test]
+    fn scripts_can_clear_the_received_messages() {
+        let midi = MockMidiHost {
+            messages: vec![
+                vec![0x90, 60, 100],
+                vec![0x90, 61, 100],
+                vec![0xB0, 7, 10],
+                vec![0x90, 62, 100],
+            ],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let script = crate::test::fixture("clear_on_control_change.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 2 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages = app
+                .midi()
+                .messages()
+                .iter()
+                .map(|m| m.bytes.clone())
+                .collect();
+        }
+
+        assert_eq!(messages, [vec![0xB0, 7, 10], vec![0x90, 62, 100]]);
+        assert_eq!(app.take_ui_requests(), [UiApiEvent::Clear]);
+        assert!(app.take_ui_requests().is_empty());
+    }
+
+    #[test]
+    fn scripts_can_filter_out_the_messages_of_other_channels() {
+        let midi = MockMidiHost {
+            messages: vec![
+                vec![0x90, 60, 100],
+                vec![0x91, 61, 100],
+                vec![0xB1, 7, 10],
+                vec![0x92, 62, 100],
+            ],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        app.load_script_sync(crate::test::fixture("filter_channel.lua"), TIMEOUT)
+            .unwrap();
+        assert_eq!(app.midi().filter().channels, Some(vec![1]));
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 2 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages().into_iter().map(|m| m.bytes));
+        }
+
+        assert_eq!(messages, [vec![0x91, 61, 100], vec![0xB1, 7, 10]]);
+
+        // the filter of the app is back once another script is loaded
+        app.load_script_sync(crate::test::fixture("log_on_load.lua"), TIMEOUT)
+            .unwrap();
+        assert_eq!(*app.midi().filter(), crate::midi::MessageFilter::default());
+    }
+
+    #[test]
+    fn scripts_cannot_widen_the_note_range_of_the_app() {
+        let mut app =
+            AudioMidiController::with_midi(Box::<MockMidiHost>::default(), imported::midimon::API);
+        app.midi_mut().set_filter(crate::midi::MessageFilter {
+            note_range: Some((36, 60)),
+            ..Default::default()
+        });
+
+        app.load_script_sync(crate::test::fixture("filter_notes.lua"), TIMEOUT)
+            .unwrap();
+        assert_eq!(app.midi().filter().note_range, Some((50, 60)));
+    }
+
+    #[test]
+    fn scheduled_midi_is_sent_after_its_delay_and_cancelled_on_reload() {
+        let midi = MockMidiHost {
+            messages: vec![vec![0x90, 60, 100]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi), imported::midimon::API);
+
+        let script = crate::test::fixture("schedule_note_off.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        app.midi_mut().update();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 2 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages());
+        }
+
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(messages[0].bytes, [0x90, 60, 100]);
+        assert_eq!(messages[1].bytes, [0x80, 60, 0]);
+        assert_eq!(messages[1].timestamp, 1111 + 50_000);
+
+        app.midi_mut().update();
+        app.load_script_sync(crate::test::fixture("log_on_load.lua"), TIMEOUT)
+            .unwrap();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while start.elapsed() < Duration::from_millis(150) {
+            app.process_script_events().unwrap();
+            messages.extend(app.midi_mut().take_messages().into_iter().map(|m| m.bytes));
+        }
+
+        assert!(!messages.contains(&vec![0x80, 60, 0]));
+    }
+
+    #[test]
+    fn ports_can_be_connected_to_by_pattern() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        app.midi_mut().connect_to_input_matching("DEV?").unwrap();
+        assert_eq!(app.midi().selected_port_name(), Some(MIDI_DEVICES[0]));
+
+        app.midi_mut().connect_to_input_matching("/v2$/").unwrap();
+        assert_eq!(app.midi().selected_port_name(), Some(MIDI_DEVICES[2]));
+
+        assert!(app.midi_mut().connect_to_input_matching("synth*").is_err());
+        assert!(app.midi_mut().connect_to_input_matching("/dev(/").is_err());
+        assert_eq!(app.midi().selected_port_name(), Some(MIDI_DEVICES[2]));
+
+        let script = crate::test::fixture("connect_by_pattern.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        let start = std::time::Instant::now();
+        while app.midi().selected_port_name() != Some(MIDI_DEVICES[1]) && start.elapsed() < TIMEOUT
+        {
+            app.process_script_events().unwrap();
+        }
+        assert_eq!(app.midi().selected_port_name(), Some(MIDI_DEVICES[1]));
+    }
+
+    #[test]
+    fn disconnecting_midi_stops_the_stream_and_notifies_the_script() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        app.load_script_sync(crate::test::fixture("alert_on_disconnect.lua"), TIMEOUT)
+            .unwrap();
+
+        app.midi_mut().connect_to_input(MIDI_DEVICES[1]).unwrap();
+        app.midi_mut().set_running(true);
+        app.midi_mut().disconnect();
+
+        assert!(!app.midi().is_running());
+        assert_eq!(app.midi().selected_port_name(), None);
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            "disconnected from dev1"
+        );
+    }
+
+    #[test]
+    fn disconnecting_audio_drops_the_selection_and_its_audio() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            "",
+        );
+        let audio = app.audio_mut();
+        audio
+            .connect_to_input_by_index(0, AudioChannelSelection::Mono(0))
+            .unwrap();
+        audio.update().unwrap();
+        assert!(!audio.buffer().data.is_empty());
+
+        audio.disconnect();
+        audio.update().unwrap();
+        assert!(audio.selected_device().is_none());
+        assert!(audio.selected_channels().is_none());
+        assert!(audio.buffer().data.is_empty());
+    }
+
+    #[test]
+    fn a_syx_file_is_sent_intact_to_the_connected_device() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        let output = MockMidiOutput::default();
+        let sent = output.sent.clone();
+        app.set_midi_output(Box::new(output));
+
+        let file = crate::test::fixture("dump.syx");
+        let dump = SysExDump::from_file(&file).unwrap();
+        assert_eq!(dump.messages().len(), 2);
+        assert!(app.send_sysex(&dump).is_err());
+
+        app.midi_mut().connect_to_input(MIDI_DEVICES[1]).unwrap();
+        app.send_sysex(&dump).unwrap();
+
+        let sent = sent.borrow();
+        assert!(sent.iter().all(|(device, _)| device == MIDI_DEVICES[1]));
+        let bytes: Vec<u8> = sent.iter().flat_map(|(_, bytes)| bytes.clone()).collect();
+        assert_eq!(bytes, std::fs::read(file).unwrap());
+    }
+
+    #[test]
+    fn scripts_can_send_valid_sysex() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        let output = MockMidiOutput::default();
+        let sent = output.sent.clone();
+        app.set_midi_output(Box::new(output));
+        app.midi_mut().connect_to_input(MIDI_DEVICES[0]).unwrap();
+
+        let script = crate::test::fixture("send_sysex.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        assert_eq!(app.wait_for_alert(TIMEOUT).unwrap().unwrap(), "rejected");
+
+        assert_eq!(
+            *sent.borrow(),
+            [(
+                MIDI_DEVICES[0].to_owned(),
+                vec![0xF0, 0x7E, 0x7F, 0x06, 0x01, 0xF7]
+            )]
+        );
+    }
+
+    #[test]
+    fn a_midi_panic_releases_held_notes_and_silences_every_channel() {
+        let midi_host = MockMidiHost {
+            messages: vec![vec![0x90, 60, 100], vec![0x91, 64, 90], vec![0x81, 64, 0]],
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::with_midi(Box::new(midi_host), "");
+        let output = MockMidiOutput::default();
+        let sent = output.sent.clone();
+        app.set_midi_output(Box::new(output));
+        app.midi_mut().connect_to_input(MIDI_DEVICES[0]).unwrap();
+        app.midi_mut().update();
+        app.panic().unwrap();
+
+        let messages: Vec<_> = sent
+            .borrow_mut()
+            .drain(..)
+            .map(|(device, bytes)| {
+                assert_eq!(device, MIDI_DEVICES[0]);
+                bytes
+            })
+            .collect();
+        assert_eq!(messages.len(), 1 + 16 * 2);
+        assert_eq!(messages[0], [0x80, 60, 0]);
+        assert_eq!(messages[1..3], [[0xB0, 123, 0], [0xB0, 120, 0]]);
+        assert_eq!(messages[31..], [[0xBF, 123, 0], [0xBF, 120, 0]]);
+
+        // the held notes were released by the first panic
+        let script = crate::test::fixture("panic_on_load.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        assert_eq!(app.wait_for_alert(TIMEOUT).unwrap().unwrap(), "panicked");
+        assert_eq!(sent.borrow().len(), 16 * 2);
+    }
+
+    #[test]
+    fn loading_a_script_after_an_engine_crash_restarts_the_engine() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        app.load_script_sync(crate::test::fixture("error_on_midi.lua"), TIMEOUT)
+            .unwrap();
+
+        app.midi_mut().connect_to_input(MIDI_DEVICES[0]).unwrap();
+        app.midi_mut().update();
+        let start = std::time::Instant::now();
+        while app.process_engine_events().unwrap() != AppEvent::ScriptCrash {
+            assert!(start.elapsed() < TIMEOUT, "the engine did not crash");
+        }
+
+        assert!(app.is_engine_down());
+        assert_eq!(app.alert_severity(), Some(AlertSeverity::Error));
+        assert_eq!(app.process_engine_events().unwrap(), AppEvent::Continue);
+
+        let script = crate::test::fixture("alert_on_load.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        assert!(!app.is_engine_down());
+        assert_eq!(app.wait_for_alert(TIMEOUT).unwrap().unwrap(), "loaded");
+    }
+
+    #[test]
+    fn scripts_can_read_back_the_selected_device() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        let script = crate::test::fixture("read_selected_device.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            format!("nil -> {}, nil", MIDI_DEVICES[1])
+        );
+    }
+
+    #[test]
+    fn the_script_clock_advances_with_the_host_loop() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        let script = crate::test::fixture("log_time_on_midi.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input(MIDI_DEVICES[0]).unwrap();
+
+        let mut clocks = vec![];
+        for _ in 0..2 {
+            app.tick();
+            app.midi_mut().update();
+
+            let start = std::time::Instant::now();
+            let mut logs = vec![];
+            while logs.is_empty() && start.elapsed() < TIMEOUT {
+                app.process_script_events().unwrap();
+                logs = app.take_script_messages();
+            }
+            clocks.push(logs.pop().expect("no clock was logged").text);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+        }
+
+        let clocks: Vec<Vec<f64>> = clocks
+            .iter()
+            .map(|clock| clock.split(' ').map(|v| v.parse().unwrap()).collect())
+            .collect();
+
+        assert_eq!((clocks[0][0], clocks[1][0]), (1., 2.));
+        assert!(clocks[1][1] > clocks[0][1], "{clocks:?}");
+        // the clock does not move during a callback
+        assert!(
+            clocks.iter().all(|clock| clock[1] == clock[2]),
+            "{clocks:?}"
+        );
+    }
+
+    #[test]
+    fn script_logs_and_alerts_are_kept_in_order() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        let script = crate::test::fixture("log_on_load.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        let start = std::time::Instant::now();
+        let mut messages = vec![];
+        while messages.len() < 3 && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+            messages.extend(
+                app.take_script_messages()
+                    .into_iter()
+                    .map(|msg| (msg.kind, msg.text)),
+            );
+        }
+
+        assert_eq!(
+            messages,
+            [
+                (ScriptMessageKind::Log, "one".to_owned()),
+                (ScriptMessageKind::Alert, "two".to_owned()),
+                (ScriptMessageKind::Log, "three".to_owned()),
+            ]
+        );
+    }
+
+    #[test]
+    fn can_await_the_result_of_a_script_load() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        let script = crate::test::fixture("alert_on_load.lua");
+        let result = app.load_script_with_result(script.clone()).unwrap();
+        assert_eq!(
+            result.recv_timeout(TIMEOUT).unwrap(),
+            ScriptLoadResult::Loaded
+        );
+        assert_eq!(*app.loaded_script_path().unwrap(), script);
+
+        assert_eq!(app.process_script_events().unwrap(), AppEvent::ScriptLoaded);
+        assert_eq!(app.process_script_events().unwrap(), AppEvent::Continue);
+        assert_eq!(app.take_alert().unwrap(), "loaded");
+    }
+
+    #[test]
+    fn does_not_crash_the_engine_when_loading_an_invalid_script() {
         let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
 
         let invalid_script = crate::test::fixture("invalid.lua");
-        app.load_script_sync(invalid_script, TIMEOUT).unwrap_err();
-        assert_eq!(app.process_engine_events().unwrap(), AppEvent::ScriptCrash);
+        let start = std::time::Instant::now();
+        let error = app.load_script_sync(invalid_script, TIMEOUT).unwrap_err();
+        assert!(start.elapsed() < TIMEOUT);
+        assert!(error.to_string().contains("syntax error"), "{error}");
+        assert_eq!(app.process_engine_events().unwrap(), AppEvent::Continue);
 
         let valid_script = crate::test::fixture("alert_on_load.lua");
         app.load_script_sync(valid_script, TIMEOUT).unwrap();
         assert_eq!(app.process_engine_events().unwrap(), AppEvent::Continue);
     }
+
+    #[test]
+    fn info_alerts_expire_after_their_timeout_while_errors_persist() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        app.set_alert_timeout(Duration::from_secs(1));
+        let later = std::time::Instant::now() + Duration::from_secs(2);
+
+        let script = crate::test::fixture("alert_on_load.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.process_script_events().unwrap();
+        assert_eq!(app.alert_severity(), Some(AlertSeverity::Info));
+        app.expire_alert(later);
+        assert!(app.take_alert().is_none());
+
+        let invalid_script = crate::test::fixture("invalid.lua");
+        app.load_script_sync(invalid_script, TIMEOUT).unwrap_err();
+        assert_eq!(app.alert_severity(), Some(AlertSeverity::Error));
+        app.expire_alert(later);
+        assert!(app.take_alert().unwrap().contains("syntax error"));
+    }
+
+    #[test]
+    fn scripts_connect_to_the_intended_device_among_identically_named_ones() {
+        let devices = (0..2)
+            .map(|index| AudioDevice {
+                name: "mic".to_owned(),
+                num_channels: 2,
+                index,
+            })
+            .collect();
+        let audio = MockAudioHost {
+            devices,
+            ..Default::default()
+        };
+        let mut app = AudioMidiController::new(
+            Box::new(audio),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+        assert_eq!(app.audio().device_names(), ["mic #1", "mic #2"]);
+
+        let script = crate::test::fixture("connect_on_load.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        let start = std::time::Instant::now();
+        while app.audio().selected_device().is_none() && start.elapsed() < TIMEOUT {
+            app.process_script_events().unwrap();
+        }
+
+        assert_eq!(app.audio().selected_device().unwrap().index, 1);
+        assert_eq!(app.audio().selected_device_name().unwrap(), "mic #2");
+    }
+
+    #[test]
+    fn the_selected_device_keeps_its_name_once_the_devices_are_listed_again() {
+        let device = |name: &str, index| AudioDevice {
+            name: name.to_owned(),
+            num_channels: 2,
+            index,
+        };
+        let audio = MockAudioHost {
+            devices: vec![device("mic", 0), device("mic", 1)],
+            ..Default::default()
+        };
+        let next_devices = audio.next_devices.clone();
+        let mut app = AudioMidiController::new(
+            Box::new(audio),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        app.audio_mut()
+            .connect_to_input_by_index(1, AudioChannelSelection::Mono(0))
+            .unwrap();
+        assert_eq!(app.audio().selected_device_name().unwrap(), "mic #2");
+
+        // a device plugged in ahead of them shifts their indices
+        *next_devices.borrow_mut() =
+            Some(vec![device("usb", 0), device("mic", 1), device("mic", 2)]);
+        app.audio_mut().refresh_devices();
+        assert_eq!(app.audio().selected_device_name().unwrap(), "mic #2");
+
+        *next_devices.borrow_mut() = Some(vec![device("mic", 0)]);
+        app.audio_mut().refresh_devices();
+        assert_eq!(app.audio().selected_device_name(), None);
+    }
+
+    #[test]
+    fn can_forward_audio_to_a_script() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let script = crate::test::fixture("alert_on_audio.lua");
+        app.load_script_sync(script.clone(), TIMEOUT).unwrap();
+        assert_eq!(*app.loaded_script_path().unwrap(), script);
+
+        let device = app.audio().devices()[1].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Range(0..2))
+            .unwrap();
+        app.audio_mut().update().unwrap();
+
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            format!("on_audio:{}:2:{AUDIO_NUM_FRAMES}", AUDIO_DEVICES[1])
+        );
+    }
+
+    #[test]
+    fn audio_is_discarded_until_a_paused_capture_is_resumed() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+        app.audio_mut().set_running(false);
+        assert!(!app.audio().is_running());
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        app.audio_mut().update().unwrap();
+        assert!(app.audio().buffer().data.is_empty());
+
+        app.audio_mut().set_running(true);
+        app.audio_mut().update().unwrap();
+        assert_eq!(app.audio().buffer().data.len(), AUDIO_NUM_FRAMES as usize);
+    }
+
+    #[test]
+    fn selecting_a_device_index_connects_to_that_device() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+        app.audio_mut()
+            .connect_to_input_by_index(0, AudioChannelSelection::Mono(0))
+            .unwrap();
+        app.audio_mut().update().unwrap();
+        assert!(!app.audio().buffer().data.is_empty());
+
+        app.audio_mut()
+            .connect_to_input_by_index(1, AudioChannelSelection::Mono(0))
+            .unwrap();
+        assert_eq!(
+            app.audio().selected_device().unwrap().name,
+            AUDIO_DEVICES[1]
+        );
+        assert!(app.audio().buffer().data.is_empty());
+
+        let num_devices = app.audio().devices().len();
+        assert!(app
+            .audio_mut()
+            .connect_to_input_by_index(num_devices, AudioChannelSelection::Mono(0))
+            .is_err());
+        assert_eq!(
+            app.audio().selected_device().unwrap().name,
+            AUDIO_DEVICES[1]
+        );
+    }
+
+    #[test]
+    fn scripts_can_pull_the_latest_audio_window() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let script = crate::test::fixture("alert_rms_on_audio.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Range(0..2))
+            .unwrap();
+        app.audio_mut().update().unwrap();
+
+        let expected = crate::dsp::rms(&app.audio().buffer().deinterleave()[0]);
+        let rms: f32 = app
+            .wait_for_alert(TIMEOUT)
+            .unwrap()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(expected > 0.);
+        assert!((rms - expected).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn runaway_scripts_are_interrupted_by_the_watchdog() {
+        let mut app =
+            AudioMidiController::with_midi(Box::<MockMidiHost>::default(), imported::midimon::API);
+        app.set_script_timeout(Duration::from_millis(50));
+
+        let script = crate::test::fixture("infinite_loop.lua");
+        app.load_script(script).unwrap();
+
+        let alert = app.wait_for_alert(TIMEOUT).unwrap().unwrap();
+        assert!(alert.contains("timeout"), "{alert}");
+        assert_eq!(app.process_engine_events().unwrap(), AppEvent::Continue);
+    }
+
+    #[test]
+    fn audio_stream_errors_are_reported_and_trigger_a_reconnect() {
+        let audio_host = MockAudioHost::default();
+        let stream_error = audio_host.stream_error.clone();
+        let mut app = AudioMidiController::new(
+            Box::new(audio_host),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        app.audio_mut().update().unwrap();
+        assert!(!app.audio().buffer().data.is_empty());
+
+        *stream_error.borrow_mut() = Some(anyhow::anyhow!("device unplugged"));
+        let error = app.audio_mut().update().unwrap_err();
+        assert_eq!(error.to_string(), "device unplugged");
+        assert!(app.audio().buffer().data.is_empty());
+        assert_eq!(app.audio().selected_device(), Some(&device));
+
+        app.audio_mut().update().unwrap();
+        assert!(!app.audio().buffer().data.is_empty());
+    }
+
+    #[test]
+    fn an_unreachable_remote_is_not_reconnected_to() {
+        let audio_host = MockAudioHost::default();
+        let stream_error = audio_host.stream_error.clone();
+        let num_connections = audio_host.num_connections.clone();
+        let mut app = AudioMidiController::new(
+            Box::new(audio_host),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+        assert_eq!(num_connections.get(), 1);
+
+        *stream_error.borrow_mut() = Some(crate::audio::RemoteUnreachable(3).into());
+        let error = app.audio_mut().update().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "remote audio unreachable after 3 retries"
+        );
+        assert_eq!(num_connections.get(), 1);
+        assert_eq!(app.audio().selected_device(), Some(&device));
+
+        app.audio_mut().reconnect().unwrap();
+        assert_eq!(num_connections.get(), 2);
+    }
+
+    #[test]
+    fn empty_buffers_are_dropped_and_non_finite_samples_silenced() {
+        let audio_host = MockAudioHost::default();
+        let next_buffer = audio_host.next_buffer.clone();
+        let mut app = AudioMidiController::new(
+            Box::new(audio_host),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+
+        *next_buffer.borrow_mut() = Some(AudioBuffer {
+            data: vec![f32::NAN, 0.5, f32::INFINITY, -0.5, f32::NEG_INFINITY],
+            num_channels: 1,
+        });
+        app.audio_mut().update().unwrap();
+        assert_eq!(app.audio().buffer().data, [0., 0.5, 0., -0.5, 0.]);
+        assert_eq!(app.audio().num_sanitized_samples(), 3);
+
+        let version = app.audio().buffer_version();
+        *next_buffer.borrow_mut() = Some(AudioBuffer {
+            data: vec![],
+            num_channels: 2,
+        });
+        app.audio_mut().update().unwrap();
+        assert_eq!(app.audio().buffer_version(), version);
+        assert_eq!(app.audio().buffer().num_channels, 1);
+        assert_eq!(app.audio().buffer().data.len(), 5);
+    }
+
+    #[test]
+    fn a_backlog_of_high_rate_audio_is_skipped_to_the_latest_second() {
+        let audio_host = MockAudioHost {
+            sample_rate: 192_000,
+            ..Default::default()
+        };
+        let next_buffer = audio_host.next_buffer.clone();
+        let mut app = AudioMidiController::new(
+            Box::new(audio_host),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+
+        let device = app.audio().devices()[0].clone();
+        app.audio_mut()
+            .connect_to_input(&device, AudioChannelSelection::Mono(0))
+            .unwrap();
+
+        let num_frames = 192_000 * 3;
+        *next_buffer.borrow_mut() = Some(AudioBuffer {
+            data: (0..num_frames).map(|i| i as f32).collect(),
+            num_channels: 1,
+        });
+        app.audio_mut().update().unwrap();
+
+        // the whole backlog is there to be recorded, only then skipped
+        assert_eq!(app.audio().buffer().data.len(), num_frames);
+        assert_eq!(app.audio().num_skipped_frames(), 0);
+        app.audio_mut().skip_backlog();
+
+        let data = &app.audio().buffer().data;
+        assert_eq!(data.len(), 192_000);
+        assert_eq!(data.last(), Some(&((num_frames - 1) as f32)));
+        assert_eq!(app.audio().num_skipped_frames(), 192_000 * 2);
+
+        *next_buffer.borrow_mut() = Some(AudioBuffer {
+            data: vec![0.; 1024],
+            num_channels: 1,
+        });
+        app.audio_mut().update().unwrap();
+        app.audio_mut().skip_backlog();
+        assert_eq!(app.audio().buffer().data.len(), 192_000);
+        assert_eq!(app.audio().num_skipped_frames(), 192_000 * 2 + 1024);
+    }
+
+    #[test]
+    fn scripts_can_drive_the_scope_display() {
+        let mut app = AudioMidiController::new(
+            Box::<MockAudioHost>::default(),
+            Box::<MockMidiHost>::default(),
+            imported::auscope::API,
+        );
+        assert_eq!(app.scope().mode, ScopeMode::Waveform);
+
+        let script = crate::test::fixture("set_scope_settings.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        let scope = app.scope();
+        assert_eq!(scope.mode, ScopeMode::Spectrum);
+        assert_eq!(scope.trigger.unwrap().level, 0.5);
+        assert_eq!(scope.trigger.unwrap().edge, TriggerEdge::Falling);
+        assert_eq!(scope.window, Some(Duration::from_millis(20)));
+    }
+
+    #[test]
+    fn adjusting_a_param_delivers_its_value_to_the_script() {
+        let mut app =
+            AudioMidiController::with_midi(Box::<MockMidiHost>::default(), imported::midimon::API);
+
+        let script = crate::test::fixture("define_params.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        let params = app.params();
+        assert_eq!(params.len(), 1);
+        assert_eq!(params[0].name, "threshold");
+        assert_eq!(params[0].value, 0.5);
+
+        app.adjust_param(0, 10);
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            "threshold:0.6:0.6"
+        );
+
+        app.adjust_param(0, 100);
+        assert_eq!(app.params()[0].value, 1.);
+        assert_eq!(
+            app.wait_for_alert(TIMEOUT).unwrap().unwrap(),
+            "threshold:1.0:1.0"
+        );
+    }
+
+    #[test]
+    fn scripts_can_stop_the_app_with_a_reason() {
+        let mut app =
+            AudioMidiController::with_midi(Box::<MockMidiHost>::default(), imported::midimon::API);
+
+        let script = crate::test::fixture("stop_with_reason.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+
+        let start = std::time::Instant::now();
+        let mut event = AppEvent::Continue;
+        while event != AppEvent::Stopping && start.elapsed() < TIMEOUT {
+            event = app.process_script_events().unwrap();
+        }
+
+        assert_eq!(event, AppEvent::Stopping);
+        assert_eq!(app.take_alert().unwrap(), "script stopped : done");
+    }
+
+    #[test]
+    fn received_messages_are_logged_with_their_fields_and_bytes() {
+        let message = crate::midi::MidiData {
+            timestamp: 1111,
+            bytes: vec![0x92, 60, 100],
+        };
+
+        assert_eq!(
+            super::midi::log_line(&message),
+            "1111 us : note_on ch=2 note=60 velocity=100 : [92, 3C, 64]"
+        );
+    }
+
+    #[test]
+    fn aliases_change_displayed_port_names_but_not_connections() {
+        let mut app =
+            AudioMidiController::with_midi(Box::<MockMidiHost>::default(), imported::midimon::API);
+
+        let script = crate::test::fixture("alias_midi_device.lua");
+        app.load_script_sync(script, TIMEOUT).unwrap();
+
+        assert_eq!(app.midi().display_name(MIDI_DEVICES[0]), "Keys");
+        assert_eq!(app.midi().display_name(MIDI_DEVICES[1]), MIDI_DEVICES[1]);
+        assert_eq!(app.midi().display_port_names(), ["Keys", "dev1", "dev2"]);
+        assert_eq!(app.midi().port_names(), MIDI_DEVICES);
+
+        app.midi_mut().connect_to_input_by_index(0).unwrap();
+        assert_eq!(app.midi().selected_port_name().unwrap(), MIDI_DEVICES[0]);
+        assert!(app.midi_mut().connect_to_input("Keys").is_err());
+    }
+
+    #[test]
+    fn can_connect_to_a_port_by_name_or_index() {
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+
+        app.midi_mut()
+            .connect_to_input_by_name_or_index(MIDI_DEVICES[1])
+            .unwrap();
+        assert_eq!(app.midi().selected_port_name().unwrap(), MIDI_DEVICES[1]);
+
+        app.midi_mut()
+            .connect_to_input_by_name_or_index("2")
+            .unwrap();
+        assert_eq!(app.midi().selected_port_name().unwrap(), MIDI_DEVICES[2]);
+
+        let error = app
+            .midi_mut()
+            .connect_to_input_by_name_or_index("unplugged")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("[0] dev0, [1] dev1, [2] dev2"), "{error}");
+        assert!(app
+            .midi_mut()
+            .connect_to_input_by_name_or_index("3")
+            .is_err());
+    }
+
+    #[test]
+    fn recreating_a_script_reloads_it_once() {
+        let dir = std::env::temp_dir().join(format!("aud-recreate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("script.lua");
+        std::fs::copy(crate::test::fixture("alert_on_load.lua"), &script).unwrap();
+
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        app.load_script_sync(&script, TIMEOUT).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::Continue);
+
+        std::fs::remove_file(&script).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::Continue);
+
+        std::fs::copy(crate::test::fixture("alert_on_load.lua"), &script).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::ScriptLoaded);
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::Continue);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn scripts_require_sibling_modules_and_reload_when_they_change() {
+        let dir = std::env::temp_dir().join(format!("aud-require-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = dir.join("script.lua");
+        let helper = dir.join("sibling_helper.lua");
+        std::fs::copy(crate::test::fixture("require_sibling.lua"), &script).unwrap();
+        std::fs::copy(crate::test::fixture("sibling_helper.lua"), &helper).unwrap();
+
+        let mut app = AudioMidiController::with_midi(Box::<MockMidiHost>::default(), "");
+        app.load_script_sync(&script, TIMEOUT).unwrap();
+        assert_eq!(app.process_script_events().unwrap(), AppEvent::Continue);
+        assert_eq!(app.take_alert().unwrap(), "hello from a sibling");
+
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::Continue);
+
+        let changed = std::fs::read_to_string(&helper)
+            .unwrap()
+            .replace("hello", "hi again");
+        std::fs::write(&helper, changed).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(app.process_file_events().unwrap(), AppEvent::ScriptLoaded);
+
+        let start = std::time::Instant::now();
+        let alert = loop {
+            app.process_script_events().unwrap();
+            match app.take_alert() {
+                Some(alert) => break alert,
+                None => assert!(start.elapsed() < TIMEOUT, "the script was not reloaded"),
+            }
+        };
+        assert_eq!(alert, "hi again from a sibling");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
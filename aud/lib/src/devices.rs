@@ -0,0 +1,159 @@
+use crossbeam::channel::{Receiver, Sender};
+use std::{thread::JoinHandle, time::Duration};
+
+/// Default time between two enumerations of the devices.
+pub const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Number of consecutive enumerations a new set of devices
+/// must be seen in before it is notified, to avoid churn while
+/// a device is being plugged in or out.
+const DEBOUNCE_POLLS: usize = 2;
+
+/// Enumerates devices on a background thread and
+/// notifies when the set of device names changes.
+pub struct DeviceWatcher {
+    changes: Receiver<Vec<String>>,
+    stop: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl DeviceWatcher {
+    /// Poll `enumerate` every `interval`. The first enumeration is taken
+    /// as the known set of devices and does not cause a notification.
+    pub fn spawn<F>(interval: Duration, mut enumerate: F) -> Self
+    where
+        F: FnMut() -> anyhow::Result<Vec<String>> + Send + 'static,
+    {
+        let (tx, changes) = crossbeam::channel::bounded(16);
+        let (stop, stop_rx) = crossbeam::channel::bounded::<()>(1);
+
+        let handle = std::thread::spawn(move || {
+            let mut debouncer = Debouncer::new(enumerate().unwrap_or_default());
+            let mut has_failed = false;
+
+            // the stop sender is dropped with the watcher, which ends the wait
+            while let Err(crossbeam::channel::RecvTimeoutError::Timeout) =
+                stop_rx.recv_timeout(interval)
+            {
+                let devices = match enumerate() {
+                    Ok(devices) => devices,
+                    Err(e) => {
+                        // only log the first of a series of failures
+                        if !std::mem::replace(&mut has_failed, true) {
+                            log::error!("failed to enumerate devices : {e}");
+                        }
+                        continue;
+                    }
+                };
+                has_failed = false;
+
+                if let Some(devices) = debouncer.observe(devices) {
+                    if let Err(e) = tx.try_send(devices) {
+                        log::error!("failed to send device change : {e}");
+                    }
+                }
+            }
+        });
+
+        Self {
+            changes,
+            stop: Some(stop),
+            handle: Some(handle),
+        }
+    }
+
+    /// Latest set of devices, if it changed since the last call.
+    pub fn try_recv_change(&self) -> Option<Vec<String>> {
+        self.changes.try_iter().last()
+    }
+}
+
+impl Drop for DeviceWatcher {
+    fn drop(&mut self) {
+        drop(self.stop.take());
+        if let Some(Err(_)) = self.handle.take().map(JoinHandle::join) {
+            log::error!("Failed to join on device watcher thread handle");
+        }
+    }
+}
+
+/// Only lets a set of devices through once it has been stable for a few polls.
+struct Debouncer {
+    known: Vec<String>,
+    candidate: Vec<String>,
+    num_polls: usize,
+}
+
+impl Debouncer {
+    fn new(mut devices: Vec<String>) -> Self {
+        devices.sort();
+        Self {
+            known: devices.clone(),
+            candidate: devices,
+            num_polls: 0,
+        }
+    }
+
+    fn observe(&mut self, mut devices: Vec<String>) -> Option<Vec<String>> {
+        devices.sort();
+
+        if devices == self.known {
+            self.candidate = devices;
+            self.num_polls = 0;
+            return None;
+        }
+
+        if devices != self.candidate {
+            self.candidate = devices;
+            self.num_polls = 0;
+        }
+
+        self.num_polls += 1;
+        if self.num_polls < DEBOUNCE_POLLS {
+            return None;
+        }
+
+        self.known = self.candidate.clone();
+        self.num_polls = 0;
+        Some(self.known.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn short_lived_changes_are_not_notified() {
+        let mut debouncer = Debouncer::new(names(&["a"]));
+
+        assert_eq!(debouncer.observe(names(&["a", "b"])), None);
+        assert_eq!(debouncer.observe(names(&["a"])), None);
+        assert_eq!(debouncer.observe(names(&["a", "b"])), None);
+        assert_eq!(
+            debouncer.observe(names(&["b", "a"])),
+            Some(names(&["a", "b"]))
+        );
+        assert_eq!(debouncer.observe(names(&["a", "b"])), None);
+    }
+
+    #[test]
+    fn a_changed_device_set_is_notified_exactly_once() {
+        let mut polls = vec![names(&["a"]), names(&["a"]), names(&["a", "b"])].into_iter();
+        let mut last = vec![];
+        let watcher = DeviceWatcher::spawn(Duration::from_millis(1), move || {
+            if let Some(devices) = polls.next() {
+                last = devices;
+            }
+            Ok(last.clone())
+        });
+
+        std::thread::sleep(Duration::from_millis(100));
+        let changes: Vec<_> = watcher.changes.try_iter().collect();
+        assert_eq!(changes, [names(&["a", "b"])]);
+    }
+}
@@ -0,0 +1,174 @@
+use crossbeam::channel::{Sender, TrySendError};
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// Counts the events dropped because the channel they were sent
+/// on was full. Clones share the count, so that the thread sending
+/// the events and the one reporting them see the same number.
+#[derive(Debug, Clone, Default)]
+pub struct DropCounter(Arc<AtomicU64>);
+
+impl DropCounter {
+    pub fn record(&self) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// Send without blocking, counting the event as dropped if the channel is full.
+    pub fn try_send<T>(&self, sender: &Sender<T>, event: T) -> Result<(), TrySendError<T>> {
+        let result = sender.try_send(event);
+        if let Err(TrySendError::Full(_)) = result {
+            self.record();
+        }
+        result
+    }
+}
+
+/// Warns about newly dropped events at most once per interval,
+/// sending from a real-time thread is not the place to log.
+///
+/// Events dropped interval after interval are chronic, the hint
+/// on how to avoid them is then logged once.
+#[derive(Debug)]
+pub struct DropWarning {
+    what: &'static str,
+    reason: &'static str,
+    hint: Option<&'static str>,
+    interval: Duration,
+    last_count: u64,
+    last_warning: Option<Instant>,
+    /// Warnings in a row, each less than two intervals after the one before.
+    streak: u32,
+}
+
+impl DropWarning {
+    pub const DEFAULT_INTERVAL: Duration = Duration::from_secs(5);
+    /// Warnings in a row after which the drops are chronic.
+    const CHRONIC_STREAK: u32 = 3;
+
+    pub fn new(what: &'static str) -> Self {
+        Self {
+            what,
+            reason: "on a full queue",
+            hint: None,
+            interval: Self::DEFAULT_INTERVAL,
+            last_count: 0,
+            last_warning: None,
+            streak: 0,
+        }
+    }
+
+    /// Why the events are dropped, "on a full queue" by default.
+    pub fn with_reason(mut self, reason: &'static str) -> Self {
+        self.reason = reason;
+        self
+    }
+
+    /// How to avoid the drops, logged once they are chronic.
+    pub fn with_hint(mut self, hint: &'static str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    pub fn is_chronic(&self) -> bool {
+        self.streak >= Self::CHRONIC_STREAK
+    }
+
+    /// Warn if events were dropped since the last warning and it
+    /// was long enough ago, returns whether it warned.
+    pub fn check(&mut self, count: u64, now: Instant) -> bool {
+        if count <= self.last_count {
+            return false;
+        }
+
+        if matches!(self.last_warning, Some(last) if now.duration_since(last) < self.interval) {
+            return false;
+        }
+
+        log::warn!(
+            "dropped {} {} {}, {count} so far",
+            count - self.last_count,
+            self.what,
+            self.reason
+        );
+
+        self.streak = match self.last_warning {
+            Some(last) if now.duration_since(last) < self.interval * 2 => self.streak + 1,
+            _ => 1,
+        };
+        if let (true, Some(hint)) = (self.streak == Self::CHRONIC_STREAK, self.hint) {
+            log::warn!("{} keep being dropped, {hint}", self.what);
+        }
+
+        self.last_count = count;
+        self.last_warning = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sending_on_a_full_channel_counts_a_drop() {
+        let (sender, receiver) = crossbeam::channel::bounded(1);
+        let drops = DropCounter::default();
+        let shared = drops.clone();
+
+        assert!(drops.try_send(&sender, 1).is_ok());
+        assert!(drops.try_send(&sender, 2).is_err());
+        assert!(drops.try_send(&sender, 3).is_err());
+        assert_eq!(shared.count(), 2);
+
+        assert_eq!(receiver.recv().unwrap(), 1);
+        assert!(drops.try_send(&sender, 4).is_ok());
+        assert_eq!(shared.count(), 2);
+
+        drop(receiver);
+        assert!(drops.try_send(&sender, 5).is_err());
+        assert_eq!(
+            shared.count(),
+            2,
+            "a disconnected channel is not a full one"
+        );
+    }
+
+    #[test]
+    fn drops_are_warned_about_at_most_once_per_interval() {
+        let mut warning = DropWarning::new("messages");
+        let start = Instant::now();
+
+        assert!(!warning.check(0, start));
+        assert!(warning.check(3, start));
+        assert!(!warning.check(5, start + Duration::from_secs(1)));
+        assert!(warning.check(5, start + DropWarning::DEFAULT_INTERVAL));
+        assert!(!warning.check(5, start + DropWarning::DEFAULT_INTERVAL * 3));
+    }
+
+    #[test]
+    fn drops_in_interval_after_interval_are_chronic() {
+        let mut warning = DropWarning::new("audio buffers").with_hint("enlarge the queue");
+        let interval = DropWarning::DEFAULT_INTERVAL;
+        let start = Instant::now();
+
+        for i in 1..DropWarning::CHRONIC_STREAK {
+            assert!(warning.check(i as u64, start + interval * i));
+            assert!(!warning.is_chronic());
+        }
+        assert!(warning.check(10, start + interval * DropWarning::CHRONIC_STREAK));
+        assert!(warning.is_chronic());
+
+        // a quiet spell ends the streak
+        assert!(warning.check(11, start + interval * 10));
+        assert!(!warning.is_chronic());
+    }
+}
@@ -35,7 +35,11 @@ pub fn deinterleave(buffer: &[f32], num_channels: usize) -> Vec<Vec<f32>> {
 /// Interleaves multiple channel buffers into a single buffer.
 ///
 /// The input is a slice of buffers, each containing the audio samples for a single channel.
-/// This function reorganizes those samples into an interleaved buffer where channels' samples are alternated.
+/// This function reorganizes those samples into an interleaved buffer where channels' samples
+/// are alternated, frame after frame, with the channels in the order of the input.
+///
+/// Channels shorter than the longest one are padded with silence, which is logged
+/// since the channels of a buffer are expected to be of the same length.
 ///
 /// # Parameters
 /// - `buffer`: The input slice containing references to the channel buffers.
@@ -49,6 +53,9 @@ pub fn deinterleave(buffer: &[f32], num_channels: usize) -> Vec<Vec<f32>> {
 ///
 /// let interleaved_buffer = interleave(&[&[1.0, 3.0], &[2.0, 4.0]]);
 /// assert_eq!(interleaved_buffer, &[1.0, 2.0, 3.0, 4.0]);
+///
+/// let padded_buffer = interleave(&[&[1.0, 3.0][..], &[2.0][..]]);
+/// assert_eq!(padded_buffer, &[1.0, 2.0, 3.0, 0.0]);
 /// ```
 #[inline]
 pub fn interleave(buffer: &[impl AsRef<[f32]>]) -> Vec<f32> {
@@ -58,14 +65,454 @@ pub fn interleave(buffer: &[impl AsRef<[f32]>]) -> Vec<f32> {
         return vec![];
     }
 
-    let num_samples = buffer[0].as_ref().len();
+    let num_samples = buffer
+        .iter()
+        .map(|channel| channel.as_ref().len())
+        .max()
+        .unwrap_or(0);
+
+    if buffer
+        .iter()
+        .any(|channel| channel.as_ref().len() != num_samples)
+    {
+        let lengths: Vec<_> = buffer
+            .iter()
+            .map(|channel| channel.as_ref().len())
+            .collect();
+        log::warn!("interleaving channels of different lengths {lengths:?}, padding with silence");
+    }
+
     let mut out = vec![0.; num_channels * num_samples];
 
-    for channel in 0..num_channels {
-        for sample in 0..num_samples {
-            out[sample * num_channels + channel] = buffer[channel].as_ref()[sample];
+    for (channel, samples) in buffer.iter().enumerate() {
+        for (sample, &value) in samples.as_ref().iter().enumerate() {
+            out[sample * num_channels + channel] = value;
         }
     }
 
     out
 }
+
+/// Computes the root mean square level of a buffer.
+///
+/// # Parameters
+/// - `buffer`: The input buffer containing the audio samples of a single channel.
+///
+/// # Returns
+/// The RMS level of the buffer, or `0.0` if it is empty.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::rms;
+///
+/// assert_eq!(rms(&[1.0, -1.0, 1.0, -1.0]), 1.0);
+/// assert_eq!(rms(&[]), 0.0);
+/// ```
+#[inline]
+pub fn rms(buffer: &[f32]) -> f32 {
+    if buffer.is_empty() {
+        return 0.;
+    }
+
+    let sum_of_squares: f32 = buffer.iter().map(|sample| sample * sample).sum();
+    (sum_of_squares / buffer.len() as f32).sqrt()
+}
+
+/// Computes the normalized correlation of two channels, at zero lag.
+///
+/// # Parameters
+/// - `left`: The samples of the first channel.
+/// - `right`: The samples of the second channel, only the
+///   frames both channels have are compared.
+///
+/// # Returns
+/// The correlation from `-1.0`, the channels being opposite and cancelling
+/// each other out when summed to mono, to `1.0`, the channels being the same
+/// up to their level. It is `0.0` if either channel is silent.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::correlation;
+///
+/// assert_eq!(correlation(&[0.5, -0.5], &[1.0, -1.0]), 1.0);
+/// assert_eq!(correlation(&[0.5, -0.5], &[-0.5, 0.5]), -1.0);
+/// assert_eq!(correlation(&[0.5, -0.5], &[0.0, 0.0]), 0.0);
+/// ```
+pub fn correlation(left: &[f32], right: &[f32]) -> f32 {
+    let (mut product, mut left_energy, mut right_energy) = (0_f64, 0_f64, 0_f64);
+    for (&l, &r) in left.iter().zip(right) {
+        product += l as f64 * r as f64;
+        left_energy += l as f64 * l as f64;
+        right_energy += r as f64 * r as f64;
+    }
+
+    let energy = (left_energy * right_energy).sqrt();
+    if energy <= f64::EPSILON {
+        return 0.;
+    }
+
+    (product / energy).clamp(-1., 1.) as f32
+}
+
+/// Direction a signal has to cross a trigger level in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TriggerEdge {
+    #[default]
+    Rising,
+    Falling,
+}
+
+/// Finds the first sample at which a buffer crosses a level in the given direction.
+///
+/// # Parameters
+/// - `buffer`: The input buffer containing the audio samples of a single channel.
+/// - `level`: The level the signal has to cross.
+/// - `edge`: Whether the signal has to go up or down through the level.
+///
+/// # Returns
+/// The index of the first sample past the level, or `None` if the level is never crossed.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::{find_trigger, TriggerEdge};
+///
+/// let buffer = [0.0, 0.5, 1.0, 0.5, 0.0];
+/// assert_eq!(find_trigger(&buffer, 0.25, TriggerEdge::Rising), Some(1));
+/// assert_eq!(find_trigger(&buffer, 0.25, TriggerEdge::Falling), Some(4));
+/// assert_eq!(find_trigger(&buffer, 2.0, TriggerEdge::Rising), None);
+/// ```
+#[inline]
+pub fn find_trigger(buffer: &[f32], level: f32, edge: TriggerEdge) -> Option<usize> {
+    buffer
+        .windows(2)
+        .position(|pair| match edge {
+            TriggerEdge::Rising => pair[0] < level && pair[1] >= level,
+            TriggerEdge::Falling => pair[0] > level && pair[1] <= level,
+        })
+        .map(|index| index + 1)
+}
+
+/// Computes the magnitude spectrum of a buffer with a discrete Fourier transform.
+///
+/// Buffers with a power of two length go through a fast Fourier transform.
+///
+/// # Parameters
+/// - `buffer`: The input buffer containing the audio samples of a single channel.
+///
+/// # Returns
+/// The magnitude of the first half of the frequency bins, normalized so that
+/// a full scale sinusoid centered on a bin has a magnitude of `1.0`.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::magnitude_spectrum;
+///
+/// let spectrum = magnitude_spectrum(&[1.0, 0.0, -1.0, 0.0]);
+/// assert_eq!(spectrum.len(), 2);
+/// assert!(spectrum[0].abs() < 1e-6);
+/// assert!((spectrum[1] - 1.0).abs() < 1e-6);
+/// ```
+pub fn magnitude_spectrum(buffer: &[f32]) -> Vec<f32> {
+    let len = buffer.len();
+    let num_bins = len / 2;
+
+    if len.is_power_of_two() {
+        let mut re = buffer.to_vec();
+        let mut im = vec![0.; len];
+        fft(&mut re, &mut im);
+
+        return (0..num_bins)
+            .map(|bin| (re[bin] * re[bin] + im[bin] * im[bin]).sqrt() / num_bins as f32)
+            .collect();
+    }
+
+    (0..num_bins)
+        .map(|bin| {
+            let (re, im) = buffer
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(re, im), (i, &sample)| {
+                    let phase = std::f32::consts::TAU * ((bin * i) % len) as f32 / len as f32;
+                    (re + sample * phase.cos(), im - sample * phase.sin())
+                });
+
+            (re * re + im * im).sqrt() / num_bins as f32
+        })
+        .collect()
+}
+
+/// In place iterative radix-2 transform, the length must be a power of two.
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let len = re.len();
+    if len < 2 {
+        return;
+    }
+
+    let bits = len.trailing_zeros();
+    for i in 0..len {
+        let j = i.reverse_bits() >> (usize::BITS - bits);
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut size = 2;
+    while size <= len {
+        let step = -std::f32::consts::TAU / size as f32;
+        for start in (0..len).step_by(size) {
+            for k in 0..size / 2 {
+                let (sin, cos) = (step * k as f32).sin_cos();
+                let (even, odd) = (start + k, start + k + size / 2);
+                let odd_re = re[odd] * cos - im[odd] * sin;
+                let odd_im = re[odd] * sin + im[odd] * cos;
+                re[odd] = re[even] - odd_re;
+                im[odd] = im[even] - odd_im;
+                re[even] += odd_re;
+                im[even] += odd_im;
+            }
+        }
+        size *= 2;
+    }
+}
+
+/// Frequency at the center of a bin of a transform.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::bin_frequency;
+///
+/// assert_eq!(bin_frequency(1, 512, 48_000), 93.75);
+/// assert_eq!(bin_frequency(1, 1024, 48_000), 46.875);
+/// ```
+#[inline]
+pub fn bin_frequency(bin: usize, fft_size: usize, sample_rate: usize) -> f32 {
+    bin as f32 * sample_rate as f32 / fft_size.max(1) as f32
+}
+
+/// Sums multiple channel buffers into a single channel.
+///
+/// # Parameters
+/// - `buffer`: The input slice containing references to the channel buffers.
+/// - `average`: Divide the sum by the number of channels to avoid clipping.
+///
+/// # Returns
+/// A `Vec<f32>` as long as the shortest channel, containing the summed samples.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::sum_channels;
+///
+/// assert_eq!(sum_channels(&[&[1.0, 0.5, 0.25][..], &[1.0, 0.5]], false), &[2.0, 1.0]);
+/// assert_eq!(sum_channels(&[&[1.0, 0.5][..], &[0.0, 0.5]], true), &[0.5, 0.5]);
+/// ```
+#[inline]
+pub fn sum_channels(buffer: &[impl AsRef<[f32]>], average: bool) -> Vec<f32> {
+    let num_samples = buffer
+        .iter()
+        .map(|channel| channel.as_ref().len())
+        .min()
+        .unwrap_or(0);
+
+    let scale = match average {
+        true => 1. / buffer.len().max(1) as f32,
+        false => 1.,
+    };
+
+    (0..num_samples)
+        .map(|sample| {
+            let sum: f32 = buffer.iter().map(|channel| channel.as_ref()[sample]).sum();
+            sum * scale
+        })
+        .collect()
+}
+
+/// Pole of the DC blocker, the closer to 1 the lower its cutoff.
+const DC_BLOCKER_POLE: f32 = 0.995;
+
+/// Remove the DC offset of each channel of an interleaved buffer with a
+/// first order high-pass, `y[n] = x[n] - x[n-1] + R * y[n-1]`.
+///
+/// The filter starts as if the first frame had been there forever,
+/// so a constant offset does not cause a transient at the start.
+pub fn block_dc(buffer: &[f32], num_channels: usize) -> Vec<f32> {
+    let num_channels = num_channels.max(1);
+    let mut previous_inputs: Vec<f32> = buffer.iter().take(num_channels).copied().collect();
+    let mut previous_outputs = vec![0.; num_channels];
+
+    buffer
+        .chunks_exact(num_channels)
+        .flat_map(|frame| frame.iter().enumerate())
+        .map(|(chan, &input)| {
+            let output = input - previous_inputs[chan] + DC_BLOCKER_POLE * previous_outputs[chan];
+            previous_inputs[chan] = input;
+            previous_outputs[chan] = output;
+            output
+        })
+        .collect()
+}
+
+/// Replace the NaN and infinite samples of a buffer with silence,
+/// returning how many were replaced.
+pub fn zero_non_finite(buffer: &mut [f32]) -> usize {
+    buffer
+        .iter_mut()
+        .filter(|sample| !sample.is_finite())
+        .map(|sample| *sample = 0.)
+        .count()
+}
+
+/// Converts interleaved audio from one sample rate to another with linear
+/// interpolation. It keeps the last frame of each buffer it processes, so
+/// a stream can be converted one buffer at a time without discontinuities.
+///
+/// # Examples
+/// ```rust
+/// use audlib::dsp::LinearResampler;
+///
+/// let mut resampler = LinearResampler::new(24_000, 48_000, 1);
+/// assert_eq!(resampler.process(&[0.0, 1.0]), &[0.0, 0.5]);
+/// assert_eq!(resampler.process(&[0.0]), &[1.0, 0.5]);
+/// ```
+pub struct LinearResampler {
+    /// Source frames advanced for every output frame.
+    step: f64,
+    /// Position of the next output frame, from the previous frame.
+    position: f64,
+    previous: Option<Vec<f32>>,
+    num_channels: usize,
+}
+
+impl LinearResampler {
+    pub fn new(source_rate: u32, target_rate: u32, num_channels: usize) -> Self {
+        Self {
+            step: source_rate as f64 / target_rate.max(1) as f64,
+            position: 0.,
+            previous: None,
+            num_channels: num_channels.max(1),
+        }
+    }
+
+    pub fn num_channels(&self) -> usize {
+        self.num_channels
+    }
+
+    /// Convert the next buffer of the stream.
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut frames = input.chunks_exact(self.num_channels);
+        let Some(previous) = self
+            .previous
+            .take()
+            .or_else(|| frames.next().map(<[f32]>::to_vec))
+        else {
+            return vec![];
+        };
+
+        let frames: Vec<&[f32]> = std::iter::once(previous.as_slice()).chain(frames).collect();
+        let last = frames.len() - 1;
+
+        let mut output = vec![];
+        while self.position < last as f64 {
+            let index = self.position as usize;
+            let fraction = (self.position - index as f64) as f32;
+            let (from, to) = (frames[index], frames[index + 1]);
+            output.extend(
+                from.iter()
+                    .zip(to)
+                    .map(|(from, to)| from + (to - from) * fraction),
+            );
+            self.position += self.step;
+        }
+
+        self.position -= last as f64;
+        self.previous = Some(frames[last].to_vec());
+        output
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blocking_dc_recenters_each_channel() {
+        let offsets = [0.5, -0.25];
+        let buffer: Vec<f32> = (0..4_096)
+            .flat_map(|n| {
+                let sine = (std::f32::consts::TAU * n as f32 / 64.).sin() * 0.5;
+                offsets.map(|offset| offset + sine)
+            })
+            .collect();
+
+        let filtered = block_dc(&buffer, offsets.len());
+        assert_eq!(filtered.len(), buffer.len());
+
+        for channel in deinterleave(&filtered, offsets.len()) {
+            let tail = &channel[channel.len() / 2..];
+            let mean = tail.iter().sum::<f32>() / tail.len() as f32;
+            assert!(mean.abs() < 0.01, "mean of {mean}");
+
+            let peak = tail
+                .iter()
+                .fold(0_f32, |peak, sample| peak.max(sample.abs()));
+            assert!((peak - 0.5).abs() < 0.05, "peak of {peak}");
+        }
+    }
+
+    #[test]
+    fn correlation_tells_in_phase_from_anti_phase_and_uncorrelated_channels() {
+        let sine: Vec<f32> = (0..4_800)
+            .map(|n| (std::f32::consts::TAU * n as f32 / 48.).sin())
+            .collect();
+        let cosine: Vec<f32> = (0..4_800)
+            .map(|n| (std::f32::consts::TAU * n as f32 / 48.).cos())
+            .collect();
+        let inverted: Vec<f32> = sine.iter().map(|sample| -0.5 * sample).collect();
+
+        assert!((correlation(&sine, &sine) - 1.).abs() < 1e-6);
+        assert!((correlation(&sine, &inverted) + 1.).abs() < 1e-6);
+        assert!(correlation(&sine, &cosine).abs() < 1e-3);
+        assert_eq!(correlation(&sine, &[0.; 4_800]), 0.);
+        assert_eq!(correlation(&[], &[]), 0.);
+    }
+
+    #[test]
+    fn the_fast_transform_matches_the_discrete_one() {
+        let buffer: Vec<f32> = (0..64)
+            .map(|n| (std::f32::consts::TAU * 5. * n as f32 / 64.).sin() + 0.25)
+            .collect();
+
+        let fast = magnitude_spectrum(&buffer);
+        // an odd length goes through the discrete transform
+        let discrete = magnitude_spectrum(&[buffer.as_slice(), &[0.25]].concat());
+        assert_eq!(fast.len(), 32);
+        assert!((fast[5] - 1.).abs() < 1e-4, "{}", fast[5]);
+        assert!((fast[0] - 0.5).abs() < 1e-4, "{}", fast[0]);
+        assert!(fast.iter().skip(6).all(|bin| bin.abs() < 1e-4));
+        assert!((discrete[5] - fast[5]).abs() < 0.1);
+    }
+
+    #[test]
+    fn resampling_a_sine_preserves_its_frequency() {
+        let (source_rate, target_rate, frequency) = (44_100, 48_000, 1_000.);
+        let sine: Vec<f32> = (0..source_rate)
+            .map(|n| (std::f32::consts::TAU * frequency * n as f32 / source_rate as f32).sin())
+            .collect();
+
+        let mut resampler = LinearResampler::new(source_rate, target_rate, 1);
+        let resampled: Vec<f32> = sine
+            .chunks(512)
+            .flat_map(|chunk| resampler.process(chunk))
+            .collect();
+
+        let num_frames = resampled.len() as f32;
+        assert!((num_frames - target_rate as f32).abs() <= 1.);
+
+        let num_cycles = resampled
+            .windows(2)
+            .filter(|pair| pair[0] < 0. && pair[1] >= 0.)
+            .count() as f32;
+        let measured = num_cycles * target_rate as f32 / num_frames;
+        assert!((measured - frequency).abs() <= 1., "{measured} Hz");
+    }
+}
@@ -1,6 +1,6 @@
 use crossbeam::channel::Receiver;
 use notify::Watcher;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 pub struct FsWatcher {
     _watcher: notify::RecommendedWatcher,
@@ -18,6 +18,36 @@ impl FsWatcher {
         })
     }
 
+    /// Watch a single file through its parent directory, so the
+    /// watch survives the file being deleted and created again.
+    pub fn run_for_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::run_for_files(&[path.as_ref().to_path_buf()])
+    }
+
+    /// Watch files through their parent directories, each watched once.
+    pub fn run_for_files(paths: &[PathBuf]) -> anyhow::Result<Self> {
+        let mut dirs = vec![];
+        for path in paths {
+            let Some(dir) = path.parent() else {
+                anyhow::bail!("No parent directory to watch : {}", path.display());
+            };
+
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+
+        let (tx, rx) = crossbeam::channel::bounded(100);
+        let mut watcher = notify::RecommendedWatcher::new(tx, notify::Config::default())?;
+        for dir in dirs {
+            watcher.watch(dir, notify::RecursiveMode::NonRecursive)?;
+        }
+        Ok(Self {
+            _watcher: watcher,
+            events: rx,
+        })
+    }
+
     pub fn events(&self) -> Receiver<notify::Result<notify::Event>> {
         self.events.clone()
     }
@@ -39,3 +69,95 @@ pub fn list_with_extension(dir: impl AsRef<Path>, ext: &str) -> anyhow::Result<V
 
     Ok(filenames)
 }
+
+/// List the files with an extension under a directory and all its
+/// subdirectories, as sorted paths relative to that directory.
+/// Links to directories are not followed and unreadable
+/// subdirectories are skipped.
+pub fn list_recursive_with_extension(
+    dir: impl AsRef<Path>,
+    ext: &str,
+) -> anyhow::Result<Vec<String>> {
+    let dir = dir.as_ref();
+    let mut pending = vec![dir.to_path_buf()];
+    let mut filenames = vec![];
+
+    while let Some(current) = pending.pop() {
+        let entries = match std::fs::read_dir(&current) {
+            Ok(entries) => entries,
+            Err(e) if current != dir => {
+                log::warn!("skipping unreadable directory {} : {e}", current.display());
+                continue;
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    log::warn!("skipping unreadable entry in {} : {e}", current.display());
+                    continue;
+                }
+            };
+
+            let path = entry.path();
+            if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                pending.push(path);
+            } else if path.is_dir() {
+                log::debug!("not following link to directory {}", path.display());
+            } else if path.extension().and_then(|s| s.to_str()) == Some(ext) {
+                let relative = path.strip_prefix(dir)?;
+                let components: Vec<_> = relative
+                    .components()
+                    .map(|c| c.as_os_str().to_string_lossy())
+                    .collect();
+                filenames.push(components.join("/"));
+            }
+        }
+    }
+
+    filenames.sort();
+    Ok(filenames)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn lists_files_in_subdirectories_relative_to_the_root() {
+        let dir = std::env::temp_dir().join(format!("aud-list-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("drums/kicks")).unwrap();
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        for file in ["b.lua", "a.txt", "drums/snare.lua", "drums/kicks/808.lua"] {
+            std::fs::write(dir.join(file), "").unwrap();
+        }
+
+        let files = list_recursive_with_extension(&dir, "lua");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            files.unwrap(),
+            ["b.lua", "drums/kicks/808.lua", "drums/snare.lua"]
+        );
+        assert!(list_recursive_with_extension(&dir, "lua").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn links_to_directories_are_not_followed() {
+        let dir = std::env::temp_dir().join(format!("aud-list-links-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("drums")).unwrap();
+        std::fs::write(dir.join("drums/snare.lua"), "").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("drums/loop")).unwrap();
+        std::os::unix::fs::symlink(dir.join("drums/snare.lua"), dir.join("alias.lua")).unwrap();
+
+        let files = list_recursive_with_extension(&dir, "lua");
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files.unwrap(), ["alias.lua", "drums/snare.lua"]);
+    }
+}
@@ -1,6 +1,8 @@
 pub mod audio;
 pub mod comms;
 pub mod controllers;
+pub mod devices;
+pub mod drops;
 pub mod dsp;
 pub mod files;
 pub mod lua;
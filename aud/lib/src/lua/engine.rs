@@ -1,18 +1,62 @@
 use super::{
     handle::{start_engine, LuaEngineEvent, LuaEngineHandle, LuaRuntimeControlling},
     traits::{api::*, hooks::*},
-    LuaRuntime,
+    LuaRuntime, ScriptTimeout, DEFAULT_CALLBACK_TIMEOUT,
 };
-use crate::{audio::AudioBuffer, files, midi::MidiData};
-use crossbeam::channel::{Receiver, Sender};
-use std::path::{Path, PathBuf};
+use crate::{
+    audio::AudioBuffer,
+    files,
+    midi::{MidiData, NoteOffForm},
+};
+use anyhow::Context;
+use crossbeam::channel::{Receiver, RecvTimeoutError, Sender};
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+/// Log target of the lines logged by scripts, followed by the script name.
+const SCRIPT_LOG_TARGET: &str = "script::";
+
+/// Log target identifying a script as the origin of a log line.
+pub fn script_log_target(script: &str) -> String {
+    format!("{SCRIPT_LOG_TARGET}{script}")
+}
+
+/// Name of the script a log line comes from, given its log target.
+pub fn script_from_log_target(target: &str) -> Option<&str> {
+    target.strip_prefix(SCRIPT_LOG_TARGET)
+}
+
+/// Script to load into the engine.
+pub struct ScriptSource {
+    pub name: String,
+    pub chunk: String,
+    /// Directories the script can `require` modules from.
+    pub module_paths: Vec<PathBuf>,
+}
 
 pub enum HostEvent {
-    LoadScript { name: String, chunk: String },
+    /// Load a chain of scripts, the first one receiving the MIDI
+    /// messages of the host and each next one the messages that the
+    /// one before forwards. Usually a single script.
+    LoadScript {
+        stages: Vec<ScriptSource>,
+        reply: Option<Sender<ScriptLoadResult>>,
+    },
     Discover(Vec<String>),
     Connect(String),
+    Disconnect,
     Midi(MidiData),
     Audio(AudioBuffer),
+    SetCallbackTimeout(Duration),
+    /// Form of the Note Offs scripts receive in `on_midi`.
+    SetForwardedNoteOff(NoteOffForm),
+    Tick(Clock),
+    SetParam {
+        name: String,
+        value: f64,
+    },
     Stop,
     Terminate,
 }
@@ -22,7 +66,27 @@ pub enum ScriptEvent {
     Log(LogApiEvent),
     Control(ControlFlowApiEvent),
     Connect(ConnectionApiEvent),
+    Scope(ScopeApiEvent),
+    Alias(AliasApiEvent),
+    Selection(SelectionApiEvent),
+    Param(ParamApiEvent),
+    SysEx(SysExApiEvent),
+    Panic(PanicApiEvent),
+    Ui(UiApiEvent),
+    Filter(FilterApiEvent),
+    /// The script was loaded, along with the files of the modules it required.
+    Loaded {
+        modules: Vec<PathBuf>,
+    },
+    Error(String),
+}
+
+/// Outcome of loading a script, sent back to
+/// whoever asked to be notified of the load.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScriptLoadResult {
     Loaded,
+    Error(String),
 }
 
 impl From<LogApiEvent> for ScriptEvent {
@@ -43,12 +107,70 @@ impl From<ConnectionApiEvent> for ScriptEvent {
     }
 }
 
+impl From<ScopeApiEvent> for ScriptEvent {
+    fn from(event: ScopeApiEvent) -> Self {
+        Self::Scope(event)
+    }
+}
+
+impl From<AliasApiEvent> for ScriptEvent {
+    fn from(event: AliasApiEvent) -> Self {
+        Self::Alias(event)
+    }
+}
+
+impl From<SelectionApiEvent> for ScriptEvent {
+    fn from(event: SelectionApiEvent) -> Self {
+        Self::Selection(event)
+    }
+}
+
+impl From<ParamApiEvent> for ScriptEvent {
+    fn from(event: ParamApiEvent) -> Self {
+        Self::Param(event)
+    }
+}
+
+impl From<SysExApiEvent> for ScriptEvent {
+    fn from(event: SysExApiEvent) -> Self {
+        Self::SysEx(event)
+    }
+}
+
+impl From<PanicApiEvent> for ScriptEvent {
+    fn from(event: PanicApiEvent) -> Self {
+        Self::Panic(event)
+    }
+}
+
+impl From<UiApiEvent> for ScriptEvent {
+    fn from(event: UiApiEvent) -> Self {
+        Self::Ui(event)
+    }
+}
+
+impl From<FilterApiEvent> for ScriptEvent {
+    fn from(event: FilterApiEvent) -> Self {
+        Self::Filter(event)
+    }
+}
+
+/// Script loaded after the first one of a chain, in a runtime of its own.
+struct ChainedScript {
+    name: String,
+    lua: LuaRuntime,
+}
+
 #[derive(Clone)]
 pub struct ScriptLoader {
     tx: Sender<ScriptEvent>,
     rx: Receiver<HostEvent>,
     device_name: Option<String>,
     chunk_to_preload: &'static str,
+    callback_timeout: Duration,
+    forwarded_note_off: NoteOffForm,
+    /// Name of the first script, to tell which script of a chain failed.
+    script_name: Option<String>,
 }
 
 impl ScriptLoader {
@@ -62,17 +184,37 @@ impl ScriptLoader {
             rx,
             device_name: None,
             chunk_to_preload,
+            callback_timeout: DEFAULT_CALLBACK_TIMEOUT,
+            forwarded_note_off: NoteOffForm::Raw,
+            script_name: None,
         }
     }
 
-    fn load_script(&mut self, lua: &mut LuaRuntime, name: &str, chunk: &str) -> anyhow::Result<()> {
+    fn load_script(&mut self, lua: &mut LuaRuntime, script: &ScriptSource) -> anyhow::Result<()> {
+        let ScriptSource {
+            ref name,
+            ref chunk,
+            ref module_paths,
+        } = *script;
         self.stop_script(lua)?;
+        lua.set_module_paths(module_paths)?;
         lua.load_log(name.to_owned(), self.tx.clone())?;
         lua.load_alert(name.to_owned(), self.tx.clone())?;
         lua.load_connect(name.to_owned(), self.tx.clone())?;
         lua.load_resume(name.to_owned(), self.tx.clone())?;
         lua.load_pause(name.to_owned(), self.tx.clone())?;
         lua.load_stop(name.to_owned(), self.tx.clone())?;
+        lua.load_audio()?;
+        lua.load_scope(name.to_owned(), self.tx.clone())?;
+        lua.load_alias(name.to_owned(), self.tx.clone())?;
+        lua.load_selected(name.to_owned(), self.tx.clone())?;
+        lua.load_params(name.to_owned(), self.tx.clone())?;
+        lua.load_send_after(name.to_owned())?;
+        lua.load_send_sysex(name.to_owned(), self.tx.clone())?;
+        lua.load_panic(name.to_owned(), self.tx.clone())?;
+        lua.load_ui(name.to_owned(), self.tx.clone())?;
+        lua.load_filter(name.to_owned(), self.tx.clone())?;
+        lua.load_time()?;
         lua.load_chunk(self.chunk_to_preload)?;
         lua.load_chunk(chunk)?;
         log::trace!("script loaded : {name}");
@@ -80,56 +222,291 @@ impl ScriptLoader {
     }
 
     fn stop_script(&mut self, lua: &mut LuaRuntime) -> anyhow::Result<()> {
+        // sends scheduled by the script do not outlive it
+        lua.set_app_data(MidiSchedule::default());
         lua.on_stop()?;
         let _ = lua.release_script();
         log::trace!("script released");
         Ok(())
     }
 
-    fn handle_midi(&mut self, lua: &LuaRuntime, midi: MidiData) -> anyhow::Result<()> {
-        let device_name = self.device_name.as_ref().map_or("", |s| s.as_str());
+    /// Stop the scripts of the chain, leaving only the first one.
+    fn stop_chain(&mut self, chain: &mut Vec<ChainedScript>) -> anyhow::Result<()> {
+        for mut script in chain.drain(..) {
+            self.stop_script(&mut script.lua)?;
+        }
+        Ok(())
+    }
+
+    /// Load the first script in the engine's runtime,
+    /// and each script chained after it in a runtime of its own.
+    fn load_chain(
+        &mut self,
+        lua: &mut LuaRuntime,
+        chain: &mut Vec<ChainedScript>,
+        stages: &[ScriptSource],
+    ) -> anyhow::Result<()> {
+        self.stop_chain(chain)?;
+        let Some((first, chained)) = stages.split_first() else {
+            anyhow::bail!("No script to load");
+        };
+
+        self.script_name = Some(first.name.clone());
+        self.load_script(lua, first)?;
 
-        if lua
-            .on_midi(device_name, midi.bytes.as_slice())?
-            .unwrap_or(true)
-        {
-            self.tx.try_send(ScriptEvent::Midi(midi))?;
+        for (position, script) in chained.iter().enumerate() {
+            let mut stage = LuaRuntime::default();
+            stage.set_callback_timeout(self.callback_timeout);
+            if let Some(clock) = lua.app_data_mut::<Clock>().map(|clock| *clock) {
+                stage.set_app_data(clock);
+            }
+            self.load_script(&mut stage, script)
+                .with_context(|| stage_label(position + 2, &script.name))?;
+            chain.push(ChainedScript {
+                name: script.name.clone(),
+                lua: stage,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Call into every script of the chain in order,
+    /// telling which script failed when there are several.
+    fn for_each_script(
+        &self,
+        lua: &LuaRuntime,
+        chain: &[ChainedScript],
+        mut f: impl FnMut(&LuaRuntime) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        if chain.is_empty() {
+            return f(lua);
         }
 
+        let first_name = self.script_name.as_deref().unwrap_or_default();
+        let scripts = std::iter::once((first_name, lua)).chain(
+            chain
+                .iter()
+                .map(|script| (script.name.as_str(), &script.lua)),
+        );
+
+        for (position, (name, lua)) in scripts.enumerate() {
+            f(lua).with_context(|| stage_label(position + 1, name))?;
+        }
         Ok(())
     }
 
-    fn handle_audio(&mut self, lua: &LuaRuntime, audio: AudioBuffer) -> anyhow::Result<()> {
-        let device_name = self.device_name.as_ref().map_or("", |s| s.as_str());
+    /// Pass a message through the chain, each script
+    /// handling the messages the one before forwarded.
+    fn handle_midi(
+        &mut self,
+        lua: &LuaRuntime,
+        chain: &[ChainedScript],
+        midi: MidiData,
+    ) -> anyhow::Result<()> {
+        let device_name = self.device_name.as_deref().unwrap_or_default();
+        let note_off = self.forwarded_note_off;
+
+        let mut messages = vec![midi.bytes];
+        self.for_each_script(lua, chain, |lua| {
+            if let Some(mut schedule) = lua.app_data_mut::<MidiSchedule>() {
+                schedule.set_timestamp(midi.timestamp);
+            }
+
+            let mut forwarded = vec![];
+            for bytes in std::mem::take(&mut messages) {
+                forwarded.extend(lua.on_midi(device_name, bytes.as_slice(), note_off)?);
+            }
+            messages = forwarded;
+            Ok(())
+        })?;
+
+        for bytes in messages {
+            self.tx.try_send(ScriptEvent::Midi(MidiData {
+                timestamp: midi.timestamp,
+                bytes,
+            }))?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_audio(
+        &mut self,
+        lua: &LuaRuntime,
+        chain: &[ChainedScript],
+        audio: AudioBuffer,
+    ) -> anyhow::Result<()> {
+        let device_name = self.device_name.as_deref().unwrap_or_default();
         let audio = audio.deinterleave();
-        lua.on_audio(device_name, &audio)?;
+        let scripts = std::iter::once(lua).chain(chain.iter().map(|script| &script.lua));
+        for lua in scripts {
+            if let Some(mut window) = lua.app_data_mut::<AudioWindow>() {
+                window.push(&audio);
+            }
+        }
+        self.for_each_script(lua, chain, |lua| lua.on_audio(device_name, &audio))
+    }
+
+    fn handle_event(
+        &mut self,
+        lua: &mut LuaRuntime,
+        chain: &mut Vec<ChainedScript>,
+        event: HostEvent,
+    ) -> anyhow::Result<()> {
+        match event {
+            HostEvent::Stop => {
+                self.stop_chain(chain)?;
+                self.stop_script(lua)?;
+            }
+            HostEvent::LoadScript { stages, reply } => {
+                let result = match self.load_chain(lua, chain, &stages) {
+                    Ok(()) => {
+                        let mut modules = lua.required_modules()?;
+                        for script in chain.iter() {
+                            modules.extend(script.lua.required_modules()?);
+                        }
+                        self.tx.send(ScriptEvent::Loaded { modules })?;
+                        ScriptLoadResult::Loaded
+                    }
+                    Err(e) => {
+                        let name = stages.first().map_or("", |script| script.name.as_str());
+                        // along with the script of a chain that failed
+                        let e = match stages.len() {
+                            1 => e.to_string(),
+                            _ => format!("{e:#}"),
+                        };
+                        log::error!("failed to load script {name} : {e}");
+                        self.tx.send(ScriptEvent::Error(e.clone()))?;
+                        ScriptLoadResult::Error(e)
+                    }
+                };
+
+                if let Some(Err(e)) = reply.map(|reply| reply.try_send(result)) {
+                    log::error!("failed to reply with script load result : {e}");
+                }
+            }
+            HostEvent::Discover(device_names) => {
+                self.for_each_script(lua, chain, |lua| lua.on_discover(&device_names))?
+            }
+            HostEvent::Connect(device_name) => {
+                self.for_each_script(lua, chain, |lua| lua.on_connect(device_name.as_str()))?;
+                self.device_name = Some(device_name);
+            }
+            HostEvent::Disconnect => {
+                if let Some(device_name) = self.device_name.take() {
+                    self.for_each_script(lua, chain, |lua| lua.on_disconnect(&device_name))?;
+                }
+            }
+            HostEvent::Midi(midi) => self.handle_midi(lua, chain, midi)?,
+            HostEvent::Audio(audio) => self.handle_audio(lua, chain, audio)?,
+            HostEvent::SetParam { name, value } => {
+                self.for_each_script(lua, chain, |lua| lua.on_param(&name, value))?
+            }
+            HostEvent::Tick(clock) => {
+                lua.set_app_data(clock);
+                for script in chain.iter() {
+                    script.lua.set_app_data(clock);
+                }
+            }
+            HostEvent::SetCallbackTimeout(timeout) => {
+                self.callback_timeout = timeout;
+                lua.set_callback_timeout(timeout);
+                for script in chain.iter_mut() {
+                    script.lua.set_callback_timeout(timeout);
+                }
+            }
+            HostEvent::SetForwardedNoteOff(form) => self.forwarded_note_off = form,
+            HostEvent::Terminate => unreachable!("termination is handled by the run loop"),
+        }
         Ok(())
     }
+
+    /// Forward the messages scheduled by the scripts that are due. Messages
+    /// scheduled by a script of a chain do not go through the next ones.
+    fn send_due_midi(&mut self, lua: &LuaRuntime, chain: &[ChainedScript]) -> anyhow::Result<()> {
+        let scripts = std::iter::once(lua).chain(chain.iter().map(|script| &script.lua));
+        for lua in scripts {
+            let due = match lua.app_data_mut::<MidiSchedule>() {
+                Some(mut schedule) => schedule.take_due(Instant::now()),
+                None => continue,
+            };
+
+            for midi in due {
+                self.tx.try_send(ScriptEvent::Midi(midi))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Wait for the next host event, for no longer than
+    /// until the next scheduled message is due.
+    fn next_event(
+        &self,
+        lua: &LuaRuntime,
+        chain: &[ChainedScript],
+    ) -> Result<HostEvent, RecvTimeoutError> {
+        let next_due = std::iter::once(lua)
+            .chain(chain.iter().map(|script| &script.lua))
+            .filter_map(|lua| lua.app_data_mut::<MidiSchedule>()?.next_due())
+            .min();
+
+        match next_due {
+            Some(due) => self
+                .rx
+                .recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => self.rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        }
+    }
+
+    /// A script that timed out is reported as an alert, the engine keeps running.
+    fn handle_timeout(&mut self, error: anyhow::Error) -> anyhow::Result<()> {
+        if error.downcast_ref::<ScriptTimeout>().is_none() {
+            return Err(error);
+        }
+
+        // along with the script of a chain that timed out
+        let timeout = format!("{error:#}");
+        log::error!("{timeout}");
+        if let Err(e) = self.tx.try_send(LogApiEvent::Alert(timeout).into()) {
+            log::error!("failed to send timeout alert : {e}");
+        }
+        Ok(())
+    }
+}
+
+/// Position and name of a script of a chain, counted from 1.
+fn stage_label(position: usize, name: &str) -> String {
+    let name = Path::new(name)
+        .file_name()
+        .map_or(name.into(), |name| name.to_string_lossy());
+    format!("script {position} of the chain, {name}")
 }
 
 impl LuaRuntimeControlling for ScriptLoader {
     fn run(&mut self, lua: &mut LuaRuntime) -> anyhow::Result<()> {
+        lua.set_callback_timeout(self.callback_timeout);
+        lua.set_app_data(Clock::default());
+        // runtimes cannot leave their thread, so the chain lives here
+        let mut chain = vec![];
+
         loop {
-            while let Ok(event) = self.rx.recv() {
-                match event {
-                    HostEvent::Stop => self.stop_script(lua)?,
-                    HostEvent::LoadScript { name, chunk } => {
-                        self.load_script(lua, &name, &chunk)?;
-                        self.tx.send(ScriptEvent::Loaded)?
-                    }
-                    HostEvent::Discover(device_names) => lua.on_discover(&device_names)?,
-                    HostEvent::Connect(device_name) => {
-                        lua.on_connect(device_name.as_str())?;
-                        self.device_name = Some(device_name);
-                    }
-                    HostEvent::Midi(midi) => self.handle_midi(lua, midi)?,
-                    HostEvent::Audio(audio) => self.handle_audio(lua, audio)?,
-                    HostEvent::Terminate => {
-                        self.stop_script(lua).unwrap();
-                        return Ok(());
+            match self.next_event(lua, &chain) {
+                Ok(HostEvent::Terminate) => {
+                    self.stop_chain(&mut chain).unwrap();
+                    self.stop_script(lua).unwrap();
+                    return Ok(());
+                }
+                Ok(event) => {
+                    if let Err(e) = self.handle_event(lua, &mut chain, event) {
+                        self.handle_timeout(e)?;
                     }
                 }
+                Err(_) => {}
             }
+
+            self.send_due_midi(lua, &chain)?;
         }
     }
 }
@@ -139,7 +516,14 @@ pub struct ScriptController {
     script_rx: Receiver<ScriptEvent>,
     lua_handle: LuaEngineHandle,
     script_path: Option<PathBuf>,
+    /// Scripts chained after the loaded one, fed the messages it forwards.
+    chained_paths: Vec<PathBuf>,
+    /// Directories of shared modules, searched after the script's own directory.
+    module_paths: Vec<PathBuf>,
     file_watcher: Option<files::FsWatcher>,
+    /// The script and the modules it required, reloaded when any changes.
+    watched_files: Vec<PathBuf>,
+    chunk_to_preload: &'static str,
 }
 
 impl ScriptController {
@@ -153,10 +537,30 @@ impl ScriptController {
             script_rx,
             lua_handle: start_engine(loader),
             script_path: None,
+            chained_paths: vec![],
+            module_paths: vec![],
             file_watcher: None,
+            watched_files: vec![],
+            chunk_to_preload,
         }
     }
 
+    /// Replace the engine with a fresh one, with no script loaded.
+    /// The previous engine is terminated if it is still running.
+    pub fn restart_engine(&mut self) {
+        let script_path = self.script_path.take();
+        let chained_paths = std::mem::take(&mut self.chained_paths);
+        let module_paths = std::mem::take(&mut self.module_paths);
+        *self = Self::start(self.chunk_to_preload);
+        self.script_path = script_path;
+        self.chained_paths = chained_paths;
+        self.module_paths = module_paths;
+    }
+
+    pub fn is_engine_running(&self) -> bool {
+        self.lua_handle.is_running()
+    }
+
     pub fn try_send(&self, host_event: HostEvent) -> anyhow::Result<()> {
         Ok(self.host_tx.try_send(host_event)?)
     }
@@ -173,34 +577,86 @@ impl ScriptController {
         self.path()?.file_name()?.to_str()
     }
 
+    /// Scripts chained after the loaded one, in order.
+    pub fn chained_paths(&self) -> &[PathBuf] {
+        &self.chained_paths
+    }
+
     pub fn try_recv_engine_events(&self) -> anyhow::Result<LuaEngineEvent> {
         Ok(self.lua_handle.events().try_recv()?)
     }
 
+    /// Let scripts `require` the modules of these directories, once loaded
+    /// again. Their own directory is always searched first.
+    pub fn set_module_paths(&mut self, module_paths: Vec<PathBuf>) {
+        self.module_paths = module_paths;
+    }
+
     pub fn load(&mut self, script: impl AsRef<Path>) -> anyhow::Result<()> {
-        let script_path = script.as_ref();
-        if !script_path.exists() || !script_path.is_file() {
-            anyhow::bail!("Invalid script path or type");
+        self.send_load(&[script], None)
+    }
+
+    /// Load scripts one after the other, each `on_midi` fed the messages
+    /// forwarded by the previous script. The other hooks are called on
+    /// every script in order.
+    pub fn load_chain(&mut self, scripts: &[impl AsRef<Path>]) -> anyhow::Result<()> {
+        self.send_load(scripts, None)
+    }
+
+    /// Load a script without blocking. The returned receiver resolves once the
+    /// engine has loaded the script, or disconnects if the engine crashed.
+    pub fn load_with_result(
+        &mut self,
+        script: impl AsRef<Path>,
+    ) -> anyhow::Result<Receiver<ScriptLoadResult>> {
+        let (tx, rx) = crossbeam::channel::bounded(1);
+        self.send_load(&[script], Some(tx))?;
+        Ok(rx)
+    }
+
+    fn send_load(
+        &mut self,
+        scripts: &[impl AsRef<Path>],
+        reply: Option<Sender<ScriptLoadResult>>,
+    ) -> anyhow::Result<()> {
+        let mut script_paths = vec![];
+        for script_path in scripts.iter().map(AsRef::as_ref) {
+            if !script_path.exists() || !script_path.is_file() {
+                anyhow::bail!("Invalid script path or type : {}", script_path.display());
+            }
+
+            // file watcher events carry absolute paths
+            script_paths.push(script_path.canonicalize()?);
         }
 
-        self.script_path = Some(script_path.into());
+        let stages = script_paths
+            .iter()
+            .map(|script_path| {
+                Ok(ScriptSource {
+                    name: script_path.to_string_lossy().into_owned(),
+                    chunk: std::fs::read_to_string(script_path)?,
+                    module_paths: script_path
+                        .parent()
+                        .map(Path::to_path_buf)
+                        .into_iter()
+                        .chain(self.module_paths.iter().cloned())
+                        .collect(),
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+
+        let Some((script_path, chained_paths)) = script_paths.split_first() else {
+            anyhow::bail!("No script to load");
+        };
+        self.script_path = Some(script_path.clone());
+        self.chained_paths = chained_paths.to_vec();
 
         if let Err(e) = self.host_tx.try_send(HostEvent::Stop) {
             log::error!("failed to send stop event : {e}");
         }
 
-        let event = HostEvent::LoadScript {
-            name: self
-                .script_path
-                .as_ref()
-                .unwrap()
-                .to_str()
-                .unwrap()
-                .to_owned(),
-            chunk: std::fs::read_to_string(script_path)?,
-        };
-
-        self.file_watcher = files::FsWatcher::run(script_path).ok();
+        let event = HostEvent::LoadScript { stages, reply };
+        self.watch(script_paths);
 
         if let Err(e) = self.host_tx.try_send(event) {
             log::error!("failed to send load script event : {e}");
@@ -209,28 +665,56 @@ impl ScriptController {
         Ok(())
     }
 
+    /// Also reload the script when one of the modules it required changes.
+    pub fn watch_modules(&mut self, modules: Vec<PathBuf>) {
+        let Some(script_path) = self.script_path.clone() else {
+            return;
+        };
+
+        // file watcher events carry absolute paths
+        let modules = modules
+            .into_iter()
+            .filter_map(|module| module.canonicalize().ok());
+        let scripts = std::iter::once(script_path).chain(self.chained_paths.iter().cloned());
+        self.watch(scripts.chain(modules).collect());
+    }
+
+    fn watch(&mut self, files: Vec<PathBuf>) {
+        self.file_watcher = files::FsWatcher::run_for_files(&files).ok();
+        self.watched_files = files;
+    }
+
+    /// Check if the script, or a module it required, was written to since
+    /// the last call. Editors that save by deleting and re-creating the
+    /// file cause a single reload, once the file exists again.
     pub fn was_script_modified(&self) -> anyhow::Result<bool> {
-        let Some(ref watcher) = self.file_watcher else {
+        let (Some(ref watcher), Some(ref path)) = (&self.file_watcher, &self.script_path) else {
             return Ok(false);
         };
 
-        for event in watcher.events().try_iter().collect::<Vec<_>>() {
-            if self.has_file_changed(event) {
-                if self.script_path.is_some() {
-                    log::trace!("Loaded script has changed on filesystem");
-                    return Ok(true);
-                }
+        // drain all the events so a single save only reloads once
+        let events = watcher.events().try_iter().collect::<Vec<_>>();
+        let has_changed = events
+            .into_iter()
+            .any(|event| self.has_file_changed(&self.watched_files, event));
 
-                break;
-            }
+        if has_changed && path.is_file() {
+            log::trace!("Loaded script has changed on filesystem");
+            return Ok(true);
         }
 
         Ok(false)
     }
 
-    fn has_file_changed(&self, event: notify::Result<notify::Event>) -> bool {
+    fn has_file_changed(&self, paths: &[PathBuf], event: notify::Result<notify::Event>) -> bool {
         match event {
-            Ok(event) => matches!(event.kind, notify::EventKind::Modify(_)),
+            Ok(event) => {
+                let is_write = matches!(
+                    event.kind,
+                    notify::EventKind::Create(_) | notify::EventKind::Modify(_)
+                );
+                is_write && event.paths.iter().any(|changed| paths.contains(changed))
+            }
             Err(e) => {
                 log::error!("Script reload failed : {e}");
                 false
@@ -18,6 +18,13 @@ impl LuaEngineHandle {
     pub fn take_handle(&mut self) -> Option<std::thread::JoinHandle<anyhow::Result<()>>> {
         self.handle.take()
     }
+
+    /// Whether the engine thread is still running.
+    pub fn is_running(&self) -> bool {
+        self.handle
+            .as_ref()
+            .is_some_and(|handle| !handle.is_finished())
+    }
 }
 
 pub enum LuaEngineEvent {
@@ -1,4 +1,32 @@
-use std::path::Path;
+use std::{
+    cell::Cell,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, Instant},
+};
+
+/// Default duration a script callback is allowed to run for.
+pub const DEFAULT_CALLBACK_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Number of Lua instructions between two watchdog checks.
+const WATCHDOG_INSTRUCTION_INTERVAL: u32 = 1_000;
+
+/// Error returned when a script callback was
+/// interrupted for running longer than allowed.
+#[derive(Debug)]
+pub struct ScriptTimeout(pub Duration);
+
+impl std::fmt::Display for ScriptTimeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "script interrupted after {}ms timeout",
+            self.0.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for ScriptTimeout {}
 
 /// `LuaRuntime` is the type that
 /// actually runs a script. It
@@ -7,13 +35,61 @@ use std::path::Path;
 pub struct LuaRuntime {
     ctx: mlua::Lua,
     script: Option<String>,
+    timeout: Duration,
+    deadline: Rc<Cell<Option<Instant>>>,
+    /// Set by the watchdog when it interrupts the script.
+    interrupted: Rc<Cell<bool>>,
+    /// Modules loaded with the standard library, which are never reloaded.
+    builtin_modules: Vec<String>,
+    default_module_path: String,
 }
 
 impl Default for LuaRuntime {
     fn default() -> Self {
+        let ctx = mlua::Lua::new();
+        let deadline = Rc::new(Cell::new(None::<Instant>));
+        let interrupted = Rc::new(Cell::new(false));
+
+        ctx.set_hook(
+            mlua::HookTriggers::new().every_nth_instruction(WATCHDOG_INSTRUCTION_INTERVAL),
+            {
+                let (deadline, interrupted) = (deadline.clone(), interrupted.clone());
+                move |_, _| match deadline.get() {
+                    Some(deadline) if Instant::now() > deadline => {
+                        interrupted.set(true);
+                        Err(mlua::Error::RuntimeError(
+                            "script callback timed out".to_owned(),
+                        ))
+                    }
+                    _ => Ok(()),
+                }
+            },
+        );
+
+        let package = ctx.globals().get::<_, mlua::Table>("package").ok();
+        let builtin_modules = package
+            .as_ref()
+            .and_then(|package| package.get::<_, mlua::Table>("loaded").ok())
+            .map(|loaded| {
+                loaded
+                    .pairs::<String, mlua::Value>()
+                    .flatten()
+                    .map(|(name, _)| name)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let default_module_path = package
+            .and_then(|package| package.get("path").ok())
+            .unwrap_or_default();
+
         Self {
-            ctx: mlua::Lua::new(),
+            ctx,
             script: None,
+            timeout: DEFAULT_CALLBACK_TIMEOUT,
+            deadline,
+            interrupted,
+            builtin_modules,
+            default_module_path,
         }
     }
 }
@@ -27,6 +103,17 @@ impl LuaRuntime {
         self.script.is_some()
     }
 
+    /// Check if the loaded script defines a function
+    /// with this name. Scripts may omit any hook.
+    pub fn has_hook(&self, func_name: &str) -> bool {
+        self.has_script()
+            && self
+                .ctx
+                .globals()
+                .get::<&str, mlua::Function>(func_name.trim())
+                .is_ok()
+    }
+
     pub fn load_file(&mut self, script: impl AsRef<Path>) -> anyhow::Result<()> {
         let script = script.as_ref();
 
@@ -42,21 +129,101 @@ impl LuaRuntime {
     }
 
     pub fn load_chunk(&mut self, chunk: &str) -> anyhow::Result<()> {
-        self.ctx.load(chunk).exec()?;
+        self.with_watchdog(|| self.ctx.load(chunk).exec())?;
         self.script = Some(chunk.into());
         Ok(())
     }
 
+    /// Let scripts `require` the modules found in these directories, searched
+    /// in order. Modules required until now are forgotten, so that the next
+    /// script reads them again.
+    pub fn set_module_paths(&self, dirs: &[PathBuf]) -> anyhow::Result<()> {
+        let package: mlua::Table = self.ctx.globals().get("package")?;
+        let path = dirs
+            .iter()
+            .flat_map(|dir| [dir.join("?.lua"), dir.join("?").join("init.lua")])
+            .map(|pattern| pattern.to_string_lossy().into_owned())
+            .chain(std::iter::once(self.default_module_path.clone()))
+            .collect::<Vec<_>>()
+            .join(";");
+        package.set("path", path)?;
+
+        let loaded: mlua::Table = package.get("loaded")?;
+        for name in self.required_module_names()? {
+            loaded.set(name, mlua::Value::Nil)?;
+        }
+        Ok(())
+    }
+
+    /// Files of the modules the script has required.
+    pub fn required_modules(&self) -> anyhow::Result<Vec<PathBuf>> {
+        let package: mlua::Table = self.ctx.globals().get("package")?;
+        let path: String = package.get("path")?;
+        let search: mlua::Function = package.get("searchpath")?;
+
+        let mut modules = vec![];
+        for name in self.required_module_names()? {
+            if let Some(file) = search.call::<_, Option<String>>((name, path.as_str()))? {
+                modules.push(PathBuf::from(file));
+            }
+        }
+        Ok(modules)
+    }
+
+    fn required_module_names(&self) -> anyhow::Result<Vec<String>> {
+        let package: mlua::Table = self.ctx.globals().get("package")?;
+        let loaded: mlua::Table = package.get("loaded")?;
+        Ok(loaded
+            .pairs::<String, mlua::Value>()
+            .flatten()
+            .map(|(name, _)| name)
+            .filter(|name| !self.builtin_modules.contains(name))
+            .collect())
+    }
+
+    /// Set how long a single call into the script may run
+    /// before it is interrupted with a [`ScriptTimeout`].
+    pub fn set_callback_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    fn with_watchdog<T>(&self, f: impl FnOnce() -> mlua::Result<T>) -> anyhow::Result<T> {
+        self.interrupted.set(false);
+        self.deadline.set(Some(Instant::now() + self.timeout));
+        let result = f();
+        self.deadline.set(None);
+
+        match result {
+            Err(_) if self.interrupted.take() => Err(ScriptTimeout(self.timeout).into()),
+            result => Ok(result?),
+        }
+    }
+
     pub fn call<'lua, A, R>(&'lua self, func_name: &str, args: A) -> anyhow::Result<R>
     where
         A: mlua::IntoLuaMulti<'lua>,
         R: mlua::FromLuaMulti<'lua>,
     {
-        Ok(self
+        let func = self
             .ctx
             .globals()
-            .get::<&str, mlua::Function<'lua>>(func_name.trim())?
-            .call(args)?)
+            .get::<&str, mlua::Function<'lua>>(func_name.trim())?;
+
+        self.with_watchdog(|| func.call(args))
+    }
+
+    pub fn create_table(&self) -> anyhow::Result<mlua::Table<'_>> {
+        Ok(self.ctx.create_table()?)
+    }
+
+    /// Keep state shared between the script's
+    /// functions and the hooks, one per type.
+    pub fn set_app_data<T: 'static>(&self, data: T) {
+        self.ctx.set_app_data(data);
+    }
+
+    pub fn app_data_mut<T: 'static>(&self) -> Option<mlua::AppDataRefMut<'_, T>> {
+        self.ctx.app_data_mut()
     }
 
     pub fn set_fn<'lua, A, R, F>(&'lua self, name: &str, func: F) -> anyhow::Result<()>
@@ -69,4 +236,55 @@ impl LuaRuntime {
         self.ctx.globals().set(name, func)?;
         Ok(())
     }
+
+    /// Set a function in a global table, creating
+    /// the table if it does not exist yet.
+    pub fn set_table_fn<'lua, A, R, F>(
+        &'lua self,
+        table_name: &str,
+        name: &str,
+        func: F,
+    ) -> anyhow::Result<()>
+    where
+        A: mlua::FromLuaMulti<'lua>,
+        R: mlua::IntoLuaMulti<'lua>,
+        F: Fn(&'lua mlua::Lua, A) -> mlua::Result<R> + 'static,
+    {
+        let globals = self.ctx.globals();
+        let table = match globals.get::<&str, Option<mlua::Table<'lua>>>(table_name)? {
+            Some(table) => table,
+            None => {
+                let table = self.ctx.create_table()?;
+                globals.set(table_name, table.clone())?;
+                table
+            }
+        };
+
+        table.set(name, self.ctx.create_function(func)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn only_interrupted_scripts_time_out() {
+        let mut lua = LuaRuntime::default();
+        lua.set_callback_timeout(Duration::from_millis(10));
+
+        let error = lua.load_chunk("while true do end").unwrap_err();
+        assert!(error.downcast_ref::<ScriptTimeout>().is_some(), "{error}");
+
+        // an error raised after the deadline without the watchdog stepping in
+        lua.set_fn("wait_and_fail", |_, (): ()| -> mlua::Result<()> {
+            std::thread::sleep(Duration::from_millis(20));
+            Err(mlua::Error::RuntimeError("failed".to_owned()))
+        })
+        .unwrap();
+        let error = lua.load_chunk("wait_and_fail()").unwrap_err();
+        assert!(error.downcast_ref::<ScriptTimeout>().is_none(), "{error}");
+        assert!(error.to_string().contains("failed"), "{error}");
+    }
 }
@@ -6,6 +6,7 @@ use super::LuaRuntime;
 
 pub mod hooks {
     use super::*;
+    use crate::midi::NoteOffForm;
 
     pub trait TraceHookProviding {
         fn on_start(&self) -> anyhow::Result<()>;
@@ -15,26 +16,39 @@ pub mod hooks {
     pub trait ConnectionHookProviding {
         fn on_discover(&self, device_names: &[String]) -> anyhow::Result<()>;
         fn on_connect(&self, device_name: &str) -> anyhow::Result<()>;
+        fn on_disconnect(&self, device_name: &str) -> anyhow::Result<()>;
     }
 
     pub trait MidiHookProviding {
-        fn on_midi(&self, device_name: &str, bytes: &[u8]) -> anyhow::Result<Option<bool>>;
+        /// Messages to forward in place of the received one, which the
+        /// script sees with its Note Off in the given form.
+        fn on_midi(
+            &self,
+            device_name: &str,
+            bytes: &[u8],
+            note_off: NoteOffForm,
+        ) -> anyhow::Result<Vec<Vec<u8>>>;
     }
 
     pub trait AudioHookProviding {
         fn on_audio(&self, device_name: &str, data: &[Vec<f32>]) -> anyhow::Result<()>;
     }
 
+    pub trait ParamHookProviding {
+        /// A parameter was adjusted from the app.
+        fn on_param(&self, name: &str, value: f64) -> anyhow::Result<()>;
+    }
+
     impl TraceHookProviding for LuaRuntime {
         fn on_start(&self) -> anyhow::Result<()> {
-            match self.has_script() {
+            match self.has_hook("on_start") {
                 true => self.call("on_start", ()),
                 false => Ok(()),
             }
         }
 
         fn on_stop(&self) -> anyhow::Result<()> {
-            match self.has_script() {
+            match self.has_hook("on_stop") {
                 true => self.call("on_stop", ()),
                 false => Ok(()),
             }
@@ -43,37 +57,128 @@ pub mod hooks {
 
     impl ConnectionHookProviding for LuaRuntime {
         fn on_discover(&self, device_names: &[String]) -> anyhow::Result<()> {
-            match self.has_script() {
+            match self.has_hook("on_discover") {
                 true => self.call("on_discover", device_names),
                 false => Ok(()),
             }
         }
 
         fn on_connect(&self, device_name: &str) -> anyhow::Result<()> {
-            match self.has_script() {
+            match self.has_hook("on_connect") {
                 true => self.call("on_connect", device_name),
                 false => Ok(()),
             }
         }
+
+        fn on_disconnect(&self, device_name: &str) -> anyhow::Result<()> {
+            match self.has_hook("on_disconnect") {
+                true => self.call("on_disconnect", device_name),
+                false => Ok(()),
+            }
+        }
     }
 
     impl MidiHookProviding for LuaRuntime {
-        fn on_midi(&self, device_name: &str, bytes: &[u8]) -> anyhow::Result<Option<bool>> {
-            match self.has_script() {
-                true => self.call("on_midi", (device_name, bytes)),
-                false => Ok(None),
+        fn on_midi(
+            &self,
+            device_name: &str,
+            bytes: &[u8],
+            note_off: NoteOffForm,
+        ) -> anyhow::Result<Vec<Vec<u8>>> {
+            if !self.has_hook("on_midi") {
+                return Ok(vec![bytes.to_vec()]);
             }
+
+            let message = midi_message(self, &note_off.normalize(bytes))?;
+            let returned: mlua::MultiValue = self.call("on_midi", (device_name, message))?;
+
+            // returning nothing lets the message through untouched
+            if returned.is_empty() {
+                return Ok(vec![bytes.to_vec()]);
+            }
+
+            returned
+                .into_iter()
+                .filter_map(|value| match value {
+                    mlua::Value::Boolean(true) => Some(Ok(bytes.to_vec())),
+                    mlua::Value::Boolean(false) | mlua::Value::Nil => None,
+                    mlua::Value::Table(message) => Some(midi_bytes(message)),
+                    value => Some(Err(anyhow::anyhow!(
+                        "on_midi returned a {}, expected a message, a boolean or nil",
+                        value.type_name()
+                    ))),
+                })
+                .collect()
         }
     }
 
+    /// Bytes of a message returned by a script, its array part
+    /// or, when it has none, its `bytes` field.
+    fn midi_bytes(message: mlua::Table) -> anyhow::Result<Vec<u8>> {
+        let bytes = message
+            .clone()
+            .sequence_values::<u8>()
+            .collect::<mlua::Result<Vec<_>>>()?;
+
+        if !bytes.is_empty() {
+            return Ok(bytes);
+        }
+
+        Ok(message
+            .get::<_, Option<Vec<u8>>>("bytes")?
+            .unwrap_or_default())
+    }
+
+    /// The bytes of a message along with its decoded fields. The bytes are
+    /// also the array part of the table, for scripts indexing it directly.
+    fn midi_message<'lua>(
+        lua: &'lua LuaRuntime,
+        bytes: &[u8],
+    ) -> anyhow::Result<mlua::Table<'lua>> {
+        let fields = crate::midi::MidiFields::decode(bytes);
+        let message = lua.create_table()?;
+        for (i, byte) in bytes.iter().enumerate() {
+            message.raw_set(i + 1, *byte)?;
+        }
+
+        message.set("bytes", bytes)?;
+        message.set("type", fields.kind)?;
+        message.set("channel", fields.channel)?;
+        message.set("note", fields.note)?;
+        message.set("velocity", fields.velocity)?;
+        message.set("controller", fields.controller)?;
+        message.set("value", fields.value)?;
+        Ok(message)
+    }
+
     impl AudioHookProviding for LuaRuntime {
         fn on_audio(&self, device_name: &str, data: &[Vec<f32>]) -> anyhow::Result<()> {
-            match self.has_script() {
+            match self.has_hook("on_audio") {
                 true => self.call("on_audio", (device_name, data)),
                 false => Ok(()),
             }
         }
     }
+
+    impl ParamHookProviding for LuaRuntime {
+        fn on_param(&self, name: &str, value: f64) -> anyhow::Result<()> {
+            let value = {
+                let Some(mut params) = self.app_data_mut::<api::ScriptParams>() else {
+                    return Ok(());
+                };
+
+                match params.set(name, value) {
+                    Some(value) => value,
+                    None => return Ok(()),
+                }
+            };
+
+            match self.has_hook("on_param") {
+                true => self.call("on_param", (name, value)),
+                false => Ok(()),
+            }
+        }
+    }
 }
 
 pub mod api {
@@ -105,10 +210,12 @@ pub mod api {
         fn load_connect(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
     }
 
+    /// Control of the application's flow. Scripts may
+    /// pass a reason, which is logged and shown as an alert.
     pub enum ControlFlowApiEvent {
-        Pause,
-        Resume,
-        Stop,
+        Pause(Option<String>),
+        Resume(Option<String>),
+        Stop(Option<String>),
     }
 
     pub trait ControlFlowProviding<E>
@@ -120,6 +227,311 @@ pub mod api {
         fn load_stop(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
     }
 
+    /// Maximum number of frames per channel returned to a script.
+    pub(crate) const MAX_AUDIO_WINDOW_FRAMES: usize = 4096;
+
+    /// How long a script waits for the host to answer a request, a small
+    /// part of the callback timeout while leaving the host a frame to reply.
+    pub const HOST_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(50);
+
+    /// Latest audio the engine sent to the script, kept by the engine so
+    /// that scripts read it without waiting for the host.
+    #[derive(Debug, Default, Clone, PartialEq)]
+    pub struct AudioWindow {
+        channels: Vec<Vec<f32>>,
+    }
+
+    impl AudioWindow {
+        /// Append deinterleaved audio, keeping the last [`MAX_AUDIO_WINDOW_FRAMES`]
+        /// frames. The window starts over when the number of channels changes.
+        pub fn push(&mut self, audio: &[Vec<f32>]) {
+            if self.channels.len() != audio.len() {
+                self.channels = vec![vec![]; audio.len()];
+            }
+
+            for (window, samples) in self.channels.iter_mut().zip(audio) {
+                window.extend_from_slice(samples);
+                let excess = window.len().saturating_sub(MAX_AUDIO_WINDOW_FRAMES);
+                window.drain(..excess);
+            }
+        }
+
+        /// Samples of a channel, counted from 1 as in Lua.
+        fn channel(&self, channel: usize) -> Option<&[f32]> {
+            let index = channel.checked_sub(1)?;
+            self.channels.get(index).map(Vec::as_slice)
+        }
+    }
+
+    pub trait AudioWindowProviding {
+        fn load_audio(&self) -> anyhow::Result<()>;
+    }
+
+    /// What the scope displays.
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+    pub enum ScopeMode {
+        #[default]
+        Waveform,
+        Spectrum,
+        /// One channel against another, to see how they correlate.
+        Vector,
+        /// Each channel on a single row of block glyphs, for an overview.
+        Sparkline,
+    }
+
+    impl std::str::FromStr for ScopeMode {
+        type Err = anyhow::Error;
+
+        fn from_str(mode: &str) -> Result<Self, Self::Err> {
+            match mode {
+                "waveform" => Ok(Self::Waveform),
+                "spectrum" => Ok(Self::Spectrum),
+                "vector" => Ok(Self::Vector),
+                "sparkline" => Ok(Self::Sparkline),
+                _ => anyhow::bail!("unknown scope mode : {mode}"),
+            }
+        }
+    }
+
+    /// Level and direction the waveform is aligned on.
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct ScopeTrigger {
+        pub level: f32,
+        pub edge: crate::dsp::TriggerEdge,
+    }
+
+    pub enum ScopeApiEvent {
+        SetMode(ScopeMode),
+        SetTrigger(ScopeTrigger),
+        Zoom(std::time::Duration),
+    }
+
+    pub trait ScopeProviding<E>
+    where
+        E: From<ScopeApiEvent>,
+    {
+        fn load_scope(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Request to display a device under a friendlier name.
+    pub struct AliasApiEvent {
+        pub device: String,
+        pub alias: String,
+    }
+
+    pub trait AliasProviding<E>
+    where
+        E: From<AliasApiEvent>,
+    {
+        fn load_alias(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Request to send a SysEx message, framed by F0 and F7,
+    /// to the output of the connected MIDI device.
+    pub struct SysExApiEvent {
+        pub message: Vec<u8>,
+    }
+
+    pub trait SysExProviding<E>
+    where
+        E: From<SysExApiEvent>,
+    {
+        fn load_send_sysex(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Request to silence the connected MIDI device.
+    pub struct PanicApiEvent;
+
+    pub trait PanicProviding<E>
+    where
+        E: From<PanicApiEvent>,
+    {
+        fn load_panic(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Request to reset what the app displays.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum UiApiEvent {
+        /// Drop the messages or traces displayed so far.
+        Clear,
+        /// Dismiss the alert and scroll back to the latest messages.
+        Reset,
+    }
+
+    pub trait UiProviding<E>
+    where
+        E: From<UiApiEvent>,
+    {
+        fn load_ui(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Request to narrow down the MIDI messages the monitor
+    /// keeps, `None` keeping them all again.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum FilterApiEvent {
+        Channels(Option<Vec<u8>>),
+        Types(Option<Vec<&'static str>>),
+        Notes(Option<(u8, u8)>),
+    }
+
+    pub trait FilterProviding<E>
+    where
+        E: From<FilterApiEvent>,
+    {
+        fn load_filter(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Kind of device a script asks about.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DeviceKind {
+        Midi,
+        Audio,
+    }
+
+    /// Request for the name of the selected device, the
+    /// host replies with `None` when none is selected.
+    pub struct SelectionApiEvent {
+        pub kind: DeviceKind,
+        pub reply: Sender<Option<String>>,
+    }
+
+    pub trait SelectionProviding<E>
+    where
+        E: From<SelectionApiEvent>,
+    {
+        fn load_selected(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// Value of a script that can be tuned from the app, within its range.
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct ScriptParam {
+        pub name: String,
+        pub value: f64,
+        pub min: f64,
+        pub max: f64,
+    }
+
+    /// Number of steps to go from the minimum to the maximum of a parameter.
+    const NUM_PARAM_STEPS: f64 = 100.;
+
+    impl ScriptParam {
+        /// Amount a single adjustment from the app moves the value by.
+        pub fn step(&self) -> f64 {
+            (self.max - self.min) / NUM_PARAM_STEPS
+        }
+
+        fn clamp(&self, value: f64) -> f64 {
+            value.clamp(self.min, self.max)
+        }
+    }
+
+    /// Parameters defined by the loaded script, along with their values.
+    #[derive(Debug, Default)]
+    pub struct ScriptParams(Vec<ScriptParam>);
+
+    impl ScriptParams {
+        pub fn as_slice(&self) -> &[ScriptParam] {
+            &self.0
+        }
+
+        /// Add a parameter, or replace the one with the same name.
+        pub fn define(&mut self, param: ScriptParam) {
+            match self.0.iter_mut().find(|p| p.name == param.name) {
+                Some(existing) => *existing = param,
+                None => self.0.push(param),
+            }
+        }
+
+        pub fn get(&self, name: &str) -> Option<f64> {
+            Some(self.0.iter().find(|p| p.name == name)?.value)
+        }
+
+        /// Set the value of a defined parameter, clamped to its range.
+        pub fn set(&mut self, name: &str, value: f64) -> Option<f64> {
+            let param = self.0.iter_mut().find(|p| p.name == name)?;
+            param.value = param.clamp(value);
+            Some(param.value)
+        }
+
+        pub fn clear(&mut self) {
+            self.0.clear();
+        }
+    }
+
+    pub enum ParamApiEvent {
+        Define(ScriptParam),
+        Set { name: String, value: f64 },
+    }
+
+    pub trait ParamProviding<E>
+    where
+        E: From<ParamApiEvent>,
+    {
+        fn load_params(&self, name: String, tx: Sender<E>) -> anyhow::Result<()>;
+    }
+
+    /// MIDI messages scripts asked to send later, kept
+    /// by the engine until they are due or the script stops.
+    #[derive(Debug, Default)]
+    pub struct MidiSchedule {
+        /// Timestamp of the message being handled, scheduled
+        /// messages are timestamped relative to it.
+        timestamp: u64,
+        pending: Vec<(std::time::Instant, crate::midi::MidiData)>,
+    }
+
+    impl MidiSchedule {
+        pub fn set_timestamp(&mut self, timestamp: u64) {
+            self.timestamp = timestamp;
+        }
+
+        /// Schedule a message, or `None` if the delay is too
+        /// long for the time it is due to be represented.
+        fn schedule(&mut self, delay: std::time::Duration, bytes: Vec<u8>) -> Option<()> {
+            let due = std::time::Instant::now().checked_add(delay)?;
+            let timestamp = u64::try_from(delay.as_micros())
+                .ok()
+                .and_then(|delay| self.timestamp.checked_add(delay))?;
+            self.pending
+                .push((due, crate::midi::MidiData { timestamp, bytes }));
+            Some(())
+        }
+
+        /// When the next message is due, if any is pending.
+        pub fn next_due(&self) -> Option<std::time::Instant> {
+            self.pending.iter().map(|(due, _)| *due).min()
+        }
+
+        /// Take the messages due by `now`, in the order they are due.
+        pub fn take_due(&mut self, now: std::time::Instant) -> Vec<crate::midi::MidiData> {
+            let (mut due, pending) = std::mem::take(&mut self.pending)
+                .into_iter()
+                .partition::<Vec<_>, _>(|(due, _)| *due <= now);
+            self.pending = pending;
+
+            due.sort_by_key(|(due, _)| *due);
+            due.into_iter().map(|(_, data)| data).collect()
+        }
+    }
+
+    pub trait MidiSchedulingProviding {
+        fn load_send_after(&self, name: String) -> anyhow::Result<()>;
+    }
+
+    /// Time of the host loop, only updated by the engine between two
+    /// callbacks so that a script reads the same time during a callback.
+    #[derive(Debug, Default, Clone, Copy, PartialEq)]
+    pub struct Clock {
+        /// Monotonic seconds since the host started.
+        pub seconds: f64,
+        /// Frames rendered by the host since it started.
+        pub frames: u64,
+    }
+
+    pub trait ClockProviding {
+        fn load_time(&self) -> anyhow::Result<()>;
+    }
+
     impl<E> LogProviding<E> for LuaRuntime
     where
         E: From<LogApiEvent> + 'static,
@@ -173,8 +585,8 @@ pub mod api {
     {
         fn load_pause(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
             self.set_fn("pause", {
-                move |_, (): ()| {
-                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Pause.into()) {
+                move |_, reason: Option<String>| {
+                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Pause(reason).into()) {
                         log::error!("{name} ! failed to send pause event : {}", e);
                     }
                     Ok(())
@@ -184,8 +596,8 @@ pub mod api {
 
         fn load_resume(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
             self.set_fn("resume", {
-                move |_, (): ()| {
-                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Resume.into()) {
+                move |_, reason: Option<String>| {
+                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Resume(reason).into()) {
                         log::error!("{name} ! failed to send resume event : {}", e);
                     }
                     Ok(())
@@ -195,8 +607,8 @@ pub mod api {
 
         fn load_stop(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
             self.set_fn("stop", {
-                move |_, (): ()| {
-                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Stop.into()) {
+                move |_, reason: Option<String>| {
+                    if let Err(e) = tx.try_send(ControlFlowApiEvent::Stop(reason).into()) {
                         log::error!("{name} ! failed to send stop event : {}", e);
                     }
                     Ok(())
@@ -204,4 +616,419 @@ pub mod api {
             })
         }
     }
+
+    impl AudioWindowProviding for LuaRuntime {
+        fn load_audio(&self) -> anyhow::Result<()> {
+            // the audio received by a previous script is kept
+            if self.app_data_mut::<AudioWindow>().is_none() {
+                self.set_app_data(AudioWindow::default());
+            }
+
+            self.set_table_fn("audio", "channels", |lua, (): ()| {
+                Ok(lua
+                    .app_data_ref::<AudioWindow>()
+                    .map_or(0, |window| window.channels.len()))
+            })?;
+
+            self.set_table_fn("audio", "samples", |lua, channel: usize| {
+                let window = lua.app_data_ref::<AudioWindow>();
+                let samples = window.as_ref().and_then(|window| window.channel(channel));
+                Ok(samples.map(<[f32]>::to_vec).unwrap_or_default())
+            })?;
+
+            self.set_table_fn("audio", "rms", |lua, channel: usize| {
+                let window = lua.app_data_ref::<AudioWindow>();
+                let samples = window.as_ref().and_then(|window| window.channel(channel));
+                Ok(samples.map_or(0., crate::dsp::rms))
+            })
+        }
+    }
+
+    impl<E> ScopeProviding<E> for LuaRuntime
+    where
+        E: From<ScopeApiEvent> + 'static,
+    {
+        fn load_scope(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("scope", "set_mode", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |_, mode: String| {
+                    match mode.parse() {
+                        Ok(mode) => send_scope_event(&name, &tx, ScopeApiEvent::SetMode(mode)),
+                        Err(e) => log::warn!("{name} ! {e}"),
+                    }
+                    Ok(())
+                }
+            })?;
+
+            self.set_table_fn("scope", "set_trigger", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |_, (level, edge): (f32, Option<String>)| {
+                    let edge = match edge.as_deref() {
+                        None | Some("rising") => crate::dsp::TriggerEdge::Rising,
+                        Some("falling") => crate::dsp::TriggerEdge::Falling,
+                        Some(edge) => {
+                            log::warn!("{name} ! unknown trigger edge : {edge}");
+                            return Ok(());
+                        }
+                    };
+
+                    if !level.is_finite() {
+                        log::warn!("{name} ! invalid trigger level : {level}");
+                        return Ok(());
+                    }
+
+                    let trigger = ScopeTrigger { level, edge };
+                    send_scope_event(&name, &tx, ScopeApiEvent::SetTrigger(trigger));
+                    Ok(())
+                }
+            })?;
+
+            self.set_table_fn("scope", "zoom", {
+                move |_, window_ms: f64| {
+                    let window = std::time::Duration::try_from_secs_f64(window_ms / 1000.);
+                    match window {
+                        Ok(window) if !window.is_zero() => {
+                            send_scope_event(&name, &tx, ScopeApiEvent::Zoom(window))
+                        }
+                        _ => log::warn!("{name} ! invalid zoom window : {window_ms}ms"),
+                    }
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    impl<E> AliasProviding<E> for LuaRuntime
+    where
+        E: From<AliasApiEvent> + 'static,
+    {
+        fn load_alias(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("midi", "alias", {
+                move |_, (device, alias): (String, String)| {
+                    if let Err(e) = tx.try_send(AliasApiEvent { device, alias }.into()) {
+                        log::error!("{name} ! failed to send alias event : {}", e);
+                    }
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    impl<E> SysExProviding<E> for LuaRuntime
+    where
+        E: From<SysExApiEvent> + 'static,
+    {
+        fn load_send_sysex(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("midi", "send_sysex", {
+                move |_, bytes: Vec<u8>| {
+                    let message = match crate::midi::frame_sysex(&bytes) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            log::warn!("{name} ! {e}");
+                            return Ok(false);
+                        }
+                    };
+
+                    if let Err(e) = tx.try_send(SysExApiEvent { message }.into()) {
+                        log::error!("{name} ! failed to send sysex event : {}", e);
+                    }
+                    Ok(true)
+                }
+            })
+        }
+    }
+
+    impl<E> PanicProviding<E> for LuaRuntime
+    where
+        E: From<PanicApiEvent> + 'static,
+    {
+        fn load_panic(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("midi", "panic", move |_, ()| {
+                if let Err(e) = tx.try_send(PanicApiEvent.into()) {
+                    log::error!("{name} ! failed to send panic event : {}", e);
+                }
+                Ok(())
+            })
+        }
+    }
+
+    impl<E> UiProviding<E> for LuaRuntime
+    where
+        E: From<UiApiEvent> + 'static,
+    {
+        fn load_ui(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            for (fn_name, event) in [("clear", UiApiEvent::Clear), ("reset", UiApiEvent::Reset)] {
+                self.set_table_fn("ui", fn_name, {
+                    let (name, tx) = (name.clone(), tx.clone());
+                    move |_, ()| {
+                        if let Err(e) = tx.try_send(event.into()) {
+                            log::error!("{name} ! failed to send ui event : {}", e);
+                        }
+                        Ok(())
+                    }
+                })?;
+            }
+            Ok(())
+        }
+    }
+
+    impl<E> FilterProviding<E> for LuaRuntime
+    where
+        E: From<FilterApiEvent> + 'static,
+    {
+        fn load_filter(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("filter", "channels", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |_, channels: Option<Vec<i64>>| {
+                    if let Some(channel) = channels
+                        .iter()
+                        .flatten()
+                        .find(|channel| !(0..=15).contains(*channel))
+                    {
+                        log::warn!("{name} ! invalid channel : {channel}, expected 0 to 15");
+                        return Ok(false);
+                    }
+
+                    let channels =
+                        channels.map(|channels| channels.into_iter().map(|c| c as u8).collect());
+                    send_filter_event(&name, &tx, FilterApiEvent::Channels(channels));
+                    Ok(true)
+                }
+            })?;
+
+            self.set_table_fn("filter", "types", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |_, names: Option<Vec<String>>| {
+                    let types = match names {
+                        Some(names) => {
+                            let mut types = vec![];
+                            for name_of_type in names {
+                                match crate::midi::message_types(&name_of_type) {
+                                    Ok(kinds) => types.extend(kinds),
+                                    Err(e) => {
+                                        log::warn!("{name} ! {e}");
+                                        return Ok(false);
+                                    }
+                                }
+                            }
+                            types.sort();
+                            types.dedup();
+                            Some(types)
+                        }
+                        None => None,
+                    };
+
+                    send_filter_event(&name, &tx, FilterApiEvent::Types(types));
+                    Ok(true)
+                }
+            })?;
+
+            self.set_table_fn("filter", "notes", {
+                move |_, (low, high): (Option<i64>, Option<i64>)| {
+                    let range = match (low, high) {
+                        (None, None) => None,
+                        (low, high) => {
+                            let low = low.unwrap_or(0).clamp(0, 127) as u8;
+                            let high = high.unwrap_or(127).clamp(0, 127) as u8;
+                            Some((low.min(high), low.max(high)))
+                        }
+                    };
+
+                    send_filter_event(&name, &tx, FilterApiEvent::Notes(range));
+                    Ok(())
+                }
+            })
+        }
+    }
+
+    impl<E> ParamProviding<E> for LuaRuntime
+    where
+        E: From<ParamApiEvent> + 'static,
+    {
+        fn load_params(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            // parameters do not outlive the script defining them
+            self.set_app_data(ScriptParams::default());
+
+            self.set_table_fn("params", "define", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |lua, (param, default, min, max): (String, f64, f64, f64)| {
+                    let is_valid = [default, min, max].iter().all(|v| v.is_finite()) && min < max;
+                    if !is_valid {
+                        log::warn!(
+                            "{name} ! invalid parameter {param} : {default} in {min}..{max}"
+                        );
+                        return Ok(None);
+                    }
+
+                    let param = ScriptParam {
+                        name: param,
+                        value: default.clamp(min, max),
+                        min,
+                        max,
+                    };
+                    let value = param.value;
+
+                    if let Some(mut params) = lua.app_data_mut::<ScriptParams>() {
+                        params.define(param.clone());
+                    }
+                    send_param_event(&name, &tx, ParamApiEvent::Define(param));
+                    Ok(Some(value))
+                }
+            })?;
+
+            self.set_table_fn("params", "get", {
+                move |lua, param: String| {
+                    Ok(lua
+                        .app_data_ref::<ScriptParams>()
+                        .and_then(|params| params.get(&param)))
+                }
+            })?;
+
+            self.set_table_fn("params", "set", {
+                move |lua, (param, value): (String, f64)| {
+                    let value = lua
+                        .app_data_mut::<ScriptParams>()
+                        .and_then(|mut params| params.set(&param, value));
+
+                    match value {
+                        Some(value) => {
+                            let event = ParamApiEvent::Set { name: param, value };
+                            send_param_event(&name, &tx, event);
+                        }
+                        None => log::warn!("{name} ! undefined parameter : {param}"),
+                    }
+                    Ok(value)
+                }
+            })
+        }
+    }
+
+    impl<E> SelectionProviding<E> for LuaRuntime
+    where
+        E: From<SelectionApiEvent> + 'static,
+    {
+        fn load_selected(&self, name: String, tx: Sender<E>) -> anyhow::Result<()> {
+            self.set_table_fn("midi", "selected", {
+                let (name, tx) = (name.clone(), tx.clone());
+                move |_, (): ()| Ok(request_selection(&name, &tx, DeviceKind::Midi))
+            })?;
+
+            self.set_table_fn("audio", "selected", {
+                move |_, (): ()| Ok(request_selection(&name, &tx, DeviceKind::Audio))
+            })
+        }
+    }
+
+    impl MidiSchedulingProviding for LuaRuntime {
+        fn load_send_after(&self, name: String) -> anyhow::Result<()> {
+            // messages scheduled by a previous script are cancelled
+            self.set_app_data(MidiSchedule::default());
+
+            self.set_table_fn("midi", "send_after", {
+                move |lua, (delay_ms, bytes): (f64, Vec<u8>)| {
+                    let invalid_delay = || {
+                        mlua::Error::RuntimeError(format!(
+                            "{name} ! invalid send delay : {delay_ms}ms"
+                        ))
+                    };
+
+                    let delay = std::time::Duration::try_from_secs_f64(delay_ms / 1000.)
+                        .map_err(|_| invalid_delay())?;
+                    match lua.app_data_mut::<MidiSchedule>() {
+                        Some(mut schedule) => {
+                            schedule.schedule(delay, bytes).ok_or_else(invalid_delay)
+                        }
+                        None => Ok(()),
+                    }
+                }
+            })
+        }
+    }
+
+    impl ClockProviding for LuaRuntime {
+        fn load_time(&self) -> anyhow::Result<()> {
+            self.set_table_fn("time", "now", |lua, ()| {
+                Ok(lua
+                    .app_data_ref::<Clock>()
+                    .map_or(0., |clock| clock.seconds))
+            })?;
+
+            self.set_table_fn("time", "frames", |lua, ()| {
+                Ok(lua.app_data_ref::<Clock>().map_or(0, |clock| clock.frames))
+            })
+        }
+    }
+
+    fn send_param_event<E>(name: &str, tx: &Sender<E>, event: ParamApiEvent)
+    where
+        E: From<ParamApiEvent>,
+    {
+        if let Err(e) = tx.try_send(event.into()) {
+            log::error!("{name} ! failed to send param event : {}", e);
+        }
+    }
+
+    fn send_filter_event<E>(name: &str, tx: &Sender<E>, event: FilterApiEvent)
+    where
+        E: From<FilterApiEvent>,
+    {
+        if let Err(e) = tx.try_send(event.into()) {
+            log::error!("{name} ! failed to send filter event : {}", e);
+        }
+    }
+
+    fn send_scope_event<E>(name: &str, tx: &Sender<E>, event: ScopeApiEvent)
+    where
+        E: From<ScopeApiEvent>,
+    {
+        if let Err(e) = tx.try_send(event.into()) {
+            log::error!("{name} ! failed to send scope event : {}", e);
+        }
+    }
+
+    fn request_selection<E>(name: &str, tx: &Sender<E>, kind: DeviceKind) -> Option<String>
+    where
+        E: From<SelectionApiEvent>,
+    {
+        let (reply, rx) = crossbeam::channel::bounded(1);
+
+        if let Err(e) = tx.try_send(SelectionApiEvent { kind, reply }.into()) {
+            log::error!("{name} ! failed to send selection request : {}", e);
+            return None;
+        }
+
+        match rx.recv_timeout(HOST_REQUEST_TIMEOUT) {
+            Ok(selected) => selected,
+            Err(e) => {
+                log::error!("{name} ! failed to receive selected device : {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{api::*, *};
+
+    #[test]
+    fn messages_cannot_be_sent_after_an_invalid_delay() {
+        let mut lua = LuaRuntime::default();
+        lua.load_send_after("script".to_owned()).unwrap();
+
+        for delay in ["-1", "0/0", "math.huge", "1e300"] {
+            let chunk = format!("midi.send_after({delay}, {{ 0x80, 60, 0 }})");
+            let error = lua.load_chunk(&chunk).unwrap_err();
+            assert!(error.to_string().contains("invalid send delay"), "{error}");
+        }
+        assert_eq!(lua.app_data_mut::<MidiSchedule>().unwrap().next_due(), None);
+
+        lua.load_chunk("midi.send_after(50, { 0x80, 60, 0 })")
+            .unwrap();
+        assert!(lua
+            .app_data_mut::<MidiSchedule>()
+            .unwrap()
+            .next_due()
+            .is_some());
+    }
 }
@@ -0,0 +1,116 @@
+use super::MidiData;
+use std::collections::VecDeque;
+
+/// Pulses the MIDI clock sends per quarter note.
+const PULSES_PER_QUARTER: usize = 24;
+
+/// Pulses further apart than this, in microseconds, restart the tempo
+/// estimate, as the clock was most likely stopped in between.
+const MAX_PULSE_INTERVAL: u64 = 1_000_000;
+
+const CLOCK: u8 = 0xF8;
+const START: u8 = 0xFA;
+const CONTINUE: u8 = 0xFB;
+const STOP: u8 = 0xFC;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    Playing,
+    Stopped,
+}
+
+/// Follows the transport state and the tempo of an incoming MIDI clock.
+#[derive(Debug, Default, Clone)]
+pub struct MidiClock {
+    transport: Option<Transport>,
+    last_pulse: Option<u64>,
+    /// Microseconds between the last pulses, up to a quarter note of them.
+    intervals: VecDeque<u64>,
+}
+
+impl MidiClock {
+    pub fn observe(&mut self, midi: &MidiData) {
+        match midi.bytes.first() {
+            Some(&CLOCK) => self.observe_pulse(midi.timestamp),
+            Some(&START) | Some(&CONTINUE) => self.transport = Some(Transport::Playing),
+            Some(&STOP) => self.transport = Some(Transport::Stopped),
+            _ => {}
+        }
+    }
+
+    fn observe_pulse(&mut self, timestamp: u64) {
+        if let Some(last_pulse) = self.last_pulse.replace(timestamp) {
+            match timestamp.checked_sub(last_pulse) {
+                Some(interval) if interval <= MAX_PULSE_INTERVAL => {
+                    if self.intervals.len() == PULSES_PER_QUARTER {
+                        self.intervals.pop_front();
+                    }
+                    self.intervals.push_back(interval);
+                }
+                _ => self.intervals.clear(),
+            }
+        }
+    }
+
+    /// Transport state, once a start, continue or stop was received.
+    pub fn transport(&self) -> Option<Transport> {
+        self.transport
+    }
+
+    /// Tempo estimated from the spacing of the last clock pulses.
+    pub fn bpm(&self) -> Option<f32> {
+        if self.intervals.is_empty() {
+            return None;
+        }
+
+        let mean_interval = self.intervals.iter().sum::<u64>() as f32 / self.intervals.len() as f32;
+        (mean_interval > 0.).then(|| 60e6 / (mean_interval * PULSES_PER_QUARTER as f32))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn message(timestamp: u64, status: u8) -> MidiData {
+        MidiData {
+            timestamp,
+            bytes: vec![status],
+        }
+    }
+
+    #[test]
+    fn tempo_is_estimated_from_the_clock_pulse_spacing() {
+        let mut clock = MidiClock::default();
+        assert_eq!(clock.bpm(), None);
+        assert_eq!(clock.transport(), None);
+
+        clock.observe(&message(0, START));
+        assert_eq!(clock.transport(), Some(Transport::Playing));
+
+        // 120 BPM is 2 quarter notes, or 48 pulses, per second
+        let interval = 1_000_000 / 48;
+        for pulse in 0..48 {
+            clock.observe(&message(pulse * interval, CLOCK));
+        }
+        assert!(
+            (clock.bpm().unwrap() - 120.).abs() < 0.1,
+            "{:?}",
+            clock.bpm()
+        );
+
+        // the estimate follows a tempo change within a quarter note
+        let start = 48 * interval;
+        for pulse in 0..=24 {
+            clock.observe(&message(start + pulse * interval * 2, CLOCK));
+        }
+        assert!(
+            (clock.bpm().unwrap() - 60.).abs() < 0.1,
+            "{:?}",
+            clock.bpm()
+        );
+
+        clock.observe(&message(start + 60 * interval, STOP));
+        assert_eq!(clock.transport(), Some(Transport::Stopped));
+    }
+}
@@ -0,0 +1,232 @@
+/// Fields of a MIDI message, decoded from its bytes so that
+/// scripts do not have to decode the messages by hand.
+///
+/// Fields that do not apply to the type of the message are `None`.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MidiFields {
+    /// e.g. `note_on`, `control_change` or `clock`.
+    pub kind: &'static str,
+    /// Channel of a channel message, from 0 to 15.
+    pub channel: Option<u8>,
+    pub note: Option<u8>,
+    pub velocity: Option<u8>,
+    pub controller: Option<u8>,
+    /// Value of a control change, program change, pressure or
+    /// pitch bend message. Pitch bends span 0 to 16383.
+    pub value: Option<u16>,
+}
+
+impl MidiFields {
+    pub fn decode(bytes: &[u8]) -> Self {
+        let Some(&status) = bytes.first() else {
+            return Self {
+                kind: "unknown",
+                ..Default::default()
+            };
+        };
+
+        let data = |index: usize| bytes.get(index).map(|byte| byte & 0x7F);
+        let channel = Some(status & 0x0F);
+
+        match status & 0xF0 {
+            0x80 => Self::note("note_off", channel, data(1), data(2)),
+            0x90 if data(2) == Some(0) => Self::note("note_off", channel, data(1), data(2)),
+            0x90 => Self::note("note_on", channel, data(1), data(2)),
+            0xA0 => Self {
+                kind: "aftertouch",
+                channel,
+                note: data(1),
+                value: data(2).map(u16::from),
+                ..Default::default()
+            },
+            0xB0 => Self {
+                kind: "control_change",
+                channel,
+                controller: data(1),
+                value: data(2).map(u16::from),
+                ..Default::default()
+            },
+            0xC0 => Self::channel_value("program_change", channel, data(1).map(u16::from)),
+            0xD0 => Self::channel_value("channel_pressure", channel, data(1).map(u16::from)),
+            0xE0 => {
+                let value = data(1)
+                    .zip(data(2))
+                    .map(|(lsb, msb)| (msb as u16) << 7 | lsb as u16);
+                Self::channel_value("pitch_bend", channel, value)
+            }
+            _ => Self {
+                kind: match status {
+                    0xF0 => "sysex",
+                    0xF8 => "clock",
+                    0xFA => "start",
+                    0xFB => "continue",
+                    0xFC => "stop",
+                    0xFE => "active_sensing",
+                    0xFF => "reset",
+                    _ => "unknown",
+                },
+                ..Default::default()
+            },
+        }
+    }
+
+    fn note(
+        kind: &'static str,
+        channel: Option<u8>,
+        note: Option<u8>,
+        velocity: Option<u8>,
+    ) -> Self {
+        Self {
+            kind,
+            channel,
+            note,
+            velocity,
+            ..Default::default()
+        }
+    }
+
+    fn channel_value(kind: &'static str, channel: Option<u8>, value: Option<u16>) -> Self {
+        Self {
+            kind,
+            channel,
+            value,
+            ..Default::default()
+        }
+    }
+}
+
+/// How Note Offs are represented, since devices send either
+/// a Note Off or a Note On with a velocity of 0 to release a note.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NoteOffForm {
+    /// As they were sent.
+    #[default]
+    Raw,
+    /// `0x8n`, Note Ons with a velocity of 0 becoming Note Offs.
+    NoteOff,
+    /// `0x9n` with a velocity of 0, losing the release velocity of Note Offs.
+    NoteOn,
+}
+
+impl NoteOffForm {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Raw => Self::NoteOff,
+            Self::NoteOff => Self::NoteOn,
+            Self::NoteOn => Self::Raw,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Raw => "raw",
+            Self::NoteOff => "note-off",
+            Self::NoteOn => "note-on",
+        }
+    }
+
+    /// Bytes of the message with its Note Off in this form,
+    /// any other message is returned as it is.
+    pub fn normalize(self, bytes: &[u8]) -> Vec<u8> {
+        match (self, bytes) {
+            (Self::NoteOff, &[status, note, 0]) if status & 0xF0 == 0x90 => {
+                vec![0x80 | status & 0x0F, note, 0]
+            }
+            (Self::NoteOn, &[status, note, _]) if status & 0xF0 == 0x80 => {
+                vec![0x90 | status & 0x0F, note, 0]
+            }
+            _ => bytes.to_vec(),
+        }
+    }
+}
+
+impl std::str::FromStr for NoteOffForm {
+    type Err = anyhow::Error;
+
+    fn from_str(form: &str) -> anyhow::Result<Self> {
+        match form {
+            "raw" => Ok(Self::Raw),
+            "note-off" => Ok(Self::NoteOff),
+            "note-on" => Ok(Self::NoteOn),
+            _ => anyhow::bail!("Invalid Note Off form : {form}, use raw, note-off or note-on"),
+        }
+    }
+}
+
+/// e.g. `note_on ch=2 note=60 velocity=100`, skipping the fields that do not apply.
+impl std::fmt::Display for MidiFields {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        let fields = [
+            ("ch", self.channel.map(u16::from)),
+            ("note", self.note.map(u16::from)),
+            ("velocity", self.velocity.map(u16::from)),
+            ("cc", self.controller.map(u16::from)),
+            ("value", self.value),
+        ];
+
+        for (name, value) in fields {
+            if let Some(value) = value {
+                write!(f, " {name}={value}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn channel_messages_are_decoded() {
+        assert_eq!(
+            MidiFields::decode(&[0x93, 60, 100]),
+            MidiFields::note("note_on", Some(3), Some(60), Some(100))
+        );
+        assert_eq!(MidiFields::decode(&[0x90, 60, 0]).kind, "note_off");
+        assert_eq!(
+            MidiFields::decode(&[0xB1, 7, 127]),
+            MidiFields {
+                kind: "control_change",
+                channel: Some(1),
+                controller: Some(7),
+                value: Some(127),
+                ..Default::default()
+            }
+        );
+        assert_eq!(MidiFields::decode(&[0xE0, 0, 0x40]).value, Some(8192));
+        assert_eq!(MidiFields::decode(&[0xF8]).kind, "clock");
+        assert_eq!(MidiFields::decode(&[]).kind, "unknown");
+        assert_eq!(
+            MidiFields::decode(&[0x92, 60, 100]).to_string(),
+            "note_on ch=2 note=60 velocity=100"
+        );
+    }
+
+    #[test]
+    fn both_note_off_representations_normalize_to_the_chosen_form() {
+        let note_off = [0x83, 60, 64];
+        let note_on_without_velocity = [0x93, 60, 0];
+
+        for bytes in [&note_off[..], &note_on_without_velocity] {
+            assert_eq!(NoteOffForm::NoteOff.normalize(bytes)[..2], [0x83, 60]);
+            assert_eq!(NoteOffForm::NoteOn.normalize(bytes), [0x93, 60, 0]);
+            assert_eq!(NoteOffForm::Raw.normalize(bytes), bytes);
+        }
+        assert_eq!(NoteOffForm::NoteOff.normalize(&note_off), note_off);
+
+        for bytes in [&[0x93, 60, 100][..], &[0xB0, 7, 0], &[0xF8]] {
+            assert_eq!(NoteOffForm::NoteOff.normalize(bytes), bytes);
+            assert_eq!(NoteOffForm::NoteOn.normalize(bytes), bytes);
+        }
+
+        assert_eq!(
+            "note-on".parse::<NoteOffForm>().unwrap(),
+            NoteOffForm::NoteOn
+        );
+        assert!("off".parse::<NoteOffForm>().is_err());
+    }
+}
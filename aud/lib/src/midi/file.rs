@@ -0,0 +1,275 @@
+use super::*;
+use std::{
+    path::Path,
+    time::{Duration, Instant},
+};
+
+/// Plays a Standard MIDI File as if it was a MIDI input port,
+/// emitting its events at the time they were recorded.
+pub struct FileMidiInput {
+    name: String,
+    /// Events of all tracks, with their time in microseconds from the start.
+    events: Vec<(u64, Vec<u8>)>,
+    is_connected: bool,
+    is_active: bool,
+    /// Next event to play.
+    position: usize,
+    /// Playback time, in microseconds, of the current pass through the file.
+    elapsed: u64,
+    /// Playback time of the previous passes, when looping.
+    offset: u64,
+    last_update: Option<Instant>,
+    speed: f32,
+    looping: bool,
+}
+
+impl FileMidiInput {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Self::new(name, &std::fs::read(path)?)
+    }
+
+    fn new(name: String, bytes: &[u8]) -> anyhow::Result<Self> {
+        Ok(Self {
+            name,
+            events: parse_smf(bytes)?,
+            is_connected: false,
+            is_active: true,
+            position: 0,
+            elapsed: 0,
+            offset: 0,
+            last_update: None,
+            speed: 1.,
+            looping: false,
+        })
+    }
+
+    /// Play the file faster, above 1, or slower, below 1, than recorded.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed.max(0.);
+    }
+
+    /// Start over from the beginning of the file once it has been played.
+    pub fn set_looping(&mut self, looping: bool) {
+        self.looping = looping;
+    }
+
+    fn duration(&self) -> u64 {
+        self.events.last().map_or(0, |(time, _)| *time)
+    }
+
+    /// Play the events that are due once `elapsed` has passed.
+    fn advance(&mut self, elapsed: Duration) -> Vec<MidiData> {
+        if !self.is_connected {
+            return vec![];
+        }
+
+        self.elapsed += (elapsed.as_secs_f64() * 1e6 * self.speed as f64) as u64;
+
+        let mut messages = vec![];
+        loop {
+            while let Some((time, bytes)) = self.events.get(self.position) {
+                if *time > self.elapsed {
+                    return messages;
+                }
+
+                messages.push(MidiData {
+                    timestamp: self.offset + time,
+                    bytes: bytes.clone(),
+                });
+                self.position += 1;
+            }
+
+            // an empty file would loop forever
+            if !self.looping || self.duration() == 0 {
+                return messages;
+            }
+
+            self.position = 0;
+            self.offset += self.duration();
+            self.elapsed -= self.duration();
+        }
+    }
+}
+
+impl MidiReceiving for FileMidiInput {
+    fn is_midi_stream_active(&self) -> bool {
+        self.is_active
+    }
+
+    fn set_midi_stream_active(&mut self, should_be_active: bool) {
+        self.is_active = should_be_active;
+    }
+
+    fn list_midi_devices(&self) -> anyhow::Result<Vec<String>> {
+        Ok(vec![self.name.clone()])
+    }
+
+    fn connect_to_midi_device(&mut self, device_name: &str) -> anyhow::Result<()> {
+        if device_name != self.name {
+            anyhow::bail!("[ MIDI ] : Cannot find file {device_name}");
+        }
+
+        self.is_connected = true;
+        self.position = 0;
+        self.elapsed = 0;
+        self.offset = 0;
+        self.last_update = None;
+        Ok(())
+    }
+
+    fn disconnect_from_midi_device(&mut self) {
+        self.is_connected = false;
+    }
+
+    fn produce_midi_messages(&mut self) -> Vec<MidiData> {
+        let now = Instant::now();
+        let elapsed = self
+            .last_update
+            .replace(now)
+            .map_or(Duration::ZERO, |last| now.duration_since(last));
+
+        if !self.is_active {
+            return vec![];
+        }
+
+        self.advance(elapsed)
+    }
+}
+
+/// Tempo of a file that does not set one, 120 BPM.
+const DEFAULT_TEMPO: u64 = 500_000;
+
+/// Merge the tracks of a Standard MIDI File into a list of
+/// events timed in microseconds, following its tempo changes.
+fn parse_smf(bytes: &[u8]) -> anyhow::Result<Vec<(u64, Vec<u8>)>> {
+    let smf = midly::Smf::parse(bytes).map_err(|e| anyhow::anyhow!("Invalid MIDI file : {e}"))?;
+
+    // ticks are either a fraction of a quarter note or of an SMPTE frame
+    let (ticks_per_quarter, ticks_per_second) = match smf.header.timing {
+        midly::Timing::Metrical(ticks) => (ticks.as_int().max(1) as u64, None),
+        midly::Timing::Timecode(fps, subframes) => {
+            (1, Some((fps.as_int() as u64 * subframes as u64).max(1)))
+        }
+    };
+
+    // (tick, track, tempo or message), the track keeps the order of simultaneous events
+    let mut events = vec![];
+    for (track, track_events) in smf.tracks.iter().enumerate() {
+        let mut tick = 0;
+        for event in track_events {
+            tick += event.delta.as_int() as u64;
+            if let Some(event) = TrackEvent::from_kind(event.kind) {
+                events.push((tick, track, event));
+            }
+        }
+    }
+    events.sort_by_key(|(tick, track, _)| (*tick, *track));
+
+    let mut tempo = DEFAULT_TEMPO;
+    let mut last_tick = 0;
+    let mut time = 0;
+    let mut timed = vec![];
+    for (tick, _, event) in events {
+        time += match ticks_per_second {
+            Some(ticks_per_second) => (tick - last_tick) * 1_000_000 / ticks_per_second,
+            None => (tick - last_tick) * tempo / ticks_per_quarter,
+        };
+        last_tick = tick;
+
+        match event {
+            TrackEvent::Tempo(new_tempo) => tempo = new_tempo,
+            TrackEvent::Message(bytes) => timed.push((time, bytes)),
+        }
+    }
+
+    Ok(timed)
+}
+
+enum TrackEvent {
+    /// Microseconds per quarter note.
+    Tempo(u64),
+    Message(Vec<u8>),
+}
+
+impl TrackEvent {
+    fn from_kind(kind: midly::TrackEventKind<'_>) -> Option<Self> {
+        let bytes = match kind {
+            midly::TrackEventKind::Meta(midly::MetaMessage::Tempo(tempo)) => {
+                return Some(Self::Tempo(tempo.as_int() as u64))
+            }
+            midly::TrackEventKind::Midi { channel, message } => {
+                let mut bytes = vec![];
+                midly::live::LiveEvent::Midi { channel, message }
+                    .write_std(&mut bytes)
+                    .ok()?;
+                bytes
+            }
+            // escaped events carry raw bytes, sysex ones omit their leading F0
+            midly::TrackEventKind::SysEx(body) => [&[0xF0], body].concat(),
+            midly::TrackEventKind::Escape(bytes) => bytes.to_vec(),
+            midly::TrackEventKind::Meta(_) => return None,
+        };
+        Some(Self::Message(bytes))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn smf(track: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        bytes.extend(b"MThd");
+        bytes.extend(6u32.to_be_bytes());
+        // format 0, one track, 96 ticks per quarter note
+        bytes.extend([0, 0, 0, 1, 0, 96]);
+        bytes.extend(b"MTrk");
+        bytes.extend((track.len() as u32).to_be_bytes());
+        bytes.extend(track);
+        bytes
+    }
+
+    #[test]
+    fn events_are_played_in_order_following_tempo_changes() {
+        #[rustfmt::skip]
+        let file = smf(&[
+            // note on at 0
+            0x00, 0x90, 60, 100,
+            // running status note on after a quarter note at 120 BPM, 0.5s
+            0x60, 64, 100,
+            // tempo changes to 240 BPM
+            0x00, 0xFF, 0x51, 0x03, 0x03, 0xD0, 0x90,
+            // note off after a quarter note at 240 BPM, 0.25s
+            0x60, 0x80, 60, 0,
+            0x00, 0xFF, 0x2F, 0x00,
+        ]);
+
+        let mut input = FileMidiInput::new("song.mid".into(), &file).unwrap();
+        input.connect_to_midi_device("song.mid").unwrap();
+
+        let times = |messages: Vec<MidiData>| -> Vec<u64> {
+            messages.iter().map(|midi| midi.timestamp).collect()
+        };
+
+        assert_eq!(times(input.advance(Duration::from_millis(400))), [0]);
+        assert_eq!(times(input.advance(Duration::from_millis(200))), [500_000]);
+
+        input.set_speed(2.);
+        let messages = input.advance(Duration::from_millis(100));
+        assert_eq!(messages[0].bytes, [0x80, 60, 0]);
+        assert_eq!(times(messages), [750_000]);
+
+        input.set_looping(true);
+        assert_eq!(times(input.advance(Duration::ZERO)), [750_000]);
+        assert_eq!(
+            times(input.advance(Duration::from_millis(250))),
+            [1_250_000]
+        );
+    }
+}
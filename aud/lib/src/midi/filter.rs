@@ -0,0 +1,191 @@
+use super::MidiFields;
+
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+
+/// Types of message a filter can keep, as scripts see them in `msg.type`.
+pub const MESSAGE_TYPES: &[&str] = &[
+    "note_on",
+    "note_off",
+    "aftertouch",
+    "control_change",
+    "program_change",
+    "channel_pressure",
+    "pitch_bend",
+    "sysex",
+    "clock",
+    "start",
+    "continue",
+    "stop",
+    "active_sensing",
+    "reset",
+];
+
+/// Types named by a script, either as in [`MESSAGE_TYPES`] or as
+/// `note` for both Note Ons and Note Offs, or `cc` for control changes.
+pub fn message_types(name: &str) -> anyhow::Result<Vec<&'static str>> {
+    match name {
+        "note" => Ok(vec!["note_on", "note_off"]),
+        "cc" => Ok(vec!["control_change"]),
+        _ => match MESSAGE_TYPES.iter().find(|&&kind| kind == name) {
+            Some(&kind) => Ok(vec![kind]),
+            None => anyhow::bail!("unknown message type : {name}"),
+        },
+    }
+}
+
+/// Decides which incoming MIDI messages are kept by the monitor.
+///
+/// The note range and velocity threshold only apply to note messages,
+/// all other messages are kept unless `notes_only` is set. The channels
+/// only apply to channel messages.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct MessageFilter {
+    /// Inclusive range of note numbers to keep.
+    pub note_range: Option<(u8, u8)>,
+    /// Minimum velocity a Note On needs to be kept.
+    /// Note Offs are always kept to avoid hanging notes.
+    pub min_velocity: Option<u8>,
+    /// Drop every message that is not a Note On or Note Off.
+    pub notes_only: bool,
+    /// Channels to keep, from 0 to 15.
+    pub channels: Option<Vec<u8>>,
+    /// Types of message to keep, see [`MESSAGE_TYPES`].
+    pub types: Option<Vec<&'static str>>,
+}
+
+impl MessageFilter {
+    pub fn accepts(&self, bytes: &[u8]) -> bool {
+        if self.channels.is_some() || self.types.is_some() {
+            let fields = MidiFields::decode(bytes);
+            if let (Some(channels), Some(channel)) = (&self.channels, fields.channel) {
+                if !channels.contains(&channel) {
+                    return false;
+                }
+            }
+
+            if let Some(ref types) = self.types {
+                if !types.contains(&fields.kind) {
+                    return false;
+                }
+            }
+        }
+
+        let &[status, key, velocity, ..] = bytes else {
+            return !self.notes_only;
+        };
+
+        let is_note_off = status & 0xF0 == NOTE_OFF || (status & 0xF0 == NOTE_ON && velocity == 0);
+        let is_note_on = status & 0xF0 == NOTE_ON && !is_note_off;
+
+        if !is_note_on && !is_note_off {
+            return !self.notes_only;
+        }
+
+        if let Some((low, high)) = self.note_range {
+            if !(low..=high).contains(&key) {
+                return false;
+            }
+        }
+
+        match self.min_velocity {
+            Some(min_velocity) if is_note_on => velocity >= min_velocity,
+            _ => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const CC: &[u8] = &[0xB0, 1, 64];
+    const CLOCK: &[u8] = &[0xF8];
+
+    fn note_on(key: u8, velocity: u8) -> [u8; 3] {
+        [NOTE_ON | 0x3, key, velocity]
+    }
+
+    fn note_off(key: u8) -> [u8; 3] {
+        [NOTE_OFF | 0x3, key, 0]
+    }
+
+    #[test]
+    fn accepts_everything_by_default() {
+        let filter = MessageFilter::default();
+        assert!(filter.accepts(&note_on(0, 1)));
+        assert!(filter.accepts(&note_off(127)));
+        assert!(filter.accepts(CC));
+        assert!(filter.accepts(CLOCK));
+    }
+
+    #[test]
+    fn note_range_is_inclusive() {
+        let filter = MessageFilter {
+            note_range: Some((36, 60)),
+            ..Default::default()
+        };
+
+        assert!(!filter.accepts(&note_on(35, 100)));
+        assert!(filter.accepts(&note_on(36, 100)));
+        assert!(filter.accepts(&note_on(60, 100)));
+        assert!(!filter.accepts(&note_on(61, 100)));
+        assert!(!filter.accepts(&note_off(35)));
+        assert!(filter.accepts(&note_off(60)));
+    }
+
+    #[test]
+    fn velocity_threshold_only_applies_to_note_ons() {
+        let filter = MessageFilter {
+            min_velocity: Some(64),
+            ..Default::default()
+        };
+
+        assert!(!filter.accepts(&note_on(60, 63)));
+        assert!(filter.accepts(&note_on(60, 64)));
+        assert!(filter.accepts(&note_on(60, 127)));
+        assert!(filter.accepts(&note_on(60, 0)));
+        assert!(filter.accepts(&note_off(60)));
+    }
+
+    #[test]
+    fn non_note_messages_are_only_dropped_when_filtering_notes_only() {
+        let mut filter = MessageFilter {
+            note_range: Some((36, 60)),
+            min_velocity: Some(64),
+            notes_only: false,
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(CC));
+        assert!(filter.accepts(CLOCK));
+
+        filter.notes_only = true;
+        assert!(!filter.accepts(CC));
+        assert!(!filter.accepts(CLOCK));
+        assert!(filter.accepts(&note_on(48, 100)));
+    }
+
+    #[test]
+    fn channels_and_types_only_keep_the_matching_messages() {
+        let mut filter = MessageFilter {
+            channels: Some(vec![0, 3]),
+            ..Default::default()
+        };
+
+        assert!(filter.accepts(&note_on(60, 100)));
+        assert!(filter.accepts(CC));
+        assert!(!filter.accepts(&[0x91, 60, 100]));
+        assert!(filter.accepts(CLOCK), "system messages have no channel");
+
+        filter.types = Some(message_types("note").unwrap());
+        assert!(filter.accepts(&note_on(60, 100)));
+        assert!(filter.accepts(&[NOTE_ON | 0x3, 60, 0]));
+        assert!(!filter.accepts(CC));
+        assert!(!filter.accepts(CLOCK));
+
+        assert_eq!(message_types("cc").unwrap(), ["control_change"]);
+        assert_eq!(message_types("clock").unwrap(), ["clock"]);
+        assert!(message_types("notes").is_err());
+    }
+}
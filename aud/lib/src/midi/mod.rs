@@ -1,6 +1,22 @@
+mod clock;
+mod fields;
+mod file;
+mod filter;
+mod names;
+mod panic;
+mod pattern;
 mod stream;
+mod sysex;
 
+pub use clock::*;
+pub use fields::*;
+pub use file::*;
+pub use filter::*;
+pub use names::*;
+pub use panic::*;
+pub use pattern::*;
 pub use stream::*;
+pub use sysex::*;
 
 pub trait MidiReceiving {
     ///
@@ -11,8 +27,15 @@ pub trait MidiReceiving {
     fn list_midi_devices(&self) -> anyhow::Result<Vec<String>>;
     ///
     fn connect_to_midi_device(&mut self, device_name: &str) -> anyhow::Result<()>;
+    /// Close the connection to the current device, if any.
+    fn disconnect_from_midi_device(&mut self) {}
     ///
     fn produce_midi_messages(&mut self) -> Vec<MidiData>;
+    /// Messages dropped so far because they arrived faster than they were
+    /// produced. Only receivers fed from another thread can drop any.
+    fn num_dropped_messages(&self) -> u64 {
+        0
+    }
 }
 
 pub trait MidiProducing {
@@ -0,0 +1,270 @@
+use std::path::Path;
+
+/// General MIDI level 1 instrument names, indexed by program number.
+const GM_PROGRAMS: [&str; 128] = [
+    // Piano
+    "Acoustic Grand Piano",
+    "Bright Acoustic Piano",
+    "Electric Grand Piano",
+    "Honky-tonk Piano",
+    "Electric Piano 1",
+    "Electric Piano 2",
+    "Harpsichord",
+    "Clavinet",
+    // Chromatic Percussion
+    "Celesta",
+    "Glockenspiel",
+    "Music Box",
+    "Vibraphone",
+    "Marimba",
+    "Xylophone",
+    "Tubular Bells",
+    "Dulcimer",
+    // Organ
+    "Drawbar Organ",
+    "Percussive Organ",
+    "Rock Organ",
+    "Church Organ",
+    "Reed Organ",
+    "Accordion",
+    "Harmonica",
+    "Tango Accordion",
+    // Guitar
+    "Acoustic Guitar (nylon)",
+    "Acoustic Guitar (steel)",
+    "Electric Guitar (jazz)",
+    "Electric Guitar (clean)",
+    "Electric Guitar (muted)",
+    "Overdriven Guitar",
+    "Distortion Guitar",
+    "Guitar Harmonics",
+    // Bass
+    "Acoustic Bass",
+    "Electric Bass (finger)",
+    "Electric Bass (pick)",
+    "Fretless Bass",
+    "Slap Bass 1",
+    "Slap Bass 2",
+    "Synth Bass 1",
+    "Synth Bass 2",
+    // Strings
+    "Violin",
+    "Viola",
+    "Cello",
+    "Contrabass",
+    "Tremolo Strings",
+    "Pizzicato Strings",
+    "Orchestral Harp",
+    "Timpani",
+    // Ensemble
+    "String Ensemble 1",
+    "String Ensemble 2",
+    "Synth Strings 1",
+    "Synth Strings 2",
+    "Choir Aahs",
+    "Voice Oohs",
+    "Synth Voice",
+    "Orchestra Hit",
+    // Brass
+    "Trumpet",
+    "Trombone",
+    "Tuba",
+    "Muted Trumpet",
+    "French Horn",
+    "Brass Section",
+    "Synth Brass 1",
+    "Synth Brass 2",
+    // Reed
+    "Soprano Sax",
+    "Alto Sax",
+    "Tenor Sax",
+    "Baritone Sax",
+    "Oboe",
+    "English Horn",
+    "Bassoon",
+    "Clarinet",
+    // Pipe
+    "Piccolo",
+    "Flute",
+    "Recorder",
+    "Pan Flute",
+    "Blown Bottle",
+    "Shakuhachi",
+    "Whistle",
+    "Ocarina",
+    // Synth Lead
+    "Lead 1 (square)",
+    "Lead 2 (sawtooth)",
+    "Lead 3 (calliope)",
+    "Lead 4 (chiff)",
+    "Lead 5 (charang)",
+    "Lead 6 (voice)",
+    "Lead 7 (fifths)",
+    "Lead 8 (bass + lead)",
+    // Synth Pad
+    "Pad 1 (new age)",
+    "Pad 2 (warm)",
+    "Pad 3 (polysynth)",
+    "Pad 4 (choir)",
+    "Pad 5 (bowed)",
+    "Pad 6 (metallic)",
+    "Pad 7 (halo)",
+    "Pad 8 (sweep)",
+    // Synth Effects
+    "FX 1 (rain)",
+    "FX 2 (soundtrack)",
+    "FX 3 (crystal)",
+    "FX 4 (atmosphere)",
+    "FX 5 (brightness)",
+    "FX 6 (goblins)",
+    "FX 7 (echoes)",
+    "FX 8 (sci-fi)",
+    // Ethnic
+    "Sitar",
+    "Banjo",
+    "Shamisen",
+    "Koto",
+    "Kalimba",
+    "Bagpipe",
+    "Fiddle",
+    "Shanai",
+    // Percussive
+    "Tinkle Bell",
+    "Agogo",
+    "Steel Drums",
+    "Woodblock",
+    "Taiko Drum",
+    "Melodic Tom",
+    "Synth Drum",
+    "Reverse Cymbal",
+    // Sound Effects
+    "Guitar Fret Noise",
+    "Breath Noise",
+    "Seashore",
+    "Bird Tweet",
+    "Telephone Ring",
+    "Helicopter",
+    "Applause",
+    "Gunshot",
+];
+
+/// Standard names of the commonly used Control Change numbers.
+pub fn controller_name(controller: u8) -> Option<&'static str> {
+    let name = match controller {
+        0 => "Bank Select",
+        1 => "Modulation",
+        2 => "Breath",
+        4 => "Foot",
+        5 => "Portamento Time",
+        6 => "Data Entry",
+        7 => "Volume",
+        8 => "Balance",
+        10 => "Pan",
+        11 => "Expression",
+        32 => "Bank Select LSB",
+        64 => "Sustain",
+        65 => "Portamento",
+        66 => "Sostenuto",
+        67 => "Soft Pedal",
+        71 => "Resonance",
+        74 => "Cutoff",
+        120 => "All Sound Off",
+        121 => "Reset All Controllers",
+        123 => "All Notes Off",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Program names used to decode Program Change messages.
+///
+/// Defaults to the General MIDI instrument names, which
+/// can be overridden per program for non-GM gear.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProgramNames {
+    names: Vec<String>,
+}
+
+impl Default for ProgramNames {
+    fn default() -> Self {
+        Self {
+            names: GM_PROGRAMS.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+impl ProgramNames {
+    /// Load the GM names overridden by the entries of a file.
+    /// See [`ProgramNames::with_overrides`] for the file format.
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::default().with_overrides(&std::fs::read_to_string(path)?)
+    }
+
+    /// Override program names with lines of `<program> <name>`,
+    /// where `program` is in `0..=127`. Empty lines and lines
+    /// starting with `#` are ignored.
+    pub fn with_overrides(mut self, overrides: &str) -> anyhow::Result<Self> {
+        let lines = overrides
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'));
+
+        for line in lines {
+            let Some((program, name)) = line.split_once(char::is_whitespace) else {
+                anyhow::bail!("Invalid program name entry : {line}");
+            };
+
+            match program.parse::<u8>() {
+                Ok(program) if (program as usize) < self.names.len() => {
+                    self.names[program as usize] = name.trim().to_owned()
+                }
+                _ => anyhow::bail!("Invalid program number : {program}"),
+            }
+        }
+
+        Ok(self)
+    }
+
+    pub fn get(&self, program: u8) -> Option<&str> {
+        self.names.get(program as usize).map(String::as_str)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn common_controllers_have_standard_names() {
+        assert_eq!(controller_name(1), Some("Modulation"));
+        assert_eq!(controller_name(7), Some("Volume"));
+        assert_eq!(controller_name(10), Some("Pan"));
+        assert_eq!(controller_name(11), Some("Expression"));
+        assert_eq!(controller_name(64), Some("Sustain"));
+        assert_eq!(controller_name(3), None);
+    }
+
+    #[test]
+    fn programs_default_to_general_midi_names() {
+        let names = ProgramNames::default();
+        assert_eq!(names.get(0), Some("Acoustic Grand Piano"));
+        assert_eq!(names.get(40), Some("Violin"));
+        assert_eq!(names.get(73), Some("Flute"));
+        assert_eq!(names.get(127), Some("Gunshot"));
+        assert_eq!(names.get(128), None);
+    }
+
+    #[test]
+    fn program_names_can_be_overridden() {
+        let names = ProgramNames::default()
+            .with_overrides("# my synth\n\n0 Init Patch\n 12  Wobble Bass \n")
+            .unwrap();
+
+        assert_eq!(names.get(0), Some("Init Patch"));
+        assert_eq!(names.get(12), Some("Wobble Bass"));
+        assert_eq!(names.get(1), Some("Bright Acoustic Piano"));
+
+        assert!(ProgramNames::default().with_overrides("128 Nope").is_err());
+        assert!(ProgramNames::default().with_overrides("Nope").is_err());
+    }
+}
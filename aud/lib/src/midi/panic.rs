@@ -0,0 +1,88 @@
+use super::MidiData;
+use std::collections::BTreeSet;
+
+const NOTE_OFF: u8 = 0x80;
+const NOTE_ON: u8 = 0x90;
+const CONTROL_CHANGE: u8 = 0xB0;
+const ALL_SOUND_OFF: u8 = 120;
+const ALL_NOTES_OFF: u8 = 123;
+const NUM_CHANNELS: u8 = 16;
+
+/// Notes received as on and not yet released, by channel and key.
+#[derive(Debug, Default, Clone)]
+pub struct HeldNotes {
+    notes: BTreeSet<(u8, u8)>,
+}
+
+impl HeldNotes {
+    pub fn observe(&mut self, midi: &MidiData) {
+        let [status, key, velocity, ..] = midi.bytes[..] else {
+            return;
+        };
+
+        let channel = status & 0x0F;
+        match status & 0xF0 {
+            NOTE_ON if velocity > 0 => {
+                self.notes.insert((channel, key));
+            }
+            NOTE_ON | NOTE_OFF => {
+                self.notes.remove(&(channel, key));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.notes.clear();
+    }
+
+    /// Messages silencing everything: a note off for each held note, then
+    /// All Notes Off and All Sound Off on every channel, for the notes that
+    /// were not seen and the ones still ringing out.
+    pub fn panic_messages(&self) -> Vec<Vec<u8>> {
+        let note_offs = self
+            .notes
+            .iter()
+            .map(|&(channel, key)| vec![NOTE_OFF | channel, key, 0]);
+
+        let controls = (0..NUM_CHANNELS).flat_map(|channel| {
+            [
+                vec![CONTROL_CHANGE | channel, ALL_NOTES_OFF, 0],
+                vec![CONTROL_CHANGE | channel, ALL_SOUND_OFF, 0],
+            ]
+        });
+
+        note_offs.chain(controls).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn observe(notes: &mut HeldNotes, bytes: &[u8]) {
+        notes.observe(&MidiData {
+            timestamp: 0,
+            bytes: bytes.to_vec(),
+        });
+    }
+
+    #[test]
+    fn released_notes_are_no_longer_held() {
+        let mut notes = HeldNotes::default();
+        observe(&mut notes, &[0x90, 60, 100]);
+        observe(&mut notes, &[0x93, 64, 90]);
+        observe(&mut notes, &[0x90, 67, 80]);
+        observe(&mut notes, &[0x90, 67, 0]);
+        observe(&mut notes, &[0x83, 64, 0]);
+        observe(&mut notes, &[0x93, 60, 0]);
+        observe(&mut notes, &[0xF8]);
+
+        let messages = notes.panic_messages();
+        assert_eq!(messages[0], [0x80, 60, 0]);
+        assert_eq!(messages.len(), 1 + 2 * NUM_CHANNELS as usize);
+
+        notes.clear();
+        assert_eq!(notes.panic_messages().len(), 2 * NUM_CHANNELS as usize);
+    }
+}
@@ -0,0 +1,111 @@
+use std::str::FromStr;
+
+/// Matches port names, which vary from host to host for the same device.
+///
+/// A pattern wrapped in slashes, e.g. `/launchpad.*mk3/`, is a regular
+/// expression searched for in the name. Anything else is a glob matched
+/// against the whole name, e.g. `Launchpad Pro*`. Both ignore case.
+#[derive(Debug, Clone)]
+pub enum PortPattern {
+    Glob(glob::Pattern),
+    Regex(regex::Regex),
+}
+
+impl FromStr for PortPattern {
+    type Err = anyhow::Error;
+
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        let regex = pattern
+            .strip_prefix('/')
+            .and_then(|pattern| pattern.strip_suffix('/'));
+
+        match regex {
+            Some(regex) => Ok(Self::Regex(
+                regex::RegexBuilder::new(regex)
+                    .case_insensitive(true)
+                    .build()?,
+            )),
+            None => Ok(Self::Glob(glob::Pattern::new(pattern)?)),
+        }
+    }
+}
+
+impl std::fmt::Display for PortPattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Glob(glob) => write!(f, "{glob}"),
+            Self::Regex(regex) => write!(f, "/{regex}/"),
+        }
+    }
+}
+
+impl PortPattern {
+    pub fn matches(&self, port_name: &str) -> bool {
+        match self {
+            Self::Glob(glob) => glob.matches_with(
+                port_name,
+                glob::MatchOptions {
+                    case_sensitive: false,
+                    ..Default::default()
+                },
+            ),
+            Self::Regex(regex) => regex.is_match(port_name),
+        }
+    }
+
+    /// Indices of the matching ports, in the order they are listed.
+    pub fn find(&self, port_names: &[String]) -> Vec<usize> {
+        port_names
+            .iter()
+            .enumerate()
+            .filter(|(_, name)| self.matches(name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ports() -> Vec<String> {
+        [
+            "Launchpad Pro MK3 LPProMK3 MIDI",
+            "Launchpad Pro MK3 LPProMK3 DIN",
+            "Arturia KeyStep 37",
+            "IAC Driver Bus 1",
+            "Midi Through:Midi Through Port-0 14:0",
+            "Scarlett 2i4 USB:Scarlett 2i4 USB MIDI 1 24:0",
+        ]
+        .map(str::to_owned)
+        .to_vec()
+    }
+
+    fn find(pattern: &str) -> Vec<usize> {
+        pattern.parse::<PortPattern>().unwrap().find(&ports())
+    }
+
+    #[test]
+    fn globs_match_whole_names_ignoring_case() {
+        assert_eq!(find("launchpad pro*MIDI"), [0]);
+        assert_eq!(find("Launchpad*"), [0, 1]);
+        assert_eq!(find("*keystep*"), [2]);
+        assert_eq!(find("IAC Driver Bus ?"), [3]);
+        assert_eq!(find("Scarlett 2i[0-9] USB*"), [5]);
+        assert_eq!(find("KeyStep"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn regexes_are_searched_for_in_names() {
+        assert_eq!(find("/keystep/"), [2]);
+        assert_eq!(find("/lpprom.3 (midi|din)/"), [0, 1]);
+        assert_eq!(find(r"/\d+:\d+$/"), [4, 5]);
+        assert_eq!(find("/^midi through/"), [4]);
+    }
+
+    #[test]
+    fn invalid_patterns_are_rejected() {
+        assert!("/launchpad(/".parse::<PortPattern>().is_err());
+        assert!("launchpad[".parse::<PortPattern>().is_err());
+    }
+}
@@ -1,5 +1,6 @@
 use super::*;
-use crossbeam::channel::{Receiver, Sender};
+use crate::drops::DropCounter;
+use crossbeam::channel::{Receiver, Sender, TrySendError};
 use midir::*;
 use std::sync::{
     atomic::{AtomicBool, Ordering},
@@ -12,19 +13,23 @@ pub struct HostedMidiReceiver {
     receiver: Receiver<MidiData>,
     connection: Option<MidiInputConnection<Sender<MidiData>>>,
     is_running: Arc<AtomicBool>,
+    drops: DropCounter,
+}
+
+/// Names of the system's MIDI input ports. This does not
+/// need a [`HostedMidiReceiver`], so it can be polled from any thread.
+pub fn list_host_midi_ports() -> anyhow::Result<Vec<String>> {
+    let host = MidiInput::new("aud-midi-watch")?;
+    Ok(host
+        .ports()
+        .iter()
+        .map(|port| host.port_name(port))
+        .collect::<Result<Vec<_>, _>>()?)
 }
 
 impl Default for HostedMidiReceiver {
     fn default() -> Self {
-        let (sender, receiver) = crossbeam::channel::bounded(1_000);
-
-        Self {
-            host: MidiInput::new("aud-midi-in").unwrap(),
-            connection: None,
-            sender,
-            receiver,
-            is_running: Arc::new(AtomicBool::new(true)),
-        }
+        Self::with_capacity(Self::DEFAULT_CAPACITY)
     }
 }
 
@@ -49,6 +54,14 @@ impl MidiReceiving for HostedMidiReceiver {
         Ok(())
     }
 
+    fn disconnect_from_midi_device(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            connection.close();
+            self.receiver.try_iter().for_each(drop);
+            log::trace!("[ MIDI ] : disconnected");
+        }
+    }
+
     fn list_midi_devices(&self) -> anyhow::Result<Vec<String>> {
         Ok(self
             .host
@@ -61,15 +74,38 @@ impl MidiReceiving for HostedMidiReceiver {
     fn produce_midi_messages(&mut self) -> Vec<MidiData> {
         self.receiver.try_iter().collect()
     }
+
+    fn num_dropped_messages(&self) -> u64 {
+        self.drops.count()
+    }
 }
 
 impl HostedMidiReceiver {
+    /// Messages queued between the MIDI thread and the app.
+    pub const DEFAULT_CAPACITY: usize = 1_000;
+
+    /// Queue up to `capacity` messages, at least one, between the MIDI thread
+    /// and the app. The messages received once it is full are dropped and counted.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let (sender, receiver) = crossbeam::channel::bounded(capacity.max(1));
+
+        Self {
+            host: MidiInput::new("aud-midi-in").unwrap(),
+            connection: None,
+            sender,
+            receiver,
+            is_running: Arc::new(AtomicBool::new(true)),
+            drops: DropCounter::default(),
+        }
+    }
+
     fn connect_to_input_device(
         &mut self,
         port: &MidiInputPort,
     ) -> anyhow::Result<MidiInputConnection<Sender<MidiData>>> {
         let callback = {
             let is_running = self.is_running.clone();
+            let drops = self.drops.clone();
 
             move |timestamp: u64, bytes: &[u8], sender: &mut Sender<MidiData>| {
                 if !is_running.load(Ordering::SeqCst) {
@@ -81,8 +117,8 @@ impl HostedMidiReceiver {
                     bytes: bytes.into(),
                 };
 
-                if let Err(e) = sender.try_send(midi) {
-                    log::error!("Failed to push midi message event to runtime : {e}");
+                if let Err(TrySendError::Disconnected(_)) = drops.try_send(sender, midi) {
+                    log::error!("Failed to push midi message event to runtime : disconnected");
                 }
             }
         };
@@ -92,3 +128,44 @@ impl HostedMidiReceiver {
             .map_err(|e| anyhow::anyhow!(e.to_string()))
     }
 }
+
+/// Sends MIDI to the system's output ports, staying
+/// connected to the last port it sent to.
+#[derive(Default)]
+pub struct HostedMidiSender {
+    connection: Option<(String, MidiOutputConnection)>,
+}
+
+impl HostedMidiSender {
+    fn connect_to_output_device(
+        &mut self,
+        device: &str,
+    ) -> anyhow::Result<&mut MidiOutputConnection> {
+        if !matches!(self.connection, Some((ref name, _)) if name == device) {
+            let host = MidiOutput::new("aud-midi-out")?;
+            let port = host
+                .ports()
+                .into_iter()
+                .find(|port| host.port_name(port).as_deref() == Ok(device))
+                .ok_or_else(|| anyhow::anyhow!("[ MIDI ] : Cannot find output {device}"))?;
+
+            let connection = host
+                .connect(&port, "aud-midi-out")
+                .map_err(|e| anyhow::anyhow!(e.to_string()))?;
+            log::trace!("[ MIDI ] : connected to output {device}");
+            self.connection = Some((device.to_owned(), connection));
+        }
+
+        Ok(&mut self.connection.as_mut().unwrap().1)
+    }
+}
+
+impl MidiProducing for HostedMidiSender {
+    fn send_midi_messages(&mut self, device: &str, messages: &[MidiData]) -> anyhow::Result<()> {
+        let connection = self.connect_to_output_device(device)?;
+        for message in messages {
+            connection.send(&message.bytes)?;
+        }
+        Ok(())
+    }
+}
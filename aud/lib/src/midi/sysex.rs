@@ -0,0 +1,175 @@
+use super::{MidiData, MidiProducing};
+use std::{path::Path, time::Duration};
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// Most bytes sent at once, larger dumps are sent in several
+/// batches so that the receiving device can keep up with them.
+const MAX_BATCH_LEN: usize = 256;
+
+/// Time left to the receiving device between two batches.
+const BATCH_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Frame raw bytes as a single SysEx message, adding the
+/// leading F0 and trailing F7 when they are missing.
+///
+/// Fails if any of the data bytes has its top bit set.
+pub fn frame_sysex(bytes: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let data = bytes.strip_prefix(&[SYSEX_START]).unwrap_or(bytes);
+    let data = data.strip_suffix(&[SYSEX_END]).unwrap_or(data);
+
+    if let Some(byte) = data.iter().find(|&&byte| byte & 0x80 != 0) {
+        anyhow::bail!("invalid SysEx data byte : {byte:#04X}");
+    }
+
+    let mut message = Vec::with_capacity(data.len() + 2);
+    message.push(SYSEX_START);
+    message.extend_from_slice(data);
+    message.push(SYSEX_END);
+    Ok(message)
+}
+
+/// SysEx messages to send to a device, e.g. read from a `.syx` file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SysExDump {
+    messages: Vec<Vec<u8>>,
+}
+
+impl SysExDump {
+    pub fn from_file(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        Self::from_syx(&std::fs::read(path)?)
+    }
+
+    /// Split the content of a `.syx` file, one or more messages
+    /// each framed by F0 and F7, back into its messages.
+    pub fn from_syx(bytes: &[u8]) -> anyhow::Result<Self> {
+        let mut messages = vec![];
+        let mut rest = bytes;
+
+        while !rest.is_empty() {
+            if rest[0] != SYSEX_START {
+                anyhow::bail!(
+                    "expected F0 at byte {}, found {:#04X}",
+                    bytes.len() - rest.len(),
+                    rest[0]
+                );
+            }
+
+            let Some(end) = rest.iter().position(|&byte| byte == SYSEX_END) else {
+                anyhow::bail!("unterminated SysEx message, missing F7");
+            };
+
+            messages.push(frame_sysex(&rest[..=end])?);
+            rest = &rest[end + 1..];
+        }
+
+        if messages.is_empty() {
+            anyhow::bail!("no SysEx message found");
+        }
+
+        Ok(Self { messages })
+    }
+
+    pub fn messages(&self) -> &[Vec<u8>] {
+        &self.messages
+    }
+
+    /// Whole messages grouped into batches of at most `max_len` bytes.
+    /// A message longer than that is never split, it is a batch on its own.
+    fn batches(&self, max_len: usize) -> Vec<&[Vec<u8>]> {
+        let mut batches = vec![];
+        let (mut start, mut len) = (0, 0);
+
+        for (i, message) in self.messages.iter().enumerate() {
+            if i > start && len + message.len() > max_len {
+                batches.push(&self.messages[start..i]);
+                (start, len) = (i, 0);
+            }
+            len += message.len();
+        }
+
+        if start < self.messages.len() {
+            batches.push(&self.messages[start..]);
+        }
+        batches
+    }
+
+    /// Send every message to a device, pausing between batches.
+    pub fn send(&self, output: &mut dyn MidiProducing, device: &str) -> anyhow::Result<()> {
+        for (i, batch) in self.batches(MAX_BATCH_LEN).into_iter().enumerate() {
+            if i > 0 {
+                std::thread::sleep(BATCH_INTERVAL);
+            }
+
+            let messages: Vec<MidiData> = batch
+                .iter()
+                .map(|bytes| MidiData {
+                    timestamp: 0,
+                    bytes: bytes.clone(),
+                })
+                .collect();
+            output.send_midi_messages(device, &messages)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl From<Vec<u8>> for SysExDump {
+    /// A dump of a single message, which must already be framed.
+    fn from(message: Vec<u8>) -> Self {
+        Self {
+            messages: vec![message],
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn messages_are_framed_and_validated() {
+        assert_eq!(
+            frame_sysex(&[0x7E, 0x01]).unwrap(),
+            [0xF0, 0x7E, 0x01, 0xF7]
+        );
+        assert_eq!(
+            frame_sysex(&[0xF0, 0x7E, 0x01, 0xF7]).unwrap(),
+            [0xF0, 0x7E, 0x01, 0xF7]
+        );
+        assert_eq!(frame_sysex(&[]).unwrap(), [0xF0, 0xF7]);
+        assert!(frame_sysex(&[0xF0, 0x90, 0x3C, 0xF7]).is_err());
+    }
+
+    #[test]
+    fn syx_files_are_split_into_their_messages() {
+        let dump = SysExDump::from_syx(&[0xF0, 0x01, 0xF7, 0xF0, 0x02, 0x03, 0xF7]).unwrap();
+        assert_eq!(
+            dump.messages(),
+            [vec![0xF0, 0x01, 0xF7], vec![0xF0, 0x02, 0x03, 0xF7]]
+        );
+
+        assert!(SysExDump::from_syx(&[]).is_err());
+        assert!(SysExDump::from_syx(&[0x01, 0xF0, 0xF7]).is_err());
+        assert!(SysExDump::from_syx(&[0xF0, 0x01]).is_err());
+        assert!(SysExDump::from_syx(&[0xF0, 0x81, 0xF7]).is_err());
+    }
+
+    #[test]
+    fn large_dumps_are_batched_without_splitting_messages() {
+        let message = |len: usize| frame_sysex(&vec![0x10; len - 2]).unwrap();
+        let dump = SysExDump {
+            messages: vec![message(4), message(4), message(10), message(3)],
+        };
+
+        let lens: Vec<Vec<usize>> = dump
+            .batches(8)
+            .iter()
+            .map(|batch| batch.iter().map(Vec::len).collect())
+            .collect();
+        assert_eq!(lens, [vec![4, 4], vec![10], vec![3]]);
+        assert_eq!(dump.batches(MAX_BATCH_LEN).len(), 1);
+    }
+}
@@ -15,6 +15,11 @@ function on_discover(device_names) end
 -- @param device_name string: Name of the audio device we've just connected to
 function on_connect(device_name) end
 
+-- Called when an audio device is disconnected
+--
+-- @param device_name string: Name of the device we were connected to
+function on_disconnect(device_name) end
+
 -- Called when audio is received.
 --
 -- @param device_name string: Name of the device sending the audio
@@ -11,11 +11,80 @@ function connect(device_name) end
 -- @return string: Alert message
 function alert(message) end
 
--- Pause the stream
-function pause() end
+-- Pause the stream, optionally
+-- with a reason shown as an alert
+function pause(reason) end
 
--- Resume streaming
-function resume() end
+-- Resume streaming, optionally
+-- with a reason shown as an alert
+function resume(reason) end
 
--- Request to stop the application
-function stop() end
+-- Request to stop the application,
+-- optionally with a reason shown as an alert
+function stop(reason) end
+
+-- Clock of `aud`, only advancing between two calls into the script
+time = {}
+
+-- Time elapsed since `aud` started
+--
+-- @return number: Monotonic time in seconds
+function time.now() end
+
+-- Number of frames `aud` has rendered since it started
+--
+-- @return number: Frame count
+function time.frames() end
+
+-- Latest audio received by the script, bounded to the last 4096 frames
+audio = {}
+
+-- Number of channels in the latest audio window
+--
+-- @return number: Channel count
+function audio.channels() end
+
+-- Samples of a channel in the latest audio window
+--
+-- @param channel number: Channel number, starting at 1
+-- @return table: List of samples, empty if the channel does not exist
+function audio.samples(channel) end
+
+-- Root mean square level of a channel in the latest audio window
+--
+-- @param channel number: Channel number, starting at 1
+-- @return number: RMS level, 0 if the channel does not exist
+function audio.rms(channel) end
+
+-- Name of the connected audio device
+--
+-- @return string: Device name, nil if no device is connected
+function audio.selected() end
+
+-- Scope display settings
+scope = {}
+
+-- Switch what the scope displays, unknown modes are ignored
+--
+-- @param mode string: "waveform", "spectrum", "vector" or "sparkline"
+function scope.set_mode(mode) end
+
+-- Align the waveform on the first crossing of a level
+--
+-- @param level number: Level to cross, between -1 and 1
+-- @param edge string: "rising" or "falling", defaults to "rising"
+function scope.set_trigger(level, edge) end
+
+-- Set how much audio the scope displays
+--
+-- @param window_ms number: Displayed duration in milliseconds
+function scope.zoom(window_ms) end
+
+-- Display of `aud`
+ui = {}
+
+-- Clear the traces held on screen, e.g. persistence and history
+function ui.clear() end
+
+-- Dismiss the alert
+function ui.reset() end
@@ -15,12 +15,37 @@ function on_discover(device_names) end
 -- @param device_name string: Name of the MIDI device we've just connected to
 function on_connect(device_name) end
 
+-- Called when a MIDI connection is closed
+--
+-- @param device_name string: Name of the device we were connected to
+function on_disconnect(device_name) end
+
 -- Called when MIDI bytes are received.
 --
 -- @param device_name string: Name of the MIDI device sending this MIDI
--- @param bytes table: A table of bytes representing the raw MIDI message.
--- @return bool: Should this message be displayed?
-function on_midi(device_name, bytes) end
+-- @param msg table: The raw bytes of the MIDI message, e.g. `msg[1]` is the
+--                   status byte, along with the fields decoded from them :
+--                   - `msg.bytes` table: the raw bytes
+--                   - `msg.type` string: e.g. "note_on", "note_off", "control_change",
+--                     "program_change", "pitch_bend", "clock", "sysex"
+--                   - `msg.channel` number: from 0 to 15, nil if not a channel message
+--                   - `msg.note`, `msg.velocity` number: for note messages
+--                   - `msg.controller`, `msg.value` number: for control changes,
+--                     `msg.value` is also set by program changes, pressure and pitch bends
+-- @return The messages to display in place of this one :
+--         - nothing, or `true`, keeps the message as received
+--         - `nil` or `false` drops it
+--         - a table of bytes, or a table with a `bytes` field, replaces it,
+--           `msg` itself can be modified and returned
+--         - several values expand it into several messages, in order,
+--           e.g. `return msg, { 0x80, msg.note, 0 }`
+function on_midi(device_name, msg) end
+
+-- Called when a parameter is adjusted from the params panel
+--
+-- @param name string: Name of the parameter, as passed to `params.define`
+-- @param value number: Its new value, also returned by `params.get`
+function on_param(name, value) end
 
 -- Called when `aud` is stopping
 function on_stop() end
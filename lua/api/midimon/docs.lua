@@ -3,7 +3,9 @@
 -- Write to the `aud` log file
 function log(message) end
 
--- Connect to the specified MIDI device
+-- Connect to the specified MIDI device. Names vary between hosts, so
+-- the name may also be a glob, e.g. "Launchpad Pro*", or a regex wrapped
+-- in slashes, e.g. "/keystep/", connecting to the first matching device
 function connect(device_name) end
 
 -- Send an alert to `aud`
@@ -11,11 +13,119 @@ function connect(device_name) end
 -- @return string: Alert message
 function alert(message) end
 
--- Pause the stream
-function pause() end
+-- Pause the stream, optionally
+-- with a reason shown as an alert
+function pause(reason) end
 
--- Resume streaming
-function resume() end
+-- Resume streaming, optionally
+-- with a reason shown as an alert
+function resume(reason) end
 
--- Request to stop the application
-function stop() end
+-- Request to stop the application,
+-- optionally with a reason shown as an alert
+function stop(reason) end
+
+-- Clock of `aud`, only advancing between two calls into the script
+time = {}
+
+-- Time elapsed since `aud` started
+--
+-- @return number: Monotonic time in seconds
+function time.now() end
+
+-- Number of frames `aud` has rendered since it started
+--
+-- @return number: Frame count
+function time.frames() end
+
+-- MIDI device settings
+midi = {}
+
+-- Display a device under another name, connecting still uses its real name
+--
+-- @param device_name string: Name reported by the device
+-- @param display_name string: Name to display instead
+function midi.alias(device_name, display_name) end
+
+-- Name of the connected MIDI device
+--
+-- @return string: Device name, nil if no device is connected
+function midi.selected() end
+
+-- Send a MIDI message once a delay has elapsed, e.g. a note off.
+-- Pending messages are cancelled when the script is stopped or reloaded
+--
+-- @param delay_ms number: Delay before sending, in milliseconds, raises an error if negative or too long
+-- @param bytes table: Bytes of the message to send
+function midi.send_after(delay_ms, bytes) end
+
+-- Send a SysEx message to the output of the connected MIDI device.
+-- The leading 0xF0 and trailing 0xF7 are added when missing
+--
+-- @param bytes table: Bytes of the message, all data bytes below 0x80
+-- @return boolean: false if the message is not valid SysEx
+function midi.send_sysex(bytes) end
+
+-- Silence the connected MIDI device: release the notes it was seen
+-- holding, then send All Notes Off and All Sound Off on every channel
+function midi.panic() end
+
+-- Parameters tuned from the params panel of `aud`
+params = {}
+
+-- Expose a parameter in the params panel, redefining it resets its value
+--
+-- @param name string: Name of the parameter
+-- @param default number: Initial value, clamped to the range
+-- @param min number: Lowest value
+-- @param max number: Highest value, greater than `min`
+-- @return number: Initial value, nil if the range is invalid
+function params.define(name, default, min, max) end
+
+-- Current value of a parameter
+--
+-- @param name string: Name of the parameter
+-- @return number: Its value, nil if it is not defined
+function params.get(name) end
+
+-- Set the value of a parameter, the panel follows
+--
+-- @param name string: Name of the parameter
+-- @param value number: New value, clamped to the range
+-- @return number: Value set, nil if it is not defined
+function params.set(name, value) end
+
+-- Messages kept by the monitor, on top of its command line filter.
+-- Scripts still receive every message in `on_midi`.
+-- The filter set by a script is undone when another script is loaded
+filter = {}
+
+-- Only keep the channel messages of some channels
+--
+-- @param channels table: Channels from 0 to 15, as in `msg.channel`, nil to keep all
+-- @return boolean: false if a channel is out of range
+function filter.channels(channels) end
+
+-- Only keep some types of message
+--
+-- @param types table: Types as in `msg.type`, or "note" for Note Ons
+--                     and Note Offs and "cc" for control changes, nil to keep all
+-- @return boolean: false if a type is unknown
+function filter.types(types) end
+
+-- Only keep the notes of a range, the other messages are kept.
+-- Notes outside of the command line range are never kept
+--
+-- @param low number: Lowest note kept, clamped to 0 to 127, 0 if nil
+-- @param high number: Highest note kept, clamped to 0 to 127, 127 if nil.
+--                     Keep all notes again if both are nil
+function filter.notes(low, high) end
+
+-- Display of `aud`
+ui = {}
+
+-- Clear the received messages, as pressing `c` does without confirmation
+function ui.clear() end
+
+-- Dismiss the alert and scroll back to the latest messages
+function ui.reset() end
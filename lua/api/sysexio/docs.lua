@@ -11,11 +11,14 @@ function connect(device_name) end
 -- @return string: Alert message
 function alert(message) end
 
--- Pause the stream
-function pause() end
+-- Pause the stream, optionally
+-- with a reason shown as an alert
+function pause(reason) end
 
--- Resume streaming
-function resume() end
+-- Resume streaming, optionally
+-- with a reason shown as an alert
+function resume(reason) end
 
--- Request to stop the application
-function stop() end
+-- Request to stop the application,
+-- optionally with a reason shown as an alert
+function stop(reason) end